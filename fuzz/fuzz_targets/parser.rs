@@ -0,0 +1,41 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Random bytes rarely get past the lexer, so this target spells programs out
+//! of tokens the lexer is sure to accept, to give the parser a workout. Run
+//! with `cargo fuzz run parser`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+/// Keywords that take each kind of argument, and the things that go between
+/// them. A byte picks each token.
+#[rustfmt::skip]
+const TOKENS: &[&str] = &[
+    "fd", "bk", "rt", "lt", "seth", "setx", "sety", "setpos", "setxy", "home", "pu", "pd",
+    "repeat", "every", "forever", "for", "fn", "to", "end", "let", "param", "random", "sqrt", "pr",
+    "setpc", "setsc", "settc", "setgradient", "setspeed", "setshape", "symmetry", "scale",
+    "translate", "pushtransform", "poptransform", "erase", "towards", "distance", "[", "]",
+    "{", "}", "(", ")", "+", "-", "*", "/", "^", "%", "=", "<", ">", "0", "1", "2.5",
+    "1e999", ":x", "\"x", "x", "repcount", "\n",
+];
+
+fuzz_target!(|data: &[u8]| {
+    let tokens: Vec<_> = data
+        .iter()
+        .map(|byte| TOKENS[*byte as usize % TOKENS.len()])
+        .collect();
+    turtle_rust::check(&tokens.join(" "));
+});
@@ -0,0 +1,70 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Drawing from Rust code instead of Logo.
+
+use crate::model::prefs::Preferences;
+use crate::runtime;
+use crate::runtime::warning::Warnings;
+use druid::PlatformError;
+
+mod turtle;
+
+pub use druid::Color;
+pub use turtle::Turtle;
+
+/// Open the turtle window and run `program` in it. The program gets its own
+/// thread, and starts as soon as the window opens; this returns when the
+/// window is closed.
+///
+/// ```no_run
+/// turtle_rust::run(|turtle| {
+///     for _ in 0..4 {
+///         turtle.forward(100.0);
+///         turtle.right(90.0);
+///     }
+/// })
+/// .unwrap();
+/// ```
+pub fn run<F>(program: F) -> Result<(), PlatformError>
+where
+    F: FnOnce(&mut Turtle) + Send + 'static,
+{
+    let (launcher, data) = crate::desktop::build(Preferences::load());
+
+    let render_tx = data.render_tx.clone();
+    let speed = data.speed.shared();
+    let prefs = data.prefs.clone();
+    data.session.start(move |control| {
+        let mut turtle = Turtle::new(render_tx, speed, control, &prefs);
+        program(&mut turtle);
+    });
+
+    launcher.launch(data)
+}
+
+/// Lex and parse a Logo program without running it, and say what's wrong
+/// with it, if anything. However garbled the program, this gives back errors
+/// rather than panicking; the targets in `fuzz/` check that.
+///
+/// ```
+/// assert!(turtle_rust::check("repeat 4 [fd 100 rt 90]").is_empty());
+/// assert_eq!(turtle_rust::check("repeat 4").len(), 1);
+/// ```
+pub fn check(program: &str) -> Vec<String> {
+    runtime::check(program, &Preferences::default(), &Warnings::default())
+        .iter()
+        .map(|err| err.to_string())
+        .collect()
+}
@@ -0,0 +1,207 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::constants::*;
+use crate::model::prefs::Preferences;
+use crate::model::render::*;
+use crate::runtime::control::Control;
+use druid::Color;
+use druid::Point;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+
+/// A turtle driven from Rust. It draws on the same canvas as Logo, through
+/// the same render commands, so the speed slider, Step, and Stop all work on
+/// it too. Once the program is stopped, the turtle ignores everything else
+/// it's asked to do.
+pub struct Turtle {
+    render_tx: Arc<RenderTx>,
+    speed: Arc<AtomicU32>,
+    control: Arc<Control>,
+    color: Color,
+    /// Degrees clockwise from up, always in [0, 360).
+    heading: f64,
+    pen_flags: u32,
+    pos: Point,
+    sent: u32,
+    stopped: bool,
+}
+
+impl Turtle {
+    pub(crate) fn new(
+        render_tx: Arc<RenderTx>,
+        speed: Arc<AtomicU32>,
+        control: Arc<Control>,
+        prefs: &Preferences,
+    ) -> Self {
+        Self {
+            render_tx,
+            speed,
+            control,
+            color: prefs.pen_color.clone(),
+            heading: 0.0,
+            pen_flags: PEN_FLAGS_DEFAULT,
+            pos: Point::ZERO,
+            sent: 0,
+            stopped: false,
+        }
+    }
+
+    pub fn forward(&mut self, distance: f64) {
+        let angle = (90.0 - self.heading).to_radians();
+        let p = Point::new(
+            self.pos.x + distance * angle.cos(),
+            self.pos.y + distance * angle.sin(),
+        );
+        self.move_to(angle, p);
+    }
+
+    pub fn backward(&mut self, distance: f64) {
+        self.forward(-distance);
+    }
+
+    /// Turn anticlockwise, in degrees.
+    pub fn left(&mut self, angle: f64) {
+        self.set_heading(self.heading - angle);
+    }
+
+    /// Turn clockwise, in degrees.
+    pub fn right(&mut self, angle: f64) {
+        self.set_heading(self.heading + angle);
+    }
+
+    /// Point the turtle this many degrees clockwise from straight up.
+    pub fn set_heading(&mut self, heading: f64) {
+        self.heading = heading.rem_euclid(360.0);
+        // A tiny negative angle rounds up to a whole turn.
+        if self.heading >= 360.0 {
+            self.heading = 0.0;
+        }
+        self.tx(RenderCommand::Turn(self.heading));
+    }
+
+    pub fn heading(&self) -> f64 {
+        self.heading
+    }
+
+    /// Go straight to a point, with the middle of the canvas at (0, 0) and y
+    /// going up. The turtle keeps its heading.
+    pub fn go_to(&mut self, x: f64, y: f64) {
+        let p = Point::new(x, y);
+        let d = p - self.pos;
+        self.move_to(d.y.atan2(d.x), p);
+    }
+
+    /// Back to the middle of the canvas, pointing up.
+    pub fn home(&mut self) {
+        self.go_to(0.0, 0.0);
+        self.set_heading(0.0);
+    }
+
+    pub fn position(&self) -> Point {
+        self.pos
+    }
+
+    pub fn pen_up(&mut self) {
+        self.pen_flags = pen_up(self.pen_flags);
+    }
+
+    pub fn pen_down(&mut self) {
+        self.pen_flags = pen_down(self.pen_flags);
+    }
+
+    pub fn pen_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Wipe the drawing without moving the turtle.
+    pub fn clean(&mut self) {
+        self.tx(RenderCommand::Clean);
+    }
+
+    pub fn show(&mut self) {
+        self.tx(RenderCommand::ShowTurtle(true));
+    }
+
+    pub fn hide(&mut self) {
+        self.tx(RenderCommand::ShowTurtle(false));
+    }
+
+    /// Whether the program has been stopped from the window, or the window
+    /// has closed. Long loops can check this to finish early.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    fn move_to(&mut self, angle: f64, p: Point) {
+        let move_to = MoveTo::new(angle, self.color.clone(), 0.0, self.pen_flags, p);
+        self.tx(RenderCommand::MoveTo(move_to));
+        self.pos = p;
+    }
+
+    fn tx(&mut self, cmd: RenderCommand) {
+        if self.stopped || self.control.check().is_err() {
+            self.stopped = true;
+            return;
+        }
+
+        self.sent += 1;
+        if self.sent % self.speed.load(Ordering::Relaxed) == 0 {
+            thread::sleep(RENDER_DELAY);
+        }
+
+        if self.render_tx.unbounded_send(cmd).is_err() {
+            self.stopped = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc;
+
+    #[test]
+    fn it_draws_like_logo() {
+        let (tx, mut rx) = mpsc::unbounded();
+        let speed = Arc::new(AtomicU32::new(MAX_SPEED));
+        let control = Arc::new(Control::default());
+        let mut turtle = Turtle::new(Arc::new(tx), speed, control.clone(), &Default::default());
+
+        turtle.forward(10.0);
+        turtle.right(90.0);
+        turtle.pen_up();
+        turtle.forward(5.0);
+        turtle.left(450.0);
+
+        assert!((turtle.position() - Point::new(5.0, 10.0)).hypot() < 1e-9);
+        assert_eq!(turtle.heading(), 0.0);
+
+        let mut got = Vec::new();
+        while let Ok(Some(cmd)) = rx.try_next() {
+            got.push(cmd);
+        }
+        assert_eq!(got.len(), 4);
+        assert!(matches!(&got[0], RenderCommand::MoveTo(m) if is_pen_down(m.pen_flags)));
+        assert_eq!(got[1], RenderCommand::Turn(90.0));
+        assert!(matches!(&got[2], RenderCommand::MoveTo(m) if !is_pen_down(m.pen_flags)));
+
+        control.stop();
+        turtle.forward(10.0);
+        assert!(turtle.is_stopped());
+        assert!(rx.try_next().is_err());
+    }
+}
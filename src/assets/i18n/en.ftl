@@ -0,0 +1,111 @@
+# Interface text in English. Every key here should also be in the other
+# languages; anything missing falls back to this file.
+
+## Menus
+
+menu-file = File
+menu-file-open = Open...
+menu-file-save = Save
+menu-file-save-as = Save As...
+menu-file-watch = Watch for Changes
+menu-file-restore-backup = Restore Backup
+menu-file-export-image = Export Image...
+menu-file-export-image-cropped = Export Image Cropped to Drawing...
+menu-file-export-animation = Export Animation...
+menu-file-export-commands = Export Commands...
+menu-file-import-commands = Import Commands...
+
+menu-edit = Edit
+menu-edit-copy-image = Copy Image
+menu-edit-clear-console = Clear Console
+menu-edit-preferences = Preferences...
+
+menu-view = View
+menu-view-dark-theme = Dark Theme
+menu-view-light-theme = Light Theme
+menu-view-triangle-turtle = Triangle Turtle
+menu-view-turtle-bitmap = Turtle Bitmap
+menu-view-turtle-image = Turtle Image...
+menu-view-background-image = Background Image...
+menu-view-clear-background = Clear Background Image
+menu-view-grid = Show Grid
+menu-view-trace = Show Path Trace
+menu-view-command-log = Show Command Log
+menu-view-presentation = Presentation Mode
+menu-view-reset = Reset View
+
+menu-interpreter = Interpreter
+menu-interpreter-go = Go
+menu-interpreter-step = Step
+menu-interpreter-stop = Stop
+menu-interpreter-format = Format
+menu-interpreter-check = Check Syntax
+menu-interpreter-record = Record Actions
+menu-interpreter-live = Live Mode
+menu-interpreter-english = English Keywords
+menu-interpreter-french = French Keywords
+menu-interpreter-spanish = Spanish Keywords
+menu-interpreter-faster = Faster
+menu-interpreter-slower = Slower
+
+menu-examples = Examples
+menu-examples-gallery = Example Gallery...
+
+menu-help = Help
+menu-help-reference = Language Reference
+
+## Windows
+
+window-untitled = Untitled
+gallery-title = Example Gallery
+gallery-load = Load
+help-title = Language Reference
+log-title = Render commands
+log-pause = Pause
+
+## Status bar and console
+
+canvas-description = drawing area, turtle at { $x },{ $y } heading { $heading }
+status-bar = turtle: { $x },{ $y }   heading: { $heading }   commands: { $commands }   clipped: { $clipped }   speed:
+console-stats = { $commands } commands, { $pixels } pixels drawn, { $clipped } lines clipped
+console-paused = paused
+console-reading = waiting for input; type a line below and press Enter
+console-prefs-saved = preferences saved; canvas and drawing sizes apply on next launch
+console-exported = exported { $path }
+console-imported = imported { $path }
+console-exporting-animation = exporting animation...
+console-syntax-ok = no syntax errors found
+console-run = Run #{ $number }
+console-backup-found = unsaved work from last time was kept; choose File > Restore Backup to bring it back
+error-backup = error: backup: { $error }
+error-copy = error: copy: { $error }
+error-export = error: export: { $error }
+error-file = error: { $path }: { $error }
+error-format-comments = error: format: remove comments first
+error-import = error: import: { $error }
+error-open = error: open: { $error }
+error-preferences = error: preferences: { $error }
+error-reload = error: reload: { $error }
+error-save = error: save: { $error }
+error-watch = error: watch: { $error }
+
+## Preferences
+
+prefs-title = Preferences
+prefs-pen-color = Pen color
+prefs-background-color = Background color
+prefs-speed = Default speed
+prefs-width = Canvas width
+prefs-height = Canvas height
+prefs-surface-width = Drawing width
+prefs-surface-height = Drawing height
+prefs-time-limit = Time limit (s)
+prefs-autosave = Backup every (s)
+prefs-export-step = Animation step
+prefs-export-delay = Frame delay (ms)
+prefs-library-path = Library path
+prefs-antialias = Smooth scaling when zoomed out
+prefs-optimize = Optimize programs before running
+prefs-command-flash = Flash the status bar for each command when slow
+prefs-language = Language
+prefs-save = Save
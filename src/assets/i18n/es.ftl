@@ -0,0 +1,110 @@
+# Texto de la interfaz en español.
+
+## Menús
+
+menu-file = Archivo
+menu-file-open = Abrir...
+menu-file-save = Guardar
+menu-file-save-as = Guardar como...
+menu-file-watch = Vigilar cambios
+menu-file-restore-backup = Restaurar copia de seguridad
+menu-file-export-image = Exportar imagen...
+menu-file-export-image-cropped = Exportar imagen recortada al dibujo...
+menu-file-export-animation = Exportar animación...
+menu-file-export-commands = Exportar órdenes...
+menu-file-import-commands = Importar órdenes...
+
+menu-edit = Edición
+menu-edit-copy-image = Copiar imagen
+menu-edit-clear-console = Borrar la consola
+menu-edit-preferences = Preferencias...
+
+menu-view = Ver
+menu-view-dark-theme = Tema oscuro
+menu-view-light-theme = Tema claro
+menu-view-triangle-turtle = Tortuga triángulo
+menu-view-turtle-bitmap = Tortuga dibujada
+menu-view-turtle-image = Imagen de la tortuga...
+menu-view-background-image = Imagen de fondo...
+menu-view-clear-background = Quitar imagen de fondo
+menu-view-grid = Mostrar cuadrícula
+menu-view-trace = Mostrar trazado
+menu-view-command-log = Mostrar registro de órdenes
+menu-view-presentation = Modo presentación
+menu-view-reset = Restablecer vista
+
+menu-interpreter = Intérprete
+menu-interpreter-go = Ejecutar
+menu-interpreter-step = Paso a paso
+menu-interpreter-stop = Detener
+menu-interpreter-format = Dar formato
+menu-interpreter-check = Comprobar la sintaxis
+menu-interpreter-record = Grabar acciones
+menu-interpreter-live = Modo en vivo
+menu-interpreter-english = Palabras clave en inglés
+menu-interpreter-french = Palabras clave en francés
+menu-interpreter-spanish = Palabras clave en español
+menu-interpreter-faster = Más rápido
+menu-interpreter-slower = Más lento
+
+menu-examples = Ejemplos
+menu-examples-gallery = Galería de ejemplos...
+
+menu-help = Ayuda
+menu-help-reference = Referencia del lenguaje
+
+## Ventanas
+
+window-untitled = Sin título
+gallery-title = Galería de ejemplos
+gallery-load = Cargar
+help-title = Referencia del lenguaje
+log-title = Órdenes de dibujo
+log-pause = Pausa
+
+## Barra de estado y consola
+
+canvas-description = área de dibujo, tortuga en { $x },{ $y } rumbo { $heading }
+status-bar = tortuga: { $x },{ $y }   rumbo: { $heading }   órdenes: { $commands }   recortadas: { $clipped }   velocidad:
+console-stats = { $commands } órdenes, { $pixels } píxeles dibujados, { $clipped } líneas recortadas
+console-paused = en pausa
+console-reading = esperando una respuesta; escribe una línea abajo y pulsa Intro
+console-prefs-saved = preferencias guardadas; los tamaños del lienzo y del dibujo se aplican al volver a abrir
+console-exported = exportado: { $path }
+console-imported = importado: { $path }
+console-exporting-animation = exportando animación...
+console-syntax-ok = no hay errores de sintaxis
+console-run = Ejecución n.º { $number }
+console-backup-found = se conservó el trabajo sin guardar de la última vez; elija Archivo > Restaurar copia de seguridad para recuperarlo
+error-backup = error: copia de seguridad: { $error }
+error-copy = error: copiar: { $error }
+error-export = error: exportar: { $error }
+error-file = error: { $path }: { $error }
+error-format-comments = error: formato: quita los comentarios primero
+error-import = error: importar: { $error }
+error-open = error: abrir: { $error }
+error-preferences = error: preferencias: { $error }
+error-reload = error: recargar: { $error }
+error-save = error: guardar: { $error }
+error-watch = error: vigilar: { $error }
+
+## Preferencias
+
+prefs-title = Preferencias
+prefs-pen-color = Color del lápiz
+prefs-background-color = Color de fondo
+prefs-speed = Velocidad por defecto
+prefs-width = Ancho del lienzo
+prefs-height = Alto del lienzo
+prefs-surface-width = Ancho del dibujo
+prefs-surface-height = Alto del dibujo
+prefs-time-limit = Límite de tiempo (s)
+prefs-autosave = Copia de seguridad cada (s)
+prefs-export-step = Paso de animación
+prefs-export-delay = Retardo entre cuadros (ms)
+prefs-library-path = Ruta de bibliotecas
+prefs-antialias = Suavizar al alejar
+prefs-optimize = Optimizar los programas antes de ejecutarlos
+prefs-command-flash = Destellar la barra de estado con cada orden a poca velocidad
+prefs-language = Idioma
+prefs-save = Guardar
@@ -0,0 +1,110 @@
+# Texte de l'interface en français.
+
+## Menus
+
+menu-file = Fichier
+menu-file-open = Ouvrir...
+menu-file-save = Enregistrer
+menu-file-save-as = Enregistrer sous...
+menu-file-watch = Surveiller les modifications
+menu-file-restore-backup = Restaurer la sauvegarde
+menu-file-export-image = Exporter l'image...
+menu-file-export-image-cropped = Exporter l'image rognée au dessin...
+menu-file-export-animation = Exporter l'animation...
+menu-file-export-commands = Exporter les commandes...
+menu-file-import-commands = Importer des commandes...
+
+menu-edit = Édition
+menu-edit-copy-image = Copier l'image
+menu-edit-clear-console = Effacer la console
+menu-edit-preferences = Préférences...
+
+menu-view = Affichage
+menu-view-dark-theme = Thème sombre
+menu-view-light-theme = Thème clair
+menu-view-triangle-turtle = Tortue triangle
+menu-view-turtle-bitmap = Tortue dessinée
+menu-view-turtle-image = Image de la tortue...
+menu-view-background-image = Image de fond...
+menu-view-clear-background = Retirer l'image de fond
+menu-view-grid = Afficher la grille
+menu-view-trace = Afficher le tracé
+menu-view-command-log = Afficher le journal des commandes
+menu-view-presentation = Mode présentation
+menu-view-reset = Réinitialiser la vue
+
+menu-interpreter = Interpréteur
+menu-interpreter-go = Exécuter
+menu-interpreter-step = Pas à pas
+menu-interpreter-stop = Arrêter
+menu-interpreter-format = Mettre en forme
+menu-interpreter-check = Vérifier la syntaxe
+menu-interpreter-record = Enregistrer les actions
+menu-interpreter-live = Mode direct
+menu-interpreter-english = Mots-clés anglais
+menu-interpreter-french = Mots-clés français
+menu-interpreter-spanish = Mots-clés espagnols
+menu-interpreter-faster = Plus vite
+menu-interpreter-slower = Plus lent
+
+menu-examples = Exemples
+menu-examples-gallery = Galerie d'exemples...
+
+menu-help = Aide
+menu-help-reference = Référence du langage
+
+## Fenêtres
+
+window-untitled = Sans titre
+gallery-title = Galerie d'exemples
+gallery-load = Charger
+help-title = Référence du langage
+log-title = Commandes de dessin
+log-pause = Pause
+
+## Barre d'état et console
+
+canvas-description = zone de dessin, tortue en { $x },{ $y } cap { $heading }
+status-bar = tortue : { $x },{ $y }   cap : { $heading }   commandes : { $commands }   coupées : { $clipped }   vitesse :
+console-stats = { $commands } commandes, { $pixels } pixels dessinés, { $clipped } lignes coupées
+console-paused = en pause
+console-reading = en attente d'une réponse ; tapez une ligne ci-dessous et appuyez sur Entrée
+console-prefs-saved = préférences enregistrées ; les tailles du canevas et du dessin s'appliquent au prochain lancement
+console-exported = exporté : { $path }
+console-imported = importé : { $path }
+console-exporting-animation = export de l'animation...
+console-syntax-ok = aucune erreur de syntaxe
+console-run = Exécution n° { $number }
+console-backup-found = le travail non enregistré de la dernière fois a été conservé ; choisissez Fichier > Restaurer la sauvegarde pour le récupérer
+error-backup = erreur : sauvegarde : { $error }
+error-copy = erreur : copie : { $error }
+error-export = erreur : export : { $error }
+error-file = erreur : { $path } : { $error }
+error-format-comments = erreur : mise en forme : retirez d'abord les commentaires
+error-import = erreur : import : { $error }
+error-open = erreur : ouverture : { $error }
+error-preferences = erreur : préférences : { $error }
+error-reload = erreur : rechargement : { $error }
+error-save = erreur : enregistrement : { $error }
+error-watch = erreur : surveillance : { $error }
+
+## Préférences
+
+prefs-title = Préférences
+prefs-pen-color = Couleur du crayon
+prefs-background-color = Couleur du fond
+prefs-speed = Vitesse par défaut
+prefs-width = Largeur du canevas
+prefs-height = Hauteur du canevas
+prefs-surface-width = Largeur du dessin
+prefs-surface-height = Hauteur du dessin
+prefs-time-limit = Limite de temps (s)
+prefs-autosave = Sauvegarde toutes les (s)
+prefs-export-step = Pas d'animation
+prefs-export-delay = Délai entre images (ms)
+prefs-library-path = Chemin des bibliothèques
+prefs-antialias = Lisser quand on dézoome
+prefs-optimize = Optimiser les programmes avant de les exécuter
+prefs-command-flash = Faire clignoter la barre d'état à chaque commande en vitesse lente
+prefs-language = Langue
+prefs-save = Enregistrer
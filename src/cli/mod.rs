@@ -0,0 +1,91 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+
+pub mod test;
+
+/// What the command line asked for.
+#[derive(Debug, PartialEq)]
+pub enum Mode {
+    Window,
+    /// Open the window with a program loaded from this file.
+    Open(PathBuf),
+    /// Open the window and run the program piped to stdin.
+    Stdin,
+    Test(PathBuf),
+    /// Open the window with a program, and run it again whenever it changes.
+    Watch(PathBuf),
+}
+
+pub fn mode() -> Result<Mode, String> {
+    parse(std::env::args().skip(1))
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Mode, String> {
+    let mode = match args.next().as_deref() {
+        None => Mode::Window,
+        Some("-") => Mode::Stdin,
+        Some("--test") => match args.next() {
+            Some(dir) => Mode::Test(PathBuf::from(dir)),
+            None => return Err("--test needs a directory".to_string()),
+        },
+        Some("--watch") => match args.next() {
+            Some(path) => Mode::Watch(PathBuf::from(path)),
+            None => return Err("--watch needs a file".to_string()),
+        },
+        Some(arg) if arg.starts_with('-') => {
+            return Err(format!("unrecognized argument \"{}\"", arg))
+        }
+        Some(path) => Mode::Open(PathBuf::from(path)),
+    };
+
+    match args.next() {
+        None => Ok(mode),
+        Some(arg) => Err(format!("unexpected argument \"{}\"", arg)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &[&str]) -> Result<Mode, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn it_parses_arguments() {
+        assert_eq!(parse_str(&[]), Ok(Mode::Window));
+        assert_eq!(parse_str(&["-"]), Ok(Mode::Stdin));
+        assert_eq!(
+            parse_str(&["spiral.logo"]),
+            Ok(Mode::Open(PathBuf::from("spiral.logo")))
+        );
+        assert_eq!(
+            parse_str(&["--test", "tests"]),
+            Ok(Mode::Test(PathBuf::from("tests")))
+        );
+
+        assert_eq!(
+            parse_str(&["--watch", "spiral.logo"]),
+            Ok(Mode::Watch(PathBuf::from("spiral.logo")))
+        );
+
+        assert!(parse_str(&["--test"]).is_err());
+        assert!(parse_str(&["--watch"]).is_err());
+        assert!(parse_str(&["--bogus"]).is_err());
+        assert!(parse_str(&["a.logo", "b.logo"]).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::export::headless;
+use crate::model::prefs::Preferences;
+use crate::runtime::error::RuntimeResult;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Run every .logo file in `dir` headlessly, report pass or fail for each,
+/// and return the process exit code.
+pub fn run(dir: &Path) -> i32 {
+    let paths = match programs(dir) {
+        Ok(paths) => paths,
+        Err(err) => {
+            eprintln!("error: {}: {}", dir.display(), err);
+            return 2;
+        }
+    };
+
+    let mut failed = 0;
+    for path in paths.iter() {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let res = fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| check(&source).map_err(|err| err.to_string()));
+
+        match res {
+            Ok(()) => println!("test {} ... ok", name),
+            Err(err) => {
+                println!("test {} ... FAILED\n    {}", name, err);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n{} passed; {} failed", paths.len() - failed, failed);
+
+    if failed == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+/// Programs run with default preferences, so results don't depend on the
+/// user's settings.
+fn check(source: &str) -> RuntimeResult {
+    headless::run(source, &Preferences::default())?;
+    Ok(())
+}
+
+fn programs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == "logo") {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_checks_assertions() {
+        assert!(check("let a = (2 * 3) assert (a = 6)").is_ok());
+        assert!(check("assert (1 < 2) assert (3 > 2)").is_ok());
+        assert!(check("assert (1 > 2)").is_err());
+        assert!(check("assert ([1 2] = [1 2])").is_ok());
+    }
+}
@@ -12,9 +12,6 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::ptr;
-use std::sync::atomic;
-
 #[macro_export]
 macro_rules! hashmap {
     ($( $key: expr => $val: expr ),*) => {{
@@ -32,11 +29,3 @@ macro_rules! hashset {
          map
     }}
 }
-
-#[inline]
-pub fn zero<T>(input: &mut [T]) {
-    atomic::fence(atomic::Ordering::SeqCst);
-    unsafe {
-        ptr::write_bytes(input.as_mut_ptr(), 0, input.len());
-    }
-}
@@ -12,8 +12,67 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::model::prefs::Language;
+use crate::model::prefs::Theme;
+use crate::model::prefs::TurtleShape;
+use crate::model::server::ServerRequest;
+use druid::FileInfo;
+use druid::Point;
 use druid::Selector;
+use std::sync::Arc;
 
+/// Drop what a stopped run left waiting to be drawn.
+pub const CANVAS_DISCARD: Selector = Selector::new("canvas-discard");
+pub const CONSOLE_CLEAR: Selector = Selector::new("console-clear");
+pub const COPY_IMAGE: Selector = Selector::new("copy-image");
+/// Focus the input and put the caret at a byte offset into it.
+pub const EDITOR_CARET: Selector<usize> = Selector::new("editor-caret");
 pub const EXAMPLES: Selector<&'static str> = Selector::new("examples");
+pub const EXPORT_COMMANDS: Selector<FileInfo> = Selector::new("export-commands");
+pub const EXPORT_ANIMATION: Selector<FileInfo> = Selector::new("export-animation");
+pub const EXPORT_IMAGE: Selector<FileInfo> = Selector::new("export-image");
+pub const EXPORT_IMAGE_CROPPED: Selector<FileInfo> = Selector::new("export-image-cropped");
+/// Back up the input if it has changed, or drop the backup once it's saved.
+pub const FILE_BACKUP: Selector = Selector::new("file-backup");
+pub const FILE_OPEN: Selector<FileInfo> = Selector::new("file-open");
+pub const FILE_RELOAD: Selector = Selector::new("file-reload");
+pub const FILE_RESTORE: Selector = Selector::new("file-restore");
+pub const FILE_SAVE: Selector = Selector::new("file-save");
+pub const FILE_SAVE_AS: Selector<FileInfo> = Selector::new("file-save-as");
+pub const FILE_WATCH: Selector = Selector::new("file-watch");
+pub const GALLERY: Selector = Selector::new("gallery");
+pub const GALLERY_THUMBNAIL: Selector<(&'static str, Arc<Vec<u8>>)> =
+    Selector::new("gallery-thumbnail");
+pub const IMPORT_COMMANDS: Selector<FileInfo> = Selector::new("import-commands");
+pub const HELP_REFERENCE: Selector = Selector::new("help-reference");
+pub const INTERPRETER_ANSWER: Selector = Selector::new("interpreter-answer");
+pub const INTERPRETER_CHECK: Selector = Selector::new("interpreter-check");
 pub const INTERPRETER_GO: Selector = Selector::new("interpreter-go");
+pub const INTERPRETER_LANGUAGE: Selector<Language> = Selector::new("interpreter-language");
+/// Run the program again whenever the input changes.
+pub const INTERPRETER_LIVE: Selector = Selector::new("interpreter-live");
+pub const INTERPRETER_FORMAT: Selector = Selector::new("interpreter-format");
+pub const INTERPRETER_READ: Selector = Selector::new("interpreter-read");
 pub const INTERPRETER_SPEED: Selector<bool> = Selector::new("interpreter-speed");
+pub const INTERPRETER_STEP: Selector = Selector::new("interpreter-step");
+pub const INTERPRETER_STOP: Selector = Selector::new("interpreter-stop");
+pub const PREFERENCES: Selector = Selector::new("preferences");
+pub const PREFERENCES_SAVE: Selector = Selector::new("preferences-save");
+pub const SERVER_REQUEST: Selector<Arc<ServerRequest>> = Selector::new("server-request");
+/// Show a line and column of the input, counted from one.
+pub const SHOW_SOURCE: Selector<(usize, usize)> = Selector::new("show-source");
+pub const TURTLE_MOVE: Selector<(Point, bool)> = Selector::new("turtle-move");
+pub const TURTLE_RECORD: Selector = Selector::new("turtle-record");
+pub const VIEW_BACKGROUND_CLEAR: Selector = Selector::new("view-background-clear");
+pub const VIEW_BACKGROUND_IMAGE: Selector<FileInfo> = Selector::new("view-background-image");
+pub const VIEW_COMMAND_LOG: Selector = Selector::new("view-command-log");
+pub const VIEW_GRID: Selector = Selector::new("view-grid");
+/// Remember where the split between the input and the canvas was left.
+pub const VIEW_INPUT_WIDTH: Selector<f64> = Selector::new("view-input-width");
+pub const VIEW_PRESENTATION: Selector = Selector::new("view-presentation");
+pub const VIEW_RESET: Selector = Selector::new("view-reset");
+pub const VIEW_THEME: Selector<Theme> = Selector::new("view-theme");
+pub const VIEW_TRACE: Selector = Selector::new("view-trace");
+pub const VIEW_TURTLE_IMAGE: Selector<FileInfo> = Selector::new("view-turtle-image");
+pub const VIEW_TURTLE_SHAPE: Selector<TurtleShape> = Selector::new("view-turtle-shape");
+pub const WAKE: Selector = Selector::new("wake");
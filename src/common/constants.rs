@@ -12,13 +12,59 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use druid::Point;
 use druid::Size;
+use std::time::Duration;
 
 pub const DIMS: Size = Size::new(800.0, 600.0);
 
-pub const ORIGIN: Point = Point::new(DIMS.width / 2.0, DIMS.height / 2.0);
+pub const MIN_DIMS: Size = Size::new(320.0, 240.0);
 
-pub const MIN_SPEED: u32 = 32;
+pub const MAX_DIMS: Size = Size::new(4096.0, 4096.0);
+
+/// How wide the input pane starts out, before the split is dragged.
+pub const INPUT_WIDTH: f64 = 300.0;
+
+pub const MIN_INPUT_WIDTH: f64 = 160.0;
+
+pub const MAX_INPUT_WIDTH: f64 = 2000.0;
+
+pub const COMMAND_LOG_CAPACITY: usize = 500;
+
+/// How many lines the console keeps before dropping the oldest.
+pub const CONSOLE_CAPACITY: usize = 1000;
+
+pub const THUMBNAIL_DIMS: Size = Size::new(160.0, 120.0);
+
+/// Blank canvas pixels left around a drawing when an export is cropped to it.
+pub const CROP_MARGIN: u32 = 16;
+
+/// How big the turtle is drawn, in canvas pixels, until `setsize` changes it.
+pub const TURTLE_SIZE: f64 = 16.0;
+
+pub const MAX_TURTLE_SIZE: f64 = 256.0;
+
+/// The most copies `symmetry` will draw of each line.
+pub const MAX_SYMMETRY: u32 = 36;
+
+/// How deep procedures may call each other before the program is stopped,
+/// well short of running out of stack.
+pub const MAX_CALL_DEPTH: usize = 200;
+
+pub const MIN_SPEED: u32 = 1;
 
 pub const MAX_SPEED: u32 = 32;
+
+/// How long to pause between batches of render commands, so that drawing can
+/// be watched.
+pub const RENDER_DELAY: Duration = Duration::from_millis(30);
+
+/// How many seconds a program may run before it's stopped. Zero means there's
+/// no limit.
+pub const TIME_LIMIT: u32 = 30;
+
+pub const MAX_TIME_LIMIT: u32 = 3600;
+
+/// How many seconds go between backups of unsaved input.
+pub const AUTOSAVE: u32 = 30;
+
+pub const MAX_AUTOSAVE: u32 = 600;
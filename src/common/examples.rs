@@ -0,0 +1,46 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// A bundled example program.
+pub struct Example {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub source: &'static str,
+}
+
+macro_rules! example {
+    ($id:literal, $name:literal) => {
+        Example {
+            id: $id,
+            name: $name,
+            source: include_str!(concat!("../assets/", $id, ".logo")),
+        }
+    };
+}
+
+pub const EXAMPLES: &[Example] = &[
+    example!("color-ball", "Color Ball"),
+    example!("color-star", "Color Star"),
+    example!("fan-flower", "Fan Flower"),
+    example!("fill", "Fill"),
+    example!("for-loop", "For Loop"),
+    example!("spin-wheel", "Spin Wheel"),
+    example!("spiral", "Spiral"),
+    example!("squares", "Squares"),
+    example!("square-flower", "Square Flower"),
+];
+
+pub fn find(id: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.id == id)
+}
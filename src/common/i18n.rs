@@ -0,0 +1,119 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::model::prefs::Language;
+use std::fmt;
+
+const ENGLISH: &str = include_str!("../assets/i18n/en.ftl");
+const FRENCH: &str = include_str!("../assets/i18n/fr.ftl");
+const SPANISH: &str = include_str!("../assets/i18n/es.ftl");
+
+fn source(language: Language) -> &'static str {
+    match language {
+        Language::English => ENGLISH,
+        Language::French => FRENCH,
+        Language::Spanish => SPANISH,
+    }
+}
+
+/// The messages in a translation file. Only the simple part of Fluent is
+/// understood: one `key = value` per line, `#` comments, and `{ $name }`
+/// placeables.
+fn messages(source: &'static str) -> impl Iterator<Item = (&'static str, &'static str)> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, val)| (key.trim(), val.trim()))
+}
+
+fn find(language: Language, key: &str) -> Option<&'static str> {
+    messages(source(language)).find_map(|(k, val)| if k == key { Some(val) } else { None })
+}
+
+/// The interface text for `key`. Falls back to English when a translation is
+/// missing, and to the key itself if English doesn't have it either.
+pub fn text(language: Language, key: &str) -> String {
+    text_with(language, key, &[])
+}
+
+/// Like `text`, with each `{ $name }` filled in from `args`.
+pub fn text_with(language: Language, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let message = match find(language, key).or_else(|| find(Language::English, key)) {
+        Some(message) => message,
+        None => return key.to_string(),
+    };
+
+    let mut out = String::new();
+    let mut rest = message;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        out.push_str(&rest[..start]);
+        let name = rest[start + 1..end].trim().trim_start_matches('$');
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, val)) => out.push_str(&val.to_string()),
+            None => out.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_translates_every_english_message() {
+        for language in [Language::French, Language::Spanish].iter() {
+            for (key, _) in messages(ENGLISH) {
+                assert!(find(*language, key).is_some(), "{:?} {}", language, key);
+            }
+            for (key, _) in messages(source(*language)) {
+                assert!(
+                    find(Language::English, key).is_some(),
+                    "{:?} {}",
+                    language,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_falls_back() {
+        assert_eq!(text(Language::Spanish, "menu-file"), "Archivo");
+        assert_eq!(text(Language::English, "menu-file"), "File");
+        assert_eq!(text(Language::French, "no-such-key"), "no-such-key");
+    }
+
+    #[test]
+    fn it_fills_in_placeables() {
+        assert_eq!(
+            text_with(Language::English, "error-open", &[("error", &"not found")]),
+            "error: open: not found"
+        );
+        assert_eq!(
+            text_with(Language::English, "status-bar", &[("commands", &12)]),
+            "turtle: { $x },{ $y }   heading: { $heading }   commands: 12   clipped: { $clipped }   speed:"
+        );
+    }
+}
@@ -13,5 +13,12 @@
 // limitations under the License.
 
 pub mod bits;
+pub mod clock;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod commands;
 pub mod constants;
+pub mod examples;
+pub mod i18n;
+pub mod palette;
+pub mod paths;
+pub mod profile;
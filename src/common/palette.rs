@@ -0,0 +1,55 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use druid::Color;
+use std::collections::HashMap;
+
+pub type Palette = HashMap<u8, Color>;
+
+pub const PALETTE_SIZE: u8 = 16;
+
+/// What programs can call the palette colors, as in `setpc "orange`.
+pub const COLOR_NAMES: [&str; PALETTE_SIZE as usize] = [
+    "black", "blue", "lime", "cyan", "red", "magenta", "yellow", "white", "brown", "tan", "green",
+    "aqua", "salmon", "purple", "orange", "gray",
+];
+
+/// The palette index for a color name.
+pub fn color_index(name: &str) -> Option<u8> {
+    COLOR_NAMES
+        .iter()
+        .position(|item| *item == name)
+        .map(|idx| idx as u8)
+}
+
+pub fn palette() -> Palette {
+    crate::hashmap![
+        0 => Color::BLACK,
+        1 => Color::BLUE,
+        2 => Color::rgb8(0,255,0),        // lime
+        3 => Color::AQUA,                 // cyan
+        4 => Color::RED,
+        5 => Color::FUCHSIA,              // magenta
+        6 => Color::YELLOW,
+        7 => Color::WHITE,
+        8 => Color::rgb8(165, 42, 42),    // brown
+        9 => Color::rgb8(210, 180, 140),  // tan
+        10 => Color::GREEN,
+        11 => Color::rgb8(127, 255, 212), // aqua
+        12 => Color::rgb8(250, 128, 114), // salmon
+        13 => Color::rgb8(128, 0, 128),   // purple
+        14 => Color::rgb8(255, 165, 0),   // orange
+        15 => Color::rgb8(128, 128, 128)  // gray
+    ]
+}
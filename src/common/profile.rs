@@ -0,0 +1,171 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Where the time goes in a run, for finding what makes big programs slow.
+//! Timings are only taken when built with the `profiling` feature; without
+//! it, `time` just calls through. Desktop only, since the web build has no
+//! clock of its own.
+
+use std::fmt;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The parts of a run that get timed.
+#[derive(Clone, Copy, Debug)]
+pub enum Phase {
+    Lex,
+    Parse,
+    Optimize,
+    Interpret,
+    /// Handing render commands to the canvas.
+    Send,
+    /// Drawing render commands into the pixel buffer.
+    Rasterize,
+    /// Copying a new frame of the pixel buffer into an image to paint.
+    Upload,
+}
+
+const PHASES: usize = 7;
+
+/// Shared between the interpreter's thread and the canvas, and reset at the
+/// start of each run.
+static NANOS: [AtomicU64; PHASES] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+static COUNTS: [AtomicU64; PHASES] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+
+pub fn is_enabled() -> bool {
+    cfg!(feature = "profiling")
+}
+
+pub fn reset() {
+    for (nanos, count) in NANOS.iter().zip(COUNTS.iter()) {
+        nanos.store(0, Ordering::Relaxed);
+        count.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Run `f`, adding how long it took to `phase`.
+pub fn time<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    if !is_enabled() {
+        return f();
+    }
+
+    let start = Instant::now();
+    let out = f();
+    let nanos = start.elapsed().as_nanos() as u64;
+    NANOS[phase as usize].fetch_add(nanos, Ordering::Relaxed);
+    COUNTS[phase as usize].fetch_add(1, Ordering::Relaxed);
+    out
+}
+
+/// The timings so far.
+pub fn snapshot() -> Profile {
+    let mut profile = Profile::default();
+    for (time, nanos) in profile.time.iter_mut().zip(NANOS.iter()) {
+        *time = Duration::from_nanos(nanos.load(Ordering::Relaxed));
+    }
+    for (count, counted) in profile.count.iter_mut().zip(COUNTS.iter()) {
+        *count = counted.load(Ordering::Relaxed);
+    }
+    profile
+}
+
+/// How long each phase took, and how many times it ran.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Profile {
+    time: [Duration; PHASES],
+    count: [u64; PHASES],
+}
+
+impl Profile {
+    fn ms(&self, phase: Phase) -> f64 {
+        self.time[phase as usize].as_secs_f64() * 1000.0
+    }
+
+    fn count(&self, phase: Phase) -> u64 {
+        self.count[phase as usize]
+    }
+
+    /// Render commands sent for every second the interpreter ran.
+    fn throughput(&self) -> f64 {
+        let secs = self.time[Phase::Interpret as usize].as_secs_f64();
+        if secs > 0.0 {
+            self.count(Phase::Send) as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "lex {:.1}ms  parse {:.1}ms  optimize {:.1}ms  run {:.1}ms  \
+             send {:.1}ms ({} at {:.0}/s)  draw {:.1}ms ({})  upload {:.1}ms ({})",
+            self.ms(Phase::Lex),
+            self.ms(Phase::Parse),
+            self.ms(Phase::Optimize),
+            self.ms(Phase::Interpret),
+            self.ms(Phase::Send),
+            self.count(Phase::Send),
+            self.throughput(),
+            self.ms(Phase::Rasterize),
+            self.count(Phase::Rasterize),
+            self.ms(Phase::Upload),
+            self.count(Phase::Upload),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_breaks_down_a_run() {
+        let mut profile = Profile::default();
+        profile.time[Phase::Lex as usize] = Duration::from_micros(400);
+        profile.time[Phase::Interpret as usize] = Duration::from_secs(2);
+        profile.time[Phase::Send as usize] = Duration::from_millis(50);
+        profile.count[Phase::Send as usize] = 1000;
+        profile.time[Phase::Rasterize as usize] = Duration::from_millis(120);
+        profile.count[Phase::Rasterize as usize] = 1000;
+        profile.time[Phase::Upload as usize] = Duration::from_millis(30);
+        profile.count[Phase::Upload as usize] = 60;
+
+        assert_eq!(
+            profile.to_string(),
+            "lex 0.4ms  parse 0.0ms  optimize 0.0ms  run 2000.0ms  \
+             send 50.0ms (1000 at 500/s)  draw 120.0ms (1000)  upload 30.0ms (60)"
+        );
+    }
+}
@@ -29,23 +29,247 @@ impl druid::AppDelegate<AppState> for Delegate {
         cmd: &druid::Command,
         data: &mut AppState,
         _env: &Env,
+    ) -> Handled {
+        let handled = self.handle(ctx, cmd, data);
+
+        // Whatever it did may have sent the turtle somewhere or written to
+        // the console, so the canvas and console should look again.
+        if handled.is_handled() {
+            ctx.submit_command(commands::WAKE);
+        }
+
+        handled
+    }
+}
+
+impl Delegate {
+    fn handle(
+        &mut self,
+        ctx: &mut DelegateCtx,
+        cmd: &druid::Command,
+        data: &mut AppState,
     ) -> Handled {
         match cmd {
+            _ if cmd.is(commands::INTERPRETER_ANSWER) => {
+                super::interpreter::answer(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::INTERPRETER_CHECK) => {
+                super::interpreter::check(ctx, cmd, data);
+                Handled::Yes
+            }
+
             _ if cmd.is(commands::INTERPRETER_GO) => {
                 super::interpreter::go(ctx, cmd, data);
                 Handled::Yes
             }
 
+            _ if cmd.is(commands::INTERPRETER_FORMAT) => {
+                super::interpreter::format(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::INTERPRETER_LANGUAGE) => {
+                super::prefs::language(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::INTERPRETER_LIVE) => {
+                super::interpreter::live(ctx, cmd, data);
+                Handled::Yes
+            }
+
             _ if cmd.is(commands::INTERPRETER_SPEED) => {
                 super::interpreter::speed(ctx, cmd, data);
                 Handled::Yes
             }
 
+            _ if cmd.is(commands::INTERPRETER_STEP) => {
+                super::interpreter::step(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::INTERPRETER_STOP) => {
+                super::interpreter::stop(ctx, cmd, data);
+                Handled::Yes
+            }
+
             _ if cmd.is(commands::EXAMPLES) => {
                 super::examples::show(ctx, cmd, data);
                 Handled::Yes
             }
 
+            _ if cmd.is(commands::CONSOLE_CLEAR) => {
+                super::view::clear_console(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::COPY_IMAGE) => {
+                super::export::copy_image(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::EXPORT_ANIMATION) => {
+                super::export::animation(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::EXPORT_COMMANDS) => {
+                super::export::commands(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::EXPORT_IMAGE) => {
+                super::export::image(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::EXPORT_IMAGE_CROPPED) => {
+                super::export::cropped_image(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::IMPORT_COMMANDS) => {
+                super::export::import_commands(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::FILE_BACKUP) => {
+                super::file::backup(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::FILE_OPEN) => {
+                super::file::open(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::FILE_RELOAD) => {
+                super::file::reload(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::FILE_RESTORE) => {
+                super::file::restore(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::FILE_SAVE) => {
+                super::file::save(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::FILE_SAVE_AS) => {
+                super::file::save_as(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::FILE_WATCH) => {
+                super::file::watch(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::GALLERY) => {
+                super::examples::gallery(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::GALLERY_THUMBNAIL) => {
+                super::examples::thumbnail(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::HELP_REFERENCE) => {
+                super::help::reference(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::PREFERENCES) => {
+                super::prefs::show(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::PREFERENCES_SAVE) => {
+                super::prefs::save(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::SERVER_REQUEST) => {
+                super::server::request(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::TURTLE_MOVE) => {
+                super::turtle::move_to(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::TURTLE_RECORD) => {
+                super::turtle::record(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::SHOW_SOURCE) => {
+                super::view::show_source(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_BACKGROUND_CLEAR) => {
+                super::view::clear_background(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_BACKGROUND_IMAGE) => {
+                super::view::background_image(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_COMMAND_LOG) => {
+                super::view::command_log(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_GRID) => {
+                super::view::grid(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_INPUT_WIDTH) => {
+                super::prefs::input_width(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_PRESENTATION) => {
+                super::view::presentation(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_RESET) => {
+                super::view::reset(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_THEME) => {
+                super::prefs::theme(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_TRACE) => {
+                super::view::trace(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_TURTLE_IMAGE) => {
+                super::prefs::turtle_image(ctx, cmd, data);
+                Handled::Yes
+            }
+
+            _ if cmd.is(commands::VIEW_TURTLE_SHAPE) => {
+                super::prefs::turtle_shape(ctx, cmd, data);
+                Handled::Yes
+            }
+
             _ => Handled::No,
         }
     }
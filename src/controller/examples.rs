@@ -13,25 +13,71 @@
 // limitations under the License.
 
 use crate::common::commands;
+use crate::common::constants::THUMBNAIL_DIMS;
+use crate::common::examples;
+use crate::export::thumbnail;
 use crate::model::app::AppState;
+use crate::view::gallery;
 use druid::DelegateCtx;
+use druid::Target;
 use std::sync::Arc;
+use std::thread;
 
 pub fn show(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
-    let example = match *cmd.get_unchecked(commands::EXAMPLES) {
-        "color-ball" => include_str!("../assets/color-ball.logo"),
-        "color-star" => include_str!("../assets/color-star.logo"),
-        "fan-flower" => include_str!("../assets/fan-flower.logo"),
-        "fill" => include_str!("../assets/fill.logo"),
-        "for-loop" => include_str!("../assets/for-loop.logo"),
-        "spin-wheel" => include_str!("../assets/spin-wheel.logo"),
-        "spiral" => include_str!("../assets/spiral.logo"),
-        "squares" => include_str!("../assets/squares.logo"),
-        "square-flower" => include_str!("../assets/square-flower.logo"),
-        _ => "",
+    let example = match examples::find(cmd.get_unchecked(commands::EXAMPLES)) {
+        Some(example) => example.source,
+        None => "",
     };
 
     let input = Arc::make_mut(&mut data.input);
     input.clear();
     input.push_str(example);
+
+    // An example is a new program, not a change to the open file.
+    data.document = None;
+    data.watch = None;
+}
+
+pub fn gallery(ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    ctx.new_window(gallery::window());
+
+    if !data.thumbnails.is_empty() {
+        return;
+    }
+
+    // Render the thumbnails once, in the background; each one shows up in the
+    // gallery as soon as it's ready.
+    let sink = ctx.get_external_handle();
+    let prefs = data.prefs.clone();
+    let width = THUMBNAIL_DIMS.width as u32;
+    let height = THUMBNAIL_DIMS.height as u32;
+
+    let spawned = thread::Builder::new()
+        .name("thumbnails".to_string())
+        .spawn(move || {
+            for example in examples::EXAMPLES {
+                match thumbnail::render(example.source, &prefs, width, height) {
+                    Ok(rgba) => {
+                        let payload = (example.id, Arc::new(rgba));
+                        let selector = commands::GALLERY_THUMBNAIL;
+                        if sink
+                            .submit_command(selector, payload, Target::Auto)
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                    Err(err) => eprintln!("thumbnail {}: {}", example.id, err),
+                }
+            }
+        });
+
+    if let Err(err) = spawned {
+        eprintln!("thumbnails: {}", err);
+    }
+}
+
+pub fn thumbnail(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let (id, rgba) = cmd.get_unchecked(commands::GALLERY_THUMBNAIL);
+    Arc::make_mut(&mut data.thumbnails).insert(*id, rgba.clone());
 }
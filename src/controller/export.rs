@@ -0,0 +1,155 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::report;
+use super::write_console;
+use crate::common::commands;
+use crate::common::constants::CROP_MARGIN;
+use crate::common::i18n;
+use crate::export::animation;
+use crate::export::image;
+use crate::export::json;
+use crate::graphics;
+use crate::model::app::AppState;
+use crate::model::console::Severity;
+use crate::model::pixbuf::PixBuf;
+use druid::Application;
+use druid::ClipboardFormat;
+use druid::DelegateCtx;
+use druid::Target;
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+#[cfg(target_os = "macos")]
+const PNG_FORMAT: &str = "public.png";
+
+#[cfg(target_os = "windows")]
+const PNG_FORMAT: &str = "PNG";
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PNG_FORMAT: &str = "image/png";
+
+pub fn copy_image(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    match image::encode_png(&data.layers.drawing, &data.layers.background.color) {
+        Ok(png) => {
+            let format = ClipboardFormat::new(PNG_FORMAT, png);
+            Application::global().clipboard().put_formats(&[format]);
+        }
+        Err(err) => report(data, "error-copy", &[("error", &err)]),
+    }
+}
+
+pub fn image(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let path = cmd.get_unchecked(commands::EXPORT_IMAGE).path();
+    let pixels = data.layers.drawing.clone();
+    write_png(data, path, &pixels);
+}
+
+/// Leave out the empty canvas around the drawing.
+pub fn cropped_image(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let path = cmd.get_unchecked(commands::EXPORT_IMAGE_CROPPED).path();
+    let pixels = image::crop_to_drawing(&data.layers.drawing, CROP_MARGIN);
+    write_png(data, path, &pixels);
+}
+
+fn write_png(data: &mut AppState, path: &Path, pixels: &PixBuf) {
+    match image::encode_png(pixels, &data.layers.background.color)
+        .and_then(|png| fs::write(path, png))
+    {
+        Ok(()) => report(data, "console-exported", &[("path", &path.display())]),
+        Err(err) => report(data, "error-export", &[("error", &err)]),
+    }
+}
+
+pub fn animation(ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let path = cmd
+        .get_unchecked(commands::EXPORT_ANIMATION)
+        .path()
+        .to_owned();
+    let commands = data.log.commands().to_vec();
+    let size = data.layers.drawing.size();
+    let background = data.layers.background.color.clone();
+    let step = data.prefs.export_step as usize;
+    let delay = data.prefs.export_delay;
+    let console = data.console.clone();
+    let language = data.prefs.ui_language;
+    let sink = ctx.get_external_handle();
+
+    report(data, "console-exporting-animation", &[]);
+
+    let spawned = thread::Builder::new()
+        .name("export".to_string())
+        .spawn(move || {
+            let (severity, string) =
+                match animation::export(&path, &commands, size, &background, step, delay) {
+                    Ok(()) => (
+                        Severity::Info,
+                        i18n::text_with(language, "console-exported", &[("path", &path.display())]),
+                    ),
+                    Err(err) => (
+                        Severity::Error,
+                        i18n::text_with(language, "error-export", &[("error", &err)]),
+                    ),
+                };
+
+            write_console(&console, severity, &string);
+            let _ = sink.submit_command(commands::WAKE, (), Target::Auto);
+        });
+
+    if let Err(err) = spawned {
+        report(data, "error-export", &[("error", &err)]);
+    }
+}
+
+pub fn commands(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let path = cmd.get_unchecked(commands::EXPORT_COMMANDS).path();
+    match json::encode(data.log.commands()).and_then(|json| fs::write(path, json)) {
+        Ok(()) => report(data, "console-exported", &[("path", &path.display())]),
+        Err(err) => report(data, "error-export", &[("error", &err)]),
+    }
+}
+
+/// Replace the drawing with commands exported earlier, as if they had just
+/// been run.
+pub fn import_commands(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    if data.session.is_running() {
+        return;
+    }
+
+    let path = cmd.get_unchecked(commands::IMPORT_COMMANDS).path();
+    let imported = match fs::read_to_string(path).and_then(|json| json::decode(&json)) {
+        Ok(imported) => imported,
+        Err(err) => {
+            report(data, "error-import", &[("error", &err)]);
+            return;
+        }
+    };
+
+    data.clear();
+    for cmd in imported {
+        data.track(&cmd);
+        let rendered = graphics::render(&mut data.layers.drawing, &mut data.pos, &cmd);
+        data.stats.add(&cmd, rendered);
+        if data.command_log_paused {
+            data.recent.skip();
+        } else {
+            data.recent.push(cmd.clone());
+        }
+        data.log.push(cmd);
+    }
+    data.layers.drawing.present();
+
+    report(data, "console-imported", &[("path", &path.display())]);
+}
@@ -0,0 +1,143 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::report;
+use crate::common::commands;
+use crate::model::app::AppState;
+use crate::model::backup::Backup;
+use crate::model::watch::Watch;
+use crate::view::menu;
+use druid::DelegateCtx;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+pub fn open(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let path = cmd.get_unchecked(commands::FILE_OPEN).path();
+
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            data.input = Arc::new(text);
+            data.mark_saved(path);
+            data.watch = None;
+            discard_backup(data);
+        }
+        Err(err) => report(data, "error-open", &[("error", &err)]),
+    }
+}
+
+/// Keep a copy of unsaved input in case the app goes away before it's saved.
+pub fn backup(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    if data.prefs.autosave == 0 {
+        return;
+    }
+    if !data.is_dirty() {
+        discard_backup(data);
+        return;
+    }
+    if Arc::ptr_eq(&data.input, &data.backed_up) || data.input == data.backed_up {
+        return;
+    }
+
+    if let Some(backup) = Backup::new() {
+        match backup.write(&data.input) {
+            Ok(()) => data.backed_up = data.input.clone(),
+            Err(err) => report(data, "error-backup", &[("error", &err)]),
+        }
+    }
+}
+
+/// Bring back the input left behind last time. It stays unsaved, since the
+/// file it came from, if any, is unknown.
+pub fn restore(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    if let Some(text) = data.recovered.take() {
+        data.input = text;
+        data.document = None;
+        data.watch = None;
+    }
+}
+
+/// The input has been saved, so a backup of it isn't needed any more.
+fn discard_backup(data: &mut AppState) {
+    if data.backed_up.is_empty() {
+        return;
+    }
+
+    if let Some(backup) = Backup::new() {
+        match backup.remove() {
+            Ok(()) => data.backed_up = Arc::new(String::new()),
+            Err(err) => report(data, "error-backup", &[("error", &err)]),
+        }
+    }
+}
+
+/// Pick up a change to the watched file, and run it again.
+pub fn reload(ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let path = match &data.watch {
+        Some(watch) => watch.path().to_owned(),
+        None => return,
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            data.input = Arc::new(text);
+            data.mark_saved(&path);
+            super::interpreter::go(ctx, cmd, data);
+        }
+        Err(err) => report(data, "error-reload", &[("error", &err)]),
+    }
+}
+
+/// Start or stop watching the open file.
+pub fn watch(ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    if data.watch.take().is_some() {
+        return;
+    }
+
+    if let Some(path) = data.document.clone() {
+        let sink = ctx.get_external_handle();
+        match Watch::new(&path, sink, data.session.clone()) {
+            Ok(watch) => data.watch = Some(Arc::new(watch)),
+            Err(err) => report(data, "error-watch", &[("error", &err)]),
+        }
+    }
+}
+
+/// Save to the file that's open, or ask where to put it if there isn't one.
+pub fn save(ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    match data.document.clone() {
+        Some(path) => write(data, &path),
+        None => {
+            let options = menu::save_program_options();
+            let cmd = druid::commands::SHOW_SAVE_PANEL.with(options);
+            ctx.submit_command(cmd.to(data.window_id()));
+        }
+    }
+}
+
+pub fn save_as(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let path = cmd.get_unchecked(commands::FILE_SAVE_AS).path().to_owned();
+    write(data, &path);
+    data.watch = None;
+}
+
+fn write(data: &mut AppState, path: &Path) {
+    match fs::write(path, data.input.as_bytes()) {
+        Ok(()) => {
+            data.mark_saved(path);
+            discard_backup(data);
+        }
+        Err(err) => report(data, "error-save", &[("error", &err)]),
+    }
+}
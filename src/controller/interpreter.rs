@@ -12,73 +12,158 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::report;
+use super::write_console;
+use super::write_error;
 use crate::common::commands;
-use crate::common::constants::MAX_SPEED;
-use crate::common::constants::MIN_SPEED;
+use crate::common::i18n;
+use crate::common::profile;
 use crate::model::app::AppState;
+use crate::model::console::Severity;
+use crate::model::params;
+use crate::model::server::Reply;
+use crate::model::server::ServerRequest;
 use crate::runtime;
+use crate::runtime::control::Control;
+use crate::runtime::warning::Warnings;
 use druid::DelegateCtx;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::sync::Mutex;
 
-fn set_output(output: &Arc<Mutex<String>>, string: &str) {
-    let mut output_guard = output.lock().unwrap();
-    output_guard.clear();
-    output_guard.push_str(&string);
+/// Start `input`, held before the first statement if `stepping`. The result
+/// goes to `request` too, if another program asked for the run. Does nothing
+/// if a program is already running.
+pub fn start(
+    data: &mut AppState,
+    input: String,
+    stepping: bool,
+    request: Option<Arc<ServerRequest>>,
+) -> bool {
+    if data.session.is_running() {
+        return false;
+    }
+
+    data.clear();
+    profile::reset();
+
+    // Head everything the run writes, so runs don't blur together.
+    {
+        let mut console = data.console.lock().unwrap();
+        let number = console.next_run();
+        let header = i18n::text_with(
+            data.prefs.ui_language,
+            "console-run",
+            &[("number", &number)],
+        );
+        console.push(Severity::Run, &header);
+    }
+
+    let console = data.console.clone();
+    let render_tx = data.render_tx.clone();
+    let speed = data.speed.shared();
+    let symbols = data.symbols.clone();
+    let declared = data.declared.clone();
+    let params = params::values(&data.params);
+    let prefs = data.prefs.clone();
+
+    let job = move |control: Arc<Control>| {
+        control.set_params(params);
+        let sink = Warnings::default();
+        let reply = match runtime::entry(input.clone(), render_tx, speed, control, &prefs, &sink) {
+            Ok(out) => {
+                *symbols.lock().unwrap() = out.symbols;
+                *declared.lock().unwrap() = Some(out.params);
+                let string = format!("{}{}", out.text, out.value);
+                console.lock().unwrap().push_colored(&string, out.colors);
+                Reply::Ok(string)
+            }
+            Err(err) => {
+                write_error(&console, &err, &input);
+                Reply::Error(format!("{}", err))
+            }
+        };
+
+        for warning in sink.take() {
+            write_console(&console, Severity::Warning, &warning.to_string());
+        }
+
+        if let Some(request) = request {
+            request.reply(reply);
+        }
+    };
+
+    if stepping {
+        data.session.start_stepping(job)
+    } else {
+        data.session.start(job)
+    }
 }
 
-fn set_running(running: &Arc<AtomicBool>) -> bool {
-    match running.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed) {
-        Ok(false) => true, // Original value replaced.
-        _ => false,        // Anything else means it's still running.
+pub fn go(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    let input = data.input.to_string();
+    if !start(data, input, false, None) {
+        data.session.resume();
     }
 }
 
-fn clear_running(running: &Arc<AtomicBool>) {
-    running
-        .compare_exchange(true, false, Ordering::SeqCst, Ordering::Acquire)
-        .unwrap();
+/// Run one statement, starting the program held if it isn't running yet.
+pub fn step(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    let input = data.input.to_string();
+    if !start(data, input, true, None) {
+        data.session.step();
+    }
 }
 
-fn go_inner(data: &mut AppState) {
-    data.clear();
+pub fn stop(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.session.stop();
+}
 
-    let input = data.input.to_string();
-    let output = data.output.clone();
-    let render_tx = data.render_tx.clone();
-    let running = data.running.clone();
-    let speed = data.speed.clone();
+pub fn live(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.live = !data.live;
+}
 
-    data.thread_pool.execute(move || {
-        let string = match runtime::entry(input, render_tx, speed) {
-            Ok(val) => format!("{}", val),
-            Err(err) => format!("{}", err),
-        };
+pub fn format(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    // The printer only sees the parsed program, so comments would be lost.
+    if data.input.contains('#') {
+        report(data, "error-format-comments", &[]);
+        return;
+    }
 
-        set_output(&output, &string);
-        clear_running(&running);
-    });
+    match runtime::format(&data.input, &data.prefs) {
+        Ok(text) => data.input = Arc::new(text),
+        Err(err) => write_error(&data.console, &err, &data.input),
+    }
 }
 
-pub fn go(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
-    if set_running(&data.running) {
-        go_inner(data);
+/// Lex and parse the program without running it, and say what's wrong.
+pub fn check(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    let sink = Warnings::default();
+    let errors = runtime::check(&data.input, &data.prefs, &sink);
+    if errors.is_empty() {
+        report(data, "console-syntax-ok", &[]);
+    } else {
+        for err in errors {
+            write_error(&data.console, &err, &data.input);
+        }
+    }
+
+    for warning in sink.take() {
+        write_console(&data.console, Severity::Warning, &warning.to_string());
+    }
+}
+
+/// Give the line typed into the console to the program waiting for it.
+pub fn answer(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    if data.session.answer(&data.reply) {
+        data.reply = "".to_string().into();
     }
 }
 
 pub fn speed(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
     let faster = *cmd.get_unchecked(commands::INTERPRETER_SPEED);
 
-    data.speed
-        .fetch_update(Ordering::Acquire, Ordering::Acquire, |x| {
-            if faster && x < MAX_SPEED {
-                Some(x * 2)
-            } else if !faster && x > MIN_SPEED {
-                Some(x / 2)
-            } else {
-                Some(x)
-            }
-        });
+    if faster {
+        data.speed.faster();
+    } else {
+        data.speed.slower();
+    }
 }
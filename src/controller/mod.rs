@@ -12,6 +12,50 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::i18n;
+use crate::model::app::AppState;
+use crate::model::console::ConsoleLog;
+use crate::model::console::Severity;
+use crate::runtime::error::RuntimeError;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+
 pub mod delegate;
 mod examples;
+mod export;
+mod file;
+mod help;
 mod interpreter;
+mod prefs;
+mod server;
+mod turtle;
+mod view;
+
+fn write_console(console: &Arc<Mutex<ConsoleLog>>, severity: Severity, text: &str) {
+    console.lock().unwrap().push(severity, text);
+}
+
+/// Put an error in the console, pointing at where it is in `input`.
+fn write_error(console: &Arc<Mutex<ConsoleLog>>, err: &RuntimeError, input: &str) {
+    let source = err.location(input);
+    console
+        .lock()
+        .unwrap()
+        .push_at(Severity::Error, &err.to_string(), source);
+}
+
+/// Put the message `key` in the console, in the interface language. Keys for
+/// errors start with "error-".
+fn report(data: &AppState, key: &str, args: &[(&str, &dyn fmt::Display)]) {
+    let severity = if key.starts_with("error-") {
+        Severity::Error
+    } else {
+        Severity::Info
+    };
+    write_console(
+        &data.console,
+        severity,
+        &i18n::text_with(data.prefs.ui_language, key, args),
+    );
+}
@@ -0,0 +1,67 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::report;
+use crate::common::commands;
+use crate::model::app::AppState;
+use crate::model::prefs::TurtleShape;
+use crate::view::prefs;
+use druid::DelegateCtx;
+
+pub fn show(ctx: &mut DelegateCtx, _cmd: &druid::Command, _data: &mut AppState) {
+    ctx.new_window(prefs::window());
+}
+
+pub fn save(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    match data.prefs.save() {
+        Ok(()) => report(data, "console-prefs-saved", &[]),
+        Err(err) => report(data, "error-preferences", &[("error", &err)]),
+    }
+}
+
+pub fn language(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    data.prefs.language = *cmd.get_unchecked(commands::INTERPRETER_LANGUAGE);
+    save_quietly(data);
+}
+
+pub fn input_width(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    data.prefs.input_width = *cmd.get_unchecked(commands::VIEW_INPUT_WIDTH);
+    save_quietly(data);
+}
+
+pub fn theme(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    data.prefs.theme = *cmd.get_unchecked(commands::VIEW_THEME);
+    save_quietly(data);
+}
+
+pub fn turtle_image(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let path = cmd.get_unchecked(commands::VIEW_TURTLE_IMAGE).path();
+    data.prefs.turtle_image = path.to_string_lossy().into_owned();
+    data.prefs.turtle_shape = TurtleShape::Image;
+    data.turtle_shape = TurtleShape::Image;
+    save_quietly(data);
+}
+
+pub fn turtle_shape(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    data.prefs.turtle_shape = *cmd.get_unchecked(commands::VIEW_TURTLE_SHAPE);
+    data.turtle_shape = data.prefs.turtle_shape;
+    save_quietly(data);
+}
+
+/// Save a change made from the menus, only speaking up if it fails.
+fn save_quietly(data: &mut AppState) {
+    if let Err(err) = data.prefs.save() {
+        report(data, "error-preferences", &[("error", &err)]);
+    }
+}
@@ -0,0 +1,45 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::commands;
+use crate::model::app::AppState;
+use crate::model::server::Reply;
+use crate::model::server::Request;
+use druid::DelegateCtx;
+
+/// Carry out a request from another program. Like a run started by hand, it
+/// has to wait its turn if a program is already running.
+pub fn request(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let request = cmd.get_unchecked(commands::SERVER_REQUEST);
+
+    if data.session.is_running() {
+        request.reply(Reply::Error("busy: a program is running".to_string()));
+        return;
+    }
+
+    match request.request() {
+        Request::Logo(source) => {
+            super::interpreter::start(data, source.clone(), false, Some(request.clone()));
+        }
+        Request::Commands(list) => {
+            for cmd in list {
+                if data.render_tx.unbounded_send(cmd.clone()).is_err() {
+                    request.reply(Reply::Error("the canvas has gone away".to_string()));
+                    return;
+                }
+            }
+            request.reply(Reply::Ok(String::new()));
+        }
+    }
+}
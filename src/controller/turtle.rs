@@ -0,0 +1,64 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::write_console;
+use crate::common::commands;
+use crate::model::app::AppState;
+use crate::model::console::Severity;
+use crate::model::render::*;
+use crate::runtime;
+use druid::DelegateCtx;
+use druid::Point;
+use std::sync::Arc;
+
+/// Move the turtle by hand and show the code that would do the same thing.
+/// The move goes down the render channel like any other, so it lands in the
+/// command log too. While recording, the code is added to the input as well.
+pub fn move_to(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    if data.session.is_running() {
+        return;
+    }
+
+    let (p, down) = *cmd.get_unchecked(commands::TURTLE_MOVE);
+    let p = p.round();
+    let pen_flags = if down {
+        pen_down(PEN_FLAGS_DEFAULT)
+    } else {
+        pen_up(PEN_FLAGS_DEFAULT)
+    };
+
+    let move_to = MoveTo::new(0.0, data.prefs.pen_color.clone(), 0.0, pen_flags, p);
+    let cmd = RenderCommand::MoveTo(move_to);
+    if data.render_tx.unbounded_send(cmd).is_err() {
+        return;
+    }
+
+    let code = runtime::move_code(p, down);
+
+    if data.recording {
+        let mut input = data.input.to_string();
+        if !input.is_empty() && !input.ends_with('\n') {
+            input.push('\n');
+        }
+        input.push_str(&code);
+        input.push('\n');
+        data.input = Arc::new(input);
+    }
+
+    write_console(&data.console, Severity::Info, &code);
+}
+
+pub fn record(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.recording = !data.recording;
+}
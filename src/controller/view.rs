@@ -0,0 +1,80 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::report;
+use crate::common::commands;
+use crate::model::app::AppState;
+use crate::model::picture::Picture;
+use druid::DelegateCtx;
+use std::sync::Arc;
+
+pub fn background_image(_ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let path = cmd.get_unchecked(commands::VIEW_BACKGROUND_IMAGE).path();
+    match Picture::load(path) {
+        Ok(picture) => data.layers.background.picture = Some(Arc::new(picture)),
+        Err(err) => report(
+            data,
+            "error-file",
+            &[("path", &path.display()), ("error", &err)],
+        ),
+    }
+}
+
+pub fn clear_background(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.layers.background.picture = None;
+}
+
+pub fn clear_console(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.console.lock().unwrap().clear();
+}
+
+pub fn command_log(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.show_command_log = !data.show_command_log;
+}
+
+pub fn grid(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.layers.overlay.grid = !data.layers.overlay.grid;
+}
+
+pub fn presentation(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.presenting = !data.presenting;
+}
+
+pub fn reset(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.viewport = Default::default();
+}
+
+/// Put the caret at a line and column of the input, as long as the input
+/// still has them. A column past the end of the line lands at its end.
+pub fn show_source(ctx: &mut DelegateCtx, cmd: &druid::Command, data: &mut AppState) {
+    let (line, column) = *cmd.get_unchecked(commands::SHOW_SOURCE);
+    let mut lines = data.input.split('\n');
+    let before = line.saturating_sub(1);
+    let start: usize = lines.by_ref().take(before).map(|text| text.len() + 1).sum();
+    let text = match lines.next() {
+        Some(text) => text,
+        None => return,
+    };
+
+    let caret = start
+        + text
+            .char_indices()
+            .nth(column.saturating_sub(1))
+            .map_or(text.len(), |(idx, _)| idx);
+    ctx.submit_command(commands::EDITOR_CARET.with(caret).to(data.window_id()));
+}
+
+pub fn trace(_ctx: &mut DelegateCtx, _cmd: &druid::Command, data: &mut AppState) {
+    data.layers.overlay.trace = !data.layers.overlay.trace;
+}
@@ -0,0 +1,136 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The app itself: the window, the editor, and everything behind them.
+
+use crate::cli;
+use crate::cli::Mode;
+use crate::common::commands;
+use crate::common::i18n;
+use crate::controller::delegate::Delegate;
+use crate::model::app::AppState;
+use crate::model::backup::Backup;
+use crate::model::console::Severity;
+use crate::model::prefs::Preferences;
+use crate::model::render::RenderCommand;
+use crate::model::server;
+use crate::model::watch::Watch;
+use crate::view::window;
+use druid::AppLauncher;
+use druid::PlatformError;
+use druid::Target;
+use futures::channel::mpsc;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::process;
+use std::sync::Arc;
+
+/// The app as started from the command line.
+pub fn main() -> Result<(), PlatformError> {
+    let mode = match cli::mode() {
+        Ok(Mode::Test(dir)) => process::exit(cli::test::run(&dir)),
+        Ok(mode) => mode,
+        Err(err) => {
+            eprintln!(
+                "error: {}\nusage: turtle [<file.logo> | - | --watch <file.logo> | --test <dir>]",
+                err
+            );
+            process::exit(2);
+        }
+    };
+
+    let (launcher, mut data) = build(Preferences::load());
+
+    if let Err(err) = preload(&mode, &mut data) {
+        eprintln!("error: {}", err);
+        process::exit(2);
+    }
+
+    recover(&mut data);
+
+    let sink = launcher.get_external_handle();
+
+    if let Mode::Watch(path) = &mode {
+        match Watch::new(path, sink.clone(), data.session.clone()) {
+            Ok(watch) => data.watch = Some(Arc::new(watch)),
+            Err(err) => {
+                eprintln!("error: {}: {}", path.display(), err);
+                process::exit(2);
+            }
+        }
+    }
+
+    if data.prefs.server_port != 0 {
+        let allow = server::parse_allow_list(&data.prefs.server_allow);
+        match server::start(data.prefs.server_port, allow, sink.clone()) {
+            Ok(addr) => eprintln!("listening on {}", addr),
+            Err(err) => eprintln!("error: server: {}", err),
+        }
+    }
+
+    // Piped and watched programs run straight away, like a script.
+    if matches!(mode, Mode::Stdin | Mode::Watch(_)) {
+        sink.submit_command(commands::INTERPRETER_GO, (), Target::Auto)
+            .expect("failed to queue the first run");
+    }
+
+    launcher.launch(data)
+}
+
+/// The main window, ready to launch, and the state it starts with.
+pub fn build(prefs: Preferences) -> (AppLauncher<AppState>, AppState) {
+    let (render_tx, render_rx) = mpsc::unbounded::<RenderCommand>();
+    let window = window::window(render_rx, &prefs);
+    let data = AppState::new(render_tx, window.id, prefs);
+    let launcher = AppLauncher::with_window(window).delegate(Delegate);
+
+    (launcher, data)
+}
+
+/// Fill in the input from the file or stdin named on the command line.
+fn preload(mode: &Mode, data: &mut AppState) -> io::Result<()> {
+    match mode {
+        Mode::Open(path) | Mode::Watch(path) => {
+            let source = fs::read_to_string(path).map_err(|err| {
+                io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+            })?;
+            data.input = Arc::new(source);
+            data.mark_saved(path);
+        }
+        Mode::Stdin => {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source)?;
+            data.input = Arc::new(source);
+        }
+        Mode::Window | Mode::Test(_) => {}
+    }
+
+    Ok(())
+}
+
+/// Hold on to input that a backup kept from last time, and say how to get it
+/// back.
+fn recover(data: &mut AppState) {
+    let text = match Backup::new().and_then(|backup| backup.read()) {
+        Some(text) if text != *data.input => text,
+        _ => return,
+    };
+
+    data.recovered = Some(Arc::new(text));
+    data.console.lock().unwrap().push(
+        Severity::Warning,
+        &i18n::text(data.prefs.ui_language, "console-backup-found"),
+    );
+}
@@ -0,0 +1,124 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::graphics;
+use crate::model::pixbuf::PixBuf;
+use crate::model::render::RenderCommand;
+use druid::Color;
+use druid::Point;
+use druid::Size;
+use gif::Encoder;
+use gif::Frame;
+use gif::Repeat;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// How long the finished drawing is held before the animation loops.
+const FINAL_FRAME_DELAY_MS: u32 = 2000;
+
+/// Quantization speed passed to the GIF encoder; 1 is best, 30 is fastest.
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Replay `commands` headlessly, writing a frame to an animated GIF every
+/// `step` commands.
+pub fn export(
+    path: &Path,
+    commands: &[RenderCommand],
+    size: Size,
+    background: &Color,
+    step: usize,
+    delay_ms: u32,
+) -> io::Result<()> {
+    let width = size.width as u16;
+    let height = size.height as u16;
+
+    let file = File::create(path)?;
+    let mut encoder = Encoder::new(file, width, height, &[]).map_err(to_io_error)?;
+    encoder.set_repeat(Repeat::Infinite).map_err(to_io_error)?;
+
+    let mut pixels = PixBuf::new(width as u32, height as u32, 1);
+    let mut pos = Point::ZERO;
+
+    for chunk in commands.chunks(step.max(1)) {
+        for cmd in chunk {
+            graphics::render(&mut pixels, &mut pos, cmd);
+        }
+        pixels.present();
+
+        let frame = build_frame(&pixels, background, delay_ms);
+        encoder.write_frame(&frame).map_err(to_io_error)?;
+    }
+
+    let frame = build_frame(&pixels, background, FINAL_FRAME_DELAY_MS);
+    encoder.write_frame(&frame).map_err(to_io_error)?;
+
+    Ok(())
+}
+
+fn build_frame(pixels: &PixBuf, background: &Color, delay_ms: u32) -> Frame<'static> {
+    let mut rgba = pixels.flatten(background);
+    let mut frame = Frame::from_rgba_speed(
+        pixels.width() as u16,
+        pixels.height() as u16,
+        &mut rgba,
+        QUANTIZE_SPEED,
+    );
+    frame.delay = (delay_ms / 10) as u16;
+    frame
+}
+
+fn to_io_error(err: gif::EncodingError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::render::*;
+    use std::env;
+
+    #[test]
+    fn it_writes_a_frame_per_step() {
+        let path = env::temp_dir().join("turtle-rust-animation-test.gif");
+        let commands: Vec<_> = (1..=5)
+            .map(|i| {
+                let pos = Point::new(i as f64 * 2.0, 0.0);
+                let move_to = MoveTo::new(0.0, Color::WHITE, 0.0, PEN_FLAGS_DEFAULT, pos);
+                RenderCommand::MoveTo(move_to)
+            })
+            .collect();
+
+        export(
+            &path,
+            &commands,
+            Size::new(16.0, 16.0),
+            &Color::BLACK,
+            2,
+            40,
+        )
+        .unwrap();
+
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(File::open(&path).unwrap())
+            .unwrap();
+        let mut frames = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+
+        // Three chunks of commands, plus the held final frame.
+        assert_eq!(frames, 4);
+    }
+}
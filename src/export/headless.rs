@@ -0,0 +1,55 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::graphics;
+use crate::model::pixbuf::PixBuf;
+use crate::model::prefs::Preferences;
+use crate::runtime;
+use crate::runtime::error::RuntimeResult;
+use crate::runtime::warning::Warnings;
+use druid::Point;
+use futures::channel::mpsc;
+use std::sync::atomic::AtomicU32;
+use std::sync::Arc;
+
+/// Run `source` without a window, at the surface size in `prefs`, and return
+/// the finished drawing.
+pub fn run(source: &str, prefs: &Preferences) -> RuntimeResult<PixBuf> {
+    let (render_tx, mut render_rx) = mpsc::unbounded();
+
+    // Nobody is watching, so never throttle.
+    let speed = Arc::new(AtomicU32::new(u32::MAX));
+    let warnings = Warnings::default();
+    runtime::entry(
+        source.to_string(),
+        Arc::new(render_tx),
+        speed,
+        Default::default(),
+        prefs,
+        &warnings,
+    )?;
+
+    let surface = prefs.surface_size();
+    let mut pixels = PixBuf::new(surface.width as u32, surface.height as u32, 1);
+    let mut pos = Point::ZERO;
+    // Newer futures releases deprecate try_next for try_recv, which the
+    // locked one doesn't have yet.
+    #[allow(deprecated)]
+    while let Ok(Some(cmd)) = render_rx.try_next() {
+        graphics::render(&mut pixels, &mut pos, &cmd);
+    }
+    pixels.present();
+
+    Ok(pixels)
+}
@@ -0,0 +1,93 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::model::pixbuf::PixBuf;
+use druid::Color;
+use png::BitDepth;
+use png::ColorType;
+use png::Encoder;
+use std::io;
+
+/// Encode the pixel buffer, composited over `background`, as a PNG.
+pub fn encode_png(pixels: &PixBuf, background: &Color) -> io::Result<Vec<u8>> {
+    let rgba = pixels.flatten(background);
+    let mut out = Vec::new();
+
+    {
+        let mut encoder = Encoder::new(&mut out, pixels.width(), pixels.height());
+        encoder.set_color(ColorType::RGBA);
+        encoder.set_depth(BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(to_io_error)?;
+        writer.write_image_data(&rgba).map_err(to_io_error)?;
+    }
+
+    Ok(out)
+}
+
+/// Just the part of the drawing with ink on it, with `margin` logical pixels
+/// to spare on each side. A blank drawing comes back whole.
+pub fn crop_to_drawing(pixels: &PixBuf, margin: u32) -> PixBuf {
+    match pixels.ink_bounds() {
+        Some((x, y, width, height)) => {
+            let margin = margin * pixels.scale();
+            let left = x.saturating_sub(margin);
+            let top = y.saturating_sub(margin);
+            pixels.crop(
+                left,
+                top,
+                x - left + width + margin,
+                y - top + height + margin,
+            )
+        }
+        None => pixels.clone(),
+    }
+}
+
+fn to_io_error(err: png::EncodingError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_encodes_a_png() {
+        let pixels = PixBuf::new(4, 3, 1);
+        let png = encode_png(&pixels, &Color::BLACK).unwrap();
+
+        let decoder = png::Decoder::new(png.as_slice());
+        let (info, _) = decoder.read_info().unwrap();
+        assert_eq!((info.width, info.height), (4, 3));
+    }
+
+    #[test]
+    fn it_crops_to_the_drawing_with_a_margin() {
+        let mut pixels = PixBuf::new(20, 10, 2);
+        assert_eq!(crop_to_drawing(&pixels, 2).width(), 40);
+
+        pixels.write_xy(10, 10, &Color::WHITE);
+        pixels.write_xy(11, 12, &Color::WHITE);
+        pixels.present();
+
+        let cropped = crop_to_drawing(&pixels, 2);
+        assert_eq!((cropped.width(), cropped.height()), (10, 11));
+        assert_eq!(cropped.read_xy(4, 4), Color::WHITE);
+
+        // The margin stops at the edge.
+        let cropped = crop_to_drawing(&pixels, 100);
+        assert_eq!((cropped.width(), cropped.height()), (40, 20));
+    }
+}
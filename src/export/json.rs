@@ -0,0 +1,138 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::model::render::RenderCommand;
+use serde::Deserialize;
+use serde::Serialize;
+use std::io;
+
+/// Goes up whenever a change to the commands would stop older readers from
+/// making sense of newer files. Version 2 added paths.
+const VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct Export<'a> {
+    version: u32,
+    commands: Vec<&'a RenderCommand>,
+}
+
+#[derive(Deserialize)]
+struct Import {
+    version: u32,
+    commands: Vec<RenderCommand>,
+}
+
+/// A run's commands as JSON, to replay later or to feed to other tools.
+/// Background pictures are left out: they're there to trace over, and aren't
+/// part of the drawing.
+pub fn encode(commands: &[RenderCommand]) -> io::Result<String> {
+    let export = Export {
+        version: VERSION,
+        commands: commands
+            .iter()
+            .filter(|cmd| !matches!(cmd, RenderCommand::Background(_)))
+            .collect(),
+    };
+
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+pub fn decode(json: &str) -> io::Result<Vec<RenderCommand>> {
+    let import: Import = serde_json::from_str(json)?;
+    if import.version > VERSION {
+        let msg = format!("made by a newer version (format {})", import.version);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+    }
+
+    Ok(import.commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::image::encode_png;
+    use super::*;
+    use crate::model::picture::Picture;
+    use crate::model::pixbuf::PixBuf;
+    use crate::model::prefs::TurtleShape;
+    use crate::model::render::MoveTo;
+    use crate::model::render::PEN_FLAGS_DEFAULT;
+    use druid::Color;
+    use druid::Point;
+    use std::sync::Arc;
+
+    #[test]
+    fn it_round_trips_commands() {
+        let commands = vec![
+            RenderCommand::MoveTo(MoveTo::new(
+                90.0,
+                Color::rgba8(255, 128, 0, 64),
+                10.5,
+                PEN_FLAGS_DEFAULT,
+                Point::new(10.5, -3.25),
+            )),
+            RenderCommand::Fill(Color::BLACK),
+            RenderCommand::ScreenColor(Color::WHITE),
+            RenderCommand::Clean,
+            RenderCommand::ShowTurtle(false),
+            RenderCommand::Shape(TurtleShape::Circle),
+            RenderCommand::Size(12.0),
+            RenderCommand::Turn(45.0),
+        ];
+
+        let json = encode(&commands).unwrap();
+        assert_eq!(decode(&json).unwrap(), commands);
+    }
+
+    #[test]
+    fn it_round_trips_paths() {
+        let step = |x, y| {
+            MoveTo::new(
+                0.0,
+                Color::rgb8(255, 0, 0),
+                2.0,
+                PEN_FLAGS_DEFAULT,
+                Point::new(x, y),
+            )
+        };
+        let commands = vec![
+            RenderCommand::Path(vec![step(0.0, 10.0), step(10.0, 10.0), step(10.0, 0.0)]),
+            RenderCommand::Path(Vec::new()),
+        ];
+
+        let json = encode(&commands).unwrap();
+        assert_eq!(decode(&json).unwrap(), commands);
+    }
+
+    #[test]
+    fn it_leaves_out_backgrounds() {
+        let png = encode_png(&PixBuf::new(2, 2, 1), &Color::BLACK).unwrap();
+        let picture = Picture::decode(png.as_slice()).unwrap();
+        let commands = vec![
+            RenderCommand::Background(Arc::new(picture)),
+            RenderCommand::Clean,
+        ];
+
+        let json = encode(&commands).unwrap();
+        assert_eq!(decode(&json).unwrap(), vec![RenderCommand::Clean]);
+    }
+
+    #[test]
+    fn it_rejects_newer_files() {
+        let json = r#"{ "version": 999, "commands": [] }"#;
+        assert!(decode(json).is_err());
+        let json = r#"{ "version": 1, "commands": [] }"#;
+        assert!(decode(json).is_ok(), "older files still read");
+        assert!(decode("[]").is_err());
+    }
+}
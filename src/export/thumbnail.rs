@@ -0,0 +1,95 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::headless;
+use crate::model::prefs::Preferences;
+use crate::runtime::error::RuntimeResult;
+
+/// Run `source` headlessly and shrink the finished drawing to `width` x
+/// `height` RGBA.
+pub fn render(
+    source: &str,
+    prefs: &Preferences,
+    width: u32,
+    height: u32,
+) -> RuntimeResult<Vec<u8>> {
+    let pixels = headless::run(source, prefs)?;
+    let rgba = pixels.flatten(&prefs.screen_color);
+    Ok(shrink(
+        &rgba,
+        pixels.width(),
+        pixels.height(),
+        width,
+        height,
+    ))
+}
+
+/// Box filter: each destination pixel is the average of the source pixels
+/// that map onto it.
+fn shrink(rgba: &[u8], src_width: u32, src_height: u32, width: u32, height: u32) -> Vec<u8> {
+    let mut out = Vec::with_capacity((width * height * 4) as usize);
+
+    for y in 0..height {
+        let y0 = y * src_height / height;
+        let y1 = ((y + 1) * src_height / height).max(y0 + 1);
+
+        for x in 0..width {
+            let x0 = x * src_width / width;
+            let x1 = ((x + 1) * src_width / width).max(x0 + 1);
+
+            let mut sum = [0u32; 4];
+            for sy in y0..y1 {
+                for sx in x0..x1 {
+                    let idx = ((sy * src_width + sx) * 4) as usize;
+                    for (c, total) in sum.iter_mut().enumerate() {
+                        *total += rgba[idx + c] as u32;
+                    }
+                }
+            }
+
+            let count = (y1 - y0) * (x1 - x0);
+            out.extend(sum.iter().map(|total| (total / count) as u8));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_averages_blocks() {
+        #[rustfmt::skip]
+        let rgba = [
+            0, 0, 0, 255,   255, 255, 255, 255,
+            255, 255, 255, 255,   0, 0, 0, 255,
+        ];
+        assert_eq!(shrink(&rgba, 2, 2, 1, 1), vec![127, 127, 127, 255]);
+    }
+
+    #[test]
+    fn it_renders_a_program() {
+        let prefs = Preferences::default();
+        let rgba = render("fd 100", &prefs, 80, 60).unwrap();
+        assert_eq!(rgba.len(), 80 * 60 * 4);
+
+        let background = prefs.screen_color.as_rgba8();
+        let drawn = rgba
+            .chunks(4)
+            .any(|px| px[..3] != [background.0, background.1, background.2]);
+        assert!(drawn);
+    }
+}
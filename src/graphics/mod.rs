@@ -13,18 +13,60 @@
 // limitations under the License.
 
 use crate::model::pixbuf::PixBuf;
+use crate::model::render::*;
 use druid::Color;
 use druid::Point;
 use std::collections::VecDeque;
-use std::sync::Arc;
 
-pub fn line(pixels: &mut PixBuf, p: &Point, q: &Point, color: &Color) {
-    let bytes = Arc::make_mut(&mut pixels.bytes);
+/// What rasterizing a command did to the pixel buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rendered {
+    /// Pixel writes that landed inside the buffer.
+    pub pixels: u64,
+    /// Lines that fell partly outside the buffer.
+    pub clipped: u64,
+}
+
+/// What a stroke does to the pixels under it.
+#[derive(Clone, Copy, Debug)]
+pub enum Ink<'a> {
+    /// Blend the color over what's there, so translucent strokes build up.
+    Paint(&'a Color),
+    /// Clear back to the background.
+    Erase,
+}
+
+/// Where a point lands at device resolution. Points are fractional in
+/// geometric mode, so they go to the nearest pixel.
+fn device_xy(pixels: &PixBuf, p: &Point) -> (i32, i32) {
+    let scale = pixels.scale() as f64;
+    ((p.x * scale).round() as i32, (-p.y * scale).round() as i32)
+}
+
+fn plot(pixels: &mut PixBuf, x: i32, y: i32, ink: Ink, out: &mut Rendered) {
+    let (screen_x, screen_y) = pixels.screen_xy(x, y);
+    let scale = pixels.scale() as i32;
+    for dy in 0..scale {
+        for dx in 0..scale {
+            let (x, y) = (screen_x + dx, screen_y + dy);
+            let inside = match ink {
+                Ink::Paint(color) => pixels.blend_xy_clipped(x, y, color),
+                Ink::Erase => pixels.write_xy_clipped(x, y, &Color::TRANSPARENT),
+            };
+            if inside {
+                out.pixels += 1;
+            } else {
+                out.clipped = 1;
+            }
+        }
+    }
+}
 
-    let x0 = p.x as i32;
-    let y0 = -p.y as i32;
-    let x1 = q.x as i32;
-    let y1 = -q.y as i32;
+pub fn line(pixels: &mut PixBuf, p: &Point, q: &Point, ink: Ink) -> Rendered {
+    let mut out = Rendered::default();
+    // Rasterize at device resolution, with a brush one logical pixel wide.
+    let (x0, y0) = device_xy(pixels, p);
+    let (x1, y1) = device_xy(pixels, q);
 
     let dx = x1 - x0;
     let dy = y1 - y0;
@@ -46,8 +88,7 @@ pub fn line(pixels: &mut PixBuf, p: &Point, q: &Point, color: &Color) {
                 break;
             }
 
-            let (screen_x, screen_y) = PixBuf::screen_xy(x, y);
-            PixBuf::write_xy_inner_clipped(bytes, screen_x, screen_y, color);
+            plot(pixels, x, y, ink, &mut out);
 
             eps += ady;
             if (eps << 1) >= adx {
@@ -68,8 +109,7 @@ pub fn line(pixels: &mut PixBuf, p: &Point, q: &Point, color: &Color) {
                 break;
             }
 
-            let (screen_x, screen_y) = PixBuf::screen_xy(x, y);
-            PixBuf::write_xy_inner_clipped(bytes, screen_x, screen_y, color);
+            plot(pixels, x, y, ink, &mut out);
 
             eps += adx;
             if (eps << 1) >= ady {
@@ -79,19 +119,82 @@ pub fn line(pixels: &mut PixBuf, p: &Point, q: &Point, color: &Color) {
             y += sy;
         }
     }
+
+    out
+}
+
+/// Rasterize a single render command, tracking the turtle position. Commands
+/// that only affect the view, like turtle visibility, are ignored.
+pub fn render(pixels: &mut PixBuf, pos: &mut Point, cmd: &RenderCommand) -> Rendered {
+    match cmd {
+        RenderCommand::Clean => {
+            pixels.fill(&Color::TRANSPARENT);
+            Rendered::default()
+        }
+
+        RenderCommand::Fill(color) => Rendered {
+            pixels: flood_fill(pixels, pos, color),
+            clipped: 0,
+        },
+
+        RenderCommand::MoveTo(move_to) => render_move(pixels, pos, move_to),
+
+        RenderCommand::Path(moves) => {
+            let mut out = Rendered::default();
+            for move_to in moves {
+                let rendered = render_move(pixels, pos, move_to);
+                out.pixels += rendered.pixels;
+                out.clipped += rendered.clipped;
+            }
+            out
+        }
+
+        RenderCommand::Background(_)
+        | RenderCommand::ScreenColor(_)
+        | RenderCommand::ShowTurtle(_)
+        | RenderCommand::Shape(_)
+        | RenderCommand::Size(_)
+        | RenderCommand::Speed(_)
+        | RenderCommand::Turn(_) => Rendered::default(),
+    }
+}
+
+fn render_move(pixels: &mut PixBuf, pos: &mut Point, move_to: &MoveTo) -> Rendered {
+    let mut out = Rendered::default();
+    if is_pen_down(move_to.pen_flags) {
+        let ink = if is_pen_erase(move_to.pen_flags) {
+            Ink::Erase
+        } else {
+            Ink::Paint(&move_to.color)
+        };
+        out = line(pixels, pos, &move_to.pos, ink);
+    }
+    *pos = move_to.pos;
+    out
 }
 
-pub fn flood_fill(pixels: &mut PixBuf, pos: &druid::Point, color: &Color) {
-    let (x, y) = PixBuf::screen_xy(pos.x as i32, -pos.y as i32);
-    if !PixBuf::contains(x, y) {
-        return;
+/// Fill the area around `pos` that's the same color as `pos`, blending the
+/// fill color over it. Returns how many pixels were filled.
+pub fn flood_fill(pixels: &mut PixBuf, pos: &druid::Point, color: &Color) -> u64 {
+    let (x, y) = device_xy(pixels, pos);
+    let (x, y) = pixels.screen_xy(x, y);
+    if !pixels.contains(x, y) {
+        return 0;
     }
 
+    // Painting an opaque color over itself changes nothing, but translucent
+    // colors keep building up.
     let start_color = pixels.read_xy(x as usize, y as usize);
-    if start_color == *color {
-        return;
+    if start_color == *color && color.as_rgba8().3 == 255 {
+        return 0;
     }
 
+    // A translucent fill can leave a pixel looking just as it did, so the
+    // area is tracked separately rather than by color.
+    let width = pixels.width() as usize;
+    let mut seen = vec![false; width * pixels.height() as usize];
+
+    let mut filled = 0;
     let mut q: VecDeque<Point> = VecDeque::new();
     q.push_back(Point::new(x as f64, y as f64));
     while !q.is_empty() {
@@ -99,28 +202,141 @@ pub fn flood_fill(pixels: &mut PixBuf, pos: &druid::Point, color: &Color) {
         let x = node.x as usize;
         let y = node.y as usize;
 
+        if seen[y * width + x] {
+            continue;
+        }
+        seen[y * width + x] = true;
+
         if start_color == pixels.read_xy(x, y) {
-            pixels.write_xy(x, y, color);
+            pixels.blend_xy(x, y, color);
+            filled += 1;
 
             let left = node - (1.0, 0.0);
-            if PixBuf::contains(left.x as i32, left.y as i32) {
+            if pixels.contains(left.x as i32, left.y as i32) {
                 q.push_back(left);
             }
 
             let right = node + (1.0, 0.0);
-            if PixBuf::contains(right.x as i32, right.y as i32) {
+            if pixels.contains(right.x as i32, right.y as i32) {
                 q.push_back(right);
             }
 
             let up = node - (0.0, 1.0);
-            if PixBuf::contains(up.x as i32, up.y as i32) {
+            if pixels.contains(up.x as i32, up.y as i32) {
                 q.push_back(up);
             }
 
             let down = node + (0.0, 1.0);
-            if PixBuf::contains(down.x as i32, down.y as i32) {
+            if pixels.contains(down.x as i32, down.y as i32) {
                 q.push_back(down);
             }
         }
     }
+
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_draws_at_device_scale() {
+        let mut pixels = PixBuf::new(10, 10, 2);
+        line(
+            &mut pixels,
+            &Point::ZERO,
+            &Point::new(1.0, 0.0),
+            Ink::Paint(&Color::WHITE),
+        );
+
+        // One logical pixel to the right is two device pixels, each drawn
+        // with a 2x2 brush.
+        for x in 10..14 {
+            for y in 10..12 {
+                assert_eq!(pixels.read_xy(x, y), Color::WHITE);
+            }
+        }
+        assert_ne!(pixels.read_xy(14, 10), Color::WHITE);
+    }
+
+    #[test]
+    fn it_counts_what_it_draws() {
+        let mut pixels = PixBuf::new(10, 10, 1);
+        let out = line(
+            &mut pixels,
+            &Point::ZERO,
+            &Point::new(2.0, 0.0),
+            Ink::Paint(&Color::WHITE),
+        );
+        assert_eq!(
+            out,
+            Rendered {
+                pixels: 3,
+                clipped: 0
+            }
+        );
+
+        let out = line(
+            &mut pixels,
+            &Point::ZERO,
+            &Point::new(0.0, 20.0),
+            Ink::Paint(&Color::WHITE),
+        );
+        assert_eq!(out.clipped, 1);
+        assert_eq!(out.pixels, 6);
+    }
+
+    #[test]
+    fn it_blends_translucent_ink() {
+        let mut pixels = PixBuf::new(10, 10, 1);
+        let red = Color::rgba8(255, 0, 0, 255);
+        let blue = Color::rgba8(0, 0, 255, 128);
+
+        line(
+            &mut pixels,
+            &Point::ZERO,
+            &Point::new(2.0, 0.0),
+            Ink::Paint(&red),
+        );
+        line(
+            &mut pixels,
+            &Point::ZERO,
+            &Point::new(2.0, 0.0),
+            Ink::Paint(&blue),
+        );
+        assert_eq!(pixels.read_xy(5, 5), Color::rgba8(127, 0, 128, 255));
+
+        // On an empty buffer the color keeps its own alpha.
+        let filled = flood_fill(&mut pixels, &Point::new(-3.0, 3.0), &blue);
+        assert_eq!(filled, 97);
+        assert_eq!(pixels.read_xy(0, 0), blue);
+
+        // Filling again blends once more, and still stops at the line.
+        let filled = flood_fill(&mut pixels, &Point::new(-3.0, 3.0), &blue);
+        assert_eq!(filled, 97);
+        assert_eq!(pixels.read_xy(0, 0), Color::rgba8(0, 0, 255, 191));
+
+        line(&mut pixels, &Point::ZERO, &Point::new(2.0, 0.0), Ink::Erase);
+        assert_eq!(pixels.read_xy(5, 5), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn it_cleans_the_drawing() {
+        let mut pixels = PixBuf::new(10, 10, 1);
+        let mut pos = Point::ZERO;
+        let move_to = MoveTo::new(
+            0.0,
+            Color::WHITE,
+            0.0,
+            PEN_FLAGS_DEFAULT,
+            Point::new(2.0, 0.0),
+        );
+        render(&mut pixels, &mut pos, &RenderCommand::MoveTo(move_to));
+        assert_eq!(pixels.read_xy(5, 5), Color::WHITE);
+
+        render(&mut pixels, &mut pos, &RenderCommand::Clean);
+        assert_eq!(pixels.read_xy(5, 5), Color::TRANSPARENT);
+        assert_eq!(pos, Point::new(2.0, 0.0));
+    }
 }
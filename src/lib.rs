@@ -0,0 +1,44 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Turtle graphics, driven by Logo in the app's editor or from Rust through
+//! [`run`]. Built for wasm32, it's just the interpreter and rasterizer, for
+//! running Logo in a web page; see the `web` module.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod api;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod common;
+#[cfg(not(target_arch = "wasm32"))]
+mod controller;
+#[cfg(not(target_arch = "wasm32"))]
+mod desktop;
+mod export;
+mod graphics;
+mod model;
+mod runtime;
+#[cfg(not(target_arch = "wasm32"))]
+mod view;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use api::check;
+#[cfg(not(target_arch = "wasm32"))]
+pub use api::run;
+#[cfg(not(target_arch = "wasm32"))]
+pub use api::Turtle;
+#[cfg(not(target_arch = "wasm32"))]
+pub use desktop::main;
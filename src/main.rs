@@ -12,27 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use druid::PlatformError;
-use futures::channel::mpsc;
-
-mod common;
-mod controller;
-mod graphics;
-mod model;
-mod runtime;
-mod view;
-
-use controller::delegate::Delegate;
-use model::app::AppState;
-use model::render::RenderCommand;
-use view::window;
-
-fn main() -> Result<(), PlatformError> {
-    let (render_tx, render_rx) = mpsc::unbounded::<RenderCommand>();
-    let window = window::window(render_rx);
-    let data = AppState::new(render_tx, window.id);
-
-    druid::AppLauncher::with_window(window)
-        .delegate(Delegate)
-        .launch(data)
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> Result<(), druid::PlatformError> {
+    turtle_rust::main()
 }
+
+/// In a browser, the page calls into the library instead.
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -12,56 +12,188 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::console::ConsoleLog;
+use super::layers::Layers;
+use super::log::CommandLog;
+use super::log::CommandRing;
+use super::params::Param;
 use super::pixbuf::PixBuf;
+use super::prefs::Preferences;
+use super::prefs::TurtleShape;
+use super::render::RenderCommand;
 use super::render::RenderTx;
+use super::session::InterpreterSession;
+use super::speed::Speed;
+use super::stats::RunStats;
+use super::viewport::Viewport;
+use super::watch::Watch;
+use crate::common::constants::*;
 use druid::Data;
 use druid::Lens;
 use druid::Point;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::AtomicU32;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
-use threadpool::ThreadPool;
 
 /// Application state.
 #[derive(Clone, Data, Debug, Lens)]
 pub struct AppState {
-    pub command_count: u32,
+    /// The input as of the last backup, so that it isn't written again
+    /// until it changes.
+    pub backed_up: Arc<String>,
+    pub command_log_paused: bool,
+    /// What the console shows. Runs and exports write to it from their own
+    /// threads.
+    pub console: Arc<Mutex<ConsoleLog>>,
+    /// What the last run declared with `param`, until the sliders take it.
+    pub declared: Arc<Mutex<Option<Vec<Param>>>>,
+    /// The file the input was opened from or last saved to.
+    pub document: Option<Arc<PathBuf>>,
+    /// Counts the render commands that should flash the status bar.
+    pub flashes: u64,
+    /// The turtle's heading in degrees, clockwise from up.
+    pub heading: f64,
+    pub help_query: Arc<String>,
     pub input: Arc<String>,
-    pub output: Arc<Mutex<String>>,
-    pub pixels: PixBuf,
+    pub layers: Layers,
+    /// Run the program again each time typing stops.
+    pub live: bool,
+    pub log: CommandLog,
+    /// The sliders beside the input, which the next run takes its params
+    /// from.
+    pub params: Arc<Vec<Param>>,
     pub pos: Point,
-    pub running: Arc<AtomicBool>,
-    pub show_turtle: bool,
-    pub speed: Arc<AtomicU32>,
-    pub thread_pool: Arc<ThreadPool>,
+    pub prefs: Preferences,
+    /// Show nothing but the drawing, as big as the window allows.
+    pub presenting: bool,
+    /// Set while the program waits for a line from the console.
+    pub reading: bool,
+    pub recent: CommandRing,
+    /// Unsaved input left behind by the last time the app ran, until it's
+    /// restored.
+    pub recovered: Option<Arc<String>>,
+    /// Append the code for manual turtle moves to the input pane.
+    pub recording: bool,
+    /// The input as of the last open or save, to tell whether it has changed.
+    pub saved_input: Arc<String>,
+    pub session: Arc<InterpreterSession>,
+    pub show_command_log: bool,
+    pub speed: Speed,
+    pub stats: RunStats,
+    pub symbols: Arc<Mutex<Vec<String>>>,
+    /// How far through the command log the canvas shows, from 0 to 1. Only
+    /// moves off the end when scrubbing through a finished drawing.
+    pub timeline: f64,
+    pub thumbnails: Arc<HashMap<&'static str, Arc<Vec<u8>>>>,
+    /// The shape and size the program asked for, starting from the
+    /// preferences each run.
+    pub turtle_shape: TurtleShape,
+    pub turtle_size: f64,
     pub render_tx: Arc<RenderTx>,
+    /// The line being typed for the program to read.
+    pub reply: Arc<String>,
+    pub viewport: Viewport,
+    /// Set while the open file is being watched for changes.
+    pub watch: Option<Arc<Watch>>,
 
     #[data(same_fn = "PartialEq::eq")]
     window_id: druid::WindowId,
 }
 
 impl AppState {
-    pub fn new(render_tx: RenderTx, window_id: druid::WindowId) -> Self {
+    pub fn new(render_tx: RenderTx, window_id: druid::WindowId, prefs: Preferences) -> Self {
+        let surface = prefs.surface_size();
+        let pixels = PixBuf::new(surface.width as u32, surface.height as u32, 1);
+        let layers = Layers::new(pixels, &prefs.screen_color);
+        let speed = Speed::new(prefs.speed);
+        let turtle_shape = prefs.turtle_shape;
+
         Self {
-            command_count: 0,
+            backed_up: "".to_string().into(),
+            command_log_paused: false,
+            console: Arc::new(Mutex::new(ConsoleLog::new(CONSOLE_CAPACITY))),
+            declared: Arc::new(Mutex::new(None)),
+            document: None,
+            flashes: 0,
+            heading: 0.0,
+            help_query: "".to_string().into(),
             input: "".to_string().into(),
-            output: Arc::new(Mutex::new("".to_string())),
-            pixels: Default::default(),
+            layers,
+            live: false,
+            log: Default::default(),
+            params: Arc::new(Vec::new()),
             pos: Point::ZERO,
-            running: Arc::new(AtomicBool::new(false)),
-            show_turtle: false,
-            speed: Arc::new(AtomicU32::new(4)),
-            thread_pool: Arc::new(ThreadPool::new(1)),
+            prefs,
+            presenting: false,
+            reading: false,
+            recent: CommandRing::new(COMMAND_LOG_CAPACITY),
+            recovered: None,
+            recording: false,
+            saved_input: "".to_string().into(),
+            session: Default::default(),
+            show_command_log: false,
+            speed,
+            stats: Default::default(),
+            symbols: Arc::new(Mutex::new(Vec::new())),
+            timeline: 1.0,
+            thumbnails: Default::default(),
+            turtle_shape,
+            turtle_size: TURTLE_SIZE,
             render_tx: Arc::new(render_tx),
+            reply: "".to_string().into(),
+            viewport: Default::default(),
+            watch: None,
             window_id,
         }
     }
 
+    pub fn window_id(&self) -> druid::WindowId {
+        self.window_id
+    }
+
+    /// Whether the input has changed since it was last opened or saved.
+    pub fn is_dirty(&self) -> bool {
+        !Arc::ptr_eq(&self.input, &self.saved_input) && self.input != self.saved_input
+    }
+
+    pub fn mark_saved(&mut self, path: &Path) {
+        self.document = Some(Arc::new(path.to_owned()));
+        self.saved_input = self.input.clone();
+    }
+
     pub fn clear(&mut self) {
-        self.command_count = 0;
-        self.pixels.clear();
+        self.stats = Default::default();
+        self.log.clear();
+        self.recent.clear();
+        self.layers.reset(&self.prefs.screen_color);
+        self.reset_turtle();
+        self.timeline = 1.0;
+    }
+
+    /// Put the turtle back the way it starts out.
+    pub fn reset_turtle(&mut self) {
         self.pos = Point::ZERO;
-        self.show_turtle = true;
+        self.heading = 0.0;
+        self.layers.overlay.turtle = true;
+        self.turtle_shape = self.prefs.turtle_shape;
+        self.turtle_size = TURTLE_SIZE;
+    }
+
+    /// Follow the turtle's look as commands go by. Where it is comes from
+    /// drawing, which is up to the caller.
+    pub fn track(&mut self, cmd: &RenderCommand) {
+        match cmd {
+            RenderCommand::Background(picture) => {
+                self.layers.background.picture = Some(picture.clone())
+            }
+            RenderCommand::ScreenColor(color) => self.layers.background.color = color.clone(),
+            RenderCommand::ShowTurtle(val) => self.layers.overlay.turtle = *val,
+            RenderCommand::Shape(shape) => self.turtle_shape = *shape,
+            RenderCommand::Size(size) => self.turtle_size = *size,
+            RenderCommand::Turn(heading) => self.heading = *heading,
+            _ => {}
+        }
     }
 }
@@ -0,0 +1,90 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::paths;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const BACKUP_FILE: &str = "backup.logo";
+
+/// A copy of input that hasn't been saved, kept on disk so that it survives
+/// the app going away before it could be saved. Only written while there are
+/// unsaved changes, so one found at startup was left behind.
+#[derive(Clone, Debug)]
+pub struct Backup {
+    path: PathBuf,
+}
+
+impl Backup {
+    /// The backup in the config directory, if there is one.
+    pub fn new() -> Option<Self> {
+        paths::config_dir().map(|dir| Self::at(dir.join(BACKUP_FILE)))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// What was backed up, if anything was.
+    pub fn read(&self) -> Option<String> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .filter(|text| !text.is_empty())
+    }
+
+    pub fn write(&self, input: &str) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Write beside it first, so that a crash partway through doesn't
+        // spoil the backup that was already there.
+        let part = self.path.with_extension("part");
+        fs::write(&part, input)?;
+        fs::rename(part, &self.path)
+    }
+
+    /// Throw the backup away, once what it held has been saved.
+    pub fn remove(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn it_keeps_input_until_removed() {
+        let dir = env::temp_dir().join(format!("turtle-backup-{}", process::id()));
+        let backup = Backup::at(dir.join(BACKUP_FILE));
+        assert_eq!(backup.read(), None);
+
+        backup.write("fd 100\n").unwrap();
+        backup.write("fd 100 rt 90\n").unwrap();
+        assert_eq!(backup.read().as_deref(), Some("fd 100 rt 90\n"));
+
+        backup.remove().unwrap();
+        assert_eq!(backup.read(), None);
+        backup.remove().unwrap();
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
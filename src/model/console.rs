@@ -0,0 +1,299 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use druid::Color;
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::Range;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How an entry is colored in the console.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    /// Results, printed text, and messages from the app.
+    Info,
+    Warning,
+    Error,
+    /// The header above everything from one run.
+    Run,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// How long after the app started the entry was written. It's shown as
+    /// `+HH:MM:SS`, so it isn't taken for the time of day.
+    pub elapsed: Duration,
+    pub severity: Severity,
+    pub text: String,
+    /// The line and column of the input an error is about, counted from one.
+    pub source: Option<(usize, usize)>,
+    /// Colors a program chose for what it printed, by ranges of bytes in
+    /// `text`.
+    pub colors: Vec<(Range<usize>, Color)>,
+}
+
+impl Entry {
+    fn lines(&self) -> usize {
+        self.text.lines().count()
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.elapsed.as_secs();
+        let time = format!("+{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+        match self.severity {
+            Severity::Run => write!(f, "{} \u{2014} {}", self.text, time),
+            _ => write!(f, "[{}] {}", time, self.text),
+        }
+    }
+}
+
+/// Everything written to the console, oldest first. Once it holds more than
+/// `capacity` lines, the oldest entries drop off the front.
+#[derive(Debug)]
+pub struct ConsoleLog {
+    entries: VecDeque<Entry>,
+    /// How many entries have dropped off the front or been cleared.
+    dropped: u64,
+    lines: usize,
+    capacity: usize,
+    start: Instant,
+    version: u64,
+    runs: u64,
+}
+
+impl ConsoleLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            dropped: 0,
+            lines: 0,
+            capacity,
+            start: Instant::now(),
+            version: 0,
+            runs: 0,
+        }
+    }
+
+    /// Add an entry at the end. Blank text is left out, and the newest entry
+    /// is kept even if it's longer than the whole log.
+    pub fn push(&mut self, severity: Severity, text: &str) {
+        self.push_at(severity, text, None);
+    }
+
+    /// Add an entry that points at a line and column of the input.
+    pub fn push_at(&mut self, severity: Severity, text: &str, source: Option<(usize, usize)>) {
+        self.add(severity, text, source, Vec::new());
+    }
+
+    /// Add what a program printed, with the colors it chose for parts of it.
+    pub fn push_colored(&mut self, text: &str, colors: Vec<(Range<usize>, Color)>) {
+        self.add(Severity::Info, text, None, colors);
+    }
+
+    fn add(
+        &mut self,
+        severity: Severity,
+        text: &str,
+        source: Option<(usize, usize)>,
+        colors: Vec<(Range<usize>, Color)>,
+    ) {
+        let text = text.trim_end();
+        if text.is_empty() {
+            return;
+        }
+
+        // Trimming may have cut the end off a colored range.
+        let colors = colors
+            .into_iter()
+            .filter_map(|(range, color)| {
+                let end = range.end.min(text.len());
+                (range.start < end).then_some((range.start..end, color))
+            })
+            .collect();
+
+        let entry = Entry {
+            elapsed: self.start.elapsed(),
+            severity,
+            text: text.to_string(),
+            source,
+            colors,
+        };
+        self.lines += entry.lines();
+        self.entries.push_back(entry);
+
+        while self.lines > self.capacity && self.entries.len() > 1 {
+            if let Some(old) = self.entries.pop_front() {
+                self.lines -= old.lines();
+                self.dropped += 1;
+            }
+        }
+
+        self.version += 1;
+    }
+
+    /// Count another run, and return its number. Runs stay numbered from the
+    /// first after the console is cleared.
+    pub fn next_run(&mut self) -> u64 {
+        self.runs += 1;
+        self.runs
+    }
+
+    pub fn clear(&mut self) {
+        self.dropped += self.entries.len() as u64;
+        self.entries.clear();
+        self.lines = 0;
+        self.version += 1;
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter()
+    }
+
+    /// The number of the oldest entry. Entries are numbered from the first
+    /// ever written, so each keeps its number as older ones drop off.
+    pub fn first(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Goes up with every entry, so a view can tell when to redraw.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// The whole log as plain text, for the clipboard.
+    pub fn text(&self) -> String {
+        let lines: Vec<_> = self.entries.iter().map(|entry| entry.to_string()).collect();
+        lines.join("\n")
+    }
+
+    /// The text between two places in the log, for copying a selection. Each
+    /// place is an entry number and a byte offset into the entry as shown,
+    /// and they can come in either order. Entries gone from the log are left
+    /// out.
+    pub fn text_between(&self, from: (u64, usize), to: (u64, usize)) -> String {
+        let (start, end) = if from <= to { (from, to) } else { (to, from) };
+
+        let lines: Vec<_> = self
+            .entries
+            .iter()
+            .zip(self.dropped..)
+            .filter(|(_, num)| (start.0..=end.0).contains(num))
+            .map(|(entry, num)| {
+                let line = entry.to_string();
+                let left = if num == start.0 { start.1 } else { 0 };
+                let right = if num == end.0 { end.1 } else { line.len() };
+                line.get(left..right.min(line.len()))
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect();
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_most_recent_lines() {
+        let mut log = ConsoleLog::new(3);
+        log.push(Severity::Info, "1\n2");
+        log.push(Severity::Warning, "  \n");
+        log.push_at(Severity::Error, "3\n", Some((1, 2)));
+        assert_eq!(log.version(), 2);
+
+        log.push(Severity::Info, "4");
+        let texts: Vec<_> = log.entries().map(|entry| entry.text.as_str()).collect();
+        assert_eq!(texts, vec!["3", "4"]);
+        assert_eq!(log.entries().next().unwrap().source, Some((1, 2)));
+        assert_eq!(log.text(), "[+00:00:00] 3\n[+00:00:00] 4");
+
+        log.push(Severity::Info, "5\n6\n7\n8");
+        assert_eq!(log.entries().count(), 1, "the newest entry stays");
+    }
+
+    #[test]
+    fn it_stamps_entries_with_the_elapsed_time() {
+        let entry = Entry {
+            elapsed: Duration::from_secs(3 * 3600 + 25 * 60 + 7),
+            severity: Severity::Info,
+            text: "hello".to_string(),
+            source: None,
+            colors: Vec::new(),
+        };
+        assert_eq!(entry.to_string(), "[+03:25:07] hello");
+
+        let header = Entry {
+            severity: Severity::Run,
+            text: "Run #3".to_string(),
+            ..entry
+        };
+        assert_eq!(header.to_string(), "Run #3 \u{2014} +03:25:07");
+    }
+
+    #[test]
+    fn it_keeps_colors_inside_the_text() {
+        let mut log = ConsoleLog::new(10);
+        let colors = vec![
+            (0..2, Color::WHITE),
+            (2..4, Color::BLACK),
+            (4..5, Color::WHITE),
+        ];
+        log.push_colored("hi\n\n\n", colors);
+
+        let entry = log.entries().next().unwrap();
+        assert_eq!(entry.text, "hi");
+        assert_eq!(entry.colors, vec![(0..2, Color::WHITE)]);
+    }
+
+    #[test]
+    fn it_numbers_runs() {
+        let mut log = ConsoleLog::new(10);
+        assert_eq!(log.next_run(), 1);
+        log.push(Severity::Info, "done");
+
+        log.clear();
+        assert_eq!(log.entries().count(), 0);
+        assert_eq!(log.version(), 2);
+        assert_eq!(log.next_run(), 2);
+    }
+
+    #[test]
+    fn it_copies_between_two_places() {
+        let mut log = ConsoleLog::new(3);
+        log.push(Severity::Info, "gone");
+        log.push(Severity::Info, "one");
+        log.push(Severity::Info, "two");
+        log.push(Severity::Info, "three");
+        assert_eq!(log.first(), 1, "the first entry dropped off");
+
+        // Offsets count the time in front, which is 12 bytes.
+        assert_eq!(log.text_between((1, 13), (1, 15)), "ne");
+        assert_eq!(
+            log.text_between((3, 14), (1, 12)),
+            "one\n[+00:00:00] two\n[+00:00:00] th"
+        );
+        assert_eq!(log.text_between((2, 12), (2, 99)), "two");
+        assert_eq!(log.text_between((0, 0), (1, 2)), "[+");
+        assert_eq!(log.text_between((2, 3), (2, 3)), "");
+
+        log.clear();
+        assert_eq!(log.first(), 4);
+        assert_eq!(log.text_between((1, 0), (3, 99)), "");
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::picture::Picture;
+use super::pixbuf::PixBuf;
+use druid::Color;
+use druid::Data;
+use druid::Lens;
+use std::sync::Arc;
+
+/// The canvas, bottom to top. Each layer is cleared on its own, so `clean`
+/// wipes the drawing and leaves the background alone. Overlays are only ever
+/// painted on screen, which keeps them out of exports.
+#[derive(Clone, Data, Debug, Lens)]
+pub struct Layers {
+    pub background: Background,
+    pub drawing: PixBuf,
+    pub overlay: Overlay,
+}
+
+#[derive(Clone, Data, Debug, Lens)]
+pub struct Background {
+    /// Starts each run as the preferred screen color; `setsc` changes it.
+    pub color: Color,
+    /// A picture to trace over. It stays put from one run to the next.
+    pub picture: Option<Arc<Picture>>,
+}
+
+/// Things drawn over the canvas to help, which aren't part of the drawing.
+#[derive(Clone, Copy, Data, Debug, Default, Lens, PartialEq)]
+pub struct Overlay {
+    pub grid: bool,
+    pub trace: bool,
+    pub turtle: bool,
+}
+
+impl Layers {
+    pub fn new(drawing: PixBuf, screen_color: &Color) -> Self {
+        Self {
+            background: Background {
+                color: screen_color.clone(),
+                picture: None,
+            },
+            drawing,
+            overlay: Default::default(),
+        }
+    }
+
+    /// Back to how a run starts: nothing drawn, over the preferred screen
+    /// color.
+    pub fn reset(&mut self, screen_color: &Color) {
+        self.drawing.clear();
+        self.background.color = screen_color.clone();
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::render::RenderCommand;
+use druid::Data;
+use druid::Point;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// Every render command consumed by the canvas during the current run, in
+/// order. Overlays, replay, and export all work from this log.
+#[derive(Clone, Data, Debug, Default)]
+pub struct CommandLog {
+    commands: Arc<Vec<RenderCommand>>,
+}
+
+impl CommandLog {
+    pub fn push(&mut self, cmd: RenderCommand) {
+        Arc::make_mut(&mut self.commands).push(cmd);
+    }
+
+    pub fn clear(&mut self) {
+        Arc::make_mut(&mut self.commands).clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn commands(&self) -> &[RenderCommand] {
+        &self.commands
+    }
+
+    /// The line segments traced by the turtle, in turtle coordinates.
+    pub fn segments(&self) -> Vec<(Point, Point)> {
+        let mut pos = Point::ZERO;
+        let mut segments = Vec::new();
+
+        for move_to in self.commands.iter().flat_map(|cmd| cmd.moves()) {
+            segments.push((pos, move_to.pos));
+            pos = move_to.pos;
+        }
+
+        segments
+    }
+}
+
+/// The most recent render commands, for the command log panel. Once it's
+/// full, the oldest command drops off the front to make room for each new one.
+/// Commands that go by while the panel is paused are counted but not kept.
+#[derive(Clone, Data, Debug)]
+pub struct CommandRing {
+    /// Each command, with its number in the whole run.
+    commands: Arc<VecDeque<(usize, RenderCommand)>>,
+    capacity: usize,
+    consumed: usize,
+}
+
+impl CommandRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            commands: Arc::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            consumed: 0,
+        }
+    }
+
+    pub fn push(&mut self, cmd: RenderCommand) {
+        let commands = Arc::make_mut(&mut self.commands);
+        if commands.len() == self.capacity {
+            commands.pop_front();
+        }
+        self.consumed += 1;
+        commands.push_back((self.consumed, cmd));
+    }
+
+    /// Count a command without keeping it.
+    pub fn skip(&mut self) {
+        self.consumed += 1;
+    }
+
+    pub fn clear(&mut self) {
+        Arc::make_mut(&mut self.commands).clear();
+        self.consumed = 0;
+    }
+
+    /// One command per line, numbered by its position in the whole run. A
+    /// line says how many were skipped wherever some were.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        let mut next = None;
+        for (num, cmd) in self.commands.iter() {
+            if let Some(next) = next.filter(|next| next < num) {
+                Self::write_skipped(&mut text, num - next);
+            }
+            let _ = writeln!(text, "{:>6}  {}", num, cmd);
+            next = Some(num + 1);
+        }
+
+        if let Some(next) = next.filter(|next| *next <= self.consumed) {
+            Self::write_skipped(&mut text, self.consumed + 1 - next);
+        }
+        text
+    }
+
+    fn write_skipped(text: &mut String, count: usize) {
+        let _ = writeln!(text, "{:>6}  {} skipped while paused", "...", count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_most_recent_commands() {
+        let mut ring = CommandRing::new(2);
+        ring.push(RenderCommand::ShowTurtle(true));
+        ring.push(RenderCommand::ShowTurtle(false));
+        ring.push(RenderCommand::ShowTurtle(true));
+
+        assert_eq!(
+            ring.text(),
+            "     2  ShowTurtle false\n     3  ShowTurtle true\n"
+        );
+
+        ring.clear();
+        assert_eq!(ring.text(), "");
+    }
+
+    #[test]
+    fn it_marks_commands_skipped_while_paused() {
+        let mut ring = CommandRing::new(3);
+        ring.push(RenderCommand::ShowTurtle(true));
+        ring.skip();
+        ring.skip();
+        ring.push(RenderCommand::ShowTurtle(false));
+        ring.skip();
+
+        let text = ring.text();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "     1  ShowTurtle true",
+                "   ...  2 skipped while paused",
+                "     4  ShowTurtle false",
+                "   ...  1 skipped while paused",
+            ]
+        );
+    }
+}
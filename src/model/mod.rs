@@ -12,6 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(not(target_arch = "wasm32"))]
 pub mod app;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod backup;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod console;
+pub mod layers;
+pub mod log;
+pub mod params;
+pub mod picture;
 pub mod pixbuf;
+pub mod prefs;
 pub mod render;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod session;
+pub mod speed;
+pub mod stats;
+pub mod viewport;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod watch;
@@ -0,0 +1,102 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use druid::Data;
+use druid::Lens;
+use std::collections::HashMap;
+
+/// A number the program declared with `param`, shown as a slider under the
+/// input.
+#[derive(Clone, Data, Debug, Lens, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub min: f64,
+    pub max: f64,
+    pub value: f64,
+}
+
+impl Param {
+    pub fn new(name: &str, min: f64, max: f64, value: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            min,
+            max,
+            value,
+        }
+    }
+
+    /// How far along its range the value is, from 0 to 1, for the slider.
+    pub fn fraction(&self) -> f64 {
+        if self.max > self.min {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            0.0
+        }
+    }
+
+    /// Kept to hundredths, so the value shown beside the slider is the one
+    /// the program gets.
+    pub fn set_fraction(&mut self, fraction: f64) {
+        let value = self.min + fraction * (self.max - self.min);
+        self.value = (value * 100.0).round() / 100.0;
+    }
+}
+
+/// The values to run the program with, by name.
+pub fn values(params: &[Param]) -> HashMap<String, f64> {
+    params
+        .iter()
+        .map(|param| (param.name.clone(), param.value))
+        .collect()
+}
+
+/// The sliders for what the program just declared. A slider that was already
+/// there keeps its value, as far as its new range allows, since it may have
+/// moved while the program ran.
+pub fn refresh(shown: &[Param], declared: Vec<Param>) -> Vec<Param> {
+    let shown = values(shown);
+    declared
+        .into_iter()
+        .map(|param| match shown.get(&param.name) {
+            Some(value) => Param {
+                value: value.max(param.min).min(param.max),
+                ..param
+            },
+            None => param,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_slider_values_within_range() {
+        let shown = vec![Param::new("size", 10.0, 200.0, 150.0)];
+        let declared = vec![
+            Param::new("size", 10.0, 100.0, 10.0),
+            Param::new("turn", 0.0, 90.0, 0.0),
+        ];
+
+        let params = refresh(&shown, declared);
+        assert_eq!(params[0].value, 100.0);
+        assert_eq!(params[1].value, 0.0);
+
+        let mut param = params[0].clone();
+        param.set_fraction(0.5);
+        assert_eq!(param.value, 55.0);
+        assert_eq!(param.fraction(), 0.5);
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use druid::Size;
+use png::ColorType;
+use png::Decoder;
+use png::Transformations;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+/// A picture decoded from disk, as unpremultiplied RGBA rows.
+#[derive(PartialEq)]
+pub struct Picture {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl Picture {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::decode(File::open(path)?)
+    }
+
+    /// Decode a PNG, widening whatever color type it uses to RGBA.
+    pub fn decode(r: impl Read) -> io::Result<Self> {
+        let mut decoder = Decoder::new(r);
+        decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+
+        let (info, mut reader) = decoder.read_info()?;
+        let mut buf = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buf)?;
+
+        let rgba = match info.color_type {
+            ColorType::RGBA => buf,
+            ColorType::RGB => buf
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], u8::MAX])
+                .collect(),
+            ColorType::GrayscaleAlpha => buf
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g, u8::MAX]).collect(),
+            ColorType::Indexed => {
+                let msg = "indexed color wasn't expanded";
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            rgba,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width as f64, self.height as f64)
+    }
+
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+}
+
+impl fmt::Debug for Picture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Picture")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use png::BitDepth;
+    use png::Encoder;
+
+    #[test]
+    fn it_widens_to_rgba() {
+        let mut png = Vec::new();
+        {
+            let mut encoder = Encoder::new(&mut png, 2, 1);
+            encoder.set_color(ColorType::RGB);
+            encoder.set_depth(BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[255, 0, 0, 0, 0, 255]).unwrap();
+        }
+
+        let picture = Picture::decode(png.as_slice()).unwrap();
+        assert_eq!(picture.size(), Size::new(2.0, 1.0));
+        assert_eq!(picture.rgba(), &[255, 0, 0, 255, 0, 0, 255, 255]);
+
+        assert!(Picture::decode(&b"not a png"[..]).is_err());
+    }
+}
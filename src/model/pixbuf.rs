@@ -12,46 +12,245 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::bits;
 use crate::common::constants::*;
 use druid::Color;
 use druid::Data;
 use druid::Point;
+use druid::Size;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-#[derive(Clone, Data, Debug)]
+/// Every frame of every buffer gets its own version, so two buffers are only
+/// the same if they're showing the same frame.
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(0);
+
+fn next_version() -> u64 {
+    NEXT_VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// RGBA pixel buffer. The buffer holds `scale` device pixels per logical
+/// pixel in each direction, so drawings stay sharp on high-DPI displays.
+///
+/// Drawing goes into a back buffer, and only shows up in `bytes` and
+/// `flatten` after `present`, so a frame is never seen half drawn.
+#[derive(Clone, Debug)]
 pub struct PixBuf {
     width: u32,
     height: u32,
-    pub bytes: Arc<Vec<u8>>,
+    scale: u32,
+    back: Arc<Vec<u8>>,
+    front: Arc<Vec<u8>>,
+    version: u64,
 }
 
 impl PixBuf {
-    pub fn _width(&self) -> u32 {
+    pub fn new(width: u32, height: u32, scale: u32) -> Self {
+        let scale = scale.max(1);
+        let width = width * scale;
+        let height = height * scale;
+        let dims = width as usize * height as usize * 4;
+
+        let bytes = Arc::new(vec![0; dims]);
+
+        Self {
+            width,
+            height,
+            scale,
+            back: bytes.clone(),
+            front: bytes,
+            version: next_version(),
+        }
+    }
+
+    /// Width of the buffer in device pixels.
+    pub fn width(&self) -> u32 {
         self.width
     }
 
-    pub fn _height(&self) -> u32 {
+    /// Height of the buffer in device pixels.
+    pub fn height(&self) -> u32 {
         self.height
     }
 
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Size of the buffer in logical pixels.
+    pub fn size(&self) -> Size {
+        Size::new(
+            (self.width / self.scale) as f64,
+            (self.height / self.scale) as f64,
+        )
+    }
+
+    /// Center of the buffer in logical pixels.
+    pub fn origin(&self) -> Point {
+        let size = self.size();
+        Point::new((size.width / 2.0).floor(), (size.height / 2.0).floor())
+    }
+
+    /// The last frame presented.
     pub fn bytes(&self) -> &[u8] {
-        &self.bytes
+        &self.front
+    }
+
+    /// Which frame is showing. It changes whenever `bytes` does, so anything
+    /// made from them can be kept until then.
+    pub fn version(&self) -> u64 {
+        self.version
     }
 
+    /// Show everything drawn since the last frame. The front buffer shares
+    /// the back one until the next write, so this doesn't copy.
+    pub fn present(&mut self) {
+        if !Arc::ptr_eq(&self.front, &self.back) {
+            self.front = self.back.clone();
+            self.version = next_version();
+        }
+    }
+
+    /// Clearing shows straight away.
     pub fn clear(&mut self) {
-        let mut pixels = Arc::make_mut(&mut self.bytes);
-        bits::zero(&mut pixels);
+        self.fill(&Color::TRANSPARENT);
+        self.present();
+    }
+
+    /// Paint every pixel one color. Only the first row is written a pixel at
+    /// a time; the rest are copied from it.
+    pub fn fill(&mut self, color: &Color) {
+        let row_len = self.width as usize * 4;
+        if row_len == 0 {
+            return;
+        }
+
+        // A buffer that's still shared would be copied only to be painted
+        // over, so start from a fresh one instead.
+        if Arc::get_mut(&mut self.back).is_none() {
+            self.back = Arc::new(vec![0; self.front.len()]);
+        }
+
+        let bytes = Arc::make_mut(&mut self.back);
+        let (first, rest) = bytes.split_at_mut(row_len);
+
+        let (red, green, blue, alpha) = color.as_rgba8();
+        for px in first.chunks_exact_mut(4) {
+            px.copy_from_slice(&[red, green, blue, alpha]);
+        }
+
+        for row in rest.chunks_exact_mut(row_len) {
+            row.copy_from_slice(first);
+        }
     }
 
+    /// Copy the last frame of `src` into the drawing in progress, with its top
+    /// left corner at device pixel `(x, y)`. Whatever falls outside this
+    /// buffer is left out. Rows are copied whole, not a pixel at a time.
+    pub fn copy_rect(&mut self, src: &PixBuf, x: i32, y: i32) {
+        let left = x.max(0);
+        let top = y.max(0);
+        let right = x.saturating_add(src.width as i32).min(self.width as i32);
+        let bottom = y.saturating_add(src.height as i32).min(self.height as i32);
+        if left >= right || top >= bottom {
+            return;
+        }
+
+        let run = (right - left) as usize * 4;
+        let dst_start = left as usize * 4;
+        let src_start = (left - x) as usize * 4;
+
+        let dst_rows = Arc::make_mut(&mut self.back)
+            .chunks_exact_mut(self.width as usize * 4)
+            .skip(top as usize);
+        let src_rows = src
+            .front
+            .chunks_exact(src.width as usize * 4)
+            .skip((top - y) as usize);
+
+        for (dst, src) in dst_rows.zip(src_rows).take((bottom - top) as usize) {
+            dst[dst_start..dst_start + run].copy_from_slice(&src[src_start..src_start + run]);
+        }
+    }
+
+    /// Composite the buffer over an opaque background, returning RGBA bytes.
+    pub fn flatten(&self, background: &Color) -> Vec<u8> {
+        let (bg_red, bg_green, bg_blue, _) = background.as_rgba8();
+        let mut out = Vec::with_capacity(self.front.len());
+
+        for px in self.front.chunks_exact(4) {
+            let alpha = px[3] as u32;
+            let blend =
+                |fg: u8, bg: u8| ((fg as u32 * alpha + bg as u32 * (255 - alpha)) / 255) as u8;
+            out.push(blend(px[0], bg_red));
+            out.push(blend(px[1], bg_green));
+            out.push(blend(px[2], bg_blue));
+            out.push(255);
+        }
+
+        out
+    }
+
+    /// The smallest box around every pixel with some ink in the last frame
+    /// presented, as device pixel `(x, y, width, height)`.
+    pub fn ink_bounds(&self) -> Option<(u32, u32, u32, u32)> {
+        let width = self.width as usize;
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+        for (idx, px) in self.front.chunks_exact(4).enumerate() {
+            if px[3] == 0 {
+                continue;
+            }
+
+            let (x, y) = (idx % width, idx / width);
+            bounds = Some(match bounds {
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                None => (x, y, x, y),
+            });
+        }
+
+        bounds.map(|(x0, y0, x1, y1)| {
+            (
+                x0 as u32,
+                y0 as u32,
+                (x1 - x0 + 1) as u32,
+                (y1 - y0 + 1) as u32,
+            )
+        })
+    }
+
+    /// A copy of part of the last frame presented, in device pixels. The copy
+    /// has one device pixel per logical pixel.
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+
+        let mut bytes = Vec::with_capacity(width as usize * height as usize * 4);
+        for row in y..y + height {
+            let start = (row as usize * self.width as usize + x as usize) * 4;
+            bytes.extend_from_slice(&self.front[start..start + width as usize * 4]);
+        }
+
+        let bytes = Arc::new(bytes);
+        Self {
+            width,
+            height,
+            scale: 1,
+            back: bytes.clone(),
+            front: bytes,
+            version: next_version(),
+        }
+    }
+
+    /// Reads the drawing in progress, which may be ahead of what's shown.
     pub fn read_xy(&self, x: usize, y: usize) -> Color {
         let byte_idx = (y * (self.width as usize) + x) * 4;
 
         druid::Color::rgba8(
-            self.bytes[byte_idx],
-            self.bytes[byte_idx + 1],
-            self.bytes[byte_idx + 2],
-            self.bytes[byte_idx + 3],
+            self.back[byte_idx],
+            self.back[byte_idx + 1],
+            self.back[byte_idx + 2],
+            self.back[byte_idx + 3],
         )
     }
 
@@ -60,8 +259,8 @@ impl PixBuf {
         self.read_xy(p.x as usize, p.y as usize)
     }
 
-    fn _write_xy_inner(bytes: &mut [u8], x: usize, y: usize, color: &Color) {
-        let byte_idx = (y * (DIMS.width as usize) + x) * 4;
+    fn _write_xy_inner(bytes: &mut [u8], width: u32, x: usize, y: usize, color: &Color) {
+        let byte_idx = (y * (width as usize) + x) * 4;
         let (red, green, blue, alpha) = color.as_rgba8();
         bytes[byte_idx] = red;
         bytes[byte_idx + 1] = green;
@@ -69,38 +268,176 @@ impl PixBuf {
         bytes[byte_idx + 3] = alpha;
     }
 
-    pub fn write_xy_inner_clipped(bytes: &mut [u8], x: i32, y: i32, color: &Color) {
-        if Self::contains(x, y) {
-            Self::_write_xy_inner(bytes, x as usize, y as usize, color);
+    /// Composite a color over the pixel that's there, using source-over
+    /// blending with straight alpha.
+    pub fn blend_xy(&mut self, x: usize, y: usize, color: &Color) {
+        let (red, green, blue, alpha) = color.as_rgba8();
+        match alpha {
+            0 => {}
+            255 => self.write_xy(x, y, color),
+            _ => {
+                let byte_idx = (y * (self.width as usize) + x) * 4;
+                let bytes = Arc::make_mut(&mut self.back);
+                let dst = &mut bytes[byte_idx..byte_idx + 4];
+
+                let src_a = alpha as u32;
+                let dst_a = dst[3] as u32 * (255 - src_a) / 255;
+                let out_a = src_a + dst_a;
+                let mix =
+                    |src: u8, dst: u8| ((src as u32 * src_a + dst as u32 * dst_a) / out_a) as u8;
+
+                dst[0] = mix(red, dst[0]);
+                dst[1] = mix(green, dst[1]);
+                dst[2] = mix(blue, dst[2]);
+                dst[3] = out_a as u8;
+            }
+        }
+    }
+
+    /// Blend a pixel if it's inside the buffer, and say whether it was.
+    pub fn blend_xy_clipped(&mut self, x: i32, y: i32, color: &Color) -> bool {
+        let inside = self.contains(x, y);
+        if inside {
+            self.blend_xy(x as usize, y as usize, color);
         }
+        inside
+    }
+
+    /// Write a pixel if it's inside the buffer, and say whether it was.
+    pub fn write_xy_clipped(&mut self, x: i32, y: i32, color: &Color) -> bool {
+        let inside = self.contains(x, y);
+        if inside {
+            self.write_xy(x as usize, y as usize, color);
+        }
+        inside
     }
 
     pub fn write_xy(&mut self, x: usize, y: usize, color: &Color) {
-        let bytes = Arc::make_mut(&mut self.bytes);
-        Self::_write_xy_inner(bytes, x, y, color);
+        let bytes = Arc::make_mut(&mut self.back);
+        Self::_write_xy_inner(bytes, self.width, x, y, color);
     }
 
     fn _write(&mut self, p: Point, color: &Color) {
         self.write_xy(p.x as usize, p.y as usize, color);
     }
 
-    pub fn screen_xy(x: i32, y: i32) -> (i32, i32) {
-        (x + ORIGIN.x as i32, y + ORIGIN.y as i32)
+    /// Translate device pixel offsets from the center into buffer coordinates.
+    pub fn screen_xy(&self, x: i32, y: i32) -> (i32, i32) {
+        let origin = self.origin();
+        let scale = self.scale as i32;
+        (x + origin.x as i32 * scale, y + origin.y as i32 * scale)
     }
 
-    pub fn contains(x: i32, y: i32) -> bool {
-        x >= 0 && x < DIMS.width as i32 && y >= 0 && y < DIMS.height as i32
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32
+    }
+}
+
+impl Data for PixBuf {
+    fn same(&self, other: &Self) -> bool {
+        self.version == other.version
     }
 }
 
 impl Default for PixBuf {
     fn default() -> Self {
-        let dims = DIMS.width as usize * DIMS.height as usize * 4;
+        Self::new(DIMS.width as u32, DIMS.height as u32, 1)
+    }
+}
 
-        Self {
-            width: DIMS.width as u32,
-            height: DIMS.height as u32,
-            bytes: Arc::new(vec![0; dims]),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_shows_drawing_once_presented() {
+        let mut pixels = PixBuf::new(2, 2, 1);
+        let before = pixels.clone();
+
+        pixels.write_xy(0, 0, &Color::WHITE);
+        assert_eq!(pixels.read_xy(0, 0), Color::WHITE);
+        assert_eq!(pixels.bytes()[..4], [0, 0, 0, 0]);
+        assert!(pixels.same(&before));
+
+        pixels.present();
+        assert_eq!(pixels.bytes()[..4], [255, 255, 255, 255]);
+        assert!(!pixels.same(&before));
+
+        // Nothing new to show, so it's still the same frame.
+        let after = pixels.clone();
+        pixels.present();
+        assert!(pixels.same(&after));
+        assert_eq!(pixels.version(), after.version());
+
+        // Buffers made separately are never the same frame.
+        assert!(!PixBuf::new(2, 2, 1).same(&PixBuf::new(2, 2, 1)));
+    }
+
+    #[test]
+    fn it_crops_to_the_ink() {
+        let mut pixels = PixBuf::new(4, 3, 1);
+        assert_eq!(pixels.ink_bounds(), None);
+
+        pixels.write_xy(1, 0, &Color::WHITE);
+        pixels.write_xy(2, 1, &Color::rgba8(1, 2, 3, 4));
+        pixels.present();
+        assert_eq!(pixels.ink_bounds(), Some((1, 0, 2, 2)));
+
+        let cropped = pixels.crop(2, 1, 5, 5);
+        assert_eq!((cropped.width(), cropped.height()), (2, 2));
+        assert_eq!(cropped.read_xy(0, 0), Color::rgba8(1, 2, 3, 4));
+        assert_eq!(cropped.read_xy(1, 1), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn it_fills_and_clears() {
+        let mut pixels = PixBuf::new(3, 2, 2);
+        let shown = pixels.clone();
+
+        pixels.fill(&Color::rgba8(1, 2, 3, 4));
+        for y in 0..4 {
+            for x in 0..6 {
+                assert_eq!(pixels.read_xy(x, y), Color::rgba8(1, 2, 3, 4));
+            }
         }
+        assert!(shown.bytes().iter().all(|b| *b == 0));
+
+        pixels.clear();
+        assert!(pixels.bytes().iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn it_clips_copies_at_the_edges() {
+        let mut stamp = PixBuf::new(2, 2, 1);
+        stamp.write_xy(0, 0, &Color::rgba8(1, 0, 0, 255));
+        stamp.write_xy(1, 0, &Color::rgba8(2, 0, 0, 255));
+        stamp.write_xy(0, 1, &Color::rgba8(3, 0, 0, 255));
+        stamp.write_xy(1, 1, &Color::rgba8(4, 0, 0, 255));
+        stamp.present();
+
+        // Hanging off the top left, only the bottom right pixel lands.
+        let mut pixels = PixBuf::new(3, 3, 1);
+        pixels.copy_rect(&stamp, -1, -1);
+        assert_eq!(pixels.read_xy(0, 0), Color::rgba8(4, 0, 0, 255));
+        assert_eq!(pixels.read_xy(1, 0), Color::TRANSPARENT);
+        assert_eq!(pixels.read_xy(0, 1), Color::TRANSPARENT);
+
+        // Hanging off the bottom right, only the top left pixel lands.
+        pixels.copy_rect(&stamp, 2, 2);
+        assert_eq!(pixels.read_xy(2, 2), Color::rgba8(1, 0, 0, 255));
+        assert_eq!(pixels.read_xy(1, 2), Color::TRANSPARENT);
+
+        // Off the edge on one side only.
+        pixels.copy_rect(&stamp, 2, 0);
+        assert_eq!(pixels.read_xy(2, 0), Color::rgba8(1, 0, 0, 255));
+        assert_eq!(pixels.read_xy(2, 1), Color::rgba8(3, 0, 0, 255));
+
+        // Entirely outside, so nothing changes.
+        let before = pixels.read_xy(0, 0);
+        pixels.copy_rect(&stamp, -2, 0);
+        pixels.copy_rect(&stamp, 0, 3);
+        pixels.copy_rect(&stamp, i32::MAX, i32::MIN);
+        assert_eq!(pixels.read_xy(0, 0), before);
+        assert_eq!(pixels.read_xy(0, 1), Color::TRANSPARENT);
     }
 }
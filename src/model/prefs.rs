@@ -0,0 +1,447 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::constants::*;
+use crate::common::paths;
+use druid::Color;
+use druid::Data;
+use druid::Lens;
+use druid::Size;
+use serde::Deserialize;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const LIBRARY_DIR: &str = "library";
+const PREFS_FILE: &str = "preferences";
+
+/// A language for the interface, or for keywords that work alongside the
+/// English ones.
+#[derive(Clone, Copy, Data, Debug, PartialEq)]
+pub enum Language {
+    English,
+    French,
+    Spanish,
+}
+
+impl Language {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+            Language::Spanish => "es",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "en" => Some(Language::English),
+            "fr" => Some(Language::French),
+            "es" => Some(Language::Spanish),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Data, Debug, PartialEq)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+/// What the turtle looks like on the canvas.
+#[derive(Clone, Copy, Data, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TurtleShape {
+    Circle,
+    Triangle,
+    Turtle,
+    /// A picture from disk, named by `turtle_image`.
+    Image,
+}
+
+impl TurtleShape {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TurtleShape::Circle => "circle",
+            TurtleShape::Triangle => "triangle",
+            TurtleShape::Turtle => "turtle",
+            TurtleShape::Image => "image",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "circle" => Some(TurtleShape::Circle),
+            "triangle" => Some(TurtleShape::Triangle),
+            "turtle" => Some(TurtleShape::Turtle),
+            "image" => Some(TurtleShape::Image),
+            _ => None,
+        }
+    }
+}
+
+/// User preferences, persisted to disk and applied at startup.
+#[derive(Clone, Data, Debug, Lens, PartialEq)]
+pub struct Preferences {
+    pub antialias: bool,
+    /// Seconds between backups of unsaved input. Zero turns them off.
+    pub autosave: u32,
+    /// Flash the status bar for each render command at slow speeds.
+    pub command_flash: bool,
+    pub export_delay: u32,
+    pub export_step: u32,
+    pub height: f64,
+    /// Where the split between the input and the canvas was left.
+    pub input_width: f64,
+    pub language: Language,
+    /// Directories that `load` and `save` may use, separated like `PATH`.
+    pub library_path: String,
+    /// Simplify programs before running them. Turning this off helps when
+    /// debugging the optimizer itself.
+    pub optimize: bool,
+    pub pen_color: Color,
+    pub screen_color: Color,
+    /// Addresses that may connect to the server, separated by commas.
+    pub server_allow: String,
+    /// The TCP port other programs can drive the app through, or zero to
+    /// leave the server off. Takes effect the next time the app starts.
+    pub server_port: u16,
+    pub speed: u32,
+    /// The size of the drawing, which can be bigger than the canvas that
+    /// shows it. The canvas pans and zooms over the rest.
+    pub surface_height: f64,
+    pub surface_width: f64,
+    pub theme: Theme,
+    /// Seconds a program may run, not counting time held or slowed down to
+    /// watch. Zero turns the limit off.
+    pub time_limit: u32,
+    pub turtle_image: String,
+    pub turtle_shape: TurtleShape,
+    /// The language of the menus, windows and console.
+    pub ui_language: Language,
+    pub width: f64,
+}
+
+impl Preferences {
+    pub fn load() -> Self {
+        match Self::path().and_then(|path| fs::read_to_string(path).ok()) {
+            Some(string) => Self::parse(&string),
+            None => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, self.format())
+    }
+
+    pub fn canvas_size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    /// Never smaller than the canvas.
+    pub fn surface_size(&self) -> Size {
+        Size::new(
+            self.surface_width.max(self.width),
+            self.surface_height.max(self.height),
+        )
+    }
+
+    pub fn library_dirs(&self) -> Vec<PathBuf> {
+        env::split_paths(&self.library_path)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .collect()
+    }
+
+    fn path() -> Option<PathBuf> {
+        paths::config_dir().map(|dir| dir.join(PREFS_FILE))
+    }
+
+    fn parse(string: &str) -> Self {
+        let mut prefs = Self::default();
+
+        for line in string.lines() {
+            if let Some((key, val)) = line.split_once('=') {
+                prefs.set(key.trim(), val.trim());
+            }
+        }
+
+        prefs
+    }
+
+    fn set(&mut self, key: &str, val: &str) {
+        match key {
+            "antialias" => {
+                if let Ok(b) = val.parse() {
+                    self.antialias = b;
+                }
+            }
+            "autosave" => {
+                if let Ok(a) = val.parse::<u32>() {
+                    self.autosave = a.min(MAX_AUTOSAVE);
+                }
+            }
+            "command_flash" => {
+                if let Ok(b) = val.parse() {
+                    self.command_flash = b;
+                }
+            }
+            "export_delay" => {
+                if let Ok(d) = val.parse::<u32>() {
+                    self.export_delay = d.clamp(10, 1000);
+                }
+            }
+            "export_step" => {
+                if let Ok(s) = val.parse::<u32>() {
+                    self.export_step = s.max(1);
+                }
+            }
+            "height" => {
+                if let Ok(h) = val.parse::<f64>() {
+                    self.height = h.clamp(MIN_DIMS.height, MAX_DIMS.height);
+                }
+            }
+            "input_width" => {
+                if let Ok(w) = val.parse::<f64>() {
+                    self.input_width = w.clamp(MIN_INPUT_WIDTH, MAX_INPUT_WIDTH);
+                }
+            }
+            "language" => {
+                if let Some(language) = Language::from_code(val) {
+                    self.language = language;
+                }
+            }
+            "library_path" => self.library_path = val.to_string(),
+            "optimize" => {
+                if let Ok(b) = val.parse() {
+                    self.optimize = b;
+                }
+            }
+            "pen_color" => {
+                if let Ok(rgba) = u32::from_str_radix(val, 16) {
+                    self.pen_color = Color::from_rgba32_u32(rgba);
+                }
+            }
+            "screen_color" => {
+                if let Ok(rgba) = u32::from_str_radix(val, 16) {
+                    self.screen_color = Color::from_rgba32_u32(rgba);
+                }
+            }
+            "server_allow" => self.server_allow = val.to_string(),
+            "server_port" => {
+                if let Ok(p) = val.parse() {
+                    self.server_port = p;
+                }
+            }
+            "speed" => {
+                if let Ok(s) = val.parse::<u32>() {
+                    self.speed = s.clamp(MIN_SPEED, MAX_SPEED);
+                }
+            }
+            "surface_height" => {
+                if let Ok(h) = val.parse::<f64>() {
+                    self.surface_height = h.clamp(MIN_DIMS.height, MAX_DIMS.height);
+                }
+            }
+            "surface_width" => {
+                if let Ok(w) = val.parse::<f64>() {
+                    self.surface_width = w.clamp(MIN_DIMS.width, MAX_DIMS.width);
+                }
+            }
+            "theme" => match val {
+                "dark" => self.theme = Theme::Dark,
+                "light" => self.theme = Theme::Light,
+                _ => {}
+            },
+            "time_limit" => {
+                if let Ok(t) = val.parse::<u32>() {
+                    self.time_limit = t.min(MAX_TIME_LIMIT);
+                }
+            }
+            "turtle_image" => self.turtle_image = val.to_string(),
+            "turtle_shape" => {
+                if let Some(shape) = TurtleShape::from_name(val) {
+                    self.turtle_shape = shape;
+                }
+            }
+            "ui_language" => {
+                if let Some(language) = Language::from_code(val) {
+                    self.ui_language = language;
+                }
+            }
+            "width" => {
+                if let Ok(w) = val.parse::<f64>() {
+                    self.width = w.clamp(MIN_DIMS.width, MAX_DIMS.width);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn format(&self) -> String {
+        format!(
+            "antialias = {}\n\
+            autosave = {}\n\
+            command_flash = {}\n\
+            export_delay = {}\n\
+            export_step = {}\n\
+            height = {}\n\
+            input_width = {}\n\
+            language = {}\n\
+            library_path = {}\n\
+            optimize = {}\n\
+            pen_color = {:08x}\n\
+            screen_color = {:08x}\n\
+            server_allow = {}\n\
+            server_port = {}\n\
+            speed = {}\n\
+            surface_height = {}\n\
+            surface_width = {}\n\
+            theme = {}\n\
+            time_limit = {}\n\
+            turtle_image = {}\n\
+            turtle_shape = {}\n\
+            ui_language = {}\n\
+            width = {}\n",
+            self.antialias,
+            self.autosave,
+            self.command_flash,
+            self.export_delay,
+            self.export_step,
+            self.height,
+            self.input_width,
+            self.language.code(),
+            self.library_path,
+            self.optimize,
+            self.pen_color.as_rgba_u32(),
+            self.screen_color.as_rgba_u32(),
+            self.server_allow,
+            self.server_port,
+            self.speed,
+            self.surface_height,
+            self.surface_width,
+            match self.theme {
+                Theme::Dark => "dark",
+                Theme::Light => "light",
+            },
+            self.time_limit,
+            self.turtle_image,
+            self.turtle_shape.name(),
+            self.ui_language.code(),
+            self.width
+        )
+    }
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            antialias: true,
+            autosave: AUTOSAVE,
+            command_flash: false,
+            export_delay: 40,
+            export_step: 10,
+            height: DIMS.height,
+            input_width: INPUT_WIDTH,
+            language: Language::English,
+            library_path: paths::config_dir()
+                .map(|dir| dir.join(LIBRARY_DIR).to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            optimize: true,
+            pen_color: Color::WHITE,
+            screen_color: Color::BLACK,
+            server_allow: "127.0.0.1".to_string(),
+            server_port: 0,
+            speed: 4,
+            // As small as they go, so the surface follows the canvas.
+            surface_height: MIN_DIMS.height,
+            surface_width: MIN_DIMS.width,
+            theme: Theme::Dark,
+            time_limit: TIME_LIMIT,
+            turtle_image: String::new(),
+            turtle_shape: TurtleShape::Triangle,
+            ui_language: Language::English,
+            width: DIMS.width,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_round_trips() {
+        let prefs = Preferences {
+            antialias: false,
+            autosave: 0,
+            command_flash: true,
+            export_delay: 100,
+            export_step: 25,
+            height: 480.0,
+            input_width: 420.0,
+            language: Language::Spanish,
+            library_path: "/tmp/logo".to_string(),
+            optimize: false,
+            pen_color: Color::rgb8(250, 128, 114),
+            screen_color: Color::WHITE,
+            server_allow: "127.0.0.1, 10.0.0.2".to_string(),
+            server_port: 7070,
+            speed: 8,
+            surface_height: 2048.0,
+            surface_width: 4096.0,
+            theme: Theme::Light,
+            time_limit: 0,
+            turtle_image: "/tmp/turtle.png".to_string(),
+            turtle_shape: TurtleShape::Image,
+            ui_language: Language::French,
+            width: 640.0,
+        };
+        assert_eq!(Preferences::parse(&prefs.format()), prefs);
+    }
+
+    #[test]
+    fn it_clamps_and_ignores_bad_values() {
+        let prefs = Preferences::parse("width = 1\nspeed = fast\nbogus = 3\n");
+        assert_eq!(prefs.width, MIN_DIMS.width);
+        assert_eq!(prefs.speed, Preferences::default().speed);
+    }
+
+    #[test]
+    fn it_keeps_the_surface_as_big_as_the_canvas() {
+        let prefs = Preferences::parse(
+            "width = 1000
+surface_width = 4000
+surface_height = 100
+",
+        );
+        assert_eq!(prefs.surface_size(), Size::new(4000.0, DIMS.height));
+
+        let prefs = Preferences {
+            width: 400.0,
+            height: 300.0,
+            ..Default::default()
+        };
+        assert_eq!(prefs.surface_size(), prefs.canvas_size());
+    }
+}
@@ -12,11 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::picture::Picture;
+use super::prefs::TurtleShape;
 use druid::Color;
 use druid::Data;
 use druid::Point;
 use futures::channel::mpsc::UnboundedReceiver;
 use futures::channel::mpsc::UnboundedSender;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
 
 pub const PEN_FLAGS_MASK_VIS: u32 = 0xff;
 pub const PEN_FLAGS_DOWN: u32 = 1 << 0;
@@ -42,12 +48,14 @@ pub fn is_pen_erase(flags: u32) -> bool {
     flags & PEN_FLAGS_ERASE == PEN_FLAGS_ERASE
 }
 
-#[derive(Clone, Data, Debug, PartialEq)]
+#[derive(Clone, Data, Debug, Deserialize, PartialEq, Serialize)]
 pub struct MoveTo {
     angle: f64,
+    #[serde(with = "rgba")]
     pub color: Color,
     distance: f64,
     pub pen_flags: u32,
+    #[serde(with = "xy")]
     pub pos: Point,
 }
 
@@ -63,11 +71,125 @@ impl MoveTo {
     }
 }
 
-#[derive(Clone, Data, Debug, PartialEq)]
+#[derive(Clone, Data, Debug, Deserialize, PartialEq, Serialize)]
 pub enum RenderCommand {
-    Fill(Color),
+    /// A picture to show behind the drawing. It's too big to be worth saving
+    /// with the rest, so it can't be serialized.
+    #[serde(skip)]
+    Background(Arc<Picture>),
+    /// Wipe the drawing, leaving the background and the turtle alone.
+    Clean,
+    Fill(#[serde(with = "rgba")] Color),
     MoveTo(MoveTo),
+    /// Pen-down moves one after another, all in the same color and pen, so a
+    /// long drawing doesn't cost a command for every line.
+    Path(Vec<MoveTo>),
+    ScreenColor(#[serde(with = "rgba")] Color),
     ShowTurtle(bool),
+    Shape(TurtleShape),
+    /// How big the turtle is drawn, in canvas pixels.
+    Size(f64),
+    /// How many commands the canvas takes between pauses, set by the program.
+    Speed(u32),
+    /// The turtle's heading in degrees, clockwise from up.
+    Turn(f64),
+}
+
+impl RenderCommand {
+    /// The moves the turtle makes, if any.
+    pub fn moves(&self) -> &[MoveTo] {
+        match self {
+            RenderCommand::MoveTo(move_to) => std::slice::from_ref(move_to),
+            RenderCommand::Path(moves) => moves,
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for RenderCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderCommand::Background(picture) => {
+                write!(f, "Background {}x{}", picture.width(), picture.height())
+            }
+            RenderCommand::Clean => write!(f, "Clean"),
+            RenderCommand::Fill(color) => write!(f, "Fill {}", hex(color)),
+            RenderCommand::MoveTo(move_to) => write!(
+                f,
+                "MoveTo {:.1},{:.1} {} pen {}",
+                move_to.pos.x,
+                move_to.pos.y,
+                hex(&move_to.color),
+                if is_pen_down(move_to.pen_flags) {
+                    "down"
+                } else {
+                    "up"
+                }
+            ),
+            RenderCommand::Path(moves) => match moves.last() {
+                Some(last) => write!(
+                    f,
+                    "Path {} lines to {:.1},{:.1} {}",
+                    moves.len(),
+                    last.pos.x,
+                    last.pos.y,
+                    hex(&last.color)
+                ),
+                None => write!(f, "Path 0 lines"),
+            },
+            RenderCommand::ScreenColor(color) => write!(f, "ScreenColor {}", hex(color)),
+            RenderCommand::ShowTurtle(val) => write!(f, "ShowTurtle {}", val),
+            RenderCommand::Shape(shape) => write!(f, "Shape {}", shape.name()),
+            RenderCommand::Size(size) => write!(f, "Size {:.1}", size),
+            RenderCommand::Speed(speed) => write!(f, "Speed {}", speed),
+            RenderCommand::Turn(heading) => write!(f, "Turn {:.1}", heading),
+        }
+    }
+}
+
+/// "#rrggbb", with the alpha on the end when the color isn't opaque.
+fn hex(color: &Color) -> String {
+    match color.as_rgba8() {
+        (r, g, b, u8::MAX) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        (r, g, b, a) => format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a),
+    }
+}
+
+/// Colors serialize as `[r, g, b, a]`, the same as they're written in Logo.
+mod rgba {
+    use druid::Color;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        let (r, g, b, a) = color.as_rgba8();
+        [r, g, b, a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [r, g, b, a] = <[u8; 4]>::deserialize(deserializer)?;
+        Ok(Color::rgba8(r, g, b, a))
+    }
+}
+
+/// Points serialize as `[x, y]`.
+mod xy {
+    use druid::Point;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(point: &Point, serializer: S) -> Result<S::Ok, S::Error> {
+        [point.x, point.y].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Point, D::Error> {
+        let [x, y] = <[f64; 2]>::deserialize(deserializer)?;
+        Ok(Point::new(x, y))
+    }
 }
 
 pub type RenderRx = UnboundedReceiver<RenderCommand>;
@@ -93,4 +215,33 @@ mod tests {
         let got = pen_up(input);
         assert_eq!(got, PEN_FLAGS_UP | PEN_FLAGS_REVERSE);
     }
+
+    #[test]
+    fn it_describes_commands() {
+        let move_to = MoveTo::new(
+            0.0,
+            Color::RED,
+            10.0,
+            PEN_FLAGS_DEFAULT,
+            Point::new(3.0, -4.5),
+        );
+        assert_eq!(
+            RenderCommand::MoveTo(move_to.clone()).to_string(),
+            "MoveTo 3.0,-4.5 #ff0000 pen down"
+        );
+        let path = vec![move_to.clone(), move_to];
+        assert_eq!(
+            RenderCommand::Path(path).to_string(),
+            "Path 2 lines to 3.0,-4.5 #ff0000"
+        );
+        assert_eq!(
+            RenderCommand::Fill(Color::BLACK).to_string(),
+            "Fill #000000"
+        );
+        assert_eq!(RenderCommand::Turn(90.0).to_string(), "Turn 90.0");
+        assert_eq!(
+            RenderCommand::Shape(TurtleShape::Circle).to_string(),
+            "Shape circle"
+        );
+    }
 }
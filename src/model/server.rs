@@ -0,0 +1,244 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::render::RenderCommand;
+use crate::common::commands;
+use druid::ExtEventSink;
+use druid::Target;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+/// What another program can ask for, one JSON object per line:
+/// `{"logo": "fd 100"}` runs some Logo, and `{"commands": [...]}` draws
+/// render commands in the format that File > Export Commands writes.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Request {
+    Logo(String),
+    Commands(Vec<RenderCommand>),
+}
+
+/// The answer to a request, also one JSON object per line: `{"ok": "..."}`
+/// with the program's output, or `{"error": "..."}`.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reply {
+    Ok(String),
+    Error(String),
+}
+
+/// A request on its way to the app, with somewhere to send the reply.
+pub struct ServerRequest {
+    request: Request,
+    reply_tx: Mutex<mpsc::Sender<Reply>>,
+}
+
+impl ServerRequest {
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    pub fn reply(&self, reply: Reply) {
+        // The client may have gone away, and that's fine.
+        let _ = self.reply_tx.lock().unwrap().send(reply);
+    }
+}
+
+impl fmt::Debug for ServerRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerRequest")
+            .field("request", &self.request)
+            .finish()
+    }
+}
+
+/// The addresses allowed to connect, from a comma-separated list. Anything
+/// that isn't an IP address is ignored.
+pub fn parse_allow_list(list: &str) -> Vec<IpAddr> {
+    list.split(',')
+        .filter_map(|addr| addr.trim().parse().ok())
+        .collect()
+}
+
+/// Listen for other programs on `port`, taking connections only from the
+/// addresses in `allow`. Unless one of them is another machine, nothing
+/// outside this one can even reach the port.
+pub fn start(port: u16, allow: Vec<IpAddr>, sink: ExtEventSink) -> io::Result<SocketAddr> {
+    let host = if allow.iter().all(|addr| addr.is_loopback()) {
+        Ipv4Addr::LOCALHOST
+    } else {
+        Ipv4Addr::UNSPECIFIED
+    };
+
+    let listener = TcpListener::bind((host, port))?;
+    let local_addr = listener.local_addr()?;
+
+    thread::Builder::new()
+        .name("server".to_string())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let allowed = stream
+                    .peer_addr()
+                    .map(|peer| is_allowed(&allow, peer.ip()))
+                    .unwrap_or(false);
+                if !allowed {
+                    continue;
+                }
+
+                let sink = sink.clone();
+                let _ = thread::Builder::new()
+                    .name("server-client".to_string())
+                    .spawn(move || serve(stream, sink));
+            }
+        })?;
+
+    Ok(local_addr)
+}
+
+fn is_allowed(allow: &[IpAddr], addr: IpAddr) -> bool {
+    let addr = match addr {
+        IpAddr::V6(v6) => v6.to_ipv4().map(IpAddr::V4).unwrap_or(addr),
+        IpAddr::V4(_) => addr,
+    };
+    allow.contains(&addr)
+}
+
+/// Answer requests from one client until it hangs up.
+fn serve(stream: TcpStream, sink: ExtEventSink) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    answer(BufReader::new(stream), &mut writer, |request| {
+        submit(request, &sink)
+    })
+}
+
+/// Answer each request line with a reply line. The first line that isn't a
+/// request gets an error and ends the conversation. Any web page can post
+/// to a port on this machine, and a request hidden in the body would run,
+/// but the post starts with an HTTP request line, so it goes no further.
+fn answer(
+    reader: impl BufRead,
+    writer: &mut impl Write,
+    mut submit: impl FnMut(Request) -> Reply,
+) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (reply, done) = match serde_json::from_str(&line) {
+            Ok(request) => (submit(request), false),
+            Err(err) => (Reply::Error(format!("bad request: {}", err)), true),
+        };
+
+        let mut json = serde_json::to_string(&reply)?;
+        json.push('\n');
+        writer.write_all(json.as_bytes())?;
+
+        if done {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Hand the request to the app and wait for it to be dealt with.
+fn submit(request: Request, sink: &ExtEventSink) -> Reply {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    let request = Arc::new(ServerRequest {
+        request,
+        reply_tx: Mutex::new(reply_tx),
+    });
+
+    if sink
+        .submit_command(commands::SERVER_REQUEST, request, Target::Auto)
+        .is_err()
+    {
+        return Reply::Error("the app is shutting down".to_string());
+    }
+
+    reply_rx
+        .recv()
+        .unwrap_or_else(|_| Reply::Error("the program stopped without an answer".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_requests() {
+        let request: Request = serde_json::from_str(r#"{"logo": "fd 10"}"#).unwrap();
+        assert_eq!(request, Request::Logo("fd 10".to_string()));
+
+        let request: Request = serde_json::from_str(r#"{"commands": ["Clean"]}"#).unwrap();
+        assert_eq!(request, Request::Commands(vec![RenderCommand::Clean]));
+
+        assert!(serde_json::from_str::<Request>(r#"{"shell": "ls"}"#).is_err());
+
+        let reply = serde_json::to_string(&Reply::Error("no".to_string())).unwrap();
+        assert_eq!(reply, r#"{"error":"no"}"#);
+    }
+
+    #[test]
+    fn it_hangs_up_on_anything_but_requests() {
+        let input = "\n{\"logo\": \"fd 1\"}\n\
+            POST / HTTP/1.1\n\
+            Content-Type: text/plain\n\
+            \n\
+            {\"logo\": \"save \\\"x\"}\n";
+
+        let mut submitted = Vec::new();
+        let mut output = Vec::new();
+        answer(input.as_bytes(), &mut output, |request| {
+            submitted.push(request);
+            Reply::Ok(String::new())
+        })
+        .unwrap();
+
+        assert_eq!(submitted, vec![Request::Logo("fd 1".to_string())]);
+        let output = String::from_utf8(output).unwrap();
+        let replies: Vec<_> = output.lines().collect();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[0], r#"{"ok":""}"#);
+        assert!(replies[1].starts_with(r#"{"error":"bad request"#));
+    }
+
+    #[test]
+    fn it_only_allows_listed_addresses() {
+        let allow = parse_allow_list("127.0.0.1, 192.168.1.20, bogus");
+        assert_eq!(allow.len(), 2);
+
+        assert!(is_allowed(&allow, "127.0.0.1".parse().unwrap()));
+        assert!(is_allowed(&allow, "::ffff:192.168.1.20".parse().unwrap()));
+        assert!(!is_allowed(&allow, "192.168.1.21".parse().unwrap()));
+        assert!(!is_allowed(&allow, "::1".parse().unwrap()));
+        assert!(!is_allowed(&[], "127.0.0.1".parse().unwrap()));
+    }
+}
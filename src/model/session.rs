@@ -0,0 +1,297 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::runtime::control::Control;
+use std::any::Any;
+use std::fmt;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+/// What the interpreter thread is up to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionStatus {
+    Idle,
+    Running,
+    Paused,
+    /// Waiting for a line from the console.
+    Reading,
+}
+
+impl fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            SessionStatus::Idle => "idle",
+            SessionStatus::Running => "running",
+            SessionStatus::Paused => "paused",
+            SessionStatus::Reading => "reading",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Runs one program at a time on its own named thread. A crash in the
+/// interpreter is caught and kept for the console, rather than leaving the
+/// session looking busy forever.
+#[derive(Debug, Default)]
+pub struct InterpreterSession {
+    control: Arc<Control>,
+    running: Arc<AtomicBool>,
+    crash: Arc<Mutex<Option<String>>>,
+}
+
+impl InterpreterSession {
+    /// Run `job` on the interpreter thread. Returns false, and leaves the
+    /// program that's already running alone, if there is one.
+    pub fn start<F>(&self, job: F) -> bool
+    where
+        F: FnOnce(Arc<Control>) + Send + 'static,
+    {
+        self.spawn(job, false)
+    }
+
+    /// Like `start`, but held before the first statement.
+    pub fn start_stepping<F>(&self, job: F) -> bool
+    where
+        F: FnOnce(Arc<Control>) + Send + 'static,
+    {
+        self.spawn(job, true)
+    }
+
+    fn spawn<F>(&self, job: F, hold: bool) -> bool
+    where
+        F: FnOnce(Arc<Control>) + Send + 'static,
+    {
+        if self
+            .running
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return false;
+        }
+
+        self.control.reset();
+        self.control.listen();
+        if hold {
+            self.control.step();
+        }
+
+        let control = self.control.clone();
+        let running = self.running.clone();
+        let crash = self.crash.clone();
+        let spawned = thread::Builder::new()
+            .name("interpreter".to_string())
+            .spawn(move || {
+                // The job only shares the output and the symbols, which are
+                // whole strings and lists, so there's nothing half-written to
+                // worry about after a panic.
+                if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| job(control))) {
+                    *crash.lock().unwrap() = Some(panic_message(&*payload));
+                }
+                running.store(false, Ordering::Release);
+            });
+
+        if let Err(err) = spawned {
+            eprintln!("error: interpreter: {}", err);
+            self.running.store(false, Ordering::Release);
+            return false;
+        }
+
+        true
+    }
+
+    pub fn stop(&self) {
+        self.control.stop();
+    }
+
+    /// Let a held program go one more statement.
+    pub fn step(&self) {
+        self.control.step();
+    }
+
+    pub fn resume(&self) {
+        self.control.resume();
+    }
+
+    pub fn status(&self) -> SessionStatus {
+        if !self.is_running() {
+            SessionStatus::Idle
+        } else if self.control.is_paused() {
+            SessionStatus::Paused
+        } else if self.control.is_reading() {
+            SessionStatus::Reading
+        } else {
+            SessionStatus::Running
+        }
+    }
+
+    /// The line of the input a held program runs next, if it's keeping
+    /// track, which it does when it was started a step at a time.
+    pub fn held_line(&self) -> Option<usize> {
+        if self.status() == SessionStatus::Paused {
+            self.control.line()
+        } else {
+            None
+        }
+    }
+
+    /// Hand a line typed into the console to the program. Returns false if
+    /// it isn't waiting for one.
+    pub fn answer(&self, line: &str) -> bool {
+        self.control.answer(line)
+    }
+
+    /// What the program printed before it started waiting for a line.
+    pub fn prompt(&self) -> Option<String> {
+        if self.status() == SessionStatus::Reading {
+            Some(self.control.prompt())
+        } else {
+            None
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// What went wrong, the first time it's asked for after the last run
+    /// panicked.
+    pub fn poll(&self) -> Option<String> {
+        self.crash.lock().unwrap().take()
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let reason = if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.as_str()
+    } else {
+        "unknown"
+    };
+
+    format!(
+        "internal error: {}\nrun with RUST_BACKTRACE=1 for a backtrace",
+        reason
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::prefs::Preferences;
+    use crate::model::render::RenderCommand;
+    use crate::runtime;
+    use crate::runtime::warning::Warnings;
+    use futures::channel::mpsc;
+    use std::sync::atomic::AtomicU32;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    fn wait(session: &InterpreterSession) {
+        while session.is_running() {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn it_runs_one_program_at_a_time() {
+        let session = InterpreterSession::default();
+        assert!(session.start_stepping(|control| while control.check().is_ok() {}));
+        assert_eq!(session.status(), SessionStatus::Paused);
+        assert!(!session.start(|_| {}));
+
+        session.stop();
+        wait(&session);
+        assert_eq!(session.status(), SessionStatus::Idle);
+        assert_eq!(session.poll(), None);
+        assert!(session.start(|_| {}));
+    }
+
+    #[test]
+    fn it_stops_a_loop_with_nothing_in_it() {
+        let session = InterpreterSession::default();
+        assert!(session.start(|control| {
+            let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+            let _ = runtime::entry(
+                "repeat 1e12 { } for i 0 1e12 1 { }".to_string(),
+                Arc::new(render_tx),
+                Arc::new(AtomicU32::new(u32::MAX)),
+                control,
+                &Preferences::default(),
+                &Warnings::default(),
+            );
+        }));
+        thread::sleep(Duration::from_millis(50));
+        assert!(session.is_running());
+
+        session.stop();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while session.is_running() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(!session.is_running(), "the loop didn't stop");
+    }
+
+    #[test]
+    fn it_reports_panics() {
+        let session = InterpreterSession::default();
+        assert!(session.start(|_| panic!("boom")));
+        wait(&session);
+
+        let err = session.poll().unwrap();
+        assert!(err.starts_with("internal error: boom"), "{}", err);
+        assert!(err.contains("RUST_BACKTRACE"), "{}", err);
+        assert_eq!(session.poll(), None);
+    }
+
+    #[test]
+    fn it_recovers_after_a_panic() {
+        let session = InterpreterSession::default();
+        assert!(session.start(|_| panic!("boom")));
+        wait(&session);
+        assert_eq!(session.status(), SessionStatus::Idle);
+
+        let ran = Arc::new(AtomicBool::new(false));
+        let flag = ran.clone();
+        assert!(session.start(move |control| {
+            control.check().unwrap();
+            flag.store(true, Ordering::Release);
+        }));
+        wait(&session);
+        assert!(ran.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn it_passes_answers_to_the_program() {
+        let session = InterpreterSession::default();
+        let answer = Arc::new(Mutex::new(String::new()));
+        let keep = answer.clone();
+        assert!(session.start(move |control| {
+            *keep.lock().unwrap() = control.read("name? ").unwrap().0;
+        }));
+        while session.status() != SessionStatus::Reading {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(session.prompt(), Some("name? ".to_string()));
+        assert!(session.answer("turtle"));
+        wait(&session);
+        assert_eq!(*answer.lock().unwrap(), "turtle");
+        assert_eq!(session.prompt(), None);
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::constants::MAX_SPEED;
+use crate::common::constants::MIN_SPEED;
+use crate::common::constants::RENDER_DELAY;
+use druid::Data;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The longest gap between ticks that `Pace` makes up for. A tick after a
+/// long stall, like the first after the canvas has been idle, shouldn't
+/// dump everything at once.
+const MAX_GAP: Duration = Duration::from_millis(250);
+
+/// How many render commands the interpreter sends between pauses. A running
+/// program reads the shared copy, so changes take effect straight away; the
+/// plain copy is what lets the UI notice them.
+#[derive(Clone, Data, Debug)]
+pub struct Speed {
+    shared: Arc<AtomicU32>,
+    value: u32,
+}
+
+impl Speed {
+    pub fn new(value: u32) -> Self {
+        let value = value.clamp(MIN_SPEED, MAX_SPEED);
+        Self {
+            shared: Arc::new(AtomicU32::new(value)),
+            value,
+        }
+    }
+
+    pub fn get(&self) -> u32 {
+        self.value
+    }
+
+    pub fn set(&mut self, value: u32) {
+        self.value = value.clamp(MIN_SPEED, MAX_SPEED);
+        self.shared.store(self.value, Ordering::Relaxed);
+    }
+
+    pub fn faster(&mut self) {
+        self.set(self.value.saturating_mul(2));
+    }
+
+    pub fn slower(&mut self) {
+        self.set(self.value / 2);
+    }
+
+    /// The copy to hand to the interpreter.
+    pub fn shared(&self) -> Arc<AtomicU32> {
+        self.shared.clone()
+    }
+
+    /// Render commands a second, the same rate the interpreter sends them.
+    pub fn per_second(&self) -> f64 {
+        self.value as f64 / RENDER_DELAY.as_secs_f64()
+    }
+}
+
+/// Draws by the clock rather than by the tick, so a late timer catches up
+/// and animations run the same on slow and fast machines.
+#[derive(Clone, Debug, Default)]
+pub struct Pace {
+    owed: f64,
+}
+
+impl Pace {
+    /// How many commands are due after `elapsed` at `per_second`. Part of a
+    /// command carries over to the next tick.
+    pub fn due(&mut self, elapsed: Duration, per_second: f64) -> usize {
+        self.owed += elapsed.min(MAX_GAP).as_secs_f64() * per_second;
+        let due = self.owed.floor();
+        self.owed -= due;
+        due as usize
+    }
+
+    /// Nothing was left to draw, so don't save up for later.
+    pub fn rest(&mut self) {
+        self.owed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_stays_in_range() {
+        let mut speed = Speed::new(0);
+        assert_eq!(speed.get(), MIN_SPEED);
+
+        speed.set(20);
+        speed.faster();
+        assert_eq!(speed.get(), MAX_SPEED);
+        assert_eq!(speed.shared().load(Ordering::Relaxed), MAX_SPEED);
+
+        speed.set(3);
+        speed.slower();
+        speed.slower();
+        assert_eq!(speed.get(), MIN_SPEED);
+    }
+
+    #[test]
+    fn it_paces_by_the_clock() {
+        let mut pace = Pace::default();
+        let ms = Duration::from_millis;
+
+        assert_eq!(pace.due(ms(30), 100.0), 3);
+        // Two short ticks add up to one long one.
+        assert_eq!(pace.due(ms(5), 100.0), 0);
+        assert_eq!(pace.due(ms(5), 100.0), 1);
+
+        pace.due(ms(4), 100.0);
+        pace.rest();
+        assert_eq!(pace.due(ms(6), 100.0), 0);
+
+        assert_eq!(pace.due(Duration::from_secs(60), 100.0), 25);
+    }
+}
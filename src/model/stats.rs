@@ -0,0 +1,81 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::graphics::Rendered;
+use crate::model::render::RenderCommand;
+use druid::Data;
+use std::fmt;
+
+/// What the current run has drawn so far. Starts again from zero each time a
+/// program runs.
+#[derive(Clone, Copy, Data, Debug, Default, PartialEq)]
+pub struct RunStats {
+    pub commands: u64,
+    pub pixels: u64,
+    /// Lines that ran off the edge of the canvas.
+    pub clipped: u64,
+}
+
+impl RunStats {
+    /// Count a command and what drawing it did. A path counts a command for
+    /// each of its lines.
+    pub fn add(&mut self, cmd: &RenderCommand, rendered: Rendered) {
+        self.commands += cmd.moves().len().max(1) as u64;
+        self.pixels += rendered.pixels;
+        self.clipped += rendered.clipped;
+    }
+}
+
+impl fmt::Display for RunStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} commands, {} pixels drawn, {} lines clipped",
+            self.commands, self.pixels, self.clipped
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::render::MoveTo;
+    use crate::model::render::PEN_FLAGS_DEFAULT;
+    use druid::Color;
+    use druid::Point;
+
+    #[test]
+    fn it_adds_up_a_run() {
+        let mut stats = RunStats::default();
+        let rendered = Rendered {
+            pixels: 10,
+            clipped: 0,
+        };
+        stats.add(&RenderCommand::Clean, rendered);
+
+        let move_to = MoveTo::new(0.0, Color::WHITE, 0.0, PEN_FLAGS_DEFAULT, Point::ZERO);
+        let path = RenderCommand::Path(vec![move_to.clone(), move_to]);
+        let rendered = Rendered {
+            pixels: 4,
+            clipped: 2,
+        };
+        stats.add(&path, rendered);
+        stats.add(&RenderCommand::Turn(0.0), Rendered::default());
+
+        assert_eq!(
+            stats.to_string(),
+            "4 commands, 14 pixels drawn, 2 lines clipped"
+        );
+    }
+}
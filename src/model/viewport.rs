@@ -0,0 +1,78 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use druid::kurbo::Affine;
+use druid::Data;
+use druid::Point;
+use druid::Vec2;
+
+pub const MIN_ZOOM: f64 = 0.25;
+
+pub const MAX_ZOOM: f64 = 16.0;
+
+/// Zoom and pan applied when painting the canvas. The pixel buffer itself is
+/// never resized; this only changes how it is mapped onto the widget.
+#[derive(Clone, Copy, Data, Debug, PartialEq)]
+pub struct Viewport {
+    pub zoom: f64,
+    pub pan: Vec2,
+}
+
+impl Viewport {
+    pub fn affine(&self) -> Affine {
+        Affine::translate(self.pan) * Affine::scale(self.zoom)
+    }
+
+    /// Zoom by `factor`, keeping the widget point `at` fixed on screen.
+    pub fn zoom_at(&mut self, at: Point, factor: f64) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let at = at.to_vec2();
+        self.pan = at - (at - self.pan) * (zoom / self.zoom);
+        self.zoom = zoom;
+    }
+
+    pub fn pan_by(&mut self, delta: Vec2) {
+        self.pan += delta;
+    }
+
+    /// Map a widget point back into pixel buffer coordinates.
+    pub fn to_buffer(&self, p: Point) -> Point {
+        self.affine().inverse() * p
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_zooms_about_a_fixed_point() {
+        let mut viewport = Viewport::default();
+        let at = Point::new(100.0, 50.0);
+        let before = viewport.to_buffer(at);
+        viewport.zoom_at(at, 4.0);
+        let after = viewport.to_buffer(at);
+        assert!((before - after).hypot() < 1e-9);
+        assert_eq!(viewport.zoom, 4.0);
+    }
+}
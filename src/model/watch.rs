@@ -0,0 +1,102 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::session::InterpreterSession;
+use crate::common::commands;
+use druid::ExtEventSink;
+use druid::Target;
+use notify::DebouncedEvent;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Editors often save by writing a new file and renaming it over the old one,
+/// so changes are settled for a moment before reacting to them.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Watches a program on disk, and asks for it to be reloaded and run again
+/// whenever it changes. Watching stops when this is dropped.
+pub struct Watch {
+    path: PathBuf,
+    _watcher: RecommendedWatcher,
+}
+
+impl Watch {
+    pub fn new(
+        path: &Path,
+        sink: ExtEventSink,
+        session: Arc<InterpreterSession>,
+    ) -> notify::Result<Self> {
+        let path = path.canonicalize()?;
+        let (tx, rx) = mpsc::channel();
+
+        // Watching the directory rather than the file keeps working when the
+        // file is replaced.
+        let mut watcher: RecommendedWatcher = Watcher::new(tx, SETTLE_TIME)?;
+        if let Some(dir) = path.parent() {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+
+        let target = path.clone();
+        thread::spawn(move || {
+            // The channel closes when the watcher is dropped.
+            for event in rx {
+                let changed = match event {
+                    DebouncedEvent::Create(p)
+                    | DebouncedEvent::Write(p)
+                    | DebouncedEvent::Rename(_, p) => p == target,
+                    _ => false,
+                };
+                if !changed {
+                    continue;
+                }
+
+                // Only one program runs at a time, so let the last one finish.
+                session.stop();
+                while session.is_running() {
+                    thread::sleep(Duration::from_millis(10));
+                }
+
+                if sink
+                    .submit_command(commands::FILE_RELOAD, (), Target::Auto)
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            path,
+            _watcher: watcher,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Debug for Watch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watch").field("path", &self.path).finish()
+    }
+}
@@ -0,0 +1,385 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::error::*;
+use super::interpreter_types::Value;
+use super::warning::*;
+use crate::common::palette::Palette;
+use druid::Color;
+use rand::Rng;
+use std::fmt;
+
+/// Which part of a color list a value was for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorComponent {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl fmt::Display for ColorComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorComponent::Red => write!(f, "red"),
+            ColorComponent::Green => write!(f, "green"),
+            ColorComponent::Blue => write!(f, "blue"),
+            ColorComponent::Alpha => write!(f, "alpha"),
+        }
+    }
+}
+
+const COMPONENTS: [ColorComponent; 4] = [
+    ColorComponent::Red,
+    ColorComponent::Green,
+    ColorComponent::Blue,
+    ColorComponent::Alpha,
+];
+
+/// A color as a program writes it: either a palette index, or a list of red,
+/// green, blue and an optional alpha, each from 0 to 255. Everything that
+/// takes a color checks it the same way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorSpec {
+    Index(u8),
+    Rgba(u8, u8, u8, u8),
+}
+
+impl ColorSpec {
+    pub fn parse(val: &Value, warnings: &Warnings) -> RuntimeResult<Self> {
+        match val {
+            Value::List(list) => {
+                if list.len() < 3 || list.len() > 4 {
+                    let kind = ErrorKind::ColorListLength(list.len());
+                    return Err(RuntimeError::interpreter(kind));
+                }
+
+                let mut rgba = [u8::MAX; 4];
+                for (idx, item) in list.iter().enumerate() {
+                    rgba[idx] = Self::component(COMPONENTS[idx], item, warnings)?;
+                }
+
+                let [r, g, b, a] = rgba;
+                Ok(ColorSpec::Rgba(r, g, b, a))
+            }
+
+            Value::Number(num) => Self::index(*num),
+
+            Value::Void => Err(RuntimeError::interpreter(ErrorKind::VoidColor)),
+
+            Value::Word(_) => Err(RuntimeError::interpreter(ErrorKind::TypeMismatch {
+                expected: "a color",
+                got: val.type_name(),
+            })),
+        }
+    }
+
+    /// Fractional indexes round down, so `setpc (repcount / 5)` steps
+    /// through the palette, but anything out of range is an error.
+    pub fn index(num: f64) -> RuntimeResult<Self> {
+        if (0.0..256.0).contains(&num) {
+            Ok(ColorSpec::Index(num as u8))
+        } else {
+            Err(RuntimeError::interpreter(ErrorKind::InvalidPaletteIndex(
+                num,
+            )))
+        }
+    }
+
+    pub fn resolve(&self, pal: &Palette) -> RuntimeResult<Color> {
+        match *self {
+            ColorSpec::Index(idx) => match pal.get(&idx) {
+                Some(color) => Ok(color.clone()),
+                None => {
+                    let kind = ErrorKind::InvalidPaletteIndex(idx as f64);
+                    Err(RuntimeError::interpreter(kind))
+                }
+            },
+            ColorSpec::Rgba(r, g, b, a) => Ok(Color::rgba8(r, g, b, a)),
+        }
+    }
+
+    fn component(which: ColorComponent, val: &Value, warnings: &Warnings) -> RuntimeResult<u8> {
+        let comp = match val {
+            Value::Number(num) => *num,
+            _ => {
+                let kind = ErrorKind::TypeMismatch {
+                    expected: "a number",
+                    got: val.type_name(),
+                };
+                return Err(RuntimeError::interpreter(kind));
+            }
+        };
+
+        // NaN fails the range check too.
+        if !(0.0..=255.0).contains(&comp) {
+            let kind = ErrorKind::ColorComponentOutOfRange(which, comp);
+            return Err(RuntimeError::interpreter(kind));
+        }
+
+        if comp.fract() != 0.0 {
+            let kind = WarningKind::ColorComponentRounded(comp);
+            warnings.push(Stage::Interpreter, kind, None);
+        }
+
+        Ok(comp as u8)
+    }
+}
+
+/// Steps from one color to another, one move at a time. The first move is
+/// drawn in `from` and the last in `to`.
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    from: Color,
+    to: Color,
+    moves: u32,
+    done: u32,
+}
+
+impl Gradient {
+    pub fn new(from: Color, to: Color, moves: u32) -> Self {
+        Self {
+            from,
+            to,
+            moves,
+            done: 0,
+        }
+    }
+
+    /// The color for the next move.
+    pub fn step(&mut self) -> Color {
+        let t = if self.moves > 1 {
+            self.done as f64 / (self.moves - 1) as f64
+        } else {
+            1.0
+        };
+        self.done += 1;
+
+        let (r0, g0, b0, a0) = self.from.as_rgba8();
+        let (r1, g1, b1, a1) = self.to.as_rgba8();
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Color::rgba8(mix(r0, r1), mix(g0, g1), mix(b0, b1), mix(a0, a1))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done >= self.moves
+    }
+}
+
+/// A random color that's bright enough to see and not washed out, as a
+/// list of red, green and blue.
+pub fn pick() -> Value {
+    let mut rng = rand::thread_rng();
+    let rgb = hsv(
+        rng.gen_range(0.0..360.0),
+        rng.gen_range(0.55..0.9),
+        rng.gen_range(0.8..1.0),
+    );
+    rgb_list(rgb)
+}
+
+/// Turns `[hue saturation value]` into a list of red, green and blue. Any
+/// hue works, so it can keep counting up, but saturation and value must be
+/// from 0 to 1.
+pub fn from_hsv(val: &Value) -> RuntimeResult<Value> {
+    let list = match val {
+        Value::List(list) => list,
+        _ => {
+            let kind = ErrorKind::TypeMismatch {
+                expected: "a list",
+                got: val.type_name(),
+            };
+            return Err(RuntimeError::interpreter(kind));
+        }
+    };
+
+    if list.len() != 3 {
+        return Err(RuntimeError::interpreter(ErrorKind::Arity { expected: 3 }));
+    }
+
+    let mut hsv_list = [0.0; 3];
+    for (idx, item) in list.iter().enumerate() {
+        hsv_list[idx] = match item {
+            Value::Number(num) => *num,
+            _ => {
+                let kind = ErrorKind::TypeMismatch {
+                    expected: "a number",
+                    got: item.type_name(),
+                };
+                return Err(RuntimeError::interpreter(kind));
+            }
+        };
+    }
+
+    let [hue, sat, val] = hsv_list;
+    if !hue.is_finite() {
+        return Err(RuntimeError::interpreter(ErrorKind::InvalidHue(hue)));
+    }
+    for (which, num) in [("saturation", sat), ("value", val)].iter() {
+        // NaN fails the range check too.
+        if !(0.0..=1.0).contains(num) {
+            return Err(RuntimeError::interpreter(ErrorKind::HsvOutOfRange(
+                which, *num,
+            )));
+        }
+    }
+
+    Ok(rgb_list(hsv(hue, sat, val)))
+}
+
+fn rgb_list((r, g, b): (u8, u8, u8)) -> Value {
+    Value::List(
+        [r, g, b]
+            .iter()
+            .map(|comp| Value::Number(*comp as f64))
+            .collect(),
+    )
+}
+
+/// Converts a hue in degrees, and saturation and value from 0 to 1, to red,
+/// green and blue.
+pub fn hsv(hue: f64, sat: f64, val: f64) -> (u8, u8, u8) {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = val * sat;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = val - chroma;
+    let scale = |comp: f64| ((comp + m) * 255.0).round() as u8;
+    (scale(r), scale(g), scale(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::palette::palette;
+
+    fn list(items: &[f64]) -> Value {
+        Value::List(items.iter().map(|num| Value::Number(*num)).collect())
+    }
+
+    fn parse(val: &Value) -> RuntimeResult<ColorSpec> {
+        ColorSpec::parse(val, &Warnings::default())
+    }
+
+    #[test]
+    fn it_parses_colors() {
+        let spec = parse(&list(&[10.0, 20.0, 30.0])).unwrap();
+        assert_eq!(spec, ColorSpec::Rgba(10, 20, 30, 255));
+
+        let spec = parse(&list(&[10.0, 20.0, 30.0, 128.0])).unwrap();
+        assert_eq!(spec.resolve(&palette()), Ok(Color::rgba8(10, 20, 30, 128)));
+
+        let spec = parse(&Value::Number(4.0)).unwrap();
+        assert_eq!(spec.resolve(&palette()), Ok(Color::RED));
+    }
+
+    #[test]
+    fn it_picks_bright_colors() {
+        assert_eq!(hsv(0.0, 1.0, 1.0), (255, 0, 0));
+        assert_eq!(hsv(120.0, 1.0, 1.0), (0, 255, 0));
+        assert_eq!(hsv(-120.0, 1.0, 0.5), (0, 0, 128));
+        assert_eq!(hsv(30.0, 0.0, 1.0), (255, 255, 255));
+
+        for _ in 0..100 {
+            let spec = parse(&pick()).unwrap();
+            match spec {
+                ColorSpec::Rgba(r, g, b, 255) => assert!(r.max(g).max(b) >= 200),
+                _ => panic!("{:?}", spec),
+            }
+        }
+    }
+
+    #[test]
+    fn it_converts_hsv_lists() {
+        let rgb = from_hsv(&list(&[240.0, 1.0, 1.0])).unwrap();
+        assert_eq!(rgb, list(&[0.0, 0.0, 255.0]));
+
+        let rgb = from_hsv(&list(&[720.0 + 60.0, 1.0, 1.0])).unwrap();
+        assert_eq!(rgb, list(&[255.0, 255.0, 0.0]));
+
+        let err = from_hsv(&list(&[0.0, 1.5, 1.0])).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::HsvOutOfRange("saturation", 1.5));
+
+        let err = from_hsv(&list(&[0.0, 1.0])).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Arity { expected: 3 });
+
+        assert!(from_hsv(&Value::Number(1.0)).is_err());
+        let err = from_hsv(&list(&[f64::INFINITY, 1.0, 1.0])).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidHue(f64::INFINITY));
+    }
+
+    #[test]
+    fn it_blends_colors() {
+        let mut gradient = Gradient::new(Color::BLACK, Color::rgb8(200, 100, 0), 3);
+        assert_eq!(gradient.step(), Color::BLACK);
+        assert_eq!(gradient.step(), Color::rgb8(100, 50, 0));
+        assert!(!gradient.is_done());
+        assert_eq!(gradient.step(), Color::rgb8(200, 100, 0));
+        assert!(gradient.is_done());
+
+        let mut gradient = Gradient::new(Color::BLACK, Color::WHITE, 1);
+        assert_eq!(gradient.step(), Color::WHITE);
+        assert!(gradient.is_done());
+    }
+
+    #[test]
+    fn it_names_the_bad_component() {
+        let err = parse(&list(&[0.0, 300.0, 0.0])).err().unwrap();
+        assert_eq!(
+            err.kind,
+            ErrorKind::ColorComponentOutOfRange(ColorComponent::Green, 300.0)
+        );
+
+        let err = parse(&list(&[0.0, 0.0, 0.0, -1.0])).err().unwrap();
+        assert_eq!(
+            err.kind,
+            ErrorKind::ColorComponentOutOfRange(ColorComponent::Alpha, -1.0)
+        );
+
+        let err = parse(&list(&[f64::NAN, 0.0, 0.0])).err().unwrap();
+        assert!(matches!(
+            err.kind,
+            ErrorKind::ColorComponentOutOfRange(ColorComponent::Red, _)
+        ));
+
+        let err = parse(&list(&[0.0, 0.0])).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::ColorListLength(2));
+
+        let err = parse(&list(&[0.0; 5])).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::ColorListLength(5));
+    }
+
+    #[test]
+    fn it_checks_palette_indexes() {
+        for num in [-1.0, 256.0, f64::NAN].iter() {
+            assert!(parse(&Value::Number(*num)).is_err());
+        }
+
+        let spec = parse(&Value::Number(1.5)).unwrap();
+        assert_eq!(spec, ColorSpec::Index(1));
+
+        let spec = parse(&Value::Number(99.0)).unwrap();
+        assert_eq!(
+            spec.resolve(&palette()).err().unwrap().kind,
+            ErrorKind::InvalidPaletteIndex(99.0)
+        );
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::error::*;
+use super::parser_types::ParserFuncMap;
+use crate::common::clock;
+use crate::common::clock::Instant;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const HOLD_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Lets the UI stop a running program, or hold it and let it go one
+/// statement at a time. It also carries lines typed into the console to a
+/// program that's waiting to read one, and the values of its sliders.
+#[derive(Debug, Default)]
+pub struct Control {
+    stop: AtomicBool,
+    paused: AtomicBool,
+    steps: AtomicU32,
+    /// The line of the input the next statement is on, or zero if the
+    /// program isn't keeping track.
+    line: AtomicUsize,
+    listening: AtomicBool,
+    reading: AtomicBool,
+    prompt: Mutex<String>,
+    reply: Mutex<Option<String>>,
+    /// Slider values by name, for `param`. They stay from run to run.
+    params: Mutex<HashMap<String, f64>>,
+    /// Procedures as written by earlier runs, so a later one can call, list
+    /// or erase them. These stay from run to run as well.
+    procedures: Mutex<ParserFuncMap>,
+}
+
+impl Control {
+    /// Ready for a fresh run.
+    pub fn reset(&self) {
+        self.stop.store(false, Ordering::Release);
+        self.paused.store(false, Ordering::Release);
+        self.steps.store(0, Ordering::Release);
+        self.line.store(0, Ordering::Release);
+        self.reading.store(false, Ordering::Release);
+        self.prompt.lock().unwrap().clear();
+        *self.reply.lock().unwrap() = None;
+    }
+
+    /// Someone is there to answer `read`, and to stop a program that would
+    /// otherwise run forever. Without this, reading fails straight away, and
+    /// timed loops go round once.
+    pub fn listen(&self) {
+        self.listening.store(true, Ordering::Release);
+    }
+
+    pub fn is_listening(&self) -> bool {
+        self.listening.load(Ordering::Acquire)
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Release);
+    }
+
+    /// Hold the program, letting one more statement through.
+    pub fn step(&self) {
+        self.steps.fetch_add(1, Ordering::AcqRel);
+        self.paused.store(true, Ordering::Release);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Release);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Note that the next statement is on `line` of the input.
+    pub fn set_line(&self, line: usize) {
+        self.line.store(line, Ordering::Release);
+    }
+
+    /// The line of the input the next statement is on, if the program is
+    /// keeping track.
+    pub fn line(&self) -> Option<usize> {
+        match self.line.load(Ordering::Acquire) {
+            0 => None,
+            line => Some(line),
+        }
+    }
+
+    /// Run with these slider values.
+    pub fn set_params(&self, params: HashMap<String, f64>) {
+        *self.params.lock().unwrap() = params;
+    }
+
+    /// The slider value for `name`, if the UI has one.
+    pub fn param(&self, name: &str) -> Option<f64> {
+        self.params.lock().unwrap().get(name).copied()
+    }
+
+    pub fn set_procedures(&self, procedures: ParserFuncMap) {
+        *self.procedures.lock().unwrap() = procedures;
+    }
+
+    pub fn procedures(&self) -> ParserFuncMap {
+        self.procedures.lock().unwrap().clone()
+    }
+
+    /// Called before each statement. Waits while the program is held, and
+    /// fails once it has been stopped. Gives back roughly how long it waited.
+    pub fn check(&self) -> RuntimeResult<Duration> {
+        let mut held = Duration::ZERO;
+        loop {
+            if self.stop.load(Ordering::Acquire) {
+                return Err(RuntimeError::interpreter(ErrorKind::Stopped));
+            }
+
+            if !self.is_paused() {
+                return Ok(held);
+            }
+
+            let step = self
+                .steps
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1));
+            if step.is_ok() {
+                return Ok(held);
+            }
+
+            clock::sleep(HOLD_INTERVAL);
+            held += HOLD_INTERVAL;
+        }
+    }
+
+    /// Called each time round a loop, so that a loop with nothing in it can
+    /// still be stopped. Waits while the program is held, like `check`, but
+    /// leaves a step for the next statement to take. Gives back roughly how
+    /// long it waited.
+    pub fn check_loop(&self) -> RuntimeResult<Duration> {
+        let mut held = Duration::ZERO;
+        loop {
+            if self.stop.load(Ordering::Acquire) {
+                return Err(RuntimeError::interpreter(ErrorKind::Stopped));
+            }
+
+            if !self.is_paused() || self.steps.load(Ordering::Acquire) > 0 {
+                return Ok(held);
+            }
+
+            clock::sleep(HOLD_INTERVAL);
+            held += HOLD_INTERVAL;
+        }
+    }
+
+    /// Sleep until `tick`, failing once the program has been stopped. Gives
+    /// back roughly how long it waited.
+    pub fn wait_until(&self, tick: Instant) -> RuntimeResult<Duration> {
+        let mut waited = Duration::ZERO;
+        loop {
+            if self.stop.load(Ordering::Acquire) {
+                return Err(RuntimeError::interpreter(ErrorKind::Stopped));
+            }
+
+            if Instant::now() >= tick {
+                return Ok(waited);
+            }
+
+            clock::sleep(HOLD_INTERVAL);
+            waited += HOLD_INTERVAL;
+        }
+    }
+
+    /// Wait for a line from the console. `prompt` is what the program has
+    /// printed so far, for the console to show while it waits. Gives back
+    /// the line and roughly how long it waited.
+    pub fn read(&self, prompt: &str) -> RuntimeResult<(String, Duration)> {
+        if !self.is_listening() {
+            return Err(RuntimeError::interpreter(ErrorKind::NoInput));
+        }
+
+        *self.prompt.lock().unwrap() = prompt.to_string();
+        self.reading.store(true, Ordering::Release);
+
+        let mut waited = Duration::ZERO;
+        loop {
+            if self.stop.load(Ordering::Acquire) {
+                self.reading.store(false, Ordering::Release);
+                return Err(RuntimeError::interpreter(ErrorKind::Stopped));
+            }
+
+            if let Some(line) = self.reply.lock().unwrap().take() {
+                self.reading.store(false, Ordering::Release);
+                return Ok((line, waited));
+            }
+
+            clock::sleep(HOLD_INTERVAL);
+            waited += HOLD_INTERVAL;
+        }
+    }
+
+    pub fn is_reading(&self) -> bool {
+        self.reading.load(Ordering::Acquire)
+    }
+
+    /// What the waiting program printed before it asked.
+    pub fn prompt(&self) -> String {
+        self.prompt.lock().unwrap().clone()
+    }
+
+    /// Hand `line` to the program waiting in `read`. Returns false, and
+    /// drops the line, if nothing is waiting.
+    pub fn answer(&self, line: &str) -> bool {
+        let mut reply = self.reply.lock().unwrap();
+        if !self.is_reading() || reply.is_some() {
+            return false;
+        }
+        *reply = Some(line.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn it_steps_and_stops() {
+        let control = Control::default();
+        assert!(control.check().is_ok());
+
+        control.step();
+        control.step();
+        assert!(control.check().is_ok());
+        assert!(control.check().is_ok());
+        assert!(control.is_paused());
+
+        control.stop();
+        let err = control.check().err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Stopped);
+
+        control.reset();
+        assert!(control.check().is_ok());
+    }
+
+    #[test]
+    fn it_reads_answers() {
+        let control = Arc::new(Control::default());
+        let err = control.read("").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::NoInput);
+        assert!(!control.answer("too soon"));
+
+        control.listen();
+        let reader = control.clone();
+        let handle = thread::spawn(move || reader.read("name? ").unwrap().0);
+        while !control.answer("turtle") {
+            thread::yield_now();
+        }
+        assert_eq!(handle.join().unwrap(), "turtle");
+        assert!(!control.is_reading());
+
+        let reader = control.clone();
+        let handle = thread::spawn(move || reader.read("").err().unwrap().kind);
+        while !control.is_reading() {
+            thread::yield_now();
+        }
+        control.stop();
+        assert_eq!(handle.join().unwrap(), ErrorKind::Stopped);
+    }
+}
@@ -12,31 +12,394 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::color::ColorComponent;
+use super::interpreter_types::Value;
+use crate::common::constants::MAX_SPEED;
+use crate::common::constants::MAX_SYMMETRY;
+use crate::common::constants::MAX_TURTLE_SIZE;
+use crate::common::constants::MIN_SPEED;
+use crate::common::palette::PALETTE_SIZE;
 use crate::model::render::RenderCommand;
 use futures::channel::mpsc::TrySendError;
 use std::fmt;
 
-#[derive(Debug)]
-pub enum RuntimeError {
-    Lexer(String),
-    Parser(String),
-    Interpreter(String),
+/// Which part of the runtime raised an error.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage {
+    Lexer,
+    Parser,
+    Interpreter,
 }
 
-impl fmt::Display for RuntimeError {
+impl fmt::Display for Stage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            RuntimeError::Lexer(msg) => write!(f, "error: lexer: {}", msg),
-            RuntimeError::Parser(msg) => write!(f, "error: parser: {}", msg),
-            RuntimeError::Interpreter(msg) => write!(f, "error: interpreter: {}", msg),
+            Stage::Lexer => write!(f, "lexer"),
+            Stage::Parser => write!(f, "parser"),
+            Stage::Interpreter => write!(f, "interpreter"),
+        }
+    }
+}
+
+/// A range of characters in the program text, counted from zero.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn at(pos: usize) -> Self {
+        Self::new(pos, pos + 1)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
+    Arity {
+        expected: usize,
+    },
+    AssertionFailed,
+    CallDepth(usize),
+    CircularLoad(String),
+    ColorComponentOutOfRange(ColorComponent, f64),
+    ColorListLength(usize),
+    DidYouMean {
+        name: String,
+        suggestion: String,
+    },
+    DivByZero,
+    EmptyTransformStack,
+    ExpectedAssignment,
+    ExpectedBlock,
+    ExpectedExpression,
+    ExpectedOperator,
+    ExpectedWord,
+    HsvOutOfRange(&'static str, f64),
+    /// Something wrong in a file pulled in by `load`, and where it is in
+    /// that file.
+    InFile {
+        name: String,
+        line: Option<usize>,
+        kind: Box<ErrorKind>,
+    },
+    FileAccess {
+        name: String,
+        reason: String,
+    },
+    FileNotFound(String),
+    InvalidColorName(String),
+    InvalidExpression,
+    InvalidGradient(f64),
+    InvalidHue(f64),
+    InvalidInterval(f64),
+    InvalidFileName(String),
+    InvalidMovement,
+    InvalidNumber(String),
+    InvalidOperands(Value, Value),
+    InvalidPaletteIndex(f64),
+    InvalidParamRange(f64, f64),
+    InvalidRepeatCount(f64),
+    InvalidRotation,
+    InvalidScale(f64),
+    InvalidShape(String),
+    InvalidSize(f64),
+    InvalidSpeed(String),
+    InvalidSwitch(String),
+    InvalidSymmetry(f64),
+    NoInput,
+    Render(String),
+    ReservedWord(String),
+    Stopped,
+    SymbolConflict {
+        name: String,
+        tag: String,
+    },
+    TimeLimit(u32),
+    TypeMismatch {
+        expected: &'static str,
+        got: &'static str,
+    },
+    UndefinedFunction(String),
+    UndefinedSymbol(String),
+    UndefinedVariable(String),
+    UnexpectedEnd,
+    UnexpectedPeriod,
+    UnrecognizedCharacter(char),
+    UnrecognizedOperator(char),
+    UnsupportedOperator,
+    VoidColor,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Arity { expected } => write!(f, "{} items expected", expected),
+            ErrorKind::AssertionFailed => write!(f, "assertion failed"),
+            ErrorKind::CallDepth(depth) => write!(
+                f,
+                "procedures called each other more than {} deep; is there a call that never stops?",
+                depth
+            ),
+            ErrorKind::CircularLoad(name) => write!(f, "\"{}\" loads itself", name),
+            ErrorKind::ColorComponentOutOfRange(which, comp) => {
+                write!(f, "{} component must be from 0 to 255, not {}", which, comp)
+            }
+            ErrorKind::ColorListLength(len) => write!(
+                f,
+                "a color needs red, green, blue and maybe alpha, not {} items",
+                len
+            ),
+            ErrorKind::DidYouMean { name, suggestion } => write!(
+                f,
+                "unrecognized symbol \"{}\"; did you mean \"{}\"?",
+                name, suggestion
+            ),
+            ErrorKind::DivByZero => write!(f, "division by zero"),
+            ErrorKind::EmptyTransformStack => {
+                write!(f, "poptransform without a pushtransform to go back to")
+            }
+            ErrorKind::ExpectedAssignment => write!(f, "expected an assignment"),
+            ErrorKind::ExpectedBlock => write!(f, "expected a block in {{ }} or [ ]"),
+            ErrorKind::ExpectedExpression => write!(f, "expected an expression"),
+            ErrorKind::ExpectedOperator => write!(f, "expected an operator"),
+            ErrorKind::ExpectedWord => write!(f, "expected a word"),
+            ErrorKind::HsvOutOfRange(which, num) => {
+                write!(f, "{} must be from 0 to 1, not {}", which, num)
+            }
+            ErrorKind::InFile { name, line, kind } => match line {
+                Some(line) => write!(f, "in \"{}\" line {}: {}", name, line, kind),
+                None => write!(f, "in \"{}\": {}", name, kind),
+            },
+            ErrorKind::FileAccess { name, reason } => {
+                write!(f, "cannot access \"{}\": {}", name, reason)
+            }
+            ErrorKind::FileNotFound(name) => {
+                write!(f, "no file \"{}\" in the library path", name)
+            }
+            ErrorKind::InvalidColorName(name) => write!(
+                f,
+                "no color \"{}\"; try a name like red or orange, or \"random",
+                name
+            ),
+            ErrorKind::InvalidExpression => write!(f, "failed to parse expression"),
+            ErrorKind::InvalidGradient(moves) => {
+                write!(f, "a gradient needs 1 or more moves, not {}", moves)
+            }
+            ErrorKind::InvalidHue(hue) => write!(f, "hue must be a number of degrees, not {}", hue),
+            ErrorKind::InvalidInterval(secs) => {
+                write!(
+                    f,
+                    "interval {} must be a number of seconds, 0 or more",
+                    secs
+                )
+            }
+            ErrorKind::InvalidFileName(name) => {
+                write!(f, "\"{}\" must be a file name, not a path", name)
+            }
+            ErrorKind::InvalidMovement => write!(f, "movement must be forward or backward"),
+            ErrorKind::InvalidNumber(num) => write!(f, "failed to parse number \"{}\"", num),
+            ErrorKind::InvalidOperands(a, b) => write!(f, "cannot evaluate {:?} {:?}", a, b),
+            ErrorKind::InvalidPaletteIndex(idx) => write!(
+                f,
+                "no palette color {}; use a number from 0 to {}",
+                idx,
+                PALETTE_SIZE - 1
+            ),
+            ErrorKind::InvalidParamRange(min, max) => {
+                write!(f, "param range {} to {} must go from low to high", min, max)
+            }
+            ErrorKind::InvalidRepeatCount(count) => {
+                write!(f, "repeat count {} must be a number, zero or more", count)
+            }
+            ErrorKind::InvalidRotation => write!(f, "rotation must be right or left"),
+            ErrorKind::InvalidScale(factor) => {
+                write!(f, "scale must be a number other than 0, not {}", factor)
+            }
+            ErrorKind::InvalidShape(name) => write!(
+                f,
+                "no turtle shape \"{}\"; try triangle, turtle, or circle",
+                name
+            ),
+            ErrorKind::InvalidSize(size) => write!(
+                f,
+                "turtle size {} must be between 1 and {}",
+                size, MAX_TURTLE_SIZE
+            ),
+            ErrorKind::InvalidSpeed(speed) => write!(
+                f,
+                "speed {} must be between {} and {}, or \"instant",
+                speed, MIN_SPEED, MAX_SPEED
+            ),
+            ErrorKind::InvalidSwitch(word) => {
+                write!(f, "expected true or false, not \"{}\"", word)
+            }
+            ErrorKind::InvalidSymmetry(ways) => write!(
+                f,
+                "symmetry {} must be a whole number from 1 to {}",
+                ways, MAX_SYMMETRY
+            ),
+            ErrorKind::NoInput => write!(f, "nothing to read from; run it from the app"),
+            ErrorKind::Render(msg) => write!(f, "{}", msg),
+            ErrorKind::ReservedWord(name) => write!(f, "{} is a reserved word", name),
+            ErrorKind::Stopped => write!(f, "stopped"),
+            ErrorKind::SymbolConflict { name, tag } => {
+                write!(f, "symbol \"{}\" already exists with tag {}", name, tag)
+            }
+            ErrorKind::TimeLimit(secs) => write!(
+                f,
+                "program took too long (more than {} second{}); the limit is in Preferences",
+                secs,
+                if *secs == 1 { "" } else { "s" }
+            ),
+            ErrorKind::TypeMismatch { expected, got } => {
+                write!(f, "expected {}, not {}", expected, got)
+            }
+            ErrorKind::UndefinedFunction(name) => write!(f, "no such function \"{}\"", name),
+            ErrorKind::UndefinedSymbol(name) => write!(f, "unrecognized symbol \"{}\"", name),
+            ErrorKind::UndefinedVariable(name) => write!(f, "no such variable \"{}\"", name),
+            ErrorKind::UnexpectedEnd => write!(f, "more expected at the end"),
+            ErrorKind::UnexpectedPeriod => write!(f, "unexpected period"),
+            ErrorKind::UnrecognizedCharacter(c) => write!(f, "unrecognized character \'{}\'", c),
+            ErrorKind::UnrecognizedOperator(c) => write!(f, "unrecognized operator \'{}\'", c),
+            ErrorKind::UnsupportedOperator => write!(f, "cannot evaluate operator"),
+            ErrorKind::VoidColor => write!(f, "color cannot be void"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuntimeError {
+    pub stage: Stage,
+    pub kind: ErrorKind,
+    pub span: Option<Span>,
+    /// The line of the program the error is on, counted from one.
+    pub line: Option<usize>,
+}
+
+impl RuntimeError {
+    pub fn lexer(kind: ErrorKind, span: Span) -> Self {
+        Self {
+            stage: Stage::Lexer,
+            kind,
+            span: Some(span),
+            line: None,
+        }
+    }
+
+    pub fn parser(kind: ErrorKind) -> Self {
+        Self {
+            stage: Stage::Parser,
+            kind,
+            span: None,
+            line: None,
+        }
+    }
+
+    pub fn interpreter(kind: ErrorKind) -> Self {
+        Self {
+            stage: Stage::Interpreter,
+            kind,
+            span: None,
+            line: None,
+        }
+    }
+
+    /// Notes the line the error is on, unless it's already known from a
+    /// statement nested further in.
+    pub fn on_line(mut self, line: usize) -> Self {
+        self.line.get_or_insert(line);
+        self
+    }
+
+    /// Where in `input` the error is, as a line and column counted from one.
+    /// An error known only by its line points at the start of it.
+    pub fn location(&self, input: &str) -> Option<(usize, usize)> {
+        match (self.span, self.line) {
+            (Some(span), _) => {
+                let before: Vec<char> = input.chars().take(span.start).collect();
+                let line = before.iter().filter(|c| **c == '\n').count() + 1;
+                let column = before.iter().rev().take_while(|c| **c != '\n').count() + 1;
+                Some((line, column))
+            }
+            (None, Some(line)) => Some((line, 1)),
+            (None, None) => None,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.span) {
+            (Some(line), _) => write!(f, "error: {}: line {}: {}", self.stage, line, self.kind),
+            // Positions are shown counting from one.
+            (None, Some(span)) => write!(
+                f,
+                "error: {}: {}: {}",
+                self.stage,
+                span.start + 1,
+                self.kind
+            ),
+            (None, None) => write!(f, "error: {}: {}", self.stage, self.kind),
         }
     }
 }
 
 impl From<TrySendError<RenderCommand>> for RuntimeError {
     fn from(err: TrySendError<RenderCommand>) -> Self {
-        Self::Interpreter(err.to_string())
+        Self::interpreter(ErrorKind::Render(err.to_string()))
     }
 }
 
 pub type RuntimeResult<T = ()> = Result<T, RuntimeError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_displays_friendly_text() {
+        let err = RuntimeError::lexer(ErrorKind::UnrecognizedCharacter('$'), Span::at(4));
+        assert_eq!(
+            err.to_string(),
+            "error: lexer: 5: unrecognized character '$'"
+        );
+
+        let err = RuntimeError::parser(ErrorKind::UndefinedSymbol("foo".to_string()));
+        assert_eq!(
+            err.to_string(),
+            "error: parser: unrecognized symbol \"foo\""
+        );
+
+        let err = err.on_line(3).on_line(1);
+        assert_eq!(
+            err.to_string(),
+            "error: parser: line 3: unrecognized symbol \"foo\""
+        );
+
+        let kind = ErrorKind::TypeMismatch {
+            expected: "a number",
+            got: "a list",
+        };
+        let err = RuntimeError::interpreter(kind);
+        assert_eq!(
+            err.to_string(),
+            "error: interpreter: expected a number, not a list"
+        );
+    }
+
+    #[test]
+    fn it_finds_the_location() {
+        let input = "fd 10\nrt 9$0";
+        let err = RuntimeError::lexer(ErrorKind::UnrecognizedCharacter('$'), Span::at(10));
+        assert_eq!(err.location(input), Some((2, 5)));
+
+        let err = RuntimeError::parser(ErrorKind::ExpectedBlock);
+        assert_eq!(err.location(input), None);
+        assert_eq!(err.on_line(2).location(input), Some((2, 1)));
+    }
+}
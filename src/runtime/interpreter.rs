@@ -12,42 +12,75 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::color;
+use super::color::ColorSpec;
+use super::color::Gradient;
+use super::control::Control;
 use super::error::*;
 use super::interpreter_types::*;
 use super::lexer_types::*;
+use super::library::Library;
 use super::parser_types::*;
+use super::printer::Printer;
+use super::transform::Transform;
+use super::warning::*;
+use crate::common::clock;
+use crate::common::clock::Instant;
+use crate::common::constants::*;
+use crate::common::palette::*;
+use crate::common::profile;
+use crate::common::profile::Phase;
+use crate::model::params::Param;
+use crate::model::prefs::Preferences;
+use crate::model::prefs::TurtleShape;
 use crate::model::render::*;
 use druid::Color;
 use druid::Point;
+use druid::Rect;
+use druid::Size;
 use rand::Rng;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
 use std::sync::atomic::AtomicU32;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::thread;
 use std::time::Duration;
 
 type VarMap = HashMap<String, Value>;
 
-type Palette = HashMap<u8, Color>;
-
 #[derive(Clone, Debug)]
 struct State {
-    angle: f64,
+    /// Degrees clockwise from up, always in [0, 360).
+    heading: f64,
     color: Color,
+    /// Changes the color a little with each move, until it runs out.
+    gradient: Option<Gradient>,
+    /// Keep positions fractional instead of rounding after every move.
+    geometric: bool,
     pen_flags: u32,
     pos: Point,
     screen_color: Color,
+    shown: bool,
+    /// How many ways each line is drawn, turned evenly around the center.
+    symmetry: u32,
+    /// Where the turtle's coordinates land on the canvas.
+    transform: Transform,
 }
 
 impl State {
-    pub fn new() -> Self {
+    pub fn new(prefs: &Preferences) -> Self {
         Self {
-            angle: 0.0,
-            color: Color::WHITE,
+            heading: 0.0,
+            color: prefs.pen_color.clone(),
+            gradient: None,
+            geometric: false,
             pen_flags: PEN_FLAGS_DEFAULT,
             pos: Point::ZERO,
-            screen_color: Color::BLACK,
+            screen_color: prefs.screen_color.clone(),
+            shown: true,
+            symmetry: 1,
+            transform: Transform::default(),
         }
     }
 }
@@ -74,78 +107,268 @@ pub struct Interpreter {
     pal: Palette,
     render_tx: Arc<RenderTx>,
     render_tx_count: u32,
+    /// Pen-down moves waiting to go to the canvas together.
+    path: Vec<MoveTo>,
+    /// A turn made partway through `path`, sent once the path has gone.
+    turn_after: Option<f64>,
     speed: Arc<AtomicU32>,
+    control: Arc<Control>,
+    /// When the program is stopped for running too long. Time spent held or
+    /// slowed down to watch pushes it back.
+    deadline: Option<Instant>,
+    time_limit: u32,
     state: State,
+    /// How many procedure calls deep the program is.
+    calls: usize,
+    /// Saved by pushtransform, most recent last.
+    transforms: Vec<Transform>,
+    bounds: Size,
+    /// Where the turtle first drew off the canvas.
+    left_canvas: Option<Point>,
+    /// Lines that ran off the edge of the canvas.
+    clipped: u32,
+    /// The box around the lines drawn since the last clean.
+    drawn: Option<Rect>,
+    library: Library,
+    erased: HashSet<String>,
+    /// Procedures the program has redefined so far, and the key of the
+    /// definition now in effect.
+    current: HashMap<String, String>,
+    text: String,
+    /// Set by settextcolor, for whatever is printed after.
+    text_color: Option<Color>,
+    colors: Vec<(Range<usize>, Color)>,
+    /// Declared with `param`, in the order they first came up.
+    params: Vec<Param>,
+    /// The procedures as the program wrote them, for pops and save, when
+    /// the ones that run have been rewritten by the optimizer.
+    written: Option<ParserFuncMap>,
+    warnings: Warnings,
 }
 
 impl Interpreter {
-    pub fn new(render_tx: Arc<RenderTx>, speed: Arc<AtomicU32>) -> Self {
-        let pal = crate::hashmap![
-            0 => Color::BLACK,
-            1 => Color::BLUE,
-            2 => Color::rgb8(0,255,0),        // lime
-            3 => Color::AQUA,                 // cyan
-            4 => Color::RED,
-            5 => Color::FUCHSIA,              // magenta
-            6 => Color::YELLOW,
-            7 => Color::WHITE,
-            8 => Color::rgb8(165, 42, 42),    // brown
-            9 => Color::rgb8(210, 180, 140),  // tan
-            10 => Color::GREEN,
-            11 => Color::rgb8(127, 255, 212), // aqua
-            12 => Color::rgb8(250, 128, 114), // salmon
-            13 => Color::rgb8(128, 0, 128),   // purple
-            14 => Color::rgb8(255, 165, 0),   // orange
-            15 => Color::rgb8(128, 128, 128)  // gray
-        ];
+    pub fn new(
+        render_tx: Arc<RenderTx>,
+        speed: Arc<AtomicU32>,
+        control: Arc<Control>,
+        prefs: &Preferences,
+        warnings: &Warnings,
+    ) -> Self {
+        let pal = palette();
 
         Self {
             pal,
             render_tx,
             render_tx_count: 0,
+            path: Vec::new(),
+            turn_after: None,
             speed,
-            state: State::new(),
+            control,
+            deadline: None,
+            time_limit: prefs.time_limit,
+            state: State::new(prefs),
+            calls: 0,
+            transforms: Vec::new(),
+            bounds: prefs.surface_size() / 2.0,
+            left_canvas: None,
+            clipped: 0,
+            drawn: None,
+            library: Library::new(prefs.library_dirs()),
+            erased: HashSet::new(),
+            current: HashMap::new(),
+            text: String::new(),
+            text_color: None,
+            colors: Vec::new(),
+            params: Vec::new(),
+            written: None,
+            warnings: warnings.clone(),
         }
     }
 
+    pub fn with_written(mut self, fmap: ParserFuncMap) -> Self {
+        self.written = Some(fmap);
+        self
+    }
+
     pub fn go(&mut self, input: &ParserOutput) -> RuntimeResult<Value> {
+        self.deadline = match self.time_limit {
+            0 => None,
+            secs => Some(Instant::now() + Duration::from_secs(secs as u64)),
+        };
+
         let mut vmap = VarMap::new();
         let mut frame = Frame::new(&input.fmap, &mut vmap, 0);
-        self.run(&mut frame, &input.list)
+        let result = self.run(&mut frame, &input.list);
+        let flushed = self.flush();
+
+        // Mentioned once at the end, even if the program failed, since a
+        // turtle that wanders off usually keeps going.
+        if let Some(p) = self.left_canvas {
+            let kind = WarningKind::OffCanvas {
+                x: p.x,
+                y: p.y,
+                clipped: self.clipped,
+            };
+            self.warnings.push(Stage::Interpreter, kind, None);
+        }
+
+        result.and_then(|val| flushed.map(|_| val))
+    }
+
+    /// Everything the program printed, leaving the interpreter's copy empty.
+    pub fn take_text(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+
+    /// The colors set with settextcolor, by where they fall in the text,
+    /// leaving the interpreter's copy empty.
+    pub fn take_colors(&mut self) -> Vec<(Range<usize>, Color)> {
+        std::mem::take(&mut self.colors)
+    }
+
+    /// The params the program declared, leaving the interpreter's copy empty.
+    pub fn take_params(&mut self) -> Vec<Param> {
+        std::mem::take(&mut self.params)
+    }
+
+    fn print(&mut self, line: &str) {
+        self.write(&format!("{}\n", line));
+    }
+
+    fn write(&mut self, text: &str) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        if let Some(color) = &self.text_color {
+            self.colors.push((start..self.text.len(), color.clone()));
+        }
+    }
+
+    /// Procedures that haven't been erased, in name order, as the keys of
+    /// the definitions in effect.
+    fn procedures<'b>(&self, frame: &Frame<'b>) -> Vec<&'b str> {
+        let mut names: Vec<_> = frame
+            .fmap
+            .keys()
+            .filter(|key| written_name(key) == key.as_str() && !self.erased.contains(*key))
+            .collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| match self.current.get(name) {
+                Some(key) => frame.fmap.get_key_value(key).unwrap().0.as_str(),
+                None => name.as_str(),
+            })
+            .collect()
+    }
+
+    /// The procedures still in effect after a run, under their own names,
+    /// for the next one to start with. `fmap` is the map as written.
+    pub fn kept(&self, fmap: &ParserFuncMap) -> ParserFuncMap {
+        fmap.keys()
+            .filter(|key| written_name(key) == key.as_str() && !self.erased.contains(*key))
+            .map(|name| (name.clone(), fmap[self.current(name)].clone()))
+            .collect()
+    }
+
+    /// Which definition of a procedure is in effect.
+    fn current<'b>(&'b self, name: &'b str) -> &'b str {
+        self.current.get(name).map_or(name, String::as_str)
     }
 
     fn run(&mut self, frame: &mut Frame, list: &[ParserNode]) -> RuntimeResult<Value> {
         let mut val = Value::Void;
         for node in list.iter() {
+            // A line marker only says where the next statement is, so it
+            // isn't a step of its own.
+            if let ParserNode::Line(line) = node {
+                self.control.set_line(*line);
+                continue;
+            }
+            // Show everything so far before holding.
+            if self.control.is_paused() {
+                self.flush()?;
+            }
+            let held = self.control.check()?;
+            self.check_time(held)?;
             val = self.eval_node(frame, node)?;
         }
         Ok(val)
     }
 
+    /// `run` checks before each statement, which a loop with an empty body
+    /// never gets to, so loops check each time round as well.
+    fn check_loop(&mut self) -> RuntimeResult {
+        let held = self.control.check_loop()?;
+        self.check_time(held)
+    }
+
+    fn check_time(&mut self, idle: Duration) -> RuntimeResult {
+        if let Some(deadline) = self.deadline.as_mut() {
+            *deadline += idle;
+            if Instant::now() > *deadline {
+                return Err(RuntimeError::interpreter(ErrorKind::TimeLimit(
+                    self.time_limit,
+                )));
+            }
+        }
+        Ok(())
+    }
+
     fn eval_node(&mut self, frame: &mut Frame, node: &ParserNode) -> RuntimeResult<Value> {
         match node {
+            ParserNode::Assert(node) => self.eval_assert(frame, node),
             ParserNode::BinExpr(bin_expr) => self.eval_bin_expr(frame, bin_expr),
             ParserNode::Call(node) => self.eval_call(frame, node),
-            ParserNode::Clean => Ok(self.eval_clean()),
+            ParserNode::Clean => self.eval_clean(),
             ParserNode::ClearScreen => self.eval_clear_screen(),
+            ParserNode::ColorName(idx) => Ok(Value::Number(*idx as f64)),
+            ParserNode::DrawingBounds => Ok(self.eval_drawing_bounds()),
+            ParserNode::Erase(name) => self.eval_erase(name),
+            ParserNode::Every(node) => self.eval_every(frame, node),
             ParserNode::Fill => self.eval_fill(),
+            ParserNode::Fn(key) => Ok(self.eval_fn(key)),
             ParserNode::For(node) => self.eval_for(frame, node),
+            ParserNode::Heading => Ok(Value::Number(self.state.heading)),
             ParserNode::Home => self.eval_home(),
+            ParserNode::Hsv(node) => {
+                let val = self.eval_node(frame, node.arg())?;
+                color::from_hsv(&val)
+            }
             ParserNode::Let(node) => self.eval_let(frame, node),
             ParserNode::List(node) => self.eval_list(frame, node),
+            ParserNode::Load(node) => self.eval_load(frame, node),
+            ParserNode::LoadPicture(name) => self.eval_load_picture(name),
             ParserNode::Math(node) => self.eval_math(frame, node),
             ParserNode::Move(node) => self.eval_move(frame, node),
             ParserNode::Number(num) => Ok(Value::Number(*num)),
+            ParserNode::Param(node) => self.eval_param(frame, node),
             ParserNode::Pen(node) => Ok(self.eval_pen(node)),
+            ParserNode::PickColor => Ok(color::pick()),
+            ParserNode::Point(node) => self.eval_point(frame, node),
+            ParserNode::Pops => Ok(self.eval_pops(frame)),
+            ParserNode::Pots => Ok(self.eval_pots(frame)),
+            ParserNode::Print(node) => self.eval_print(frame, node),
             ParserNode::Random(node) => self.eval_random(frame, node),
+            ParserNode::Read(op) => self.eval_read(*op),
             ParserNode::Repcount => Ok(self.eval_repcount(frame)),
             ParserNode::Repeat(node) => self.eval_repeat(frame, node),
             ParserNode::Rotate(node) => self.eval_rotate(frame, node),
+            ParserNode::Save(name) => self.eval_save(frame, name),
+            ParserNode::SetGeometric(val) => Ok(self.eval_set_geometric(*val)),
+            ParserNode::SetGradient(node) => self.eval_set_gradient(frame, node),
             ParserNode::SetHeading(node) => self.eval_set_heading(frame, node),
             ParserNode::SetPenColor(node) => self.eval_set_pen_color(frame, node),
             ParserNode::SetPosition(node) => self.eval_set_pos(frame, node),
             ParserNode::SetScreenColor(node) => self.eval_set_screen_color(frame, node),
+            ParserNode::SetShape(shape) => self.eval_set_shape(*shape),
+            ParserNode::SetSize(node) => self.eval_set_size(frame, node),
+            ParserNode::SetSpeed(node) => self.eval_set_speed(frame, node),
+            ParserNode::SetTextColor(node) => self.eval_set_text_color(frame, node),
             ParserNode::ShowTurtle(val) => self.eval_show_turtle(*val),
+            ParserNode::Shown => Ok(Self::truth(self.state.shown)),
+            ParserNode::Symmetry(node) => self.eval_symmetry(frame, node),
+            ParserNode::Transform(node) => self.eval_transform(frame, node),
+            ParserNode::UnaryExpr(node) => self.eval_unary_expr(frame, node),
             ParserNode::Word(word) => self.eval_word(frame, word),
             _ => Ok(Value::Void),
         }
@@ -156,43 +379,112 @@ impl Interpreter {
         Self::get_number(&val)
     }
 
+    fn eval_assert(&mut self, frame: &mut Frame, node: &AssertNode) -> RuntimeResult<Value> {
+        let cond = self.eval_node_as_number(frame, node.cond())?;
+        if cond == 0.0 {
+            return Err(RuntimeError::interpreter(ErrorKind::AssertionFailed));
+        }
+
+        Ok(Value::Void)
+    }
+
     fn eval_bin_expr(&mut self, frame: &mut Frame, bin_expr: &BinExprNode) -> RuntimeResult<Value> {
-        let a = self.eval_node(frame, &bin_expr.a())?;
-        let op = bin_expr.op();
-        let b = self.eval_node(frame, &bin_expr.b())?;
+        let a = self.eval_node(frame, bin_expr.a())?;
+        let b = self.eval_node(frame, bin_expr.b())?;
+        Self::apply_bin_op(bin_expr.op(), &a, &b)
+    }
 
+    /// Applies a binary operator to values that have already been evaluated.
+    /// The optimizer shares this to fold constants.
+    pub fn apply_bin_op(op: LexerOperator, a: &Value, b: &Value) -> RuntimeResult<Value> {
         match op {
-            LexerOperator::Add => Self::eval_add(&a, &b),
-            LexerOperator::Divide => Self::eval_divide(&a, &b),
-            LexerOperator::Modulo => Self::eval_modulo(&a, &b),
-            LexerOperator::Multiply => Self::eval_multiply(&a, &b),
-            LexerOperator::Power => Self::eval_power(&a, &b),
-            LexerOperator::Subtract => Self::eval_subtract(&a, &b),
-            _ => {
-                let msg = "cannot evaluate operator".to_string();
-                Err(RuntimeError::Interpreter(msg))
-            }
+            LexerOperator::Add => Self::eval_add(a, b),
+            LexerOperator::Divide => Self::eval_divide(a, b),
+            LexerOperator::Equal => Ok(Self::truth(a == b)),
+            LexerOperator::Greater => Self::eval_compare(a, b, |a, b| a > b),
+            LexerOperator::Less => Self::eval_compare(a, b, |a, b| a < b),
+            LexerOperator::Modulo => Self::eval_modulo(a, b),
+            LexerOperator::Multiply => Self::eval_multiply(a, b),
+            LexerOperator::Power => Self::eval_power(a, b),
+            LexerOperator::Subtract => Self::eval_subtract(a, b),
+            _ => Err(RuntimeError::interpreter(ErrorKind::UnsupportedOperator)),
         }
     }
 
     fn eval_call(&mut self, frame: &mut Frame, node: &CallNode) -> RuntimeResult<Value> {
         let name = node.name();
-        if let Some(func) = frame.fmap.get(name) {
-            let mut child_frame = Frame::new(frame.fmap, &mut frame.vmap, frame.repcount);
-            self.run(&mut child_frame, &func.list)
+        if let Some(func) = frame
+            .fmap
+            .get(self.current(name))
+            .filter(|_| !self.erased.contains(name))
+        {
+            if self.calls == MAX_CALL_DEPTH {
+                return Err(RuntimeError::interpreter(ErrorKind::CallDepth(
+                    MAX_CALL_DEPTH,
+                )));
+            }
+
+            self.calls += 1;
+            let mut child_frame = Frame::new(frame.fmap, frame.vmap, frame.repcount);
+            let val = self.run(&mut child_frame, &func.list);
+            self.calls -= 1;
+            val
         } else {
-            let msg = format!("no such function \"{}\"", name);
-            Err(RuntimeError::Interpreter(msg))
+            let kind = ErrorKind::UndefinedFunction(name.to_string());
+            Err(RuntimeError::interpreter(kind))
         }
     }
 
-    fn eval_clean(&mut self) -> Value {
-        Value::Void
+    fn eval_clean(&mut self) -> RuntimeResult<Value> {
+        self.drawn = None;
+        self.tx(RenderCommand::Clean)?;
+        Ok(Value::Void)
     }
 
+    /// Erase the drawing and send the turtle home without drawing on the
+    /// way, as UCB Logo does. The pen, the colors, and whether the turtle is
+    /// showing all stay as they were.
     fn eval_clear_screen(&mut self) -> RuntimeResult<Value> {
-        self.eval_home()?;
-        Ok(self.eval_clean())
+        self.eval_clean()?;
+
+        let pen_flags = self.state.pen_flags;
+        self.state.pen_flags = pen_up(pen_flags);
+        let homed = self.eval_home();
+        self.state.pen_flags = pen_flags;
+        homed
+    }
+
+    /// In canvas coordinates, so transforms and symmetry copies count where
+    /// they land.
+    fn eval_drawing_bounds(&self) -> Value {
+        match self.drawn {
+            Some(rect) => Value::List(
+                [rect.x0, rect.y0, rect.x1, rect.y1]
+                    .iter()
+                    .map(|n| Value::Number(*n))
+                    .collect(),
+            ),
+            None => Value::List(ValueList::new()),
+        }
+    }
+
+    fn eval_erase(&mut self, name: &str) -> RuntimeResult<Value> {
+        if self.erased.insert(name.to_string()) {
+            Ok(Value::Void)
+        } else {
+            let kind = ErrorKind::UndefinedFunction(name.to_string());
+            Err(RuntimeError::interpreter(kind))
+        }
+    }
+
+    /// Reaching a definition puts it in effect, even over an erase.
+    fn eval_fn(&mut self, key: &str) -> Value {
+        let name = written_name(key);
+        self.erased.remove(name);
+        if key != name {
+            self.current.insert(name.to_string(), key.to_string());
+        }
+        Value::Void
     }
 
     fn eval_fill(&mut self) -> RuntimeResult<Value> {
@@ -201,6 +493,47 @@ impl Interpreter {
         Ok(Value::Void)
     }
 
+    /// Runs until stopped, so this only returns with an error. With nobody to
+    /// stop it, as in a headless run, the block goes round once instead. A
+    /// block that takes longer than the interval skips ticks rather than
+    /// hurrying to catch up.
+    fn eval_every(&mut self, frame: &mut Frame, node: &EveryNode) -> RuntimeResult<Value> {
+        let secs = match node.interval() {
+            Some(interval) => self.eval_node_as_number(frame, interval)?,
+            None => 0.0,
+        };
+        if !(secs >= 0.0 && secs.is_finite()) {
+            return Err(RuntimeError::interpreter(ErrorKind::InvalidInterval(secs)));
+        }
+
+        let interval = Duration::from_secs_f64(secs);
+        let list = node.list();
+        let mut child_frame = Frame::new(frame.fmap, frame.vmap, 0);
+        let mut tick = Instant::now();
+
+        loop {
+            self.check_loop()?;
+            child_frame.repcount += 1;
+            self.run(&mut child_frame, list)?;
+
+            if !self.control.is_listening() {
+                return Ok(Value::Void);
+            }
+
+            tick += interval;
+            let now = Instant::now();
+            if tick < now {
+                tick = now;
+            }
+
+            // Waiting doesn't count against the time limit, but the work in
+            // between does.
+            self.flush()?;
+            let waited = self.control.wait_until(tick)?;
+            self.check_time(waited)?;
+        }
+    }
+
     fn eval_for(&mut self, frame: &mut Frame, node: &ForNode) -> RuntimeResult<Value> {
         let var = node.var();
         let initial = self.eval_node_as_number(frame, node.initial())?;
@@ -215,6 +548,7 @@ impl Interpreter {
         frame.vmap.insert(var.to_string(), Value::Number(i));
 
         while i < limit {
+            self.check_loop()?;
             self.run(frame, list)?;
 
             i += step;
@@ -224,8 +558,12 @@ impl Interpreter {
         Ok(Value::Void)
     }
 
+    /// Like standard Logo, this draws the way back if the pen is down, and
+    /// leaves the turtle facing up.
     fn eval_home(&mut self) -> RuntimeResult<Value> {
         self.move_to(Point::ZERO)?;
+        self.state.heading = 0.0;
+        self.turn()?;
         Ok(Value::Void)
     }
 
@@ -245,29 +583,34 @@ impl Interpreter {
         Ok(Value::List(out))
     }
 
+    fn eval_load_picture(&mut self, name: &str) -> RuntimeResult<Value> {
+        let picture = self.library.read_picture(name)?;
+        self.tx(RenderCommand::Background(Arc::new(picture)))?;
+        Ok(Value::Void)
+    }
+
+    fn eval_load(&mut self, frame: &mut Frame, node: &LoadNode) -> RuntimeResult<Value> {
+        self.run(frame, node.list())?;
+        Ok(Value::Void)
+    }
+
     fn eval_math(&mut self, frame: &mut Frame, node: &MathNode) -> RuntimeResult<Value> {
         let arg = self.eval_node_as_number(frame, node.arg())?;
+        Ok(Value::Number(Self::apply_math(node.op(), arg)))
+    }
 
-        let res = match node.op() {
-            MathOp::Atan => {
-                let rads = arg.to_radians();
-                Value::Number(rads.atan())
-            }
-            MathOp::Cos => {
-                let rads = arg.to_radians();
-                Value::Number(rads.cos())
-            }
-            MathOp::Log10 => Value::Number(arg.log10()),
-            MathOp::Ln => Value::Number(arg.ln()),
-            MathOp::Round => Value::Number(arg.round()),
-            MathOp::Sin => {
-                let rads = arg.to_radians();
-                Value::Number(rads.sin())
-            }
-            MathOp::Sqrt => Value::Number(arg.sqrt()),
-        };
-
-        Ok(res)
+    /// Applies a math function to an evaluated argument. The optimizer shares
+    /// this to fold constants.
+    pub fn apply_math(op: MathOp, arg: f64) -> f64 {
+        match op {
+            MathOp::Atan => arg.to_radians().atan(),
+            MathOp::Cos => arg.to_radians().cos(),
+            MathOp::Log10 => arg.log10(),
+            MathOp::Ln => arg.ln(),
+            MathOp::Round => arg.round(),
+            MathOp::Sin => arg.to_radians().sin(),
+            MathOp::Sqrt => arg.sqrt(),
+        }
     }
 
     fn eval_move(&mut self, frame: &mut Frame, node: &MoveNode) -> RuntimeResult<Value> {
@@ -282,10 +625,7 @@ impl Interpreter {
                 self.move_by(-distance)?;
                 Ok(Value::Void)
             }
-            _ => {
-                let msg = "movement must be forward or backward".to_string();
-                Err(RuntimeError::Interpreter(msg))
-            }
+            _ => Err(RuntimeError::interpreter(ErrorKind::InvalidMovement)),
         }
     }
 
@@ -300,6 +640,80 @@ impl Interpreter {
         Value::Void
     }
 
+    fn eval_point(&mut self, frame: &mut Frame, node: &PointNode) -> RuntimeResult<Value> {
+        let x = self.eval_node_as_number(frame, node.x())?;
+        let y = self.eval_node_as_number(frame, node.y())?;
+        let dx = x - self.state.pos.x;
+        let dy = y - self.state.pos.y;
+
+        let res = match node.op() {
+            PointOp::Distance => dx.hypot(dy),
+            // Headings start at up and turn clockwise.
+            PointOp::Towards => dx.atan2(dy).to_degrees().rem_euclid(360.0),
+        };
+
+        Ok(Value::Number(res))
+    }
+
+    fn eval_pops(&mut self, frame: &mut Frame) -> Value {
+        for name in self.procedures(frame) {
+            let text = Printer::new(self.written.as_ref().unwrap_or(frame.fmap)).func(name);
+            self.print(text.trim_end());
+        }
+        Value::Void
+    }
+
+    fn eval_pots(&mut self, frame: &mut Frame) -> Value {
+        for key in self.procedures(frame) {
+            self.print(&format!("fn {}", written_name(key)));
+        }
+        Value::Void
+    }
+
+    fn eval_print(&mut self, frame: &mut Frame, node: &PrintNode) -> RuntimeResult<Value> {
+        let val = self.eval_node(frame, node.arg())?;
+        if val == Value::Void {
+            return Err(RuntimeError::interpreter(ErrorKind::TypeMismatch {
+                expected: "a value",
+                got: val.type_name(),
+            }));
+        }
+
+        match node.op() {
+            PrintOp::Print => self.print(&val.plain()),
+            PrintOp::Show => self.print(&val.to_string()),
+            PrintOp::Type => self.write(&val.plain()),
+        }
+        Ok(Value::Void)
+    }
+
+    /// The value comes from the slider if there is one, kept within the
+    /// range, and otherwise starts at the low end.
+    fn eval_param(&mut self, frame: &mut Frame, node: &ParamNode) -> RuntimeResult<Value> {
+        let min = self.eval_node_as_number(frame, node.min())?;
+        let max = self.eval_node_as_number(frame, node.max())?;
+        if min.is_nan() || max.is_nan() || min > max {
+            return Err(RuntimeError::interpreter(ErrorKind::InvalidParamRange(
+                min, max,
+            )));
+        }
+
+        let value = self
+            .control
+            .param(node.name())
+            .map_or(min, |value| value.max(min).min(max));
+        let param = Param::new(node.name(), min, max, value);
+        match self.params.iter_mut().find(|p| p.name == param.name) {
+            Some(declared) => *declared = param,
+            None => self.params.push(param),
+        }
+
+        frame
+            .vmap
+            .insert(node.name().to_string(), Value::Number(value));
+        Ok(Value::Void)
+    }
+
     fn eval_random(&mut self, frame: &mut Frame, node: &RandomNode) -> RuntimeResult<Value> {
         let max = self.eval_node_as_number(frame, node.max())?;
         let intmax = max.round() as u32;
@@ -307,16 +721,39 @@ impl Interpreter {
         Ok(Value::Number(num as f64))
     }
 
+    /// A list reads each word as a number if it looks like one, and a word
+    /// is a number only if the whole line is.
+    fn eval_read(&mut self, op: ReadOp) -> RuntimeResult<Value> {
+        self.flush()?;
+        let (line, waited) = self.control.read(&self.text)?;
+        self.check_time(waited)?;
+
+        let val = match op {
+            ReadOp::List => Value::List(line.split_whitespace().map(Value::parse).collect()),
+            ReadOp::Word => Value::parse(line.trim()),
+        };
+        Ok(val)
+    }
+
     fn eval_repcount(&mut self, frame: &mut Frame) -> Value {
         Value::Number(frame.repcount as f64)
     }
 
+    /// Fractional counts round down, like palette indexes, so `repeat 2.5`
+    /// runs twice. A count that's negative or not a number is an error.
     fn eval_repeat(&mut self, frame: &mut Frame, node: &RepeatNode) -> RuntimeResult<Value> {
         let count = self.eval_node_as_number(frame, node.count())?;
+        if !count.is_finite() || count < 0.0 {
+            return Err(RuntimeError::interpreter(ErrorKind::InvalidRepeatCount(
+                count,
+            )));
+        }
+
         let list = node.list();
-        let mut child_frame = Frame::new(frame.fmap, &mut frame.vmap, 0);
+        let mut child_frame = Frame::new(frame.fmap, frame.vmap, 0);
 
         for _ in 0..count as usize {
+            self.check_loop()?;
             child_frame.repcount += 1;
             self.run(&mut child_frame, list)?;
         }
@@ -328,19 +765,30 @@ impl Interpreter {
         let angle = self.eval_node_as_number(frame, node.angle())?;
 
         match node.direction() {
-            Direction::Left => {
-                self.state.angle -= angle.to_radians();
-                Ok(Value::Void)
-            }
-            Direction::Right => {
-                self.state.angle += angle.to_radians();
-                Ok(Value::Void)
-            }
-            _ => {
-                let msg = "rotation must be right or left".to_string();
-                Err(RuntimeError::Interpreter(msg))
-            }
+            Direction::Left => self.state.heading = Self::normalize(self.state.heading - angle),
+            Direction::Right => self.state.heading = Self::normalize(self.state.heading + angle),
+            _ => return Err(RuntimeError::interpreter(ErrorKind::InvalidRotation)),
         }
+
+        self.turn()?;
+        Ok(Value::Void)
+    }
+
+    /// Write every procedure to a file in the library, in name order.
+    fn eval_save(&mut self, frame: &mut Frame, name: &str) -> RuntimeResult<Value> {
+        let mut text = String::new();
+        for name in self.procedures(frame) {
+            text.push_str(&Printer::new(self.written.as_ref().unwrap_or(frame.fmap)).func(name));
+            text.push('\n');
+        }
+
+        self.library.write(name, &text)?;
+        Ok(Value::Void)
+    }
+
+    fn eval_set_geometric(&mut self, val: bool) -> Value {
+        self.state.geometric = val;
+        Value::Void
     }
 
     fn eval_set_heading(
@@ -349,7 +797,8 @@ impl Interpreter {
         node: &SetHeadingNode,
     ) -> RuntimeResult<Value> {
         let angle = self.eval_node_as_number(frame, node.angle())?;
-        self.state.angle = angle.to_radians();
+        self.state.heading = Self::normalize(angle);
+        self.turn()?;
         Ok(Value::Void)
     }
 
@@ -358,8 +807,39 @@ impl Interpreter {
         frame: &mut Frame,
         node: &SetPenColorNode,
     ) -> RuntimeResult<Value> {
-        let val = self.eval_node(frame, node.color())?;
-        self.state.color = Self::get_color(&self.pal, &val)?;
+        self.state.color = self.eval_color(frame, node.color())?;
+        self.state.gradient = None;
+        Ok(Value::Void)
+    }
+
+    fn eval_set_gradient(
+        &mut self,
+        frame: &mut Frame,
+        node: &SetGradientNode,
+    ) -> RuntimeResult<Value> {
+        let colors = self.eval_node(frame, node.colors())?;
+        let (from, to) = match &colors {
+            Value::List(list) if list.len() == 2 => {
+                (self.get_color(&list[0])?, self.get_color(&list[1])?)
+            }
+            Value::List(_) => {
+                return Err(RuntimeError::interpreter(ErrorKind::Arity { expected: 2 }));
+            }
+            _ => {
+                let kind = ErrorKind::TypeMismatch {
+                    expected: "a list",
+                    got: colors.type_name(),
+                };
+                return Err(RuntimeError::interpreter(kind));
+            }
+        };
+
+        let moves = self.eval_node_as_number(frame, node.moves())?.round();
+        if !(1.0..=u32::MAX as f64).contains(&moves) {
+            return Err(RuntimeError::interpreter(ErrorKind::InvalidGradient(moves)));
+        }
+
+        self.state.gradient = Some(Gradient::new(from, to, moves as u32));
         Ok(Value::Void)
     }
 
@@ -386,29 +866,131 @@ impl Interpreter {
         frame: &mut Frame,
         node: &SetScreenColorNode,
     ) -> RuntimeResult<Value> {
-        let val = self.eval_node(frame, node.color())?;
-        self.state.screen_color = Self::get_color(&self.pal, &val)?;
+        self.state.screen_color = self.eval_color(frame, node.color())?;
+        self.tx(RenderCommand::ScreenColor(self.state.screen_color.clone()))?;
+        Ok(Value::Void)
+    }
+
+    fn eval_set_shape(&mut self, shape: TurtleShape) -> RuntimeResult<Value> {
+        self.tx(RenderCommand::Shape(shape))?;
+        Ok(Value::Void)
+    }
+
+    fn eval_set_size(&mut self, frame: &mut Frame, node: &SetSizeNode) -> RuntimeResult<Value> {
+        let size = self.eval_node_as_number(frame, node.size())?;
+        if !(1.0..=MAX_TURTLE_SIZE).contains(&size) {
+            return Err(RuntimeError::interpreter(ErrorKind::InvalidSize(size)));
+        }
+
+        self.tx(RenderCommand::Size(size))?;
+        Ok(Value::Void)
+    }
+
+    fn eval_symmetry(&mut self, frame: &mut Frame, node: &SymmetryNode) -> RuntimeResult<Value> {
+        let ways = self.eval_node_as_number(frame, node.ways())?;
+        if !(1.0..=MAX_SYMMETRY as f64).contains(&ways) || ways.fract() != 0.0 {
+            return Err(RuntimeError::interpreter(ErrorKind::InvalidSymmetry(ways)));
+        }
+
+        self.state.symmetry = ways as u32;
+        Ok(Value::Void)
+    }
+
+    /// The turtle keeps its own position and heading, so after a change it
+    /// shows up wherever they now land on the canvas.
+    fn eval_transform(&mut self, frame: &mut Frame, node: &TransformNode) -> RuntimeResult<Value> {
+        match node {
+            TransformNode::Pop => match self.transforms.pop() {
+                Some(transform) => self.state.transform = transform,
+                None => {
+                    return Err(RuntimeError::interpreter(ErrorKind::EmptyTransformStack));
+                }
+            },
+            TransformNode::Push => {
+                self.transforms.push(self.state.transform);
+                return Ok(Value::Void);
+            }
+            TransformNode::Rotate(angle) => {
+                let angle = self.eval_node_as_number(frame, angle)?;
+                self.state.transform.rotate(angle);
+            }
+            TransformNode::Scale(factor) => {
+                let factor = self.eval_node_as_number(frame, factor)?;
+                if factor == 0.0 || !factor.is_finite() {
+                    return Err(RuntimeError::interpreter(ErrorKind::InvalidScale(factor)));
+                }
+                self.state.transform.scale(factor);
+            }
+            TransformNode::Translate(x, y) => {
+                let x = self.eval_node_as_number(frame, x)?;
+                let y = self.eval_node_as_number(frame, y)?;
+                self.state.transform.translate(x, y);
+            }
+        }
+
+        let pos = self.state.transform.apply(self.state.pos);
+        let move_to = MoveTo::new(
+            0.0,
+            self.state.color.clone(),
+            0.0,
+            pen_up(self.state.pen_flags),
+            pos,
+        );
+        self.tx(RenderCommand::MoveTo(move_to))?;
+        self.turn()?;
+        Ok(Value::Void)
+    }
+
+    /// Change the pace straight away, and tell the canvas so the speed
+    /// control follows along.
+    fn eval_set_speed(&mut self, frame: &mut Frame, node: &SetSpeedNode) -> RuntimeResult<Value> {
+        let speed = self.eval_node_as_number(frame, node.speed())?.round();
+        if !(MIN_SPEED as f64..=MAX_SPEED as f64).contains(&speed) {
+            let kind = ErrorKind::InvalidSpeed(speed.to_string());
+            return Err(RuntimeError::interpreter(kind));
+        }
+
+        self.speed.store(speed as u32, Ordering::Relaxed);
+        self.tx(RenderCommand::Speed(speed as u32))?;
+        Ok(Value::Void)
+    }
+
+    fn eval_set_text_color(
+        &mut self,
+        frame: &mut Frame,
+        node: &SetTextColorNode,
+    ) -> RuntimeResult<Value> {
+        self.text_color = Some(self.eval_color(frame, node.color())?);
         Ok(Value::Void)
     }
 
     fn eval_show_turtle(&mut self, val: bool) -> RuntimeResult<Value> {
+        self.state.shown = val;
         let cmd = RenderCommand::ShowTurtle(val);
         self.tx(cmd)?;
         Ok(Value::Void)
     }
 
+    fn eval_unary_expr(&mut self, frame: &mut Frame, node: &UnaryExprNode) -> RuntimeResult<Value> {
+        let a = self.eval_node_as_number(frame, node.a())?;
+
+        match node.op() {
+            UnaryOp::Negate => Ok(Value::Number(-a)),
+        }
+    }
+
     fn eval_word(&mut self, frame: &mut Frame, word: &str) -> RuntimeResult<Value> {
         if let Some(value) = frame.vmap.get(word) {
             Ok(value.clone())
         } else {
-            let msg = format!("no such variable \"{}\"", word);
-            Err(RuntimeError::Interpreter(msg))
+            let kind = ErrorKind::UndefinedVariable(word.to_string());
+            Err(RuntimeError::interpreter(kind))
         }
     }
 
     fn err_eval_bin_expr(a: &Value, b: &Value) -> RuntimeResult<Value> {
-        let msg = format!("cannot evaluate {:?} {:?}", a, b);
-        Err(RuntimeError::Interpreter(msg))
+        let kind = ErrorKind::InvalidOperands(a.clone(), b.clone());
+        Err(RuntimeError::interpreter(kind))
     }
 
     fn eval_add(a: &Value, b: &Value) -> RuntimeResult<Value> {
@@ -436,9 +1018,19 @@ impl Interpreter {
         }
     }
 
+    fn eval_compare(a: &Value, b: &Value, cmp: fn(f64, f64) -> bool) -> RuntimeResult<Value> {
+        match (a, b) {
+            (Value::Number(a_num), Value::Number(b_num)) => Ok(Self::truth(cmp(*a_num, *b_num))),
+            _ => Self::err_eval_bin_expr(a, b),
+        }
+    }
+
     fn eval_divide(a: &Value, b: &Value) -> RuntimeResult<Value> {
         match a {
             Value::Number(a_num) => match b {
+                Value::Number(other_num) if *other_num == 0.0 => {
+                    Err(RuntimeError::interpreter(ErrorKind::DivByZero))
+                }
                 Value::Number(other_num) => Ok(Value::Number(a_num / other_num)),
                 _ => Self::err_eval_bin_expr(a, b),
             },
@@ -449,6 +1041,9 @@ impl Interpreter {
     fn eval_modulo(a: &Value, b: &Value) -> RuntimeResult<Value> {
         match a {
             Value::Number(a_num) => match b {
+                Value::Number(b_num) if *b_num == 0.0 => {
+                    Err(RuntimeError::interpreter(ErrorKind::DivByZero))
+                }
                 Value::Number(b_num) => Ok(Value::Number(a_num % b_num)),
                 _ => Self::err_eval_bin_expr(a, b),
             },
@@ -486,50 +1081,46 @@ impl Interpreter {
         }
     }
 
-    fn get_color_component(val: &Value) -> RuntimeResult<u8> {
-        let comp = Self::get_number(val)?;
-        if (0.0..=255.0).contains(&comp) {
-            Ok(comp as u8)
-        } else {
-            let msg = format!("color component out of bounds \"{}\"", comp);
-            Err(RuntimeError::Interpreter(msg))
+    fn eval_color(&mut self, frame: &mut Frame, node: &ParserNode) -> RuntimeResult<Color> {
+        // The optimizer may have already looked the color up in the palette.
+        if let ParserNode::Color(color) = node {
+            return Ok(color.clone());
         }
-    }
-
-    fn get_color(pal: &Palette, val: &Value) -> RuntimeResult<Color> {
-        match val {
-            Value::List(list) => {
-                Self::vlist_expect(&list, 3)?;
-                let red = Self::get_color_component(&list[0])?;
-                let green = Self::get_color_component(&list[1])?;
-                let blue = Self::get_color_component(&list[2])?;
 
-                Ok(Color::rgb8(red as u8, green as u8, blue as u8))
-            }
+        let val = self.eval_node(frame, node)?;
+        self.get_color(&val)
+    }
 
-            Value::Number(num) => {
-                let idx = *num as u8;
-                if let Some(color) = pal.get(&idx) {
-                    Ok(color.clone())
-                } else {
-                    let msg = format!("invalid palette index {}", idx);
-                    Err(RuntimeError::Interpreter(msg))
-                }
-            }
+    fn get_color(&self, val: &Value) -> RuntimeResult<Color> {
+        ColorSpec::parse(val, &self.warnings)?.resolve(&self.pal)
+    }
 
-            _ => {
-                let msg = "color cannot be void".to_string();
-                Err(RuntimeError::Interpreter(msg))
-            }
-        }
+    /// Comparisons produce 1 for true and 0 for false.
+    fn truth(val: bool) -> Value {
+        Value::Number(if val { 1.0 } else { 0.0 })
     }
 
     fn get_number(val: &Value) -> RuntimeResult<f64> {
         if let Value::Number(num) = val {
             Ok(*num)
         } else {
-            let msg = "expected a number".to_string();
-            Err(RuntimeError::Interpreter(msg))
+            let kind = ErrorKind::TypeMismatch {
+                expected: "a number",
+                got: val.type_name(),
+            };
+            Err(RuntimeError::interpreter(kind))
+        }
+    }
+
+    /// Keep headings in one turn, so that long runs of turns don't build up
+    /// a huge angle and lose precision.
+    fn normalize(degrees: f64) -> f64 {
+        let degrees = degrees.rem_euclid(360.0);
+        // A tiny negative angle rounds up to a whole turn.
+        if degrees >= 360.0 {
+            0.0
+        } else {
+            degrees
         }
     }
 
@@ -538,11 +1129,14 @@ impl Interpreter {
     }
 
     fn move_by(&mut self, distance: f64) -> RuntimeResult {
-        let angle = (90.0_f64).to_radians() - self.state.angle;
-        let p = Point::new(
-            (self.state.pos.x + distance * angle.cos()).round(),
-            (self.state.pos.y + distance * angle.sin()).round(),
+        let angle = (90.0 - self.state.heading).to_radians();
+        let mut p = Point::new(
+            self.state.pos.x + distance * angle.cos(),
+            self.state.pos.y + distance * angle.sin(),
         );
+        if !self.state.geometric {
+            p = p.round();
+        }
         self.move_to_inner(angle, p)?;
         self.state.pos = p;
         Ok(())
@@ -555,33 +1149,158 @@ impl Interpreter {
         Ok(())
     }
 
+    /// Draws to `p`, in the turtle's coordinates, and tells the canvas
+    /// where that lands.
     fn move_to_inner(&mut self, angle: f64, p: Point) -> RuntimeResult {
+        let from = self.state.transform.apply(self.state.pos);
+        let to = self.state.transform.apply(p);
+
+        // Moving off with the pen up leaves nothing out of sight.
+        let drawing = is_pen_down(self.state.pen_flags);
+        if drawing && self.left_canvas.is_none() && self.is_off_canvas(to) {
+            self.left_canvas = Some(to);
+        }
+
+        if let Some(gradient) = self.state.gradient.as_mut() {
+            self.state.color = gradient.step();
+            if gradient.is_done() {
+                self.state.gradient = None;
+            }
+        }
+
+        if self.state.symmetry > 1 && is_pen_down(self.state.pen_flags) {
+            self.draw_copies(angle, from, to)?;
+        }
+
+        self.mark_drawn(from, to);
         let move_to = MoveTo::new(
             angle,
             self.state.color.clone(),
             0.0,
             self.state.pen_flags,
-            p,
+            to,
         );
 
         self.tx(RenderCommand::MoveTo(move_to))
     }
 
+    /// The turned copies of the line from `from` to `p` that symmetry adds.
+    /// Each is reached with the pen up, and the pen goes back to the start of
+    /// the real line afterwards, so the canvas needs nothing special.
+    fn draw_copies(&mut self, angle: f64, from: Point, p: Point) -> RuntimeResult {
+        let flags = self.state.pen_flags;
+        let color = self.state.color.clone();
+
+        for way in 1..self.state.symmetry {
+            let turn = 2.0 * std::f64::consts::PI * way as f64 / self.state.symmetry as f64;
+            let (sin, cos) = turn.sin_cos();
+            let rotate = |q: Point| Point::new(q.x * cos - q.y * sin, q.x * sin + q.y * cos);
+
+            let start = MoveTo::new(
+                angle + turn,
+                color.clone(),
+                0.0,
+                pen_up(flags),
+                rotate(from),
+            );
+            self.tx(RenderCommand::MoveTo(start))?;
+            self.mark_drawn(rotate(from), rotate(p));
+            let end = MoveTo::new(angle + turn, color.clone(), 0.0, flags, rotate(p));
+            self.tx(RenderCommand::MoveTo(end))?;
+        }
+
+        let back = MoveTo::new(angle, color, 0.0, pen_up(flags), from);
+        self.tx(RenderCommand::MoveTo(back))
+    }
+
+    fn is_off_canvas(&self, p: Point) -> bool {
+        p.x.abs() > self.bounds.width || p.y.abs() > self.bounds.height
+    }
+
+    /// Grow the drawing's box to take in a line, if the pen leaves ink, and
+    /// count it if it runs off the canvas.
+    fn mark_drawn(&mut self, from: Point, to: Point) {
+        let flags = self.state.pen_flags;
+        if !is_pen_down(flags) || is_pen_erase(flags) {
+            return;
+        }
+
+        if self.is_off_canvas(from) || self.is_off_canvas(to) {
+            self.clipped += 1;
+        }
+
+        let line = Rect::from_points(from, to);
+        self.drawn = Some(match self.drawn {
+            Some(rect) => rect.union(line),
+            None => line,
+        });
+    }
+
+    /// Let the canvas know the heading, so it can point the turtle.
+    fn turn(&mut self) -> RuntimeResult {
+        let heading = self.state.transform.heading(self.state.heading);
+        self.tx(RenderCommand::Turn(heading))
+    }
+
+    /// Pen-down moves in the same color and pen are saved up and sent as
+    /// one path, at the latest when the interpreter pauses for the canvas.
     fn tx(&mut self, cmd: RenderCommand) -> RuntimeResult {
         self.render_tx_count += 1;
+        match cmd {
+            RenderCommand::MoveTo(move_to) if is_pen_down(move_to.pen_flags) => {
+                let joins = self.path.last().map_or(true, |last| {
+                    last.color == move_to.color && last.pen_flags == move_to.pen_flags
+                });
+                if !joins {
+                    self.flush()?;
+                }
+                self.path.push(move_to);
+            }
+            // Only the last heading shows by the time the path is drawn.
+            RenderCommand::Turn(heading) if !self.path.is_empty() => {
+                self.turn_after = Some(heading);
+            }
+            cmd => {
+                self.flush()?;
+                self.send(cmd)?;
+            }
+        }
+
         if self.render_tx_count % self.speed.load(Ordering::Relaxed) == 0 {
-            thread::sleep(Duration::from_millis(30));
+            self.flush()?;
+            clock::sleep(RENDER_DELAY);
+            self.check_time(RENDER_DELAY)?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the saved up moves, and the turn that came after them.
+    fn flush(&mut self) -> RuntimeResult {
+        let cmd = match self.path.len() {
+            0 => None,
+            1 => self.path.pop().map(RenderCommand::MoveTo),
+            _ => Some(RenderCommand::Path(std::mem::take(&mut self.path))),
+        };
+        if let Some(cmd) = cmd {
+            self.send(cmd)?;
+        }
+        if let Some(heading) = self.turn_after.take() {
+            self.send(RenderCommand::Turn(heading))?;
         }
 
-        self.render_tx.unbounded_send(cmd)?;
+        Ok(())
+    }
 
+    fn send(&self, cmd: RenderCommand) -> RuntimeResult {
+        profile::time(Phase::Send, || self.render_tx.unbounded_send(cmd))?;
         Ok(())
     }
 
     fn vlist_expect(list: &[Value], n: usize) -> RuntimeResult {
         if list.len() < n {
-            let msg = format!("{} items expected", n);
-            Err(RuntimeError::Interpreter(msg))
+            let kind = ErrorKind::Arity { expected: n };
+            Err(RuntimeError::interpreter(kind))
         } else {
             Ok(())
         }
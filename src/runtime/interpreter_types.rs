@@ -21,14 +21,67 @@ pub enum Value {
     Void,
     List(ValueList),
     Number(f64),
+    /// Text typed in by the user.
+    Word(String),
+}
+
+impl Value {
+    /// How the value's type reads in an error message.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Void => "void",
+            Value::List(_) => "a list",
+            Value::Number(_) => "a number",
+            Value::Word(_) => "a word",
+        }
+    }
+
+    /// A number if the text is one, otherwise a word. Words like "nan" and
+    /// "inf" stay words.
+    pub fn parse(text: &str) -> Self {
+        match text.parse::<f64>() {
+            Ok(num) if num.is_finite() => Value::Number(num),
+            _ => Value::Word(text.to_string()),
+        }
+    }
+
+    /// The value the way `print` writes it: a list loses its outer brackets,
+    /// but keeps those of any lists inside it.
+    pub fn plain(&self) -> String {
+        match self {
+            Value::List(list) => join(list),
+            _ => self.to_string(),
+        }
+    }
 }
 
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Void => write!(f, "void"),
-            Value::List(_) => write!(f, "[]"),
-            Value::Number(num) => write!(f, "{}", num),
+            Value::List(list) => write!(f, "[{}]", join(list)),
+            Value::Number(num) => write!(f, "{}", number(*num)),
+            Value::Word(word) => write!(f, "{}", word),
         }
     }
 }
+
+/// A number as it's printed. Whole numbers are written out in full, digit
+/// for digit, but fractions round to 15 significant digits so that
+/// arithmetic noise doesn't show: `0.1 + 0.2` prints as 0.3. Negative zero
+/// prints as 0.
+fn number(num: f64) -> String {
+    if num == 0.0 {
+        "0".to_string()
+    } else if num.fract() == 0.0 {
+        format!("{:.0}", num)
+    } else {
+        let rounded: f64 = format!("{:.14e}", num).parse().unwrap_or(num);
+        rounded.to_string()
+    }
+}
+
+fn join(list: &[Value]) -> String {
+    let items: Vec<_> = list.iter().map(|item| item.to_string()).collect();
+    items.join(" ")
+}
@@ -0,0 +1,723 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::model::prefs::Language;
+
+/// Every command and function built into the language. The parser matches
+/// words against this table, and the language reference is generated from it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Keyword {
+    Arctan,
+    Assert,
+    Backward,
+    Clean,
+    ClearScreen,
+    Cos,
+    Distance,
+    DrawingBounds,
+    Erase,
+    Every,
+    Fill,
+    Fn,
+    For,
+    Forever,
+    Forward,
+    Heading,
+    HideTurtle,
+    Home,
+    Hsv,
+    Left,
+    Let,
+    Ln,
+    Load,
+    LoadPicture,
+    Log10,
+    Param,
+    PenDown,
+    PenErase,
+    PenPaint,
+    PenReverse,
+    PenUp,
+    PickColor,
+    Pops,
+    PopTransform,
+    Pots,
+    Print,
+    PushTransform,
+    Random,
+    ReadList,
+    ReadWord,
+    Repcount,
+    Repeat,
+    Right,
+    RotateCs,
+    Round,
+    Save,
+    Scale,
+    SetGeometric,
+    SetGradient,
+    SetHeading,
+    SetPenColor,
+    SetPenColorHsv,
+    SetPos,
+    SetScreenColor,
+    SetScreenColorHsv,
+    SetShape,
+    SetSize,
+    SetSpeed,
+    SetTextColor,
+    SetX,
+    SetXY,
+    SetY,
+    Show,
+    ShowTurtle,
+    Shown,
+    Sin,
+    Sqrt,
+    Symmetry,
+    Towards,
+    Translate,
+    Type,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct KeywordDef {
+    pub keyword: Keyword,
+    pub names: &'static [&'static str],
+    pub syntax: &'static str,
+    pub description: &'static str,
+}
+
+macro_rules! kw {
+    ($keyword: ident, [$( $name: expr ),+], $syntax: expr, $description: expr) => {
+        KeywordDef {
+            keyword: Keyword::$keyword,
+            names: &[$( $name ),+],
+            syntax: $syntax,
+            description: $description,
+        }
+    };
+}
+
+pub const KEYWORDS: &[KeywordDef] = &[
+    kw!(Arctan, ["arctan"], "arctan num", "Arctangent of num."),
+    kw!(
+        Assert,
+        ["assert"],
+        "assert (a = b)",
+        "Stop with an error unless the condition is true."
+    ),
+    kw!(
+        Backward,
+        ["bk", "backward"],
+        "backward distance",
+        "Move the turtle backward."
+    ),
+    kw!(
+        Clean,
+        ["clean"],
+        "clean",
+        "Erase the drawing without moving the turtle."
+    ),
+    kw!(
+        ClearScreen,
+        ["cs", "clearscreen"],
+        "clearscreen",
+        "Erase the drawing and send the turtle home. The pen and colors stay."
+    ),
+    kw!(Cos, ["cos"], "cos degrees", "Cosine of an angle."),
+    kw!(
+        Distance,
+        ["distance"],
+        "distance [x y]",
+        "How far the turtle is from a position."
+    ),
+    kw!(
+        DrawingBounds,
+        ["drawingbounds"],
+        "drawingbounds",
+        "The box around everything drawn since the last clean, as [left bottom right top]; empty before anything is drawn."
+    ),
+    kw!(
+        Erase,
+        ["er", "erase"],
+        "erase \"name",
+        "Forget a procedure so it can no longer be called."
+    ),
+    kw!(
+        Every,
+        ["every"],
+        "every seconds { ... }",
+        "Run a block on a timer, once every so many seconds, until stopped."
+    ),
+    kw!(
+        Fill,
+        ["fill"],
+        "fill",
+        "Flood fill the area under the turtle with the pen color."
+    ),
+    kw!(Fn, ["fn"], "fn name { ... }", "Define a procedure."),
+    kw!(
+        For,
+        ["for"],
+        "for var start limit step { ... }",
+        "Run a block while counting var from start to limit."
+    ),
+    kw!(
+        Forever,
+        ["forever"],
+        "forever { ... }",
+        "Run a block over and over until stopped."
+    ),
+    kw!(
+        Forward,
+        ["fd", "forward"],
+        "forward distance",
+        "Move the turtle forward."
+    ),
+    kw!(
+        Heading,
+        ["heading"],
+        "heading",
+        "Direction the turtle faces, from 0 up to 360; 0 is up."
+    ),
+    kw!(
+        HideTurtle,
+        ["ht", "hideturtle"],
+        "hideturtle",
+        "Hide the turtle."
+    ),
+    kw!(
+        Home,
+        ["home"],
+        "home",
+        "Move the turtle back to the center, facing up."
+    ),
+    kw!(
+        Hsv,
+        ["hsv"],
+        "hsv [hue saturation value]",
+        "A color as [r g b] from a hue in degrees, and saturation and value from 0 to 1."
+    ),
+    kw!(
+        Left,
+        ["lt", "left"],
+        "left degrees",
+        "Turn the turtle left."
+    ),
+    kw!(
+        Let,
+        ["let"],
+        "let var = value",
+        "Assign a value to a variable."
+    ),
+    kw!(Ln, ["ln"], "ln num", "Natural logarithm of num."),
+    kw!(
+        Load,
+        ["load"],
+        "load \"name",
+        "Run a file from the library, defining its procedures."
+    ),
+    kw!(
+        LoadPicture,
+        ["loadpict"],
+        "loadpict \"name",
+        "Show a PNG from the library behind the drawing."
+    ),
+    kw!(Log10, ["log10"], "log10 num", "Base 10 logarithm of num."),
+    kw!(
+        Param,
+        ["param"],
+        "param :name min max",
+        "A number from min to max, set with a slider under the input. Starts at min."
+    ),
+    kw!(
+        PenDown,
+        ["pd", "pendown"],
+        "pendown",
+        "Draw when the turtle moves."
+    ),
+    kw!(
+        PenErase,
+        ["pe", "penerase"],
+        "penerase",
+        "Erase when the turtle moves."
+    ),
+    kw!(
+        PenPaint,
+        ["ppt", "penpaint"],
+        "penpaint",
+        "Paint with the pen color when the turtle moves."
+    ),
+    kw!(
+        PenReverse,
+        ["px", "penreverse"],
+        "penreverse",
+        "Reverse colors when the turtle moves."
+    ),
+    kw!(PenUp, ["pu", "penup"], "penup", "Move without drawing."),
+    kw!(
+        PickColor,
+        ["pickcolor"],
+        "pickcolor",
+        "A random bright color as [r g b], different every time."
+    ),
+    kw!(
+        Pops,
+        ["pops"],
+        "pops",
+        "Print the definition of every procedure."
+    ),
+    kw!(
+        PopTransform,
+        ["poptransform"],
+        "poptransform",
+        "Go back to the scale, turn, and offset saved by the last pushtransform."
+    ),
+    kw!(Pots, ["pots"], "pots", "Print the name of every procedure."),
+    kw!(
+        Print,
+        ["pr", "print"],
+        "print value",
+        "Print a value on its own line, without the brackets around a list."
+    ),
+    kw!(
+        PushTransform,
+        ["pushtransform"],
+        "pushtransform",
+        "Save the scale, turn, and offset, to go back to with poptransform."
+    ),
+    kw!(
+        Random,
+        ["random"],
+        "random max",
+        "Random whole number from 0 to max."
+    ),
+    kw!(
+        ReadList,
+        ["readlist", "rl"],
+        "readlist",
+        "Wait for a line typed into the console, as a list of its words."
+    ),
+    kw!(
+        ReadWord,
+        ["readword", "rw"],
+        "readword",
+        "Wait for a line typed into the console, as one word."
+    ),
+    kw!(
+        Repcount,
+        ["repcount"],
+        "repcount",
+        "Current iteration of the innermost repeat, from 1."
+    ),
+    kw!(
+        Repeat,
+        ["repeat"],
+        "repeat count { ... }",
+        "Run a block count times."
+    ),
+    kw!(
+        Right,
+        ["rt", "right"],
+        "right degrees",
+        "Turn the turtle right."
+    ),
+    kw!(
+        RotateCs,
+        ["rotatecs"],
+        "rotatecs degrees",
+        "Turn everything drawn from now on clockwise around the current origin."
+    ),
+    kw!(
+        Round,
+        ["round"],
+        "round num",
+        "Round num to the nearest whole number."
+    ),
+    kw!(
+        Save,
+        ["save"],
+        "save \"name",
+        "Write every procedure to a file in the library."
+    ),
+    kw!(
+        Scale,
+        ["scale"],
+        "scale factor",
+        "Draw everything from now on bigger or smaller, measured from the current origin."
+    ),
+    kw!(
+        SetGeometric,
+        ["setgeom"],
+        "setgeom true",
+        "Keep exact positions with true, or round to whole pixels with false."
+    ),
+    kw!(
+        SetGradient,
+        ["setgradient"],
+        "setgradient [from to] moves",
+        "Blend the pen from one color to the other over the next so many moves."
+    ),
+    kw!(
+        SetHeading,
+        ["seth", "setheading"],
+        "setheading degrees",
+        "Point the turtle in a direction; 0 is up."
+    ),
+    kw!(
+        SetPenColor,
+        ["setpc", "setpencolor"],
+        "setpencolor color",
+        "Set the pen to a palette index, a name like \"orange or \"random, or [r g b] with an optional alpha."
+    ),
+    kw!(
+        SetPenColorHsv,
+        ["setpchsv"],
+        "setpchsv [hue saturation value]",
+        "Set the pen to a hue in degrees, with saturation and value from 0 to 1."
+    ),
+    kw!(
+        SetPos,
+        ["setpos"],
+        "setpos [x y]",
+        "Move the turtle to a position."
+    ),
+    kw!(
+        SetScreenColor,
+        ["setsc", "setscreencolor"],
+        "setscreencolor color",
+        "Set the background to a palette index, a name like \"orange or \"random, or [r g b]."
+    ),
+    kw!(
+        SetScreenColorHsv,
+        ["setschsv"],
+        "setschsv [hue saturation value]",
+        "Set the background to a hue in degrees, with saturation and value from 0 to 1."
+    ),
+    kw!(
+        SetShape,
+        ["setshape"],
+        "setshape \"shape",
+        "Draw the turtle as a triangle, turtle, or circle."
+    ),
+    kw!(
+        SetSize,
+        ["setsize"],
+        "setsize pixels",
+        "Draw the turtle bigger or smaller. The pen is unchanged."
+    ),
+    kw!(
+        SetSpeed,
+        ["setspeed"],
+        "setspeed speed",
+        "Set the animation speed, from 1 to 32, or \"instant for the fastest."
+    ),
+    kw!(
+        SetTextColor,
+        ["settc", "settextcolor"],
+        "settextcolor color",
+        "Print in a palette index, a name like \"orange or \"random, or [r g b] from now on."
+    ),
+    kw!(
+        SetX,
+        ["setx"],
+        "setx x",
+        "Move the turtle horizontally to x."
+    ),
+    kw!(
+        SetXY,
+        ["setxy"],
+        "setxy x y",
+        "Move the turtle to a position."
+    ),
+    kw!(SetY, ["sety"], "sety y", "Move the turtle vertically to y."),
+    kw!(
+        Show,
+        ["show"],
+        "show value",
+        "Print a value on its own line, with the brackets around a list."
+    ),
+    kw!(
+        ShowTurtle,
+        ["st", "showturtle"],
+        "showturtle",
+        "Show the turtle."
+    ),
+    kw!(
+        Shown,
+        ["shownp"],
+        "shownp",
+        "1 if the turtle is showing, otherwise 0."
+    ),
+    kw!(Sin, ["sin"], "sin degrees", "Sine of an angle."),
+    kw!(Sqrt, ["sqrt"], "sqrt num", "Square root of num."),
+    kw!(
+        Symmetry,
+        ["symmetry"],
+        "symmetry ways",
+        "Draw every line that many ways, turned evenly around the center; 1 turns it off."
+    ),
+    kw!(
+        Towards,
+        ["towards"],
+        "towards [x y]",
+        "Heading that points the turtle at a position."
+    ),
+    kw!(
+        Translate,
+        ["translate"],
+        "translate [x y]",
+        "Move the origin of everything drawn from now on."
+    ),
+    kw!(
+        Type,
+        ["type"],
+        "type value",
+        "Print a value like print, but without starting a new line."
+    ),
+];
+
+type Aliases = &'static [(Keyword, &'static [&'static str])];
+
+const FRENCH: Aliases = &[
+    (Keyword::Backward, &["re", "recule"]),
+    (Keyword::Clean, &["nettoie"]),
+    (Keyword::ClearScreen, &["ve", "videecran"]),
+    (Keyword::Fill, &["remplis"]),
+    (Keyword::Forward, &["av", "avance"]),
+    (Keyword::Heading, &["cap"]),
+    (Keyword::HideTurtle, &["ct", "cachetortue"]),
+    (Keyword::Home, &["origine"]),
+    (Keyword::Left, &["tg", "tournegauche"]),
+    (Keyword::PenDown, &["bc", "baissecrayon"]),
+    (Keyword::PenUp, &["lc", "levecrayon"]),
+    (Keyword::Random, &["hasard"]),
+    (Keyword::Repcount, &["compteur"]),
+    (Keyword::Repeat, &["repete"]),
+    (Keyword::Right, &["td", "tournedroite"]),
+    (Keyword::SetHeading, &["fcap", "fixecap"]),
+    (Keyword::SetPenColor, &["fcc", "fixecouleurcrayon"]),
+    (Keyword::SetPos, &["fpos", "fixepos"]),
+    (Keyword::SetScreenColor, &["fcfg", "fixecouleurfond"]),
+    (Keyword::ShowTurtle, &["mt", "montretortue"]),
+];
+
+const SPANISH: Aliases = &[
+    (Keyword::Backward, &["re", "retrocede"]),
+    (Keyword::Clean, &["limpia"]),
+    (Keyword::ClearScreen, &["bp", "borrapantalla"]),
+    (Keyword::Fill, &["rellena"]),
+    (Keyword::Forward, &["av", "avanza"]),
+    (Keyword::Heading, &["rumbo"]),
+    (Keyword::HideTurtle, &["ot", "ocultatortuga"]),
+    (Keyword::Home, &["centro"]),
+    (Keyword::Left, &["gi", "giraizquierda"]),
+    (Keyword::PenDown, &["bl", "bajalapiz"]),
+    (Keyword::PenUp, &["sl", "subelapiz"]),
+    (Keyword::Random, &["azar"]),
+    (Keyword::Repcount, &["cuentarepite"]),
+    (Keyword::Repeat, &["repite"]),
+    (Keyword::Right, &["gd", "giraderecha"]),
+    (Keyword::SetHeading, &["ponrumbo"]),
+    (Keyword::SetPenColor, &["poncl", "poncolorlapiz"]),
+    (Keyword::SetPos, &["ponpos"]),
+    (Keyword::SetScreenColor, &["poncf", "poncolorfondo"]),
+    (Keyword::ShowTurtle, &["mt", "muestratortuga"]),
+];
+
+/// Other names for keywords, in a language besides English. The English
+/// names always work as well, and win if a name could mean both, so a
+/// program that mixes the two means the same thing in every language.
+fn aliases(language: Language) -> Aliases {
+    match language {
+        Language::English => &[],
+        Language::French => FRENCH,
+        Language::Spanish => SPANISH,
+    }
+}
+
+/// Every keyword name usable in `language`.
+fn names(language: Language) -> impl Iterator<Item = &'static str> {
+    let english = KEYWORDS.iter().flat_map(|def| def.names.iter().copied());
+    let local = aliases(language)
+        .iter()
+        .flat_map(|(_, names)| names.iter().copied());
+    english.chain(local)
+}
+
+/// Keyword names and user symbols that extend `prefix`, sorted and without
+/// duplicates. An exact match isn't offered since there's nothing to complete.
+pub fn complete(prefix: &str, language: Language, symbols: &[String]) -> Vec<String> {
+    let prefix = prefix.to_lowercase();
+    if prefix.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<String> = names(language)
+        .map(String::from)
+        .chain(symbols.iter().cloned())
+        .filter(|name| name.len() > prefix.len() && name.to_lowercase().starts_with(&prefix))
+        .collect();
+
+    matches.sort();
+    matches.dedup();
+    matches
+}
+
+/// The keyword name or user symbol closest to a word that wasn't recognized,
+/// if one is close enough to be a likely typo. Short words get less leeway,
+/// so that "fo" isn't taken for "fd".
+pub fn suggest(word: &str, language: Language, symbols: &[String]) -> Option<String> {
+    let word = word.to_lowercase();
+    let leeway = match word.chars().count() {
+        0..=2 => return None,
+        3..=5 => 1,
+        _ => 2,
+    };
+
+    names(language)
+        .map(String::from)
+        .chain(symbols.iter().cloned())
+        .map(|name| (distance(&word, &name.to_lowercase()), name))
+        .filter(|(dist, _)| *dist <= leeway)
+        .min()
+        .map(|(_, name)| name)
+}
+
+/// The edit distance between two words: how many characters must be
+/// inserted, deleted, changed, or swapped with their neighbour to turn one
+/// into the other. Swaps count once, since "rigth" is one slip, not two.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+/// Find the keyword for a word, ignoring case.
+pub fn lookup(word: &str, language: Language) -> Option<Keyword> {
+    let word = word.to_lowercase();
+
+    KEYWORDS
+        .iter()
+        .find(|def| def.names.contains(&word.as_str()))
+        .map(|def| def.keyword)
+        .or_else(|| {
+            aliases(language)
+                .iter()
+                .find(|(_, names)| names.contains(&word.as_str()))
+                .map(|(keyword, _)| *keyword)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_looks_up_every_name() {
+        for def in KEYWORDS {
+            for name in def.names {
+                assert_eq!(lookup(name, Language::English), Some(def.keyword));
+                assert_eq!(
+                    lookup(&name.to_uppercase(), Language::French),
+                    Some(def.keyword)
+                );
+            }
+        }
+        assert_eq!(lookup("square", Language::English), None);
+    }
+
+    #[test]
+    fn it_looks_up_aliases_in_their_language() {
+        assert_eq!(lookup("avanza", Language::Spanish), Some(Keyword::Forward));
+        assert_eq!(lookup("Avance", Language::French), Some(Keyword::Forward));
+        assert_eq!(lookup("av", Language::French), Some(Keyword::Forward));
+        assert_eq!(lookup("avanza", Language::French), None);
+        assert_eq!(lookup("avanza", Language::English), None);
+    }
+
+    #[test]
+    fn it_has_no_duplicate_names() {
+        for language in [Language::English, Language::French, Language::Spanish].iter() {
+            let mut all: Vec<_> = names(*language).collect();
+            let count = all.len();
+            all.sort_unstable();
+            all.dedup();
+            assert_eq!(all.len(), count, "{:?}", language);
+        }
+    }
+
+    #[test]
+    fn it_completes_keywords_and_symbols() {
+        let symbols = vec!["rectangle".to_string(), "size".to_string()];
+        assert_eq!(
+            complete("setp", Language::English, &symbols),
+            vec!["setpc", "setpchsv", "setpencolor", "setpos"]
+        );
+        assert_eq!(
+            complete("re", Language::English, &symbols),
+            vec!["readlist", "readword", "rectangle", "repcount", "repeat"]
+        );
+        assert_eq!(
+            complete("SI", Language::English, &symbols),
+            vec!["sin", "size"]
+        );
+        assert_eq!(complete("ava", Language::Spanish, &symbols), vec!["avanza"]);
+        assert!(complete("repeat", Language::English, &symbols).is_empty());
+        assert!(complete("", Language::English, &symbols).is_empty());
+    }
+
+    #[test]
+    fn it_suggests_close_names() {
+        let symbols = vec!["rectangle".to_string(), "size".to_string()];
+        let suggest = |word| suggest(word, Language::English, &symbols);
+        assert_eq!(suggest("forwrd"), Some("forward".to_string()));
+        assert_eq!(suggest("Rigth"), Some("right".to_string()));
+        assert_eq!(suggest("rectagnle"), Some("rectangle".to_string()));
+        assert_eq!(suggest("sise"), Some("size".to_string()));
+        assert_eq!(
+            super::suggest("avanca", Language::French, &symbols),
+            Some("avance".to_string())
+        );
+        assert_eq!(suggest("fo"), None);
+        assert_eq!(suggest("elephant"), None);
+
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("rigth", "right"), 1);
+        assert_eq!(distance("", "fd"), 2);
+        assert_eq!(distance("größe", "grösse"), 2);
+    }
+}
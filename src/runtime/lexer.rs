@@ -14,14 +14,27 @@
 
 use super::error::*;
 use super::lexer_types::*;
+use super::warning::*;
 use std::iter::Peekable;
+use std::slice::Iter;
 use std::str::Chars;
 
+/// Identifiers start with a letter or underscore, in any script, and carry
+/// on with letters, digits, underscores, and the ? and ! of predicate names.
+pub fn is_identifier(c: char, first: bool) -> bool {
+    if first {
+        c.is_alphabetic() || c == '_'
+    } else {
+        c.is_alphanumeric() || matches!(c, '_' | '?' | '!')
+    }
+}
+
 #[derive(Clone, Debug)]
 struct LexerState {
     list: LexerList,
     symbol: String,
     number: bool,
+    negate: bool,
 }
 
 impl LexerState {
@@ -30,6 +43,25 @@ impl LexerState {
             list: LexerList::new(),
             symbol: String::new(),
             number: false,
+            negate: false,
+        }
+    }
+
+    /// Adds a token, negating it if a prefix minus came before it.
+    pub fn push(&mut self, item: LexerAny) {
+        if self.negate {
+            self.negate = false;
+            self.list.push(LexerAny::LexerNegate(Box::new(item)));
+        } else {
+            self.list.push(item);
+        }
+    }
+
+    /// Whether the last token has a value, so a minus after it can subtract.
+    pub fn after_operand(&self) -> bool {
+        match self.list.last() {
+            Some(LexerAny::LexerOperator(_)) | Some(LexerAny::LexerLine(_)) | None => false,
+            Some(_) => true,
         }
     }
 
@@ -39,13 +71,14 @@ impl LexerState {
                 if let Ok(val) = self.symbol.parse::<f64>() {
                     LexerAny::LexerNumber(val)
                 } else {
-                    let msg = format!("{}: failed to parse number \"{}\"", idx, self.symbol);
-                    return Err(RuntimeError::Lexer(msg));
+                    let start = idx - 1 - self.symbol.chars().count();
+                    let kind = ErrorKind::InvalidNumber(self.symbol.to_string());
+                    return Err(RuntimeError::lexer(kind, Span::new(start, idx - 1)));
                 }
             } else {
                 LexerAny::LexerWord(self.symbol.to_string())
             };
-            self.list.push(item);
+            self.push(item);
         }
 
         self.symbol.clear();
@@ -55,126 +88,245 @@ impl LexerState {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Lexer {
     idx: usize,
+    line: usize,
+    warnings: Warnings,
 }
 
 impl Lexer {
-    pub fn new() -> Self {
-        Self { idx: 1 }
+    pub fn new(warnings: &Warnings) -> Self {
+        Self {
+            idx: 1,
+            line: 1,
+            warnings: warnings.clone(),
+        }
     }
 
     pub fn go(&mut self, input: &str) -> RuntimeResult<LexerList> {
         let mut iter = input.chars().peekable();
-        self.lex(&mut iter)
+        self.lex(&mut iter, None)
+            .map_err(|err| err.on_line(self.line))
     }
 
-    fn lex(&mut self, iter: &mut Peekable<Chars>) -> RuntimeResult<LexerList> {
+    /// Lex until the input runs out, or until `closer` if we're inside a
+    /// block, list, or expression.
+    fn lex(
+        &mut self,
+        iter: &mut Peekable<Chars>,
+        closer: Option<char>,
+    ) -> RuntimeResult<LexerList> {
         let mut state = LexerState::new();
+        let mut closed = false;
+        let mut spaced = true;
 
         while let Some(c) = iter.next() {
+            let line = self.line;
+
             match c {
                 '#' => {
                     state.delimit(self.idx)?;
                     self.idx += Self::munch(iter);
                 }
 
+                '\n' => {
+                    state.delimit(self.idx)?;
+                    self.line += 1;
+                    state.push(LexerAny::LexerLine(self.line));
+                }
+
+                // A quoted word, such as a file name, runs to the next space or
+                // bracket.
+                '"' => {
+                    state.delimit(self.idx)?;
+
+                    let mut word = String::new();
+                    while let Some(next_c) = iter.peek() {
+                        if next_c.is_whitespace() || "[]{}()".contains(*next_c) {
+                            break;
+                        }
+                        word.push(*next_c);
+                        iter.next();
+                        self.idx += 1;
+                    }
+
+                    state.push(LexerAny::LexerQuoted(word));
+                }
+
+                // The value of a variable, as in ":size".
+                ':' => {
+                    state.delimit(self.idx)?;
+
+                    let mut name = String::new();
+                    while let Some(next_c) = iter.peek() {
+                        if !is_identifier(*next_c, name.is_empty()) {
+                            break;
+                        }
+                        name.push(*next_c);
+                        iter.next();
+                        self.idx += 1;
+                    }
+
+                    if name.is_empty() {
+                        let kind = ErrorKind::UnrecognizedCharacter(c);
+                        return Err(RuntimeError::lexer(kind, Span::at(self.idx - 1)));
+                    }
+
+                    state.push(LexerAny::LexerValueOf(name));
+                }
+
                 '{' => {
                     state.delimit(self.idx)?;
 
-                    let block = self.lex(iter)?;
+                    let block = self.lex(iter, Some('}'))?;
                     let item = LexerAny::LexerBlock(block);
-                    state.list.push(item);
+                    state.push(item);
                 }
 
                 '}' => {
                     state.delimit(self.idx)?;
+                    closed = true;
+                    self.idx += 1;
                     break;
                 }
 
                 '[' => {
                     state.delimit(self.idx)?;
 
-                    let inner = self.lex(iter)?;
+                    let inner = self.lex(iter, Some(']'))?;
                     let item = LexerAny::LexerList(inner);
-                    state.list.push(item);
+                    state.push(item);
                 }
 
                 ']' => {
                     state.delimit(self.idx)?;
+                    closed = true;
+                    self.idx += 1;
                     break;
                 }
 
                 '(' => {
                     state.delimit(self.idx)?;
 
-                    let bin_expr = self.get_bin_expr(iter)?;
-                    let item = LexerAny::LexerBinExpr(bin_expr);
-                    state.list.push(item);
+                    let item = self.get_paren_expr(iter)?;
+                    state.push(item);
                 }
 
                 ')' => {
                     state.delimit(self.idx)?;
+                    closed = true;
+                    self.idx += 1;
                     break;
                 }
 
+                // The sign of an exponent, as in "1.5e-2".
+                '-' | '+' if state.number && state.symbol.ends_with(&['e', 'E'][..]) => {
+                    state.symbol.push(c);
+                }
+
                 '-' => {
+                    // What comes before decides first. A minus stuck to the end
+                    // of a symbol, as in "x-1", always subtracts, and so does
+                    // one inside parentheses after something with a value, as
+                    // in "(10 -5)" or "(a -b)". Otherwise, right before a digit
+                    // or period it's part of a negative number. Spaced before
+                    // but not after, as in "setxy 10 -y", it negates what
+                    // follows, so that command arguments don't run together.
+                    // Anything else is left to the parser, which decides
+                    // between negation and subtraction.
+                    let after_symbol = !state.symbol.is_empty();
                     state.delimit(self.idx)?;
-
-                    if let Some(next_c) = iter.peek() {
-                        if next_c.is_digit(10) {
-                            state.number = true;
-                            state.symbol.push(c);
-                            continue;
-                        }
+                    let subtracts = after_symbol || (closer == Some(')') && state.after_operand());
+
+                    let (starts_number, starts_operand) = match iter.peek() {
+                        Some(next_c) => (
+                            next_c.is_ascii_digit() || *next_c == '.',
+                            is_identifier(*next_c, true) || matches!(next_c, ':' | '('),
+                        ),
+                        None => (false, false),
+                    };
+
+                    if subtracts {
+                        let op = Self::operator(c, self.idx)?;
+                        state.push(LexerAny::LexerOperator(op));
+                    } else if starts_number {
+                        state.number = true;
+                        state.symbol.push(c);
+                    } else if starts_operand && spaced && closer != Some(')') {
+                        state.negate = true;
+                    } else {
+                        let op = Self::operator(c, self.idx)?;
+                        let item = LexerAny::LexerOperator(op);
+                        state.push(item);
                     }
-
-                    let op = Self::operator(c, self.idx)?;
-                    let item = LexerAny::LexerOperator(op);
-                    state.list.push(item);
                 }
 
-                '+' | '*' | '/' | '=' | '%' | '^' => {
+                '+' | '*' | '/' | '=' | '%' | '^' | '<' | '>' => {
                     state.delimit(self.idx)?;
 
                     let op = Self::operator(c, self.idx)?;
                     let item = LexerAny::LexerOperator(op);
-                    state.list.push(item);
+                    state.push(item);
                 }
 
                 '.' => {
+                    // A leading period, as in ".5", starts a number.
+                    let next_digit = matches!(iter.peek(), Some(next_c) if next_c.is_ascii_digit());
+                    if state.symbol.is_empty() && next_digit {
+                        state.number = true;
+                    }
+
                     if !state.number {
-                        let msg = format!("{}: unexpected period", self.idx);
-                        return Err(RuntimeError::Lexer(msg));
+                        let kind = ErrorKind::UnexpectedPeriod;
+                        return Err(RuntimeError::lexer(kind, Span::at(self.idx - 1)));
                     }
 
                     state.symbol.push(c);
                 }
 
                 _ => {
+                    let first = state.symbol.is_empty();
+
                     if c.is_whitespace() {
                         state.delimit(self.idx)?;
-                    } else if c.is_digit(10) {
-                        if state.symbol.is_empty() {
-                            state.number = true;
-                        }
+                    } else if c.is_ascii_digit() && (first || state.number) {
+                        state.number = true;
+                        state.symbol.push(c);
+                    } else if state.number && is_identifier(c, false) {
+                        // Letters stay part of the number, so exponents work and
+                        // "10abc" is reported as a bad number rather than a word.
                         state.symbol.push(c);
-                    } else if c.is_alphanumeric() {
+                    } else if is_identifier(c, first) {
                         state.symbol.push(c);
                         state.number = false;
                     } else {
-                        let msg = format!("{}: unrecognized character \'{}\'", self.idx, c);
-                        return Err(RuntimeError::Lexer(msg));
+                        let kind = ErrorKind::UnrecognizedCharacter(c);
+                        return Err(RuntimeError::lexer(kind, Span::at(self.idx - 1)));
                     }
                 }
             }
 
+            // A block, list, or expression that ran over several lines leaves
+            // us on a later line than the one it started on.
+            if self.line != line && c != '\n' {
+                state.push(LexerAny::LexerLine(self.line));
+            }
+
+            spaced = c.is_whitespace();
             self.idx += 1;
         }
 
         state.delimit(self.idx)?;
 
+        if let Some(closer) = closer {
+            if !closed {
+                let kind = WarningKind::UnclosedBracket(closer);
+                let span = Span::at(self.idx - 1);
+                self.warnings.push(Stage::Lexer, kind, Some(span));
+            }
+        }
+
         Ok(state.list)
     }
 
@@ -186,10 +338,12 @@ impl Lexer {
             '*' => Ok(LexerOperator::Multiply),
             '/' => Ok(LexerOperator::Divide),
             '%' => Ok(LexerOperator::Modulo),
+            '<' => Ok(LexerOperator::Less),
+            '>' => Ok(LexerOperator::Greater),
             '^' => Ok(LexerOperator::Power),
             _ => {
-                let msg = format!("{}: unrecognized operator \'{}\'", idx, c);
-                Err(RuntimeError::Lexer(msg))
+                let kind = ErrorKind::UnrecognizedOperator(c);
+                Err(RuntimeError::lexer(kind, Span::at(idx - 1)))
             }
         }
     }
@@ -197,36 +351,76 @@ impl Lexer {
     fn munch(iter: &mut Peekable<Chars>) -> usize {
         let mut idx = 0;
 
-        for c in iter {
-            if c == '\n' || c == '\r' {
+        // The line break ends the comment, but is left to be lexed, so the
+        // line is counted.
+        while let Some(c) = iter.peek() {
+            if *c == '\n' || *c == '\r' {
                 break;
             }
+            iter.next();
             idx += 1;
         }
 
         idx
     }
 
-    fn get_bin_expr(&mut self, iter: &mut Peekable<Chars>) -> RuntimeResult<LexerBinExpr> {
-        let expr_list = self.lex(iter)?;
+    /// Either `(a op b)`, or a single operand such as `(-x)`.
+    fn get_paren_expr(&mut self, iter: &mut Peekable<Chars>) -> RuntimeResult<LexerAny> {
+        let mut expr_list = self.lex(iter, Some(')'))?;
+        expr_list.retain(|item| !matches!(item, LexerAny::LexerLine(_)));
         let mut expr_iter = expr_list.iter();
 
-        let a = Self::get_expression(expr_iter.next(), self.idx)?;
-        let op = Self::get_op_item(expr_iter.next(), self.idx)?;
-        let b = Self::get_expression(expr_iter.next(), self.idx)?;
+        let a = Self::get_operand(&mut expr_iter, self.idx)?;
+        Self::get_bin_expr(&mut expr_iter, a, 0, self.idx)
+    }
+
+    /// Operators after `a`, by precedence, down to `min_precedence`.
+    fn get_bin_expr(
+        expr_iter: &mut Iter<LexerAny>,
+        mut a: LexerAny,
+        min_precedence: u8,
+        idx: usize,
+    ) -> RuntimeResult<LexerAny> {
+        while let Some(item) = expr_iter.as_slice().first() {
+            // Inside parentheses, "=" compares rather than assigns.
+            let op = match Self::get_op_item(Some(item), idx)? {
+                LexerOperator::Assign => LexerOperator::Equal,
+                op => op,
+            };
+            if op.precedence() < min_precedence {
+                break;
+            }
+            expr_iter.next();
+
+            let b = Self::get_operand(expr_iter, idx)?;
+            let b = Self::get_bin_expr(expr_iter, b, op.rhs_precedence(), idx)?;
+            a = LexerAny::LexerBinExpr(LexerBinExpr::new(a, op, b));
+        }
+
+        Ok(a)
+    }
 
-        Ok(LexerBinExpr::new(a, op, b))
+    fn get_operand(expr_iter: &mut Iter<LexerAny>, idx: usize) -> RuntimeResult<LexerAny> {
+        match expr_iter.next() {
+            Some(LexerAny::LexerOperator(LexerOperator::Subtract)) => {
+                let operand = Self::get_operand(expr_iter, idx)?;
+                Ok(LexerAny::LexerNegate(Box::new(operand)))
+            }
+            item => Self::get_expression(item, idx),
+        }
     }
 
     fn get_expression(item: Option<&LexerAny>, idx: usize) -> RuntimeResult<LexerAny> {
         match item {
             Some(LexerAny::LexerBinExpr(bin_expr)) => Ok(LexerAny::LexerBinExpr(bin_expr.clone())),
             Some(LexerAny::LexerList(list)) => Ok(LexerAny::LexerList(list.clone())),
+            Some(LexerAny::LexerNegate(operand)) => Ok(LexerAny::LexerNegate(operand.clone())),
             Some(LexerAny::LexerNumber(num)) => Ok(LexerAny::LexerNumber(*num)),
+            Some(LexerAny::LexerValueOf(name)) => Ok(LexerAny::LexerValueOf(name.clone())),
             Some(LexerAny::LexerWord(word)) => Ok(LexerAny::LexerWord(word.clone())),
             _ => {
-                let msg = format!("{}: expected an expression", idx);
-                Err(RuntimeError::Lexer(msg))
+                let kind = ErrorKind::ExpectedExpression;
+                Err(RuntimeError::lexer(kind, Span::at(idx - 1)))
             }
         }
     }
@@ -235,8 +429,187 @@ impl Lexer {
         if let Some(LexerAny::LexerOperator(op)) = item {
             Ok(*op)
         } else {
-            let msg = format!("{}: expected an operator", idx);
-            Err(RuntimeError::Lexer(msg))
+            let kind = ErrorKind::ExpectedOperator;
+            Err(RuntimeError::lexer(kind, Span::at(idx - 1)))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(input: &str) -> RuntimeResult<LexerList> {
+        Lexer::new(&Warnings::default()).go(input)
+    }
+
+    fn word(word: &str) -> LexerAny {
+        LexerAny::LexerWord(word.to_string())
+    }
+
+    #[test]
+    fn it_lexes_identifiers() {
+        let list = lex("let _size = 10 empty? done! 距离 größe x2").unwrap();
+        assert_eq!(
+            list,
+            vec![
+                word("let"),
+                word("_size"),
+                LexerAny::LexerOperator(LexerOperator::Assign),
+                LexerAny::LexerNumber(10.0),
+                word("empty?"),
+                word("done!"),
+                word("距离"),
+                word("größe"),
+                word("x2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rejects_symbols_without_panicking() {
+        let err = lex("fd 10 🐢").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::UnrecognizedCharacter('🐢'));
+        assert_eq!(err.span, Some(Span::at(6)));
+
+        let err = lex("?what").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::UnrecognizedCharacter('?'));
+
+        assert!(lex("repeat 4 { fd 10 } # 🐢 comments are fine").is_ok());
+    }
+
+    #[test]
+    fn it_lexes_quoted_words() {
+        let list = lex("load \"shapes.logo fd 1").unwrap();
+        assert_eq!(list[1], LexerAny::LexerQuoted("shapes.logo".to_string()));
+        assert_eq!(list[2], LexerAny::LexerWord("fd".to_string()));
+    }
+
+    #[test]
+    fn it_lexes_values_of_variables() {
+        let list = lex("fd :size rt (:angle * 2)").unwrap();
+        assert_eq!(list[1], LexerAny::LexerValueOf("size".to_string()));
+        let mul = LexerBinExpr::new(
+            LexerAny::LexerValueOf("angle".to_string()),
+            LexerOperator::Multiply,
+            LexerAny::LexerNumber(2.0),
+        );
+        assert_eq!(list[3], LexerAny::LexerBinExpr(mul));
+
+        let err = lex("fd : size").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::UnrecognizedCharacter(':'));
+        assert_eq!(err.span, Some(Span::at(3)));
+    }
+
+    #[test]
+    fn it_tells_negation_from_subtraction() {
+        let list = lex("setxy 10 -y fd a-b fd a - b fd [-y] (a -b)").unwrap();
+        let negate_y = LexerAny::LexerNegate(Box::new(word("y")));
+        assert_eq!(list[2], negate_y);
+        assert_eq!(list[5], LexerAny::LexerOperator(LexerOperator::Subtract));
+        assert_eq!(list[9], LexerAny::LexerOperator(LexerOperator::Subtract));
+        assert_eq!(list[12], LexerAny::LexerList(vec![negate_y]));
+
+        let sub = LexerBinExpr::new(word("a"), LexerOperator::Subtract, word("b"));
+        assert_eq!(list[13], LexerAny::LexerBinExpr(sub));
+
+        // In parentheses, after a value, a minus before a digit subtracts too.
+        let num = |num| LexerAny::LexerNumber(num);
+        let list = lex("(10 -5) (a -1) (2 * -3) fd 10 -5").unwrap();
+        let sub = |a, b| LexerAny::LexerBinExpr(LexerBinExpr::new(a, LexerOperator::Subtract, b));
+        assert_eq!(list[0], sub(num(10.0), num(5.0)));
+        assert_eq!(list[1], sub(word("a"), num(1.0)));
+        let mul = LexerBinExpr::new(num(2.0), LexerOperator::Multiply, num(-3.0));
+        assert_eq!(list[2], LexerAny::LexerBinExpr(mul));
+        assert_eq!(list[3..], [word("fd"), num(10.0), num(-5.0)]);
+    }
+
+    #[test]
+    fn it_lexes_expressions_by_precedence() {
+        let list = lex("(1 + 2 * 3 ^ 2 ^ 2 > 4)").unwrap();
+        let num = |num| LexerAny::LexerNumber(num);
+        let bin = |a, op, b| LexerAny::LexerBinExpr(LexerBinExpr::new(a, op, b));
+        let pow = bin(
+            num(3.0),
+            LexerOperator::Power,
+            bin(num(2.0), LexerOperator::Power, num(2.0)),
+        );
+        let sum = bin(
+            num(1.0),
+            LexerOperator::Add,
+            bin(num(2.0), LexerOperator::Multiply, pow),
+        );
+        assert_eq!(list, vec![bin(sum, LexerOperator::Greater, num(4.0))]);
+
+        let err = lex("(1 2)").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::ExpectedOperator);
+    }
+
+    #[test]
+    fn it_writes_tokens_back_out() {
+        let input = "fn dot { repeat 4 { fd (:size * -2) setpc [1 2 3] rt -(a + 1) } }";
+        let list = lex(input).unwrap();
+        let output = list.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+        assert_eq!(lex(&output.join(" ")).unwrap(), list);
+    }
+
+    #[test]
+    fn it_lexes_numbers() {
+        let list = lex("1e3 1.5e-2 .5 -.5 2E+2 -7 (1-2)").unwrap();
+        let numbers: Vec<_> = list[..6]
+            .iter()
+            .map(|item| match item {
+                LexerAny::LexerNumber(num) => *num,
+                _ => panic!("expected a number, got {:?}", item),
+            })
+            .collect();
+        assert_eq!(numbers, vec![1000.0, 0.015, 0.5, -0.5, 200.0, -7.0]);
+
+        let sub = LexerBinExpr::new(
+            LexerAny::LexerNumber(1.0),
+            LexerOperator::Subtract,
+            LexerAny::LexerNumber(2.0),
+        );
+        assert_eq!(list[6], LexerAny::LexerBinExpr(sub));
+    }
+
+    #[test]
+    fn it_rejects_malformed_numbers() {
+        let err = lex("fd 10abc").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidNumber("10abc".to_string()));
+        assert_eq!(err.span, Some(Span::new(3, 8)));
+        assert_eq!(
+            err.to_string(),
+            "error: lexer: line 1: failed to parse number \"10abc\""
+        );
+
+        assert!(lex("1e").is_err());
+        assert!(lex("1.5.2").is_err());
+        assert!(lex("x.5").is_err());
+    }
+
+    #[test]
+    fn it_counts_lines() {
+        let list = lex("fd 1 # note\r\n\nrt (1 +\n 2) fd [1\n]").unwrap();
+        assert_eq!(list[2], LexerAny::LexerLine(2));
+        assert_eq!(list[3], LexerAny::LexerLine(3));
+        assert_eq!(list[6], LexerAny::LexerLine(4));
+        let inner = vec![LexerAny::LexerNumber(1.0), LexerAny::LexerLine(5)];
+        assert_eq!(list[8], LexerAny::LexerList(inner));
+        assert_eq!(list[9], LexerAny::LexerLine(5));
+        assert_eq!(list.len(), 10);
+
+        let err = lex("fd 1\n{ rt 1\n fd 1e }").err().unwrap();
+        assert_eq!(err.line, Some(3));
+        assert_eq!(
+            err.to_string(),
+            "error: lexer: line 3: failed to parse number \"1e\""
+        );
+    }
+
+    #[test]
+    fn it_keeps_positions_past_blocks_and_comments() {
+        let err = lex("fd -5 { rt 1 } # note\n$").err().unwrap();
+        assert_eq!(err.span, Some(Span::at(22)));
+    }
+}
@@ -12,11 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LexerOperator {
     Add,
     Assign,
     Divide,
+    Equal,
+    Greater,
+    Less,
     Modulo,
     Multiply,
     Power,
@@ -60,8 +65,86 @@ pub type LexerBlock = LexerList;
 pub enum LexerAny {
     LexerBlock(LexerBlock),
     LexerBinExpr(LexerBinExpr),
+    /// The start of a new line of the program, with its number. The parser
+    /// skips these, keeping track of which line each statement is on.
+    LexerLine(usize),
     LexerList(LexerList),
+    LexerNegate(Box<LexerAny>),
     LexerNumber(f64),
     LexerOperator(LexerOperator),
+    LexerQuoted(String),
+    LexerValueOf(String),
     LexerWord(String),
 }
+
+impl LexerOperator {
+    /// How tightly the operator binds: comparisons, then sums, then
+    /// products, then powers.
+    pub fn precedence(self) -> u8 {
+        match self {
+            LexerOperator::Assign
+            | LexerOperator::Equal
+            | LexerOperator::Greater
+            | LexerOperator::Less => 1,
+            LexerOperator::Add | LexerOperator::Subtract => 2,
+            LexerOperator::Divide | LexerOperator::Modulo | LexerOperator::Multiply => 3,
+            LexerOperator::Power => 4,
+        }
+    }
+
+    /// The lowest precedence allowed in the right operand. Powers group from
+    /// the right, so `2 ^ 3 ^ 2` is `2 ^ 9`; everything else groups from the
+    /// left.
+    pub fn rhs_precedence(self) -> u8 {
+        match self {
+            LexerOperator::Power => self.precedence(),
+            _ => self.precedence() + 1,
+        }
+    }
+}
+
+impl fmt::Display for LexerOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            LexerOperator::Add => '+',
+            LexerOperator::Assign | LexerOperator::Equal => '=',
+            LexerOperator::Divide => '/',
+            LexerOperator::Greater => '>',
+            LexerOperator::Less => '<',
+            LexerOperator::Modulo => '%',
+            LexerOperator::Multiply => '*',
+            LexerOperator::Power => '^',
+            LexerOperator::Subtract => '-',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+/// Writes tokens back out as source, on one line. Lexing the result gives the
+/// same tokens again.
+impl fmt::Display for LexerAny {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexerAny::LexerBlock(block) => write!(f, "{{ {} }}", join(block)),
+            LexerAny::LexerBinExpr(bin_expr) => {
+                write!(f, "({} {} {})", bin_expr.a(), bin_expr.op(), bin_expr.b())
+            }
+            LexerAny::LexerLine(_) => writeln!(f),
+            LexerAny::LexerList(list) => write!(f, "[{}]", join(list)),
+            LexerAny::LexerNegate(operand) => match **operand {
+                LexerAny::LexerNumber(num) => write!(f, "-({})", num),
+                _ => write!(f, "-{}", operand),
+            },
+            LexerAny::LexerNumber(num) => write!(f, "{}", num),
+            LexerAny::LexerOperator(op) => write!(f, "{}", op),
+            LexerAny::LexerQuoted(word) => write!(f, "\"{}", word),
+            LexerAny::LexerValueOf(name) => write!(f, ":{}", name),
+            LexerAny::LexerWord(word) => write!(f, "{}", word),
+        }
+    }
+}
+
+fn join(list: &[LexerAny]) -> String {
+    let items: Vec<_> = list.iter().map(|item| item.to_string()).collect();
+    items.join(" ")
+}
@@ -0,0 +1,137 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::error::*;
+use crate::model::picture::Picture;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+const EXTENSION: &str = "logo";
+
+const PICTURE_EXTENSION: &str = "png";
+
+/// The directories `load` and `save` work in. Programs name files, not paths,
+/// so they can't reach anywhere else on disk.
+#[derive(Clone, Debug, Default)]
+pub struct Library {
+    dirs: Vec<PathBuf>,
+}
+
+impl Library {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self { dirs }
+    }
+
+    /// Read a file from the first directory that has it.
+    pub fn read(&self, name: &str) -> RuntimeResult<String> {
+        let path = self.find(name, EXTENSION)?;
+        fs::read_to_string(&path).map_err(|err| Self::access_error(name, err))
+    }
+
+    /// Decode a picture from the first directory that has it.
+    pub fn read_picture(&self, name: &str) -> RuntimeResult<Picture> {
+        let path = self.find(name, PICTURE_EXTENSION)?;
+        Picture::load(&path).map_err(|err| Self::access_error(name, err))
+    }
+
+    fn find(&self, name: &str, extension: &str) -> RuntimeResult<PathBuf> {
+        let file_name = Self::file_name(name, extension)?;
+
+        for dir in self.dirs.iter() {
+            let path = dir.join(&file_name);
+            if path.is_file() {
+                return Ok(path);
+            }
+        }
+
+        let kind = ErrorKind::FileNotFound(name.to_string());
+        Err(RuntimeError::interpreter(kind))
+    }
+
+    /// Write a file to the first directory, creating it if need be.
+    pub fn write(&self, name: &str, text: &str) -> RuntimeResult {
+        let file_name = Self::file_name(name, EXTENSION)?;
+
+        let dir = match self.dirs.first() {
+            Some(dir) => dir,
+            None => {
+                let kind = ErrorKind::FileNotFound(name.to_string());
+                return Err(RuntimeError::interpreter(kind));
+            }
+        };
+
+        fs::create_dir_all(dir)
+            .and_then(|_| fs::write(dir.join(file_name), text))
+            .map_err(|err| Self::access_error(name, err))
+    }
+
+    /// A plain file name, with `extension` added if it has none.
+    fn file_name(name: &str, extension: &str) -> RuntimeResult<String> {
+        let plain =
+            !name.is_empty() && !name.starts_with('.') && !name.contains(&['/', '\\', ':'][..]);
+
+        if !plain {
+            let kind = ErrorKind::InvalidFileName(name.to_string());
+            return Err(RuntimeError::interpreter(kind));
+        }
+
+        if Path::new(name).extension().is_some() {
+            Ok(name.to_string())
+        } else {
+            Ok(format!("{}.{}", name, extension))
+        }
+    }
+
+    fn access_error(name: &str, err: io::Error) -> RuntimeError {
+        let kind = ErrorKind::FileAccess {
+            name: name.to_string(),
+            reason: err.to_string(),
+        };
+        RuntimeError::interpreter(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn it_stays_inside_its_directories() {
+        let library = Library::default();
+        for name in ["", "../secret", "/etc/passwd", ".hidden", "c:boot"].iter() {
+            let err = library.read(name).err().unwrap();
+            assert_eq!(err.kind, ErrorKind::InvalidFileName(name.to_string()));
+        }
+
+        let err = library.read("missing").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::FileNotFound("missing".to_string()));
+    }
+
+    #[test]
+    fn it_writes_and_reads_back() {
+        let dir = env::temp_dir().join(format!("turtle-library-{}", process::id()));
+        let empty = env::temp_dir().join("turtle-library-empty");
+        let library = Library::new(vec![dir.clone(), empty]);
+
+        library.write("shapes", "fn dot { fd 1 }\n").unwrap();
+        assert!(dir.join("shapes.logo").is_file());
+        assert_eq!(library.read("shapes").unwrap(), "fn dot { fd 1 }\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
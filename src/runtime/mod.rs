@@ -12,35 +12,145 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::profile;
+use crate::common::profile::Phase;
+use crate::model::params::Param;
+use crate::model::prefs::Preferences;
 use crate::model::render::RenderTx;
+use control::Control;
+use druid::Color;
+use druid::Point;
 use error::*;
 use interpreter::Interpreter;
 use interpreter_types::*;
 use lexer::Lexer;
+use library::Library;
+use optimizer::Optimizer;
 use parser::Parser;
+use parser_types::*;
+use printer::Printer;
+use std::ops::Range;
 use std::sync::atomic::AtomicU32;
 use std::sync::Arc;
+use warning::Warnings;
 
+mod color;
+pub mod control;
 pub mod error;
 mod interpreter;
 mod interpreter_types;
+pub mod keywords;
 mod lexer;
 mod lexer_types;
+mod library;
+mod optimizer;
 mod parser;
 mod parser_types;
+mod printer;
+mod transform;
+pub mod warning;
+
+pub use lexer::is_identifier;
+
+/// The result of a successful run.
+pub struct Output {
+    pub value: Value,
+    /// Anything the program printed along the way.
+    pub text: String,
+    /// The colors chosen with settextcolor, for ranges of bytes in `text`.
+    pub colors: Vec<(Range<usize>, Color)>,
+    /// Procedures and variables the program defined, for autocompletion.
+    pub symbols: Vec<String>,
+    /// The params it declared, for the sliders.
+    pub params: Vec<Param>,
+}
 
 pub fn entry(
     input: String,
     render_tx: Arc<RenderTx>,
     speed: Arc<AtomicU32>,
-) -> RuntimeResult<Value> {
-    let lexer_out = Lexer::new().go(&input)?;
-    println!("lexer out {:?}", lexer_out);
-    let parser_out = Parser::new().go(&lexer_out)?;
-    println!("parser out {:?}", parser_out);
-    let intrp_out = Interpreter::new(render_tx, speed).go(&parser_out)?;
-    println!("interpreter out {:?}", intrp_out);
-    Ok(intrp_out)
+    control: Arc<Control>,
+    prefs: &Preferences,
+    warnings: &Warnings,
+) -> RuntimeResult<Output> {
+    let lexer_out = profile::time(Phase::Lex, || Lexer::new(warnings).go(&input))?;
+    let library = Library::new(prefs.library_dirs());
+    // A program that starts out held is being stepped through, so it keeps
+    // track of where it is for the editor to show.
+    let lines = control.is_paused();
+    let mut parser_out = profile::time(Phase::Parse, || {
+        Parser::new(warnings, &library)
+            .with_language(prefs.language)
+            .with_lines(lines)
+            .with_procedures(control.procedures())
+            .go(&lexer_out)
+    })?;
+    let written = parser_out.fmap.clone();
+    let mut interpreter = Interpreter::new(render_tx, speed, control.clone(), prefs, warnings);
+    if prefs.optimize {
+        interpreter = interpreter.with_written(written.clone());
+        parser_out = profile::time(Phase::Optimize, || Optimizer::new().go(&parser_out));
+    }
+    let intrp_out = profile::time(Phase::Interpret, || interpreter.go(&parser_out))?;
+    control.set_procedures(interpreter.kept(&written));
+    Ok(Output {
+        value: intrp_out,
+        text: interpreter.take_text(),
+        colors: interpreter.take_colors(),
+        symbols: parser_out.symbols,
+        params: interpreter.take_params(),
+    })
+}
+
+/// The input rewritten in canonical form, the way the printer lays it out.
+/// Comments aren't kept.
+pub fn format(input: &str, prefs: &Preferences) -> RuntimeResult<String> {
+    let parser_out = parse(input, prefs, &Warnings::default())?;
+    Ok(Printer::new(&parser_out.fmap).program(&parser_out.list))
+}
+
+/// Lex and parse the input without running it, to find mistakes before a
+/// slow run. Returns every error found, or none if it's fine. The lexer
+/// can't carry on past an error, so if it fails that's the only one.
+pub fn check(input: &str, prefs: &Preferences, warnings: &Warnings) -> Vec<RuntimeError> {
+    let lexer_out = match Lexer::new(warnings).go(input) {
+        Ok(lexer_out) => lexer_out,
+        Err(err) => return vec![err],
+    };
+    let library = Library::new(prefs.library_dirs());
+    let mut parser = Parser::new(warnings, &library).with_language(prefs.language);
+    match parser.go(&lexer_out) {
+        Ok(_) => Vec::new(),
+        Err(_) => parser.errors().to_vec(),
+    }
+}
+
+fn parse(input: &str, prefs: &Preferences, warnings: &Warnings) -> RuntimeResult<ParserOutput> {
+    let lexer_out = Lexer::new(warnings).go(input)?;
+    let library = Library::new(prefs.library_dirs());
+    Parser::new(warnings, &library)
+        .with_language(prefs.language)
+        .go(&lexer_out)
+}
+
+/// The code that moves the turtle to a point, with the pen up unless the move
+/// draws a line.
+pub fn move_code(p: Point, pen_down: bool) -> String {
+    let x = Some(Box::new(ParserNode::Number(p.x)));
+    let y = Some(Box::new(ParserNode::Number(p.y)));
+    let set_pos = ParserNode::SetPosition(SetPositionNode::new(x, y));
+
+    let list = if pen_down {
+        vec![set_pos]
+    } else {
+        vec![
+            ParserNode::Pen(PenNode::Up),
+            set_pos,
+            ParserNode::Pen(PenNode::Down),
+        ]
+    };
+
+    Printer::inline(&ParserFuncMap::new()).program(&list)
 }
 
 #[cfg(test)]
@@ -48,15 +158,1198 @@ mod tests {
     use futures::channel::mpsc;
 
     use super::*;
-    use crate::model::render::RenderCommand;
+    use crate::common::constants::MAX_CALL_DEPTH;
+    use crate::common::constants::MAX_SPEED;
+    use crate::common::palette::palette;
+    use crate::export::image;
+    use crate::model::pixbuf::PixBuf;
+    use crate::model::prefs::Language;
+    use crate::model::prefs::TurtleShape;
+    use crate::model::render::*;
+    use druid::Color;
+    use druid::Size;
+    use std::thread;
+    use std::time::Duration;
+    use warning::WarningKind;
 
     #[test]
     fn it_goes() {
         let input = "let i = (2 ^ 3) i".to_string();
         let (render_tx, render_rx) = mpsc::unbounded::<RenderCommand>();
-        let res = entry(input, Arc::new(render_tx), Arc::new(AtomicU32::new(4)));
+        let res = entry(
+            input,
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(4)),
+            Default::default(),
+            &Preferences::default(),
+            &Warnings::default(),
+        );
         if let Err(err) = res {
             eprintln!("{}", err);
         }
     }
+
+    fn run_with(input: &str, prefs: &Preferences, warnings: &Warnings) -> RuntimeResult<Output> {
+        let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+        entry(
+            input.to_string(),
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(u32::MAX)),
+            Default::default(),
+            prefs,
+            warnings,
+        )
+    }
+
+    fn run_warned(input: &str, warnings: &Warnings) -> RuntimeResult<Output> {
+        run_with(input, &Preferences::default(), warnings)
+    }
+
+    fn run(input: &str) -> RuntimeResult<Output> {
+        run_warned(input, &Warnings::default())
+    }
+
+    #[test]
+    fn it_reports_error_kinds() {
+        let err = run("fd 1 $").err().unwrap();
+        assert_eq!(err.stage, Stage::Lexer);
+        assert_eq!(err.kind, ErrorKind::UnrecognizedCharacter('$'));
+        assert_eq!(err.span, Some(Span::at(5)));
+
+        let err = run("fd qux").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::UndefinedSymbol("qux".to_string()));
+
+        let err = run("let size = 5 forwrd siez").err().unwrap();
+        let name = "forwrd".to_string();
+        let suggestion = "forward".to_string();
+        assert_eq!(err.kind, ErrorKind::DidYouMean { name, suggestion });
+        assert_eq!(
+            err.kind.to_string(),
+            "unrecognized symbol \"forwrd\"; did you mean \"forward\"?"
+        );
+        let err = run("let size = 5 fd siez").err().unwrap();
+        let name = "siez".to_string();
+        let suggestion = "size".to_string();
+        assert_eq!(err.kind, ErrorKind::DidYouMean { name, suggestion });
+
+        let err = run("fd (1 / 0)").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::DivByZero);
+    }
+
+    #[test]
+    fn it_collects_warnings_from_every_stage() {
+        let warnings = Warnings::default();
+        let input = "let unused = 1 setpc [10.5 0 0] fd 1000 repeat 2 { rt 90";
+        assert!(run_warned(input, &warnings).is_ok());
+
+        let kinds: Vec<_> = warnings.take().into_iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                WarningKind::UnclosedBracket('}'),
+                WarningKind::UnusedVariable("unused".to_string()),
+                WarningKind::ColorComponentRounded(10.5),
+                WarningKind::OffCanvas {
+                    x: 0.0,
+                    y: 1000.0,
+                    clipped: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_counts_lines_off_the_canvas() {
+        let warnings = Warnings::default();
+        let input = "pu fd 1000 pd repeat 3 { rt 90 fd 10 } pu home pd fd 10 fd 5000";
+        assert!(run_warned(input, &warnings).is_ok());
+
+        let warning = warnings.take().pop().unwrap();
+        assert_eq!(
+            warning.kind,
+            WarningKind::OffCanvas {
+                x: 10.0,
+                y: 1000.0,
+                clipped: 4,
+            }
+        );
+        assert_eq!(
+            warning.to_string(),
+            "warning: interpreter: turtle left the canvas at [10 1000], and 4 lines went off \
+             the edge; try smaller sizes, or a bigger drawing surface in the preferences"
+        );
+
+        // Going off and back with the pen up draws nothing out of sight.
+        assert!(run_warned("pu setxy 1000 0 pd setxy 0 0", &warnings).is_ok());
+        assert_eq!(warnings.take(), Vec::new());
+
+        // A run that fails still says so.
+        assert!(run_warned("fd 10000 setsize 0", &warnings).is_err());
+        let kinds: Vec<_> = warnings.take().into_iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![WarningKind::OffCanvas {
+                x: 0.0,
+                y: 10000.0,
+                clipped: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn it_negates_expressions() {
+        let input = "let d = 10
+            let a = -d
+            assert (a = -10)
+            let b = (-d + 3)
+            assert (b = -7)
+            let c = -(d * 2)
+            assert (c = -20)
+            let e = (d-1)
+            assert (e = 9)
+            let f = (- d)
+            assert (f = -10)
+            setxy -d -5
+            fd -d";
+        assert!(run(input).is_ok());
+
+        assert!(run("fd -").is_err());
+        assert!(run("let x = (- 5 3)").is_err());
+    }
+
+    #[test]
+    fn it_optimizes_without_changing_results() {
+        let input = "let x = 3
+            let total = 0
+            repeat (2 * 5) { let total = (total + (x * 2)) setpc (repcount % 16) }
+            total";
+
+        for optimize in [true, false].iter() {
+            let prefs = Preferences {
+                optimize: *optimize,
+                ..Preferences::default()
+            };
+            let out = run_with(input, &prefs, &Warnings::default()).unwrap();
+            assert_eq!(out.value, Value::Number(60.0));
+
+            let err = run_with("repeat 2 { fd (1 / 0) }", &prefs, &Warnings::default());
+            assert_eq!(err.err().unwrap().kind, ErrorKind::DivByZero);
+        }
+    }
+
+    #[test]
+    fn it_takes_positions_as_lists_or_pairs() {
+        let input = "setpos [100 (50 + 20)]
+            let d = distance [100 70]
+            assert (d = 0)
+            setxy [30 40]
+            let d = distance 0 0
+            assert (d = 50)
+            setpos 0 0
+            let t = round towards [10 10]
+            assert (t = 45)
+            let t = towards 0 -10
+            assert (t = 180)
+            let t = towards [-10 0]
+            assert (t = 270)";
+        assert!(run(input).is_ok());
+
+        let err = run("setpos [1 2 3]").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Arity { expected: 2 });
+        assert!(run("setxy 1").is_err());
+    }
+
+    /// Run a program and collect every render command it sends.
+    fn record(input: &str) -> Vec<RenderCommand> {
+        let (render_tx, mut render_rx) = mpsc::unbounded::<RenderCommand>();
+        entry(
+            input.to_string(),
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(u32::MAX)),
+            Default::default(),
+            &Preferences::default(),
+            &Warnings::default(),
+        )
+        .unwrap();
+
+        let mut commands = Vec::new();
+        while let Ok(Some(cmd)) = render_rx.try_next() {
+            commands.push(cmd);
+        }
+        commands
+    }
+
+    fn moves(commands: &[RenderCommand]) -> Vec<(Point, bool)> {
+        commands
+            .iter()
+            .flat_map(|cmd| cmd.moves())
+            .map(|move_to| (move_to.pos, is_pen_down(move_to.pen_flags)))
+            .collect()
+    }
+
+    #[test]
+    fn it_changes_the_turtle_look() {
+        let commands = record("setshape \"circle setsize 32");
+        assert_eq!(commands[0], RenderCommand::Shape(TurtleShape::Circle));
+        assert_eq!(commands[1], RenderCommand::Size(32.0));
+
+        let err = run("setshape \"hexagon").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidShape("hexagon".to_string()));
+
+        let err = run("setsize 0").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidSize(0.0));
+    }
+
+    #[test]
+    fn it_sets_its_own_speed() {
+        let commands = record("setspeed 8 setspeed \"instant");
+        assert_eq!(commands[0], RenderCommand::Speed(8));
+        assert_eq!(commands[1], RenderCommand::Speed(MAX_SPEED));
+
+        let err = run("setspeed 0").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidSpeed("0".to_string()));
+
+        let err = run("setspeed \"fast").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidSpeed("fast".to_string()));
+    }
+
+    #[test]
+    fn it_prints_values() {
+        let out = run("type 1 type [2 3] print [4 [5 6]] show [7] pr 8 let x = 2 * 3 x").unwrap();
+        assert_eq!(out.text, "12 34 [5 6]\n[7]\n8\n");
+        assert_eq!(out.value, Value::Number(6.0));
+
+        let out = run("show 0.1 + 0.2 show 1 / 3 show 2 ^ 60 show 0 * -1").unwrap();
+        assert_eq!(out.text, "0.3\n0.333333333333333\n1152921504606846976\n0\n");
+
+        let err = run("print fd 1").err().unwrap();
+        let kind = ErrorKind::TypeMismatch {
+            expected: "a value",
+            got: "void",
+        };
+        assert_eq!(err.kind, kind);
+    }
+
+    #[test]
+    fn it_counts_repeats_in_whole_numbers() {
+        let out = run("repeat 2.9 { fd 1 } repeat 0 { fd 1 } show drawingbounds").unwrap();
+        assert_eq!(out.text, "[0 0 0 2]\n");
+
+        let err = run("repeat -1 { fd 1 }").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidRepeatCount(-1.0));
+
+        let err = run("repeat sqrt -1 { fd 1 }").err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::InvalidRepeatCount(count) if count.is_nan()));
+
+        let err = run("repeat 1/0 { fd 1 }").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::DivByZero);
+        let err = run("repeat 10 ^ 400 { fd 1 }").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidRepeatCount(f64::INFINITY));
+    }
+
+    #[test]
+    fn it_colors_printed_text() {
+        let out = run("print 1 settc \"red type 2 settc 1 print 3").unwrap();
+        assert_eq!(out.text, "1\n23\n");
+
+        let ranges: Vec<_> = out.colors.iter().map(|(range, _)| range.clone()).collect();
+        assert_eq!(ranges, vec![2..3, 3..5]);
+        assert_eq!(out.colors[1].1, palette()[&1]);
+
+        let err = run("settc \"mauve").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidColorName("mauve".to_string()));
+    }
+
+    #[test]
+    fn it_reads_from_the_console() {
+        let control = Arc::new(Control::default());
+        control.listen();
+
+        let answers = control.clone();
+        let typist = thread::spawn(move || {
+            for line in ["  fd 10 -2.5 ", "3.5"].iter() {
+                while !answers.answer(line) {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+        let out = entry(
+            "type 0 let a = readlist show a print readword".to_string(),
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(u32::MAX)),
+            control,
+            &Preferences::default(),
+            &Warnings::default(),
+        )
+        .unwrap();
+        typist.join().unwrap();
+        assert_eq!(out.text, "0[fd 10 -2.5]\n3.5\n");
+
+        let err = run("readword").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::NoInput);
+    }
+
+    #[test]
+    fn it_runs_on_a_timer_until_stopped() {
+        let control = Arc::new(Control::default());
+        control.listen();
+
+        let stopper = control.clone();
+        let stop = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            stopper.stop();
+        });
+
+        let (render_tx, mut render_rx) = mpsc::unbounded::<RenderCommand>();
+        let err = entry(
+            "every 0.01 { fd 1 }".to_string(),
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(u32::MAX)),
+            control,
+            &Preferences::default(),
+            &Warnings::default(),
+        )
+        .err()
+        .unwrap();
+        stop.join().unwrap();
+        assert_eq!(err.kind, ErrorKind::Stopped);
+
+        let mut moves = 0;
+        while let Ok(Some(_)) = render_rx.try_next() {
+            moves += 1;
+        }
+        assert!(moves > 1, "{}", moves);
+
+        // Nobody can stop it here, so it goes round once.
+        let out = run("forever { let n = repcount } n").unwrap();
+        assert_eq!(out.value, Value::Number(1.0));
+
+        let err = run("every -1 { fd 1 }").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidInterval(-1.0));
+    }
+
+    #[test]
+    fn it_shows_and_hides_the_turtle() {
+        let commands = record("ht st hideturtle");
+        assert_eq!(
+            commands,
+            vec![
+                RenderCommand::ShowTurtle(false),
+                RenderCommand::ShowTurtle(true),
+                RenderCommand::ShowTurtle(false),
+            ]
+        );
+
+        assert_eq!(run("shownp").unwrap().value, Value::Number(1.0));
+        assert_eq!(run("ht shownp").unwrap().value, Value::Number(0.0));
+        assert_eq!(run("ht st shownp").unwrap().value, Value::Number(1.0));
+    }
+
+    #[test]
+    fn it_reports_the_heading() {
+        let turns: Vec<_> = record("rt 90 lt 45 seth 180 home")
+            .iter()
+            .filter_map(|cmd| match cmd {
+                RenderCommand::Turn(heading) => Some(heading.round()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(turns, vec![90.0, 45.0, 180.0, 0.0]);
+    }
+
+    #[test]
+    fn it_keeps_the_heading_in_one_turn() {
+        let heading = |input: &str| run(input).unwrap().value;
+        assert_eq!(heading("lt 90 heading"), Value::Number(270.0));
+        assert_eq!(heading("seth -450 heading"), Value::Number(270.0));
+        assert_eq!(heading("seth 720 heading"), Value::Number(0.0));
+        assert_eq!(
+            heading("lt 0.0000000000000000001 heading"),
+            Value::Number(0.0)
+        );
+
+        let input = "repeat 1000000 { rt 0.1 lt 0.1 } heading";
+        assert_eq!(heading(input), Value::Number(0.0));
+        let input = "repeat 1000000 { rt 7 lt 4 } heading";
+        assert_eq!(heading(input), Value::Number(120.0));
+    }
+
+    #[test]
+    fn it_snaps_to_pixels_unless_geometric() {
+        let commands = record("rt 30 fd 1 setgeom true fd 1 setgeom false fd 1");
+        let half = 30.0_f64.to_radians().sin();
+        let rise = 30.0_f64.to_radians().cos();
+        let moves = moves(&commands);
+        assert_eq!(moves[0].0, Point::new(1.0, 1.0));
+        assert!((moves[1].0.x - (1.0 + half)).abs() < 1e-9);
+        assert!((moves[1].0.y - (1.0 + rise)).abs() < 1e-9);
+        assert_eq!(moves[2].0, Point::new(2.0, 3.0));
+
+        let err = run("setgeom maybe").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidSwitch("maybe".to_string()));
+    }
+
+    #[test]
+    fn it_goes_home_facing_up() {
+        let commands = record("rt 90 fd 10 home fd 10 rt 90 pu home fd 5");
+        assert_eq!(
+            moves(&commands),
+            vec![
+                (Point::new(10.0, 0.0), true),
+                (Point::ZERO, true),
+                (Point::new(0.0, 10.0), true),
+                (Point::ZERO, false),
+                (Point::new(0.0, 5.0), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_checks_syntax_with_line_numbers() {
+        let prefs = Preferences::default();
+        let warnings = Warnings::default();
+        assert_eq!(
+            check("fd 1\nrt 1 # note\n\nfd qux", &prefs, &warnings).len(),
+            1
+        );
+
+        let input = "fd 1 # note\nrepeat 2 {\n    rt (1 +\n 2)\n\n    forwrd 2\n}";
+        let err = check(input, &prefs, &warnings).remove(0);
+        assert_eq!(err.line, Some(6));
+        assert_eq!(
+            err.to_string(),
+            "error: parser: line 6: unrecognized symbol \"forwrd\"; did you mean \"forward\"?"
+        );
+
+        let err = check("fd 1\nfn sq [fd 1\nrt 90] let x\nsq", &prefs, &warnings).remove(0);
+        assert_eq!(err.kind, ErrorKind::Arity { expected: 3 });
+        assert_eq!(err.line, Some(3));
+
+        // Checking doesn't run anything.
+        assert!(check("fd (1 / 0)", &prefs, &warnings).is_empty());
+    }
+
+    #[test]
+    fn it_finds_every_error() {
+        let input = "forwrd 1 rt 1
+            fd 1
+            let x = qux
+            repeat 2 [
+                fd zzz
+                rt x
+            ]
+            5 fd 2 $";
+        let errors = check(input, &Preferences::default(), &Warnings::default());
+        assert_eq!(errors.len(), 1, "the lexer stops at $");
+
+        let errors = check(
+            &input.replace('$', ""),
+            &Preferences::default(),
+            &Warnings::default(),
+        );
+        let lines: Vec<_> = errors.iter().map(|err| err.line.unwrap()).collect();
+        assert_eq!(lines, vec![1, 3, 5, 8]);
+        assert_eq!(errors[3].kind, ErrorKind::ExpectedWord);
+
+        // A run stops at the first.
+        let err = run(&input.replace('$', "")).err().unwrap();
+        assert_eq!(err, errors[0]);
+    }
+
+    #[test]
+    fn it_runs_out_of_input_without_panicking() {
+        // Each of these uses up the items that were counted for what comes
+        // after, found by the fuzz targets.
+        for input in &[
+            "repeat seth ht",
+            "every fd - sqrt 2.5",
+            "sety repeat ln [",
+            "for x",
+        ] {
+            let errors = check(input, &Preferences::default(), &Warnings::default());
+            assert!(!errors.is_empty(), "{}", input);
+        }
+
+        // The error is on the line where the input ran out.
+        let input = "fd 1\nrepeat seth\nht";
+        let err = check(input, &Preferences::default(), &Warnings::default()).remove(0);
+        assert_eq!(err.kind, ErrorKind::UnexpectedEnd);
+        assert_eq!(err.line, Some(3));
+    }
+
+    #[test]
+    fn it_takes_brackets_for_blocks() {
+        assert_eq!(
+            record("repeat 4 [fd 50 rt 90]"),
+            record("repeat 4 { fd 50 rt 90 }")
+        );
+        assert_eq!(
+            record("fn sq [repeat 2 [setpc [255 0 0] fd 1 rt 90]] sq"),
+            record("fn sq { repeat 2 { setpc [255 0 0] fd 1 rt 90 } } sq")
+        );
+        assert_eq!(record("for i 1 3 1 [fd i]"), record("for i 1 3 1 { fd i }"));
+    }
+
+    #[test]
+    fn it_reads_infix_arguments_without_parentheses() {
+        assert_eq!(
+            record("let x = 5 fd :x + 10 rt 90 fd x * 2 - 1"),
+            record("fd 15 rt 90 fd 9")
+        );
+        assert_eq!(record("fd 10 rt 90"), record("fd (10) rt (90)"));
+        assert_eq!(record("repeat 1 + 1 [fd 1]"), record("fd 1 fd 1"));
+        assert_eq!(
+            record("let y = 3 setxy 10 -y setxy 10 - y 0"),
+            record("setxy 10 -3 setxy 7 0")
+        );
+        assert!(run("let y = 3 setxy 10 - y").is_err());
+
+        // In parentheses, a minus after a value subtracts. Outside them it
+        // starts a value of its own, so arguments stay apart.
+        let out = run("print (10 -5) let a = 3 fd (a -1) show drawingbounds").unwrap();
+        assert_eq!(out.text, "5\n[0 0 0 2]\n");
+        assert_eq!(run("fd 10 -5").err().unwrap().kind, ErrorKind::ExpectedWord);
+        assert_eq!(record("setxy 10 -5"), record("setxy 10 (0 - 5)"));
+
+        // A function of one value takes the whole expression after it.
+        assert_eq!(run("show sqrt 16 + 9").unwrap().text, "5\n");
+        let text = format("show random 10 + 100", &Preferences::default()).unwrap();
+        assert_eq!(text, "show random (10 + 100)\n");
+
+        let input = "assert 2 + 3 * 4 = 14
+            assert 2 ^ 3 ^ 2 = 512
+            assert 10 - 4 - 3 = 3
+            let z = -2 * 3 + 1
+            assert z = -5
+            assert (1 + 2 * 3) = 7";
+        assert!(run(input).is_ok());
+        assert!(run("fd 1 +").is_err());
+    }
+
+    #[test]
+    fn it_takes_classic_variable_syntax() {
+        assert_eq!(
+            record("let \"size = 10 fd :size rt (:size * 9)"),
+            record("let size = 10 fd size rt (size * 9)")
+        );
+        assert_eq!(
+            record("for \"i 1 3 1 [fd :i]"),
+            record("for i 1 3 1 { fd i }")
+        );
+
+        // A colon always means a variable, never a keyword or procedure.
+        let err = run("fd :fd").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::UndefinedSymbol("fd".to_string()));
+        assert!(run("fn dot { fd 1 } fd :dot").is_err());
+    }
+
+    #[test]
+    fn it_keeps_brackets_as_data_outside_blocks() {
+        let input = "let c = [255 0 0]
+            repeat 2 [setpc c setpos [1 2]]
+            let d = distance [1 2]
+            assert (d = 0)";
+        assert!(run(input).is_ok());
+
+        let err = run("repeat 2 fd 1").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::ExpectedBlock);
+        assert!(run("[fd 1]").is_err());
+    }
+
+    #[test]
+    fn it_clears_the_heading() {
+        let commands = record("lt 45 fd 10 cs fd 10");
+        let moves = moves(&commands);
+        assert_eq!(moves[1], (Point::ZERO, false));
+        assert_eq!(moves[2], (Point::new(0.0, 10.0), true));
+    }
+
+    #[test]
+    fn it_clears_the_screen_like_ucb_logo() {
+        let commands = record("setpc 2 setsc 1 pu ht rt 90 fd 10 cs fd 10");
+        let after: Vec<_> = commands
+            .iter()
+            .skip_while(|cmd| **cmd != RenderCommand::Clean)
+            .collect();
+
+        // Erased first, then home without a line, facing up again.
+        assert_eq!(after[0], &RenderCommand::Clean);
+        match after[1] {
+            RenderCommand::MoveTo(move_to) => {
+                assert_eq!(move_to.pos, Point::ZERO);
+                assert!(!is_pen_down(move_to.pen_flags));
+            }
+            cmd => panic!("expected a move home, not {}", cmd),
+        }
+        assert_eq!(after[2], &RenderCommand::Turn(0.0));
+
+        // The pen stays up and keeps its color, and the turtle stays hidden.
+        match after[3] {
+            RenderCommand::MoveTo(move_to) => {
+                assert_eq!(move_to.pos, Point::new(0.0, 10.0));
+                assert_eq!(move_to.color, palette()[&2]);
+                assert!(!is_pen_down(move_to.pen_flags));
+            }
+            cmd => panic!("expected a move, not {}", cmd),
+        }
+        assert_eq!(after.len(), 4);
+        assert_eq!(run("ht cs shownp").unwrap().value, Value::Number(0.0));
+
+        // The pen that was down goes down again after the trip home.
+        let moves = moves(&record("fd 10 cs fd 10"));
+        assert_eq!(moves[2], (Point::new(0.0, 10.0), true));
+    }
+
+    #[test]
+    fn it_sends_lines_as_paths() {
+        let commands = record("repeat 4 { fd 10 rt 90 } setpc 2 fd 10 pu fd 10");
+        match &commands[0] {
+            RenderCommand::Path(moves) => assert_eq!(moves.len(), 4),
+            cmd => panic!("expected a path, not {}", cmd),
+        }
+        assert_eq!(commands[1], RenderCommand::Turn(0.0));
+
+        // A new color starts again, and a lone line goes on its own.
+        assert!(matches!(&commands[2], RenderCommand::MoveTo(m) if is_pen_down(m.pen_flags)));
+        assert!(matches!(&commands[3], RenderCommand::MoveTo(m) if !is_pen_down(m.pen_flags)));
+        assert_eq!(commands.len(), 4);
+    }
+
+    #[test]
+    fn it_cleans_only_the_drawing() {
+        let commands = record("setsc 1 fd 10 clean fd 10");
+        assert_eq!(
+            commands[0],
+            RenderCommand::ScreenColor(palette()[&1].clone())
+        );
+        assert_eq!(commands[2], RenderCommand::Clean);
+        assert_eq!(commands.len(), 4);
+        assert_eq!(moves(&commands)[1], (Point::new(0.0, 20.0), true));
+    }
+
+    #[test]
+    fn it_names_colors() {
+        let commands = record("setsc \"orange setsc \"random");
+        assert_eq!(
+            commands[0],
+            RenderCommand::ScreenColor(palette()[&14].clone())
+        );
+        assert!(matches!(commands[1], RenderCommand::ScreenColor(_)));
+
+        let out = run("let c = pickcolor setpc c c").unwrap();
+        match out.value {
+            Value::List(list) => assert_eq!(list.len(), 3),
+            val => panic!("expected a list, not {}", val),
+        }
+
+        let err = run("setpc \"mauve").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidColorName("mauve".to_string()));
+    }
+
+    #[test]
+    fn it_sets_colors_by_hue() {
+        let commands = record("setschsv [0 0 0.5] setpchsv [120 1 1] fd 1");
+        assert_eq!(
+            commands[0],
+            RenderCommand::ScreenColor(Color::rgb8(128, 128, 128))
+        );
+        match &commands[1] {
+            RenderCommand::MoveTo(move_to) => assert_eq!(move_to.color, Color::rgb8(0, 255, 0)),
+            cmd => panic!("expected a move, not {}", cmd),
+        }
+
+        let err = run("setpchsv [0 1 2]").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::HsvOutOfRange("value", 2.0));
+    }
+
+    #[test]
+    fn it_blends_the_pen_color() {
+        let commands = record("setgradient [\"black \"white] 3 repeat 4 { fd 1 } setpc 4 fd 1");
+        let colors: Vec<_> = commands
+            .iter()
+            .flat_map(|cmd| cmd.moves())
+            .map(|move_to| move_to.color.clone())
+            .collect();
+        assert_eq!(
+            colors,
+            vec![
+                Color::BLACK,
+                Color::rgb8(128, 128, 128),
+                Color::WHITE,
+                Color::WHITE,
+                palette()[&4].clone(),
+            ]
+        );
+
+        let err = run("setgradient [1 2] 0").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidGradient(0.0));
+
+        let err = run("setgradient [1 2 3] 5").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Arity { expected: 2 });
+    }
+
+    #[test]
+    fn it_draws_symmetric_copies() {
+        let rounded: Vec<_> = moves(&record("symmetry 2 fd 10 symmetry 1 fd 10"))
+            .into_iter()
+            .map(|(pos, down)| (pos.round(), down))
+            .collect();
+        assert_eq!(
+            rounded,
+            vec![
+                (Point::ZERO, false),
+                (Point::new(0.0, -10.0), true),
+                (Point::ZERO, false),
+                (Point::new(0.0, 10.0), true),
+                (Point::new(0.0, 20.0), true),
+            ]
+        );
+
+        // Moves with the pen up aren't copied.
+        assert_eq!(moves(&record("symmetry 6 pu fd 10")).len(), 1);
+
+        let err = run("symmetry 2.5").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidSymmetry(2.5));
+    }
+
+    #[test]
+    fn it_transforms_positions() {
+        let commands = record(
+            "pushtransform scale 2 fd 10 poptransform fd 10 \
+             rotatecs 90 fd 10 translate [0 5]",
+        );
+        let rounded: Vec<_> = moves(&commands)
+            .into_iter()
+            .map(|(pos, down)| (pos.round(), down))
+            .collect();
+        assert_eq!(
+            rounded,
+            vec![
+                (Point::ZERO, false),
+                (Point::new(0.0, 20.0), true),
+                (Point::new(0.0, 10.0), false),
+                (Point::new(0.0, 20.0), true),
+                (Point::new(20.0, 0.0), false),
+                (Point::new(30.0, 0.0), true),
+                (Point::new(35.0, 0.0), false),
+            ]
+        );
+        assert_eq!(commands.last(), Some(&RenderCommand::Turn(90.0)));
+
+        let err = run("pushtransform poptransform poptransform")
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, ErrorKind::EmptyTransformStack);
+
+        let err = run("scale 0").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidScale(0.0));
+    }
+
+    #[test]
+    fn it_measures_the_drawing() {
+        let out = run("show drawingbounds \
+             pu setxy -5 5 pd setxy 10 -20 pu fd 100 pe fd 50 \
+             show drawingbounds clean show drawingbounds")
+        .unwrap();
+        assert_eq!(out.text, "[]\n[-5 -20 10 5]\n[]\n");
+    }
+
+    #[test]
+    fn it_saves_and_loads_procedures() {
+        let dir = std::env::temp_dir().join(format!("turtle-load-{}", std::process::id()));
+        let prefs = Preferences {
+            library_path: dir.to_string_lossy().into_owned(),
+            ..Preferences::default()
+        };
+        let warnings = Warnings::default();
+
+        run_with("fn step { fd 10 rt 90 } save \"steps", &prefs, &warnings).unwrap();
+        run_with("load \"steps repeat 4 { step }", &prefs, &warnings).unwrap();
+
+        // Called before the file that defines it is loaded.
+        run_with("repeat 4 { step } load \"steps", &prefs, &warnings).unwrap();
+
+        // Saved as written, not as the optimizer rewrote it.
+        let input = "fn sq { let size = 10 repeat 4 { fd (size * 2) setpc 4 rt 90 } } save \"sq";
+        run_with(input, &prefs, &warnings).unwrap();
+        let text = std::fs::read_to_string(dir.join("sq.logo")).unwrap();
+        assert!(text.contains("fd (size * 2)"), "{}", text);
+        assert!(!text.contains('#'), "{}", text);
+        run_with("load \"sq sq", &prefs, &warnings).unwrap();
+        let err = run_with("fd 1\nwibble\nload \"steps", &prefs, &warnings)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, ErrorKind::UndefinedSymbol("wibble".to_string()));
+        assert_eq!(err.line, Some(2));
+
+        // Mistakes in a loaded file are on the line that loads it, and say
+        // where in the file they are.
+        std::fs::write(dir.join("bad.logo"), "fd 1\nfd 10 $").unwrap();
+        let input = "fd 1\nload \"bad";
+        let err = run_with(input, &prefs, &warnings).err().unwrap();
+        let kind = ErrorKind::InFile {
+            name: "bad".to_string(),
+            line: Some(2),
+            kind: Box::new(ErrorKind::UnrecognizedCharacter('$')),
+        };
+        assert_eq!(err.kind, kind);
+        assert_eq!(err.location(input), Some((2, 1)));
+        assert_eq!(
+            err.to_string(),
+            "error: lexer: line 2: in \"bad\" line 2: unrecognized character '$'"
+        );
+
+        std::fs::write(dir.join("bad.logo"), "fd 1\n\nlet 5 = 3").unwrap();
+        let err = run_with("load \"bad", &prefs, &warnings).err().unwrap();
+        assert!(
+            matches!(&err.kind, ErrorKind::InFile { name, line: Some(3), .. } if name == "bad"),
+            "{:?}",
+            err.kind
+        );
+        assert_eq!((err.span, err.line), (None, Some(1)));
+
+        let err = run_with("load \"missing", &prefs, &warnings).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::FileNotFound("missing".to_string()));
+
+        let err = run_with("load \"../steps", &prefs, &warnings)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidFileName("../steps".to_string()));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_loads_background_pictures() {
+        let dir = std::env::temp_dir().join(format!("turtle-pict-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let png = image::encode_png(&PixBuf::new(4, 3, 1), &Color::BLACK).unwrap();
+        std::fs::write(dir.join("dot.png"), png).unwrap();
+
+        let prefs = Preferences {
+            library_path: dir.to_string_lossy().into_owned(),
+            ..Preferences::default()
+        };
+        let (render_tx, mut render_rx) = mpsc::unbounded::<RenderCommand>();
+        entry(
+            "loadpict \"dot".to_string(),
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(u32::MAX)),
+            Default::default(),
+            &prefs,
+            &Warnings::default(),
+        )
+        .unwrap();
+        match render_rx.try_next() {
+            Ok(Some(RenderCommand::Background(picture))) => {
+                assert_eq!(picture.size(), Size::new(4.0, 3.0))
+            }
+            other => panic!("{:?}", other),
+        }
+
+        let err = run_with("loadpict \"missing", &prefs, &Warnings::default())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, ErrorKind::FileNotFound("missing".to_string()));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_lets_procedures_call_each_other() {
+        // Defined further down, or calling itself, works the same.
+        let out = run(
+            "fn square { repeat 4 { side } } fn side { fd 10 rt 90 } square show drawingbounds",
+        );
+        assert_eq!(out.unwrap().text, "[0 0 10 10]\n");
+
+        let err = run("fn tick { fd 1 tock } fn tock { rt 1 tick } tick")
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, ErrorKind::CallDepth(MAX_CALL_DEPTH));
+        let err = run("fn spin { rt 1 spin } spin").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::CallDepth(MAX_CALL_DEPTH));
+    }
+
+    #[test]
+    fn it_notes_redefined_procedures() {
+        let warnings = Warnings::default();
+        let input = "fn step { fd 1 } step fn step { fd 2 } step show drawingbounds";
+        let out = run_warned(input, &warnings).unwrap();
+        assert_eq!(
+            out.text, "[0 0 0 3]\n",
+            "each takes over where it's written"
+        );
+
+        let notes: Vec<_> = warnings.take().iter().map(|w| w.to_string()).collect();
+        assert_eq!(notes, vec!["warning: parser: redefined step"]);
+
+        // The first is there from the start, and a loop picks up the change
+        // on its next time round.
+        let input = "step fn step { fd 1 } repeat 2 { step fn step { fd 10 } } show drawingbounds";
+        let out = run(input).unwrap();
+        assert_eq!(out.text, "[0 0 0 12]\n");
+
+        let input = "fn step { fd 1 } fn step { fd 2 } pops";
+        let out = run(input).unwrap();
+        assert_eq!(out.text, "fn step {\n    fd 2\n}\n");
+    }
+
+    #[test]
+    fn it_ignores_case_in_names() {
+        let input = "Let Size = 3
+            fn Step { FD :size }
+            step STEP
+            For I 1 2 1 { Fd :i }
+            Show DrawingBounds";
+        let out = run(input).unwrap();
+        assert_eq!(out.text, "[0 0 0 7]\n");
+
+        let out = run("fn Dot { fd 1 } erase \"DOT pots").unwrap();
+        assert_eq!(out.text, "");
+
+        let err = run("fn dot { fd 1 } fn DOT { fd 2 }").err();
+        assert_eq!(err, None, "a redefinition, not a new procedure");
+    }
+
+    #[test]
+    fn it_reserves_keyword_names() {
+        let err = run("let fd = 5").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "error: parser: line 1: fd is a reserved word"
+        );
+
+        for def in keywords::KEYWORDS {
+            for name in def.names {
+                let reserved = ErrorKind::ReservedWord(name.to_string());
+                for input in &[
+                    format!("let {} = 5", name),
+                    format!("fn {} {{ fd 1 }}", name),
+                ] {
+                    let err = run(input).err().unwrap();
+                    assert_eq!(err.kind, reserved, "{}", input);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_lists_and_erases_procedures() {
+        let input = "fn square { repeat 4 { fd 10 } }
+            fn dot { fd 1 }
+            pots
+            erase \"dot
+            pops";
+        let out = run(input).unwrap();
+        assert_eq!(
+            out.text,
+            "fn dot\nfn square\nfn square {\n    repeat 4 {\n        fd 10\n    }\n}\n"
+        );
+
+        let err = run("fn dot { fd 1 } erase \"dot dot").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::UndefinedFunction("dot".to_string()));
+
+        let err = run("erase \"dot").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::UndefinedFunction("dot".to_string()));
+    }
+
+    #[test]
+    fn it_keeps_procedures_from_run_to_run() {
+        let control = Arc::new(Control::default());
+        let run_again = |input: &str| {
+            let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+            entry(
+                input.to_string(),
+                Arc::new(render_tx),
+                Arc::new(AtomicU32::new(u32::MAX)),
+                control.clone(),
+                &Preferences::default(),
+                &Warnings::default(),
+            )
+        };
+
+        run_again("fn dot { fd 1 } fn step { fd 1 } fn step { fd 2 }").unwrap();
+        let out = run_again("dot step pots show drawingbounds").unwrap();
+        assert_eq!(out.text, "fn dot\nfn step\n[0 0 0 3]\n");
+
+        let warnings = Warnings::default();
+        let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+        let out = entry(
+            "fn dot { fd 5 } dot show drawingbounds".to_string(),
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(u32::MAX)),
+            control.clone(),
+            &Preferences::default(),
+            &warnings,
+        )
+        .unwrap();
+        assert_eq!(out.text, "[0 0 0 5]\n");
+        assert!(
+            warnings.take().is_empty(),
+            "running it again isn't a redefinition"
+        );
+
+        run_again("erase \"step").unwrap();
+        let err = run_again("step").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::UndefinedSymbol("step".to_string()));
+        let out = run_again("pops").unwrap();
+        assert_eq!(out.text, "fn dot {\n    fd 5\n}\n");
+    }
+
+    #[test]
+    fn it_tracks_lines_while_stepping() {
+        let input = "fd 1\nrepeat 2 {\n  fd 2\n}\n\nrt 90\n";
+        let run_held = |steps| {
+            let control = Arc::new(Control::default());
+            for _ in 0..steps {
+                control.step();
+            }
+            let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+            entry(
+                input.to_string(),
+                Arc::new(render_tx),
+                Arc::new(AtomicU32::new(u32::MAX)),
+                control.clone(),
+                &Preferences::default(),
+                &Warnings::default(),
+            )
+            .unwrap();
+            control.line()
+        };
+
+        // One step for each statement run, and none for the line markers.
+        assert_eq!(run_held(5), Some(6));
+        assert_eq!(run_held(0), None);
+    }
+
+    #[test]
+    fn it_stops_when_asked() {
+        let control = Arc::new(Control::default());
+        control.stop();
+
+        let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+        let err = entry(
+            "repeat 1000000 { fd 1 }".to_string(),
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(u32::MAX)),
+            control,
+            &Preferences::default(),
+            &Warnings::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.kind, ErrorKind::Stopped);
+    }
+
+    #[test]
+    fn it_stops_after_the_time_limit() {
+        let prefs = Preferences {
+            time_limit: 1,
+            ..Default::default()
+        };
+
+        let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+        let err = entry(
+            "repeat 1000000000 { let x = repcount }".to_string(),
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(u32::MAX)),
+            Default::default(),
+            &prefs,
+            &Warnings::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.kind, ErrorKind::TimeLimit(1));
+        assert_eq!(
+            err.to_string(),
+            "error: interpreter: program took too long (more than 1 second); \
+             the limit is in Preferences"
+        );
+
+        // Loops with nothing in them count too.
+        for input in &["repeat 1e12 { }", "for i 0 1e12 1 { }"] {
+            let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+            let err = entry(
+                input.to_string(),
+                Arc::new(render_tx),
+                Arc::new(AtomicU32::new(u32::MAX)),
+                Default::default(),
+                &prefs,
+                &Warnings::default(),
+            )
+            .err()
+            .unwrap();
+            assert_eq!(err.kind, ErrorKind::TimeLimit(1), "{}", input);
+        }
+    }
+
+    #[test]
+    fn it_takes_params_from_the_sliders() {
+        let input = "param :size 10 200 param turn 0 (45 * 2) fd size rt turn";
+        let out = run(input).unwrap();
+        assert_eq!(
+            out.params,
+            vec![
+                Param::new("size", 10.0, 200.0, 10.0),
+                Param::new("turn", 0.0, 90.0, 0.0),
+            ]
+        );
+
+        let control = Arc::new(Control::default());
+        control.set_params(
+            vec![("size".to_string(), 50.0), ("turn".to_string(), 500.0)]
+                .into_iter()
+                .collect(),
+        );
+        let (render_tx, _render_rx) = mpsc::unbounded::<RenderCommand>();
+        let out = entry(
+            format!("{} size", input),
+            Arc::new(render_tx),
+            Arc::new(AtomicU32::new(u32::MAX)),
+            control,
+            &Preferences::default(),
+            &Warnings::default(),
+        )
+        .unwrap();
+        assert_eq!(out.value, Value::Number(50.0));
+        assert_eq!(out.params[1].value, 90.0);
+
+        let err = run("param :size 200 10").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidParamRange(200.0, 10.0));
+
+        let text = format("param size 10 200", &Preferences::default()).unwrap();
+        assert_eq!(text, "param :size 10 200\n");
+    }
+
+    #[test]
+    fn it_formats_input() {
+        let prefs = Preferences::default();
+        let text = format("repeat 2{forward 5 right 90}", &prefs).unwrap();
+        assert_eq!(text, "repeat 2 {\n    fd 5\n    rt 90\n}\n");
+
+        assert!(format("repeat 2 { fd 1", &prefs).is_ok());
+        assert!(format("fd $", &prefs).is_err());
+    }
+
+    #[test]
+    fn it_takes_keywords_in_the_chosen_language() {
+        let prefs = Preferences {
+            language: Language::Spanish,
+            ..Default::default()
+        };
+        let text = format("repite 2 { avanza 5 rt 90 }", &prefs).unwrap();
+        assert_eq!(text, "repeat 2 {\n    fd 5\n    rt 90\n}\n");
+
+        let err = run("avanza 5").err().unwrap();
+        assert_eq!(err.kind, ErrorKind::UndefinedSymbol("avanza".to_string()));
+
+        let err = run_with("let avanza = 5", &prefs, &Warnings::default())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, ErrorKind::ReservedWord("avanza".to_string()));
+        assert!(run("let avanza = 5").is_ok());
+    }
 }
@@ -0,0 +1,453 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::color::ColorSpec;
+
+use super::interpreter::Interpreter;
+use super::interpreter_types::*;
+use super::parser_types::*;
+use crate::common::palette::*;
+use std::collections::HashSet;
+
+/// Rewrites the parse tree before it's interpreted, so big loops do less work
+/// per iteration. Constant arithmetic is folded, palette colors are looked up
+/// once, and expressions that can't change inside a repeat are computed before
+/// it starts.
+///
+/// Anything that would fail is left alone, so errors still come from the
+/// interpreter. The one difference is timing: a hoisted expression that fails
+/// does so before the loop draws anything, rather than partway through.
+pub struct Optimizer {
+    pal: Palette,
+    hoisted: usize,
+}
+
+impl Optimizer {
+    pub fn new() -> Self {
+        Self {
+            pal: palette(),
+            hoisted: 0,
+        }
+    }
+
+    pub fn go(&mut self, input: &ParserOutput) -> ParserOutput {
+        let list = self.optimize_list(&input.list);
+
+        let mut fmap = input.fmap.clone();
+        for func in fmap.values_mut() {
+            func.list = self.optimize_list(&func.list);
+        }
+
+        ParserOutput::new(list, fmap, input.symbols.clone())
+    }
+
+    fn optimize_list(&mut self, list: &[ParserNode]) -> ParserNodeList {
+        let mut out = ParserNodeList::new();
+        for node in list.iter() {
+            let node = self.optimize_node(node, &mut out);
+            out.push(node);
+        }
+        out
+    }
+
+    fn optimize_node(&mut self, node: &ParserNode, out: &mut ParserNodeList) -> ParserNode {
+        match node {
+            ParserNode::Every(node) => ParserNode::Every(EveryNode::new(
+                node.interval().map(Self::fold),
+                self.optimize_list(node.list()),
+            )),
+            ParserNode::For(node) => ParserNode::For(ForNode::new(
+                node.var().to_string(),
+                Self::fold(node.initial()),
+                Self::fold(node.limit()),
+                Self::fold(node.step()),
+                self.optimize_list(node.list()),
+            )),
+            ParserNode::Load(node) => {
+                ParserNode::Load(LoadNode::new(node.name(), self.optimize_list(node.list())))
+            }
+            ParserNode::Repeat(node) => {
+                let count = Self::fold(node.count());
+                let list = self.optimize_list(node.list());
+                self.hoist(RepeatNode::new(count, list), out)
+            }
+            ParserNode::SetPenColor(node) => {
+                ParserNode::SetPenColor(SetPenColorNode::new(self.resolve(node.color())))
+            }
+            ParserNode::SetScreenColor(node) => {
+                ParserNode::SetScreenColor(SetScreenColorNode::new(self.resolve(node.color())))
+            }
+            ParserNode::SetTextColor(node) => {
+                ParserNode::SetTextColor(SetTextColorNode::new(self.resolve(node.color())))
+            }
+            _ => Self::map_args(node, &mut Self::fold),
+        }
+    }
+
+    /// Replaces constant arithmetic with its result.
+    fn fold(node: &ParserNode) -> ParserNode {
+        let node = Self::map_args(node, &mut Self::fold);
+
+        match &node {
+            ParserNode::BinExpr(expr) => {
+                if let (ParserNode::Number(a), ParserNode::Number(b)) = (expr.a(), expr.b()) {
+                    let a = Value::Number(*a);
+                    let b = Value::Number(*b);
+                    if let Ok(Value::Number(num)) = Interpreter::apply_bin_op(expr.op(), &a, &b) {
+                        return ParserNode::Number(num);
+                    }
+                }
+            }
+            ParserNode::Math(expr) => {
+                if let ParserNode::Number(arg) = expr.arg() {
+                    return ParserNode::Number(Interpreter::apply_math(expr.op(), *arg));
+                }
+            }
+            ParserNode::UnaryExpr(expr) => {
+                if let ParserNode::Number(a) = expr.a() {
+                    match expr.op() {
+                        UnaryOp::Negate => return ParserNode::Number(-a),
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        node
+    }
+
+    /// Looks up a constant palette index ahead of time.
+    fn resolve(&self, node: &ParserNode) -> ParserNode {
+        let node = match Self::fold(node) {
+            ParserNode::ColorName(idx) => ParserNode::Number(idx as f64),
+            node => node,
+        };
+        // Bad indexes are left for the interpreter to report.
+        if let ParserNode::Number(num) = node {
+            if let Ok(color) = ColorSpec::index(num).and_then(|spec| spec.resolve(&self.pal)) {
+                return ParserNode::Color(color);
+            }
+        }
+        node
+    }
+
+    /// Moves invariant expressions out of a repeat body and into temporary
+    /// variables assigned just before it. This only happens when the body is
+    /// sure to run, and doesn't call any procedures, which might assign to the
+    /// variables the expressions use.
+    fn hoist(&mut self, node: RepeatNode, out: &mut ParserNodeList) -> ParserNode {
+        let runs = matches!(node.count(), ParserNode::Number(count) if *count >= 1.0);
+
+        let mut assigned = HashSet::new();
+        let mut calls = false;
+        for item in node.list().iter() {
+            calls |= Self::scan(item, &mut assigned);
+        }
+
+        if !runs || calls {
+            return ParserNode::Repeat(node);
+        }
+
+        let list = node
+            .list()
+            .iter()
+            .map(|item| Self::map_args(item, &mut |expr| self.hoist_expr(expr, &assigned, out)))
+            .collect();
+
+        ParserNode::Repeat(RepeatNode::new(node.count().clone(), list))
+    }
+
+    fn hoist_expr(
+        &mut self,
+        expr: &ParserNode,
+        assigned: &HashSet<String>,
+        out: &mut ParserNodeList,
+    ) -> ParserNode {
+        let compound = matches!(
+            expr,
+            ParserNode::BinExpr(_) | ParserNode::Math(_) | ParserNode::UnaryExpr(_)
+        );
+
+        if compound && Self::invariant(expr, assigned) {
+            // Programs can't use '#' in a name, so these never clash.
+            let name = format!("#{}", self.hoisted);
+            self.hoisted += 1;
+
+            out.push(ParserNode::Let(LetNode::new(name.clone(), expr.clone())));
+            return ParserNode::Word(name);
+        }
+
+        Self::map_args(expr, &mut |child| self.hoist_expr(child, assigned, out))
+    }
+
+    fn invariant(expr: &ParserNode, assigned: &HashSet<String>) -> bool {
+        match expr {
+            ParserNode::BinExpr(expr) => {
+                Self::invariant(expr.a(), assigned) && Self::invariant(expr.b(), assigned)
+            }
+            ParserNode::Math(expr) => Self::invariant(expr.arg(), assigned),
+            ParserNode::Number(_) => true,
+            ParserNode::UnaryExpr(expr) => Self::invariant(expr.a(), assigned),
+            ParserNode::Word(name) => !assigned.contains(name),
+            _ => false,
+        }
+    }
+
+    /// Collects the variables a node assigns to, and returns whether it calls
+    /// a procedure.
+    fn scan(node: &ParserNode, assigned: &mut HashSet<String>) -> bool {
+        match node {
+            ParserNode::Call(_) => return true,
+            ParserNode::For(node) => {
+                assigned.insert(node.var().to_string());
+            }
+            ParserNode::Let(node) => {
+                assigned.insert(node.name().to_string());
+            }
+            ParserNode::Param(node) => {
+                assigned.insert(node.name().to_string());
+            }
+            _ => {}
+        }
+
+        let mut calls = false;
+        for child in Self::children(node) {
+            calls |= Self::scan(child, assigned);
+        }
+        calls
+    }
+
+    /// Every node directly beneath this one, including loop bodies.
+    fn children(node: &ParserNode) -> Vec<&ParserNode> {
+        match node {
+            ParserNode::Assert(node) => vec![node.cond()],
+            ParserNode::BinExpr(node) => vec![node.a(), node.b()],
+            ParserNode::Every(node) => {
+                let mut children: Vec<_> = node.interval().into_iter().collect();
+                children.extend(node.list());
+                children
+            }
+            ParserNode::For(node) => {
+                let mut children = vec![node.initial(), node.limit(), node.step()];
+                children.extend(node.list());
+                children
+            }
+            ParserNode::Hsv(node) => vec![node.arg()],
+            ParserNode::Let(node) => vec![node.val()],
+            ParserNode::List(list) => list.iter().collect(),
+            ParserNode::Load(node) => node.list().iter().collect(),
+            ParserNode::Math(node) => vec![node.arg()],
+            ParserNode::Move(node) => vec![node.distance()],
+            ParserNode::Param(node) => vec![node.min(), node.max()],
+            ParserNode::Point(node) => vec![node.x(), node.y()],
+            ParserNode::Print(node) => vec![node.arg()],
+            ParserNode::Random(node) => vec![node.max()],
+            ParserNode::Repeat(node) => {
+                let mut children = vec![node.count()];
+                children.extend(node.list());
+                children
+            }
+            ParserNode::Rotate(node) => vec![node.angle()],
+            ParserNode::SetGradient(node) => vec![node.colors(), node.moves()],
+            ParserNode::SetHeading(node) => vec![node.angle()],
+            ParserNode::SetPenColor(node) => vec![node.color()],
+            ParserNode::SetPosition(node) => node
+                .x()
+                .into_iter()
+                .chain(node.y())
+                .map(|item| item.as_ref())
+                .collect(),
+            ParserNode::SetScreenColor(node) => vec![node.color()],
+            ParserNode::SetSize(node) => vec![node.size()],
+            ParserNode::SetSpeed(node) => vec![node.speed()],
+            ParserNode::SetTextColor(node) => vec![node.color()],
+            ParserNode::Symmetry(node) => vec![node.ways()],
+            ParserNode::Transform(TransformNode::Rotate(arg))
+            | ParserNode::Transform(TransformNode::Scale(arg)) => vec![arg],
+            ParserNode::Transform(TransformNode::Translate(x, y)) => vec![x, y],
+            ParserNode::UnaryExpr(node) => vec![node.a()],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Rebuilds a node with `f` applied to each expression it evaluates. Loop
+    /// bodies are left as they are.
+    fn map_args(node: &ParserNode, f: &mut dyn FnMut(&ParserNode) -> ParserNode) -> ParserNode {
+        match node {
+            ParserNode::Assert(node) => ParserNode::Assert(AssertNode::new(f(node.cond()))),
+            ParserNode::BinExpr(node) => {
+                ParserNode::BinExpr(BinExprNode::new(f(node.a()), node.op(), f(node.b())))
+            }
+            ParserNode::Every(node) => ParserNode::Every(EveryNode::new(
+                node.interval().map(&mut *f),
+                node.list().clone(),
+            )),
+            ParserNode::For(node) => ParserNode::For(ForNode::new(
+                node.var().to_string(),
+                f(node.initial()),
+                f(node.limit()),
+                f(node.step()),
+                node.list().clone(),
+            )),
+            ParserNode::Hsv(node) => ParserNode::Hsv(HsvNode::new(f(node.arg()))),
+            ParserNode::Let(node) => {
+                ParserNode::Let(LetNode::new(node.name().to_string(), f(node.val())))
+            }
+            ParserNode::List(list) => ParserNode::List(list.iter().map(&mut *f).collect()),
+            ParserNode::Math(node) => ParserNode::Math(MathNode::new(node.op(), f(node.arg()))),
+            ParserNode::Move(node) => {
+                ParserNode::Move(MoveNode::new(f(node.distance()), *node.direction()))
+            }
+            ParserNode::Param(node) => ParserNode::Param(ParamNode::new(
+                node.name().to_string(),
+                f(node.min()),
+                f(node.max()),
+            )),
+            ParserNode::Point(node) => {
+                ParserNode::Point(PointNode::new(node.op(), f(node.x()), f(node.y())))
+            }
+            ParserNode::Print(node) => ParserNode::Print(PrintNode::new(node.op(), f(node.arg()))),
+            ParserNode::Random(node) => ParserNode::Random(RandomNode::new(f(node.max()))),
+            ParserNode::Repeat(node) => {
+                ParserNode::Repeat(RepeatNode::new(f(node.count()), node.list().clone()))
+            }
+            ParserNode::Rotate(node) => {
+                ParserNode::Rotate(RotateNode::new(f(node.angle()), *node.direction()))
+            }
+            ParserNode::SetGradient(node) => {
+                ParserNode::SetGradient(SetGradientNode::new(f(node.colors()), f(node.moves())))
+            }
+            ParserNode::SetHeading(node) => {
+                ParserNode::SetHeading(SetHeadingNode::new(f(node.angle())))
+            }
+            ParserNode::SetPenColor(node) => {
+                ParserNode::SetPenColor(SetPenColorNode::new(f(node.color())))
+            }
+            ParserNode::SetPosition(node) => ParserNode::SetPosition(SetPositionNode::new(
+                node.x().map(|x| Box::new(f(x))),
+                node.y().map(|y| Box::new(f(y))),
+            )),
+            ParserNode::SetScreenColor(node) => {
+                ParserNode::SetScreenColor(SetScreenColorNode::new(f(node.color())))
+            }
+            ParserNode::SetSize(node) => ParserNode::SetSize(SetSizeNode::new(f(node.size()))),
+            ParserNode::SetSpeed(node) => ParserNode::SetSpeed(SetSpeedNode::new(f(node.speed()))),
+            ParserNode::SetTextColor(node) => {
+                ParserNode::SetTextColor(SetTextColorNode::new(f(node.color())))
+            }
+            ParserNode::Symmetry(node) => ParserNode::Symmetry(SymmetryNode::new(f(node.ways()))),
+            ParserNode::Transform(TransformNode::Rotate(angle)) => {
+                ParserNode::Transform(TransformNode::Rotate(Box::new(f(angle))))
+            }
+            ParserNode::Transform(TransformNode::Scale(factor)) => {
+                ParserNode::Transform(TransformNode::Scale(Box::new(f(factor))))
+            }
+            ParserNode::Transform(TransformNode::Translate(x, y)) => {
+                ParserNode::Transform(TransformNode::Translate(Box::new(f(x)), Box::new(f(y))))
+            }
+            ParserNode::UnaryExpr(node) => {
+                ParserNode::UnaryExpr(UnaryExprNode::new(node.op(), f(node.a())))
+            }
+            _ => node.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::lexer::Lexer;
+    use super::super::lexer_types::*;
+    use super::super::library::Library;
+    use super::super::parser::Parser;
+    use super::super::warning::Warnings;
+    use super::*;
+    use druid::Color;
+
+    fn optimize(input: &str) -> ParserNodeList {
+        let warnings = Warnings::default();
+        let lexer_out = Lexer::new(&warnings).go(input).unwrap();
+        let parser_out = Parser::new(&warnings, &Library::default())
+            .go(&lexer_out)
+            .unwrap();
+        Optimizer::new().go(&parser_out).list
+    }
+
+    fn forward(distance: ParserNode) -> ParserNode {
+        ParserNode::Move(MoveNode::new(distance, Direction::Forward))
+    }
+
+    #[test]
+    fn it_folds_constants() {
+        let list = optimize("repeat (360 / 5) { fd (2 * (3 + 4)) } fd (1 / 0)");
+
+        let body = vec![forward(ParserNode::Number(14.0))];
+        let repeat = RepeatNode::new(ParserNode::Number(72.0), body);
+        assert_eq!(list[0], ParserNode::Repeat(repeat));
+
+        // Division by zero is left for the interpreter to report.
+        let div = BinExprNode::new(
+            ParserNode::Number(1.0),
+            LexerOperator::Divide,
+            ParserNode::Number(0.0),
+        );
+        assert_eq!(list[1], forward(ParserNode::BinExpr(div)));
+    }
+
+    #[test]
+    fn it_resolves_palette_colors() {
+        let list = optimize("setpc (2 + 2) setsc 99");
+
+        let color = SetPenColorNode::new(ParserNode::Color(Color::RED));
+        assert_eq!(list[0], ParserNode::SetPenColor(color));
+
+        let color = SetScreenColorNode::new(ParserNode::Number(99.0));
+        assert_eq!(list[1], ParserNode::SetScreenColor(color));
+    }
+
+    #[test]
+    fn it_hoists_invariant_expressions() {
+        let list = optimize("let x = 2 let y = 0 repeat 4 { fd (x * 10) let y = (y + x) }");
+
+        let product = BinExprNode::new(
+            ParserNode::Word("x".to_string()),
+            LexerOperator::Multiply,
+            ParserNode::Number(10.0),
+        );
+        let hoisted = LetNode::new("#0".to_string(), ParserNode::BinExpr(product));
+        assert_eq!(list[2], ParserNode::Let(hoisted));
+
+        // The sum uses y, which the body assigns, so it stays put.
+        if let ParserNode::Repeat(repeat) = &list[3] {
+            assert_eq!(
+                repeat.list()[0],
+                forward(ParserNode::Word("#0".to_string()))
+            );
+
+            let sum = BinExprNode::new(
+                ParserNode::Word("y".to_string()),
+                LexerOperator::Add,
+                ParserNode::Word("x".to_string()),
+            );
+            let node = LetNode::new("y".to_string(), ParserNode::BinExpr(sum));
+            assert_eq!(repeat.list()[1], ParserNode::Let(node));
+        } else {
+            panic!("expected a repeat, got {:?}", list[3]);
+        }
+    }
+
+    #[test]
+    fn it_leaves_loops_that_might_not_run() {
+        let input = "let x = 2 let n = 0 repeat n { fd (x * 10) }";
+        assert_eq!(optimize(input).len(), 3);
+    }
+}
@@ -13,15 +13,26 @@
 // limitations under the License.
 
 use super::error::*;
+use super::keywords;
+use super::keywords::Keyword;
+use super::lexer::Lexer;
 use super::lexer_types::*;
+use super::library::Library;
 use super::parser_types::*;
+use super::warning::*;
+use crate::common::constants::MAX_SPEED;
+use crate::common::palette;
+use crate::model::prefs::Language;
+use crate::model::prefs::TurtleShape;
 use std::collections::HashMap;
+use std::collections::HashSet;
 
 #[derive(Clone, Debug)]
 struct ListIter<'a> {
     list: &'a [LexerAny],
     idx: usize,
     depth: usize,
+    line: usize,
 }
 
 impl<'a> ListIter<'a> {
@@ -30,40 +41,98 @@ impl<'a> ListIter<'a> {
             list,
             idx: 0,
             depth: 0,
+            line: 1,
         }
     }
 
+    /// An iterator for a block inside this list, which starts on the line
+    /// we've got to.
+    fn nested<'b>(&self, list: &'b [LexerAny]) -> ListIter<'b> {
+        ListIter {
+            line: self.line,
+            ..ListIter::new(list)
+        }
+    }
+
+    /// The items left, not counting line starts.
+    fn remaining(&self) -> impl Iterator<Item = &LexerAny> {
+        self.list[self.idx..]
+            .iter()
+            .filter(|item| !matches!(item, LexerAny::LexerLine(_)))
+    }
+
+    /// The line the next item is on.
+    fn next_line(&self) -> usize {
+        self.list[self.idx..]
+            .iter()
+            .take_while(|item| matches!(item, LexerAny::LexerLine(_)))
+            .last()
+            .map(|item| match item {
+                LexerAny::LexerLine(line) => *line,
+                _ => self.line,
+            })
+            .unwrap_or(self.line)
+    }
+
     fn is_empty(&self) -> bool {
-        self.idx >= self.list.len()
+        self.remaining().next().is_none()
     }
 
     fn expect(&self, n: usize) -> RuntimeResult {
-        if self.idx + n > self.list.len() {
-            let msg = format!("{} items expected", n);
-            Err(RuntimeError::Parser(msg))
+        if self.remaining().count() < n {
+            let kind = ErrorKind::Arity { expected: n };
+            Err(RuntimeError::parser(kind))
         } else {
             Ok(())
         }
     }
 
     fn expect_assign(&mut self) -> RuntimeResult {
-        if let LexerAny::LexerOperator(op) = self.next() {
+        if let LexerAny::LexerOperator(op) = self.next()? {
             if op == LexerOperator::Assign {
                 return Ok(());
             }
         }
 
-        let msg = "expected an assignment".to_string();
-        Err(RuntimeError::Parser(msg))
+        Err(RuntimeError::parser(ErrorKind::ExpectedAssignment))
+    }
+
+    fn peek(&self) -> Option<&LexerAny> {
+        self.remaining().next()
+    }
+
+    /// Skip the rest of the line, after an error.
+    fn skip_line(&mut self) {
+        while let Some(item) = self.list.get(self.idx) {
+            if let LexerAny::LexerLine(_) = item {
+                break;
+            }
+            self.idx += 1;
+        }
     }
 
-    fn next(&mut self) -> LexerAny {
-        let temp = self.idx;
-        self.idx += 1;
-        self.list[temp].clone()
+    /// The next item, skipping line starts. Running out is an error on the
+    /// line we'd got to, so a caller that counted wrong can't panic.
+    fn next(&mut self) -> RuntimeResult<LexerAny> {
+        while let Some(item) = self.list.get(self.idx) {
+            self.idx += 1;
+            match item {
+                LexerAny::LexerLine(line) => self.line = *line,
+                item => return Ok(item.clone()),
+            }
+        }
+
+        Err(RuntimeError::parser(ErrorKind::UnexpectedEnd).on_line(self.line))
     }
 }
 
+/// Procedure and variable names don't depend on case, as in traditional
+/// Logo, so `Square` and `square` are the same procedure. Every name is
+/// defined and looked up through here.
+fn symbol(name: &str) -> String {
+    name.to_lowercase()
+}
+
 #[derive(Clone, Debug, PartialEq)]
 enum SymbolTag {
     Func,
@@ -74,88 +143,321 @@ enum SymbolTag {
 pub struct Parser {
     smap: HashMap<String, SymbolTag>,
     fmap: ParserFuncMap,
+    lets: Vec<String>,
+    used: HashSet<String>,
+    /// Procedures whose bodies have been parsed.
+    defined: HashSet<String>,
+    language: Language,
+    /// Mark where each statement is, so the editor can show it.
+    lines: bool,
+    library: Library,
+    loading: Vec<String>,
+    /// Calls to names that weren't defined yet, and the lines they're on.
+    /// Any still undefined once everything is parsed are errors.
+    forward: Vec<(String, usize)>,
+    errors: Vec<RuntimeError>,
+    warnings: Warnings,
 }
 
 impl Parser {
-    pub fn new() -> Self {
+    pub fn new(warnings: &Warnings, library: &Library) -> Self {
         Self {
             smap: HashMap::new(),
             fmap: ParserFuncMap::new(),
+            lets: Vec::new(),
+            used: HashSet::new(),
+            defined: HashSet::new(),
+            language: Language::English,
+            lines: false,
+            library: library.clone(),
+            loading: Vec::new(),
+            forward: Vec::new(),
+            errors: Vec::new(),
+            warnings: warnings.clone(),
+        }
+    }
+
+    /// Accept keyword names in `language` as well as English.
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Put a line marker ahead of each statement in the input, though not in
+    /// files it loads.
+    pub fn with_lines(mut self, lines: bool) -> Self {
+        self.lines = lines;
+        self
+    }
+
+    /// Start with procedures kept from earlier runs. Defining one again
+    /// replaces it without a warning, as running the same input twice does.
+    pub fn with_procedures(mut self, procedures: ParserFuncMap) -> Self {
+        for (name, func) in procedures {
+            self.smap.insert(name.clone(), SymbolTag::Func);
+            self.fmap.insert(name, func);
         }
+        self
     }
 
+    /// Parse the whole input, failing with the first error. The parser
+    /// carries on past errors to find the rest, which `errors` returns.
     pub fn go(&mut self, input: &[LexerAny]) -> RuntimeResult<ParserOutput> {
+        self.errors.clear();
+        self.declare_fns(input);
         let mut iter = ListIter::new(input);
-        let list = self.parse(&mut iter)?;
-        Ok(ParserOutput::new(list, self.fmap.to_owned()))
+        let list = self.parse(&mut iter);
+        self.resolve_forward();
+        if let Some(err) = self.errors.first() {
+            return Err(err.clone());
+        }
+
+        self.warn_unused();
+        Ok(ParserOutput::new(
+            list,
+            self.fmap.to_owned(),
+            self.symbols(),
+        ))
+    }
+
+    /// Note every procedure defined anywhere in `list` before parsing any of
+    /// them, so that one can call another defined further down, or itself.
+    /// A name that's already taken is left for `parse_fn` to complain about.
+    fn declare_fns(&mut self, list: &[LexerAny]) {
+        let mut words = list
+            .iter()
+            .filter(|item| !matches!(item, LexerAny::LexerLine(_)));
+        while let Some(item) = words.next() {
+            match item {
+                LexerAny::LexerWord(word)
+                    if keywords::lookup(word, self.language) == Some(Keyword::Fn) =>
+                {
+                    if let Some(LexerAny::LexerWord(name)) = words.next() {
+                        let name = symbol(name);
+                        let reserved = keywords::lookup(&name, self.language).is_some();
+                        if !reserved && !self.smap.contains_key(&name) {
+                            self.smap.insert(name.clone(), SymbolTag::Func);
+                            let func = ParserFuncDef::new(false, 0, ParserNodeList::new());
+                            self.fmap.insert(name, func);
+                        }
+                    }
+                }
+                LexerAny::LexerBlock(block) | LexerAny::LexerList(block) => self.declare_fns(block),
+                _ => {}
+            }
+        }
+    }
+
+    /// Calls made before the procedure was defined are fine once it is, by
+    /// a file loaded further down, say. The rest are undefined after all, and
+    /// join the other errors in line order, ahead of any on the same line
+    /// since whatever followed the name was most likely meant for it.
+    fn resolve_forward(&mut self) {
+        let mut errors: Vec<_> = std::mem::take(&mut self.forward)
+            .into_iter()
+            .filter(|(name, _)| self.smap.get(name) != Some(&SymbolTag::Func))
+            .map(|(name, line)| {
+                RuntimeError::parser(ErrorKind::UndefinedSymbol(name)).on_line(line)
+            })
+            .collect();
+        errors.append(&mut self.errors);
+        errors.sort_by_key(|err| err.line);
+        self.errors = errors;
+    }
+
+    /// Names of every procedure and variable defined so far, sorted.
+    fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.smap.keys().cloned().collect();
+        symbols.sort();
+        symbols
     }
 
-    fn parse(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNodeList> {
+    fn warn_unused(&self) {
+        for name in self.lets.iter().filter(|name| !self.used.contains(*name)) {
+            let kind = WarningKind::UnusedVariable(name.to_string());
+            self.warnings.push(Stage::Parser, kind, None);
+        }
+    }
+
+    /// Every error found by the last run of `go`, in order.
+    pub fn errors(&self) -> &[RuntimeError] {
+        &self.errors
+    }
+
+    /// Statements up to the end of the list. A statement with an error is
+    /// noted and left out, and parsing picks up again on the next line, since
+    /// that's where the next statement most likely starts.
+    fn parse(&mut self, iter: &mut ListIter) -> ParserNodeList {
         let mut list = ParserNodeList::new();
 
         while !iter.is_empty() {
-            let word = self.get_word(iter)?;
-            let node = self.parse_word(iter, &word)?;
-            list.push(node);
+            let line = iter.next_line();
+            let res = self
+                .get_word(iter)
+                .and_then(|word| self.parse_word(iter, &word));
+            match res {
+                Ok(node) => {
+                    if self.lines && self.loading.is_empty() {
+                        list.push(ParserNode::Line(line));
+                    }
+                    list.push(node);
+                }
+                Err(err) => {
+                    self.errors.push(err.on_line(line));
+                    iter.skip_line();
+                }
+            }
         }
 
-        Ok(list)
+        list
     }
 
     fn parse_word(&mut self, iter: &mut ListIter, word: &str) -> RuntimeResult<ParserNode> {
-        let res = match word.to_lowercase().as_str() {
-            "arctan" => self.parse_math(iter, MathOp::Atan)?,
-            "bk" | "backward" => self.parse_backward(iter)?,
-            "clean" => self.parse_clean(),
-            "cs" | "clearscreen" => self.parse_clear_screen(),
-            "cos" => self.parse_math(iter, MathOp::Cos)?,
-            "fd" | "forward" => self.parse_forward(iter)?,
-            "fill" => self.parse_fill(),
-            "fn" => self.parse_fn(iter)?,
-            "for" => self.parse_for(iter)?,
-            "ht" | "hideturtle" => ParserNode::ShowTurtle(false),
-            "home" => self.parse_home(),
-            "let" => self.parse_let(iter)?,
-            "log10" => self.parse_math(iter, MathOp::Log10)?,
-            "ln" => self.parse_math(iter, MathOp::Ln)?,
-            "lt" | "left" => self.parse_left(iter)?,
-            "pd" | "pendown" => self.parse_pen_down(),
-            "pe" | "penerase" => self.parse_pen_erase(),
-            "ppt" | "penpaint" => self.parse_pen_paint(),
-            "px" | "penreverse" => self.parse_pen_reverse(),
-            "pu" | "penup" => self.parse_pen_up(),
-            "random" => self.parse_random(iter)?,
-            "repcount" => ParserNode::Repcount,
-            "repeat" => self.parse_repeat(iter)?,
-            "round" => self.parse_math(iter, MathOp::Round)?,
-            "rt" | "right" => self.parse_right(iter)?,
-            "seth" | "setheading" => self.parse_set_heading(iter)?,
-            "setpc" | "setpencolor" => self.parse_set_pen_color(iter)?,
-            "setpos" => self.parse_set_pos(iter)?,
-            "setsc" | "setscreencolor" => self.parse_set_screen_color(iter)?,
-            "setxy" => self.parse_setxy(iter)?,
-            "setx" => self.parse_setx(iter)?,
-            "sety" => self.parse_sety(iter)?,
-            "sin" => self.parse_math(iter, MathOp::Sin)?,
-            "sqrt" => self.parse_math(iter, MathOp::Sqrt)?,
-            "st" | "showturtle" => ParserNode::ShowTurtle(true),
-            _ => self.parse_other(iter, word)?,
+        let keyword = match keywords::lookup(word, self.language) {
+            Some(keyword) => keyword,
+            None => return self.parse_other(iter, word),
+        };
+
+        let res = match keyword {
+            Keyword::Arctan => self.parse_math(iter, MathOp::Atan)?,
+            Keyword::Assert => self.parse_assert(iter)?,
+            Keyword::Backward => self.parse_backward(iter)?,
+            Keyword::Clean => self.parse_clean(),
+            Keyword::ClearScreen => self.parse_clear_screen(),
+            Keyword::Cos => self.parse_math(iter, MathOp::Cos)?,
+            Keyword::Distance => self.parse_point(iter, PointOp::Distance)?,
+            Keyword::DrawingBounds => ParserNode::DrawingBounds,
+            Keyword::Erase => self.parse_erase(iter)?,
+            Keyword::Every => self.parse_every(iter)?,
+            Keyword::Fill => self.parse_fill(),
+            Keyword::Fn => self.parse_fn(iter)?,
+            Keyword::For => self.parse_for(iter)?,
+            Keyword::Forever => self.parse_forever(iter)?,
+            Keyword::Forward => self.parse_forward(iter)?,
+            Keyword::Heading => ParserNode::Heading,
+            Keyword::HideTurtle => ParserNode::ShowTurtle(false),
+            Keyword::Home => self.parse_home(),
+            Keyword::Hsv => self.parse_hsv(iter)?,
+            Keyword::Left => self.parse_left(iter)?,
+            Keyword::Let => self.parse_let(iter)?,
+            Keyword::Load => self.parse_load(iter)?,
+            Keyword::LoadPicture => self.parse_load_picture(iter)?,
+            Keyword::Ln => self.parse_math(iter, MathOp::Ln)?,
+            Keyword::Log10 => self.parse_math(iter, MathOp::Log10)?,
+            Keyword::Param => self.parse_param(iter)?,
+            Keyword::PenDown => self.parse_pen_down(),
+            Keyword::PenErase => self.parse_pen_erase(),
+            Keyword::PenPaint => self.parse_pen_paint(),
+            Keyword::PenReverse => self.parse_pen_reverse(),
+            Keyword::PenUp => self.parse_pen_up(),
+            Keyword::PickColor => ParserNode::PickColor,
+            Keyword::Pops => ParserNode::Pops,
+            Keyword::PopTransform => ParserNode::Transform(TransformNode::Pop),
+            Keyword::Pots => ParserNode::Pots,
+            Keyword::Print => self.parse_print(iter, PrintOp::Print)?,
+            Keyword::PushTransform => ParserNode::Transform(TransformNode::Push),
+            Keyword::Random => self.parse_random(iter)?,
+            Keyword::ReadList => ParserNode::Read(ReadOp::List),
+            Keyword::ReadWord => ParserNode::Read(ReadOp::Word),
+            Keyword::Repcount => ParserNode::Repcount,
+            Keyword::Repeat => self.parse_repeat(iter)?,
+            Keyword::Right => self.parse_right(iter)?,
+            Keyword::RotateCs => self.parse_transform(iter, TransformNode::Rotate)?,
+            Keyword::Round => self.parse_math(iter, MathOp::Round)?,
+            Keyword::Save => self.parse_save(iter)?,
+            Keyword::Scale => self.parse_transform(iter, TransformNode::Scale)?,
+            Keyword::SetGeometric => self.parse_set_geometric(iter)?,
+            Keyword::SetGradient => self.parse_set_gradient(iter)?,
+            Keyword::SetHeading => self.parse_set_heading(iter)?,
+            Keyword::SetPenColor => self.parse_set_pen_color(iter)?,
+            Keyword::SetPenColorHsv => {
+                let color_node = self.parse_hsv(iter)?;
+                ParserNode::SetPenColor(SetPenColorNode::new(color_node))
+            }
+            Keyword::SetPos => self.parse_set_pos(iter)?,
+            Keyword::SetScreenColor => self.parse_set_screen_color(iter)?,
+            Keyword::SetScreenColorHsv => {
+                let color_node = self.parse_hsv(iter)?;
+                ParserNode::SetScreenColor(SetScreenColorNode::new(color_node))
+            }
+            Keyword::SetShape => self.parse_set_shape(iter)?,
+            Keyword::SetSize => self.parse_set_size(iter)?,
+            Keyword::SetSpeed => self.parse_set_speed(iter)?,
+            Keyword::SetTextColor => self.parse_set_text_color(iter)?,
+            Keyword::SetX => self.parse_setx(iter)?,
+            Keyword::SetXY => self.parse_set_pos(iter)?,
+            Keyword::SetY => self.parse_sety(iter)?,
+            Keyword::Show => self.parse_print(iter, PrintOp::Show)?,
+            Keyword::ShowTurtle => ParserNode::ShowTurtle(true),
+            Keyword::Shown => ParserNode::Shown,
+            Keyword::Sin => self.parse_math(iter, MathOp::Sin)?,
+            Keyword::Sqrt => self.parse_math(iter, MathOp::Sqrt)?,
+            Keyword::Symmetry => self.parse_symmetry(iter)?,
+            Keyword::Towards => self.parse_point(iter, PointOp::Towards)?,
+            Keyword::Translate => self.parse_translate(iter)?,
+            Keyword::Type => self.parse_print(iter, PrintOp::Type)?,
         };
 
         Ok(res)
     }
 
     fn parse_other(&mut self, iter: &mut ListIter, word: &str) -> RuntimeResult<ParserNode> {
-        match self.smap.get(word) {
-            Some(SymbolTag::Func) => self.parse_call(iter, word),
-            Some(SymbolTag::Var) => Ok(ParserNode::Word(word.to_string())),
-            _ => {
-                let msg = format!("unrecognized symbol \"{}\"", word);
-                Err(RuntimeError::Parser(msg))
+        let name = symbol(word);
+        match self.smap.get(&name) {
+            Some(SymbolTag::Func) => self.parse_call(iter, &name),
+            Some(SymbolTag::Var) => {
+                self.used.insert(name.clone());
+                Ok(ParserNode::Word(name))
             }
+            // Close to a name we know, it's most likely a typo. Otherwise it
+            // may be a procedure still to come.
+            _ => match keywords::suggest(word, self.language, &self.symbols()) {
+                Some(suggestion) => {
+                    let name = word.to_string();
+                    Err(RuntimeError::parser(ErrorKind::DidYouMean {
+                        name,
+                        suggestion,
+                    }))
+                }
+                None => {
+                    let call = CallNode::new(&name, LexerList::new());
+                    self.forward.push((name, iter.line));
+                    Ok(ParserNode::Call(call))
+                }
+            },
         }
     }
 
+    /// `:name` only ever means a variable, even if a keyword or procedure
+    /// has the same name.
+    fn parse_value_of(&mut self, name: &str) -> RuntimeResult<ParserNode> {
+        let name = symbol(name);
+        match self.smap.get(&name) {
+            Some(SymbolTag::Var) => {
+                self.used.insert(name.clone());
+                Ok(ParserNode::Word(name))
+            }
+            Some(tag) => {
+                let kind = ErrorKind::SymbolConflict {
+                    name: name.to_string(),
+                    tag: format!("{:?}", tag),
+                };
+                Err(RuntimeError::parser(kind))
+            }
+            None => {
+                let kind = ErrorKind::UndefinedSymbol(name.to_string());
+                Err(RuntimeError::parser(kind))
+            }
+        }
+    }
+
+    fn parse_assert(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let cond_node = self.get_parse_expr(iter)?;
+        let node = AssertNode::new(cond_node);
+        Ok(ParserNode::Assert(node))
+    }
+
     fn parse_backward(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
         iter.expect(1)?;
         let distance_node = self.get_parse_expr(iter)?;
@@ -198,12 +500,43 @@ impl Parser {
             LexerAny::LexerBinExpr(bin_expr) => self.parse_bin_expr(iter, &bin_expr),
             LexerAny::LexerNumber(num) => Ok(ParserNode::Number(*num)),
             LexerAny::LexerList(list) => self.parse_list(&list),
-            LexerAny::LexerWord(word) => self.parse_word(iter, &word),
-            _ => {
-                let msg = "failed to parse expression".to_string();
-                Err(RuntimeError::Parser(msg))
+            LexerAny::LexerNegate(operand) => {
+                let anode = self.parse_expr(iter, operand)?;
+                Ok(Self::negate(anode))
+            }
+            LexerAny::LexerOperator(LexerOperator::Subtract) => {
+                iter.expect(1)?;
+                let anode = self.get_operand(iter)?;
+                Ok(Self::negate(anode))
             }
+            LexerAny::LexerValueOf(name) => self.parse_value_of(name),
+            LexerAny::LexerWord(word) => self.parse_word(iter, &word),
+            _ => Err(RuntimeError::parser(ErrorKind::InvalidExpression)),
+        }
+    }
+
+    fn negate(anode: ParserNode) -> ParserNode {
+        let node = UnaryExprNode::new(UnaryOp::Negate, anode);
+        ParserNode::UnaryExpr(node)
+    }
+
+    fn parse_erase(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let name = symbol(&self.get_quoted(iter)?);
+        if !self.fmap.contains_key(&name) {
+            return Err(RuntimeError::parser(ErrorKind::UndefinedFunction(name)));
         }
+        Ok(ParserNode::Erase(name))
+    }
+
+    fn parse_every(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(2)?;
+        let interval_node = self.get_parse_expr(iter)?;
+        let block = self.get_block(iter)?;
+        let mut block_iter = iter.nested(&block);
+        let node_list = self.parse(&mut block_iter);
+        let every_node = EveryNode::new(Some(interval_node), node_list);
+        Ok(ParserNode::Every(every_node))
     }
 
     fn parse_fill(&mut self) -> ParserNode {
@@ -212,20 +545,43 @@ impl Parser {
 
     fn parse_fn(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
         iter.expect(2)?;
-        let name = self.get_word(iter)?;
+        let name = symbol(&self.get_word(iter)?);
         self.check_symbol(&name, SymbolTag::Func)?;
         let block = self.get_block(iter)?;
-        let mut block_iter = ListIter::new(&block);
-        let list = self.parse(&mut block_iter)?;
+        let mut block_iter = iter.nested(&block);
+        let list = self.parse(&mut block_iter);
         let func = ParserFuncDef::new(false, 0, list);
-        self.fmap.insert(name, func);
-        Ok(ParserNode::Placeholder)
+
+        // The first definition is there from the start, so it can be called
+        // from above. Each one after that takes over once the program gets
+        // to it, so it's kept apart until then.
+        let key = if self.defined.insert(name.clone()) {
+            name
+        } else {
+            let kind = WarningKind::Redefined(name.clone());
+            self.warnings.push(Stage::Parser, kind, None);
+            (1..)
+                .map(|count| redefinition(&name, count))
+                .find(|key| !self.fmap.contains_key(key))
+                .unwrap()
+        };
+        self.fmap.insert(key.clone(), func);
+        Ok(ParserNode::Fn(key))
+    }
+
+    fn parse_forever(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let block = self.get_block(iter)?;
+        let mut block_iter = iter.nested(&block);
+        let node_list = self.parse(&mut block_iter);
+        let every_node = EveryNode::new(None, node_list);
+        Ok(ParserNode::Every(every_node))
     }
 
     fn parse_for(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
         iter.expect(5)?;
 
-        let var = self.get_word(iter)?;
+        let var = self.get_name(iter)?;
         self.check_symbol(&var, SymbolTag::Var)?;
 
         let initial = self.get_parse_expr(iter)?;
@@ -233,8 +589,8 @@ impl Parser {
         let step = self.get_parse_expr(iter)?;
 
         let block = self.get_block(iter)?;
-        let mut block_iter = ListIter::new(&block);
-        let node_list = self.parse(&mut block_iter)?;
+        let mut block_iter = iter.nested(&block);
+        let node_list = self.parse(&mut block_iter);
 
         let for_node = ForNode::new(var, initial, limit, step, node_list);
         Ok(ParserNode::For(for_node))
@@ -251,13 +607,23 @@ impl Parser {
         ParserNode::Home
     }
 
+    fn parse_hsv(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let arg_node = self.get_parse_expr(iter)?;
+        Ok(ParserNode::Hsv(HsvNode::new(arg_node)))
+    }
+
     fn parse_let(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
         iter.expect(3)?;
-        let var = self.get_word(iter)?;
+        let var = self.get_name(iter)?;
         self.check_symbol(&var, SymbolTag::Var)?;
+        if !self.lets.contains(&var) {
+            self.lets.push(var.clone());
+        }
         iter.expect_assign()?;
-        let rhs = iter.next();
+        let rhs = iter.next()?;
         let rhs_node = self.parse_expr(iter, &rhs)?;
+        let rhs_node = self.parse_infix(iter, rhs_node, 0)?;
         let l_node = LetNode::new(var, rhs_node);
         Ok(ParserNode::Let(l_node))
     }
@@ -274,13 +640,61 @@ impl Parser {
 
         let mut node_list = ParserNodeList::new();
         while !list_iter.is_empty() {
-            let expr = self.get_expr(&mut list_iter)?;
-            let node = self.parse_expr(&mut list_iter, &expr)?;
+            let node = self.get_parse_expr(&mut list_iter)?;
             node_list.push(node);
         }
         Ok(ParserNode::List(node_list))
     }
 
+    /// Files are loaded while parsing, so that the procedures they define can
+    /// be called from the rest of the program.
+    fn parse_load(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let name = self.get_quoted(iter)?;
+        if self.loading.contains(&name) {
+            return Err(RuntimeError::parser(ErrorKind::CircularLoad(name)));
+        }
+
+        // Errors in the file are put on the line that loads it, since that's
+        // the line the user can see, and say where in the file they are.
+        let source = self.library.read(&name)?;
+        let file = name.clone();
+        let load_line = iter.line;
+        let elsewhere = |err: RuntimeError| RuntimeError {
+            stage: err.stage,
+            kind: ErrorKind::InFile {
+                name: file.clone(),
+                line: err.location(&source).map(|(line, _)| line),
+                kind: Box::new(err.kind.clone()),
+            },
+            span: None,
+            line: Some(load_line),
+        };
+        let lexer_out = Lexer::new(&self.warnings).go(&source).map_err(elsewhere)?;
+
+        let count = self.errors.len();
+        let forward = self.forward.len();
+        self.declare_fns(&lexer_out);
+        self.loading.push(name);
+        let list = self.parse(&mut ListIter::new(&lexer_out));
+        let name = self.loading.pop().unwrap();
+        for (_, line) in self.forward[forward..].iter_mut() {
+            *line = iter.line;
+        }
+        if self.errors.len() > count {
+            let err = self.errors.split_off(count).remove(0);
+            return Err(elsewhere(err));
+        }
+
+        Ok(ParserNode::Load(LoadNode::new(&name, list)))
+    }
+
+    fn parse_load_picture(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let name = self.get_quoted(iter)?;
+        Ok(ParserNode::LoadPicture(name))
+    }
+
     fn parse_math(&mut self, iter: &mut ListIter, op: MathOp) -> RuntimeResult<ParserNode> {
         iter.expect(1)?;
         let arg_node = self.get_parse_expr(iter)?;
@@ -288,6 +702,27 @@ impl Parser {
         Ok(ParserNode::Math(math_node))
     }
 
+    /// The name can be written `:size` as well as bare or quoted, since
+    /// that's how the program will go on to use it.
+    fn parse_param(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(3)?;
+        let var = match iter.peek() {
+            Some(LexerAny::LexerValueOf(name)) => {
+                let name = symbol(name);
+                iter.next()?;
+                name
+            }
+            _ => self.get_name(iter)?,
+        };
+        self.check_symbol(&var, SymbolTag::Var)?;
+        if !self.lets.contains(&var) {
+            self.lets.push(var.clone());
+        }
+        let min = self.get_parse_expr(iter)?;
+        let max = self.get_parse_expr(iter)?;
+        Ok(ParserNode::Param(ParamNode::new(var, min, max)))
+    }
+
     fn parse_pen_down(&mut self) -> ParserNode {
         let pen_node = PenNode::Down;
         ParserNode::Pen(pen_node)
@@ -313,10 +748,21 @@ impl Parser {
         ParserNode::Pen(pen_node)
     }
 
+    fn parse_point(&mut self, iter: &mut ListIter, op: PointOp) -> RuntimeResult<ParserNode> {
+        let (x_node, y_node) = self.get_position(iter)?;
+        let point_node = PointNode::new(op, x_node, y_node);
+        Ok(ParserNode::Point(point_node))
+    }
+
+    fn parse_print(&mut self, iter: &mut ListIter, op: PrintOp) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let arg_node = self.get_parse_expr(iter)?;
+        Ok(ParserNode::Print(PrintNode::new(op, arg_node)))
+    }
+
     fn parse_random(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
         iter.expect(1)?;
-        let max = iter.next();
-        let max_node = self.parse_expr(iter, &max)?;
+        let max_node = self.get_parse_expr(iter)?;
         let random_node = RandomNode::new(max_node);
         Ok(ParserNode::Random(random_node))
     }
@@ -325,8 +771,8 @@ impl Parser {
         iter.expect(2)?;
         let count_node = self.get_parse_expr(iter)?;
         let block = self.get_block(iter)?;
-        let mut block_iter = ListIter::new(&block);
-        let node_list = self.parse(&mut block_iter)?;
+        let mut block_iter = iter.nested(&block);
+        let node_list = self.parse(&mut block_iter);
         let repeat_node = RepeatNode::new(count_node, node_list);
         Ok(ParserNode::Repeat(repeat_node))
     }
@@ -338,6 +784,12 @@ impl Parser {
         Ok(ParserNode::Rotate(rotate_node))
     }
 
+    fn parse_save(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let name = self.get_quoted(iter)?;
+        Ok(ParserNode::Save(name))
+    }
+
     fn parse_set_heading(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
         iter.expect(1)?;
         let angle_node = self.get_parse_expr(iter)?;
@@ -345,33 +797,128 @@ impl Parser {
         Ok(ParserNode::SetHeading(node))
     }
 
-    fn parse_set_pen_color(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+    /// A color given by name, as in `"orange`, or any expression.
+    fn get_color(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
         iter.expect(1)?;
-        let color_node = self.get_parse_expr(iter)?;
+        match iter.peek() {
+            Some(LexerAny::LexerQuoted(_)) => match self.get_quoted(iter)?.as_str() {
+                "random" => Ok(ParserNode::PickColor),
+                name => match palette::color_index(name) {
+                    Some(idx) => Ok(ParserNode::ColorName(idx)),
+                    None => Err(RuntimeError::parser(ErrorKind::InvalidColorName(
+                        name.to_string(),
+                    ))),
+                },
+            },
+            _ => self.get_parse_expr(iter),
+        }
+    }
+
+    fn parse_set_pen_color(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        let color_node = self.get_color(iter)?;
         let pen_color_node = SetPenColorNode::new(color_node);
         Ok(ParserNode::SetPenColor(pen_color_node))
     }
 
     fn parse_set_pos(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
-        iter.expect(1)?;
-        let pos = self.get_list(iter)?;
-        let mut pos_iter = ListIter::new(&pos);
-        self.parse_setxy(&mut pos_iter)
+        let (x_node, y_node) = self.get_position(iter)?;
+        let pos_node = SetPositionNode::new(Some(Box::new(x_node)), Some(Box::new(y_node)));
+        Ok(ParserNode::SetPosition(pos_node))
     }
 
     fn parse_set_screen_color(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
-        iter.expect(1)?;
-        let color_node = self.get_parse_expr(iter)?;
+        let color_node = self.get_color(iter)?;
         let pen_color_node = SetScreenColorNode::new(color_node);
         Ok(ParserNode::SetScreenColor(pen_color_node))
     }
 
-    fn parse_setxy(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+    fn parse_set_geometric(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let word = self.get_word(iter)?;
+        match word.as_str() {
+            "true" => Ok(ParserNode::SetGeometric(true)),
+            "false" => Ok(ParserNode::SetGeometric(false)),
+            _ => Err(RuntimeError::parser(ErrorKind::InvalidSwitch(word))),
+        }
+    }
+
+    /// The colors may be a list written out, where names work as they do
+    /// for setpc, or any expression that gives a list of two colors.
+    fn parse_set_gradient(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
         iter.expect(2)?;
-        let x_node = self.get_parse_expr(iter)?;
-        let y_node = self.get_parse_expr(iter)?;
-        let pos_node = SetPositionNode::new(Some(Box::new(x_node)), Some(Box::new(y_node)));
-        Ok(ParserNode::SetPosition(pos_node))
+
+        let colors_node = if let Some(LexerAny::LexerList(list)) = iter.peek() {
+            let list = list.clone();
+            iter.next()?;
+
+            let mut colors_iter = iter.nested(&list);
+            let from_node = self.get_color(&mut colors_iter)?;
+            let to_node = self.get_color(&mut colors_iter)?;
+            if !colors_iter.is_empty() {
+                return Err(RuntimeError::parser(ErrorKind::Arity { expected: 2 }));
+            }
+            ParserNode::List(vec![from_node, to_node])
+        } else {
+            self.get_parse_expr(iter)?
+        };
+
+        let moves_node = self.get_parse_expr(iter)?;
+        let gradient_node = SetGradientNode::new(colors_node, moves_node);
+        Ok(ParserNode::SetGradient(gradient_node))
+    }
+
+    fn parse_symmetry(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let ways_node = self.get_parse_expr(iter)?;
+        Ok(ParserNode::Symmetry(SymmetryNode::new(ways_node)))
+    }
+
+    fn parse_transform(
+        &mut self,
+        iter: &mut ListIter,
+        op: fn(Box<ParserNode>) -> TransformNode,
+    ) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let arg_node = self.get_parse_expr(iter)?;
+        Ok(ParserNode::Transform(op(Box::new(arg_node))))
+    }
+
+    fn parse_translate(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        let (x_node, y_node) = self.get_position(iter)?;
+        let transform_node = TransformNode::Translate(Box::new(x_node), Box::new(y_node));
+        Ok(ParserNode::Transform(transform_node))
+    }
+
+    fn parse_set_shape(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let name = self.get_quoted(iter)?;
+        match TurtleShape::from_name(&name) {
+            Some(shape) if shape != TurtleShape::Image => Ok(ParserNode::SetShape(shape)),
+            _ => Err(RuntimeError::parser(ErrorKind::InvalidShape(name))),
+        }
+    }
+
+    fn parse_set_size(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let size_node = self.get_parse_expr(iter)?;
+        Ok(ParserNode::SetSize(SetSizeNode::new(size_node)))
+    }
+
+    fn parse_set_speed(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        iter.expect(1)?;
+        let speed_node = match iter.peek() {
+            Some(LexerAny::LexerQuoted(_)) => match self.get_quoted(iter)?.as_str() {
+                "instant" => ParserNode::Number(MAX_SPEED as f64),
+                word => return Err(RuntimeError::parser(ErrorKind::InvalidSpeed(word.into()))),
+            },
+            _ => self.get_parse_expr(iter)?,
+        };
+        Ok(ParserNode::SetSpeed(SetSpeedNode::new(speed_node)))
+    }
+
+    fn parse_set_text_color(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        let color_node = self.get_color(iter)?;
+        Ok(ParserNode::SetTextColor(SetTextColorNode::new(color_node)))
     }
 
     fn parse_setx(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
@@ -398,61 +945,140 @@ impl Parser {
         Ok(args)
     }
 
+    /// Brackets work as well as braces, so classic Logo such as
+    /// `repeat 4 [fd 50 rt 90]` runs as it is.
     fn get_block(&mut self, iter: &mut ListIter) -> RuntimeResult<LexerBlock> {
-        if let LexerAny::LexerBlock(block) = iter.next() {
-            Ok(block)
-        } else {
-            let msg = "expected a block".to_string();
-            Err(RuntimeError::Parser(msg))
+        match iter.next()? {
+            LexerAny::LexerBlock(block) | LexerAny::LexerList(block) => Ok(block),
+            _ => Err(RuntimeError::parser(ErrorKind::ExpectedBlock)),
         }
     }
 
     fn get_expr(&mut self, iter: &mut ListIter) -> RuntimeResult<LexerAny> {
-        match iter.next() {
+        match iter.next()? {
             LexerAny::LexerBinExpr(bin_expr) => Ok(LexerAny::LexerBinExpr(bin_expr)),
             LexerAny::LexerList(list) => Ok(LexerAny::LexerList(list)),
+            LexerAny::LexerNegate(operand) => Ok(LexerAny::LexerNegate(operand)),
             LexerAny::LexerNumber(num) => Ok(LexerAny::LexerNumber(num)),
+            LexerAny::LexerOperator(LexerOperator::Subtract) => {
+                Ok(LexerAny::LexerOperator(LexerOperator::Subtract))
+            }
+            LexerAny::LexerValueOf(name) => Ok(LexerAny::LexerValueOf(name)),
             LexerAny::LexerWord(word) => Ok(LexerAny::LexerWord(word)),
-            _ => {
-                let msg = "expected an expression".to_string();
-                Err(RuntimeError::Parser(msg))
+            _ => Err(RuntimeError::parser(ErrorKind::ExpectedExpression)),
+        }
+    }
+
+    /// A position, either as a list like `[100 (50 + 20)]` or as two bare
+    /// expressions. Everything that takes a position reads it through here,
+    /// so they all accept both forms.
+    fn get_position(&mut self, iter: &mut ListIter) -> RuntimeResult<(ParserNode, ParserNode)> {
+        iter.expect(1)?;
+
+        if let Some(LexerAny::LexerList(list)) = iter.peek() {
+            let list = list.clone();
+            iter.next()?;
+
+            let mut pos_iter = ListIter::new(&list);
+            let pos = self.get_pair(&mut pos_iter)?;
+            if !pos_iter.is_empty() {
+                return Err(RuntimeError::parser(ErrorKind::Arity { expected: 2 }));
             }
+
+            Ok(pos)
+        } else {
+            self.get_pair(iter)
         }
     }
 
-    fn get_list(&mut self, iter: &mut ListIter) -> RuntimeResult<LexerList> {
-        if let LexerAny::LexerList(list) = iter.next() {
-            Ok(list)
+    fn get_pair(&mut self, iter: &mut ListIter) -> RuntimeResult<(ParserNode, ParserNode)> {
+        iter.expect(2)?;
+        let x_node = self.get_parse_expr(iter)?;
+        let y_node = self.get_parse_expr(iter)?;
+        Ok((x_node, y_node))
+    }
+
+    fn get_quoted(&mut self, iter: &mut ListIter) -> RuntimeResult<String> {
+        if let LexerAny::LexerQuoted(word) = iter.next()? {
+            Ok(word)
         } else {
-            let msg = "expected a list".to_string();
-            Err(RuntimeError::Parser(msg))
+            Err(RuntimeError::parser(ErrorKind::ExpectedWord))
         }
     }
 
     fn get_word(&mut self, iter: &mut ListIter) -> RuntimeResult<String> {
-        if let LexerAny::LexerWord(word) = iter.next() {
+        if let LexerAny::LexerWord(word) = iter.next()? {
             Ok(word)
         } else {
-            let msg = "expected a word".to_string();
-            Err(RuntimeError::Parser(msg))
+            Err(RuntimeError::parser(ErrorKind::ExpectedWord))
+        }
+    }
+
+    /// The name of a variable being set, either bare or quoted as in
+    /// `let "size = 10`.
+    fn get_name(&mut self, iter: &mut ListIter) -> RuntimeResult<String> {
+        match iter.next()? {
+            LexerAny::LexerQuoted(word) | LexerAny::LexerWord(word) => Ok(symbol(&word)),
+            _ => Err(RuntimeError::parser(ErrorKind::ExpectedWord)),
         }
     }
 
+    /// An expression, running on through any infix operators, so that
+    /// `fd size * 2 rt 90` moves by `size * 2`.
     fn get_parse_expr(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
+        let anode = self.get_operand(iter)?;
+        self.parse_infix(iter, anode, 0)
+    }
+
+    /// A single operand, without the operators after it.
+    fn get_operand(&mut self, iter: &mut ListIter) -> RuntimeResult<ParserNode> {
         let val = self.get_expr(iter)?;
         self.parse_expr(iter, &val)
     }
 
+    /// Operators after `anode`, by precedence, down to `min_precedence`.
+    /// Outside parentheses "=" still compares, as in `assert x = 4`.
+    fn parse_infix(
+        &mut self,
+        iter: &mut ListIter,
+        mut anode: ParserNode,
+        min_precedence: u8,
+    ) -> RuntimeResult<ParserNode> {
+        while let Some(LexerAny::LexerOperator(op)) = iter.peek() {
+            let op = match *op {
+                LexerOperator::Assign => LexerOperator::Equal,
+                op => op,
+            };
+            if op.precedence() < min_precedence {
+                break;
+            }
+            iter.next()?;
+
+            let bnode = self.get_operand(iter)?;
+            let bnode = self.parse_infix(iter, bnode, op.rhs_precedence())?;
+            anode = ParserNode::BinExpr(BinExprNode::new(anode, op, bnode));
+        }
+
+        Ok(anode)
+    }
+
+    /// Claim `name` for a procedure or variable. Keyword names are reserved,
+    /// since the keyword would always win when the name is used.
     fn check_symbol(&mut self, name: &str, tag: SymbolTag) -> RuntimeResult {
+        if keywords::lookup(name, self.language).is_some() {
+            let kind = ErrorKind::ReservedWord(name.to_string());
+            return Err(RuntimeError::parser(kind));
+        }
+
         if let Some(existing_tag) = self.smap.get(name) {
             if *existing_tag == tag {
                 Ok(())
             } else {
-                let msg = format!(
-                    "symbol \"{}\" already exists with tag {:?}",
-                    name, existing_tag
-                );
-                Err(RuntimeError::Parser(msg))
+                let kind = ErrorKind::SymbolConflict {
+                    name: name.to_string(),
+                    tag: format!("{:?}", existing_tag),
+                };
+                Err(RuntimeError::parser(kind))
             }
         } else {
             self.smap.insert(name.to_string(), tag);
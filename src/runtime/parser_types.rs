@@ -13,8 +13,27 @@
 // limitations under the License.
 
 use super::lexer_types::*;
+use crate::model::prefs::TurtleShape;
+use druid::Color;
 use std::collections::HashMap;
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssertNode {
+    cond: Box<ParserNode>,
+}
+
+impl AssertNode {
+    pub fn new(cond: ParserNode) -> Self {
+        Self {
+            cond: Box::new(cond),
+        }
+    }
+
+    pub fn cond(&self) -> &ParserNode {
+        &self.cond
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct BinExprNode {
     a: Box<ParserNode>,
@@ -44,6 +63,31 @@ impl BinExprNode {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnaryOp {
+    Negate,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnaryExprNode {
+    op: UnaryOp,
+    a: Box<ParserNode>,
+}
+
+impl UnaryExprNode {
+    pub fn new(op: UnaryOp, a: ParserNode) -> Self {
+        Self { op, a: Box::new(a) }
+    }
+
+    pub fn op(&self) -> UnaryOp {
+        self.op
+    }
+
+    pub fn a(&self) -> &ParserNode {
+        &self.a
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct CallNode {
     name: String,
@@ -61,6 +105,10 @@ impl CallNode {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn args(&self) -> &LexerList {
+        &self.args
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -71,6 +119,31 @@ pub enum Direction {
     Right,
 }
 
+/// Runs a block over and over until the program is stopped, waiting for the
+/// next tick in between. Without an interval it never waits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EveryNode {
+    interval: Option<Box<ParserNode>>,
+    list: ParserNodeList,
+}
+
+impl EveryNode {
+    pub fn new(interval: Option<ParserNode>, list: ParserNodeList) -> Self {
+        Self {
+            interval: interval.map(Box::new),
+            list,
+        }
+    }
+
+    pub fn interval(&self) -> Option<&ParserNode> {
+        self.interval.as_deref()
+    }
+
+    pub fn list(&self) -> &ParserNodeList {
+        &self.list
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ForNode {
     var: String,
@@ -118,6 +191,22 @@ impl ForNode {
     }
 }
 
+/// A color from hue, saturation and value, worked out when it runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HsvNode {
+    arg: Box<ParserNode>,
+}
+
+impl HsvNode {
+    pub fn new(arg: ParserNode) -> Self {
+        Self { arg: Box::new(arg) }
+    }
+
+    pub fn arg(&self) -> &ParserNode {
+        &self.arg
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct LetNode {
     name: String,
@@ -141,6 +230,30 @@ impl LetNode {
     }
 }
 
+/// Everything in a file pulled in by `load`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadNode {
+    name: String,
+    list: ParserNodeList,
+}
+
+impl LoadNode {
+    pub fn new(name: &str, list: ParserNodeList) -> Self {
+        Self {
+            name: String::from(name),
+            list,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn list(&self) -> &ParserNodeList {
+        &self.list
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MathOp {
     Atan,
@@ -198,6 +311,36 @@ impl MoveNode {
     }
 }
 
+/// A variable the UI can set with a slider, between `min` and `max`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamNode {
+    name: String,
+    min: Box<ParserNode>,
+    max: Box<ParserNode>,
+}
+
+impl ParamNode {
+    pub fn new(name: String, min: ParserNode, max: ParserNode) -> Self {
+        Self {
+            name,
+            min: Box::new(min),
+            max: Box::new(max),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn min(&self) -> &ParserNode {
+        &self.min
+    }
+
+    pub fn max(&self) -> &ParserNode {
+        &self.max
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum PenNode {
     Down,
@@ -207,6 +350,79 @@ pub enum PenNode {
     Up,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrintOp {
+    Print,
+    Show,
+    Type,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReadOp {
+    List,
+    Word,
+}
+
+/// Writes a value to the program's output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintNode {
+    op: PrintOp,
+    arg: Box<ParserNode>,
+}
+
+impl PrintNode {
+    pub fn new(op: PrintOp, arg: ParserNode) -> Self {
+        Self {
+            op,
+            arg: Box::new(arg),
+        }
+    }
+
+    pub fn op(&self) -> PrintOp {
+        self.op
+    }
+
+    pub fn arg(&self) -> &ParserNode {
+        &self.arg
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointOp {
+    Distance,
+    Towards,
+}
+
+/// Measures from the turtle to a point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointNode {
+    op: PointOp,
+    x: Box<ParserNode>,
+    y: Box<ParserNode>,
+}
+
+impl PointNode {
+    pub fn new(op: PointOp, x: ParserNode, y: ParserNode) -> Self {
+        Self {
+            op,
+            x: Box::new(x),
+            y: Box::new(y),
+        }
+    }
+
+    pub fn op(&self) -> PointOp {
+        self.op
+    }
+
+    pub fn x(&self) -> &ParserNode {
+        &self.x
+    }
+
+    pub fn y(&self) -> &ParserNode {
+        &self.y
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct RandomNode {
     max: Box<ParserNode>,
@@ -285,6 +501,30 @@ impl SetHeadingNode {
     }
 }
 
+/// Blends the pen between two colors over the next `moves` moves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetGradientNode {
+    colors: Box<ParserNode>,
+    moves: Box<ParserNode>,
+}
+
+impl SetGradientNode {
+    pub fn new(colors: ParserNode, moves: ParserNode) -> Self {
+        Self {
+            colors: Box::new(colors),
+            moves: Box::new(moves),
+        }
+    }
+
+    pub fn colors(&self) -> &ParserNode {
+        &self.colors
+    }
+
+    pub fn moves(&self) -> &ParserNode {
+        &self.moves
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct SetPenColorNode {
     color: Box<ParserNode>,
@@ -339,31 +579,151 @@ impl SetScreenColorNode {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetSizeNode {
+    size: Box<ParserNode>,
+}
+
+impl SetSizeNode {
+    pub fn new(size: ParserNode) -> Self {
+        Self {
+            size: Box::new(size),
+        }
+    }
+
+    pub fn size(&self) -> &ParserNode {
+        &self.size
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetSpeedNode {
+    speed: Box<ParserNode>,
+}
+
+impl SetSpeedNode {
+    pub fn new(speed: ParserNode) -> Self {
+        Self {
+            speed: Box::new(speed),
+        }
+    }
+
+    pub fn speed(&self) -> &ParserNode {
+        &self.speed
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetTextColorNode {
+    color: Box<ParserNode>,
+}
+
+impl SetTextColorNode {
+    pub fn new(color: ParserNode) -> Self {
+        Self {
+            color: Box::new(color),
+        }
+    }
+
+    pub fn color(&self) -> &ParserNode {
+        &self.color
+    }
+}
+
+/// How many turned copies of each line to draw, counting the line itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymmetryNode {
+    ways: Box<ParserNode>,
+}
+
+impl SymmetryNode {
+    pub fn new(ways: ParserNode) -> Self {
+        Self {
+            ways: Box::new(ways),
+        }
+    }
+
+    pub fn ways(&self) -> &ParserNode {
+        &self.ways
+    }
+}
+
+/// Changes where the turtle's coordinates land on the canvas.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransformNode {
+    Pop,
+    Push,
+    Rotate(Box<ParserNode>),
+    Scale(Box<ParserNode>),
+    Translate(Box<ParserNode>, Box<ParserNode>),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum ParserNode {
+    Assert(AssertNode),
     BinExpr(BinExprNode),
     Call(CallNode),
     Clean,
     ClearScreen,
+    /// A palette color resolved ahead of time by the optimizer.
+    Color(Color),
+    /// A palette color by name, kept as its index.
+    ColorName(u8),
+    /// The box around everything drawn so far.
+    DrawingBounds,
+    Erase(String),
+    Every(EveryNode),
     Fill,
+    /// Where a procedure was defined. The definition itself is in the
+    /// function map, under the name or, for a redefinition, its own key.
+    Fn(String),
     For(ForNode),
+    Heading,
     Home,
+    Hsv(HsvNode),
     Let(LetNode),
+    /// Where the next statement is in the input. Only there when the program
+    /// is being stepped through.
+    Line(usize),
     List(ParserNodeList),
+    Load(LoadNode),
+    /// A picture to show behind the drawing, named like a library file.
+    LoadPicture(String),
     Math(MathNode),
     Move(MoveNode),
     Number(f64),
+    Param(ParamNode),
     Pen(PenNode),
-    Placeholder,
+    /// A random bright color.
+    PickColor,
+    Point(PointNode),
+    Pops,
+    Pots,
+    Print(PrintNode),
     Random(RandomNode),
+    /// Waits for a line from the console.
+    Read(ReadOp),
     Repcount,
     Repeat(RepeatNode),
     Rotate(RotateNode),
+    Save(String),
+    /// Whether positions stay fractional rather than snapping to whole
+    /// pixels after each move.
+    SetGeometric(bool),
+    SetGradient(SetGradientNode),
     SetHeading(SetHeadingNode),
     SetPenColor(SetPenColorNode),
     SetPosition(SetPositionNode),
     SetScreenColor(SetScreenColorNode),
+    SetShape(TurtleShape),
+    SetSize(SetSizeNode),
+    SetSpeed(SetSpeedNode),
+    SetTextColor(SetTextColorNode),
     ShowTurtle(bool),
+    Shown,
+    Symmetry(SymmetryNode),
+    Transform(TransformNode),
+    UnaryExpr(UnaryExprNode),
     Word(String),
 }
 
@@ -392,14 +752,30 @@ impl ParserFuncDef {
 
 pub type ParserFuncMap = HashMap<String, ParserFuncDef>;
 
+/// The function map key for the `count`th redefinition of a procedure. A
+/// name can't have a `#` in it, so this never clashes with one.
+pub fn redefinition(name: &str, count: usize) -> String {
+    format!("{}#{}", name, count)
+}
+
+/// The name of the procedure a function map key belongs to.
+pub fn written_name(key: &str) -> &str {
+    key.split('#').next().unwrap_or(key)
+}
+
 #[derive(Clone, Debug)]
 pub struct ParserOutput {
     pub list: ParserNodeList,
     pub fmap: ParserFuncMap,
+    pub symbols: Vec<String>,
 }
 
 impl ParserOutput {
-    pub fn new(list: ParserNodeList, fmap: ParserFuncMap) -> Self {
-        Self { list, fmap }
+    pub fn new(list: ParserNodeList, fmap: ParserFuncMap, symbols: Vec<String>) -> Self {
+        Self {
+            list,
+            fmap,
+            symbols,
+        }
     }
 }
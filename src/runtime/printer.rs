@@ -0,0 +1,404 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::parser_types::*;
+use crate::common::palette::COLOR_NAMES;
+
+const INDENT: &str = "    ";
+
+/// Turns parsed programs back into source. The output is canonical: one
+/// statement per line, blocks indented, short names, and every expression in
+/// parentheses. Parsing it again gives back the same tree.
+pub struct Printer<'a> {
+    fmap: &'a ParserFuncMap,
+    inline: bool,
+    depth: usize,
+    text: String,
+}
+
+impl<'a> Printer<'a> {
+    /// A printer for a whole program, one statement per line.
+    pub fn new(fmap: &'a ParserFuncMap) -> Self {
+        Self {
+            fmap,
+            inline: false,
+            depth: 0,
+            text: String::new(),
+        }
+    }
+
+    /// A printer that keeps everything on one line, for short snippets.
+    pub fn inline(fmap: &'a ParserFuncMap) -> Self {
+        Self {
+            inline: true,
+            ..Self::new(fmap)
+        }
+    }
+
+    pub fn program(mut self, list: &[ParserNode]) -> String {
+        self.statements(list);
+        self.text
+    }
+
+    pub fn func(mut self, name: &str) -> String {
+        self.fn_def(name);
+        self.text
+    }
+
+    fn statements(&mut self, list: &[ParserNode]) {
+        let list = list
+            .iter()
+            .filter(|node| !matches!(node, ParserNode::Line(_)));
+        for (idx, node) in list.enumerate() {
+            if self.inline {
+                if idx > 0 {
+                    self.text.push(' ');
+                }
+            } else {
+                for _ in 0..self.depth {
+                    self.text.push_str(INDENT);
+                }
+            }
+
+            self.node(node);
+
+            if !self.inline {
+                self.text.push('\n');
+            }
+        }
+    }
+
+    fn block(&mut self, list: &[ParserNode]) {
+        if list.is_empty() {
+            self.text.push_str("{ }");
+        } else if self.inline {
+            self.text.push_str("{ ");
+            self.statements(list);
+            self.text.push_str(" }");
+        } else {
+            self.text.push_str("{\n");
+            self.depth += 1;
+            self.statements(list);
+            self.depth -= 1;
+            for _ in 0..self.depth {
+                self.text.push_str(INDENT);
+            }
+            self.text.push('}');
+        }
+    }
+
+    fn fn_def(&mut self, name: &str) {
+        self.text.push_str("fn ");
+        self.text.push_str(written_name(name));
+        self.text.push(' ');
+        if let Some(func) = self.fmap.get(name) {
+            self.block(&func.list);
+        } else {
+            self.block(&[]);
+        }
+    }
+
+    /// A keyword followed by its arguments.
+    fn command(&mut self, name: &str, args: &[&ParserNode]) {
+        self.text.push_str(name);
+        for arg in args {
+            self.text.push(' ');
+            self.node(arg);
+        }
+    }
+
+    fn position(&mut self, name: &str, x: &ParserNode, y: &ParserNode) {
+        self.text.push_str(name);
+        self.text.push_str(" [");
+        self.node(x);
+        self.text.push(' ');
+        self.node(y);
+        self.text.push(']');
+    }
+
+    fn node(&mut self, node: &ParserNode) {
+        match node {
+            ParserNode::Assert(node) => self.command("assert", &[node.cond()]),
+            ParserNode::BinExpr(node) => {
+                self.text.push('(');
+                self.node(node.a());
+                self.text.push_str(&format!(" {} ", node.op()));
+                self.node(node.b());
+                self.text.push(')');
+            }
+            ParserNode::Call(node) => {
+                self.text.push_str(node.name());
+                for arg in node.args() {
+                    self.text.push_str(&format!(" {}", arg));
+                }
+            }
+            ParserNode::Clean => self.command("clean", &[]),
+            ParserNode::ClearScreen => self.command("cs", &[]),
+            ParserNode::Color(color) => {
+                let (r, g, b, a) = color.as_rgba8();
+                if a == u8::MAX {
+                    self.text.push_str(&format!("[{} {} {}]", r, g, b));
+                } else {
+                    self.text.push_str(&format!("[{} {} {} {}]", r, g, b, a));
+                }
+            }
+            ParserNode::ColorName(idx) => self
+                .text
+                .push_str(&format!("\"{}", COLOR_NAMES[*idx as usize])),
+            ParserNode::DrawingBounds => self.command("drawingbounds", &[]),
+            ParserNode::Erase(name) => self.text.push_str(&format!("erase \"{}", name)),
+            ParserNode::Every(node) => {
+                match node.interval() {
+                    Some(interval) => self.command("every", &[interval]),
+                    None => self.command("forever", &[]),
+                }
+                self.text.push(' ');
+                self.block(node.list());
+            }
+            ParserNode::Fill => self.command("fill", &[]),
+            ParserNode::Fn(name) => self.fn_def(name),
+            ParserNode::For(node) => {
+                self.text.push_str("for ");
+                self.text.push_str(node.var());
+                for arg in [node.initial(), node.limit(), node.step()].iter() {
+                    self.text.push(' ');
+                    self.node(arg);
+                }
+                self.text.push(' ');
+                self.block(node.list());
+            }
+            ParserNode::Heading => self.command("heading", &[]),
+            ParserNode::Home => self.command("home", &[]),
+            ParserNode::Hsv(node) => self.command("hsv", &[node.arg()]),
+            ParserNode::Let(node) => {
+                self.text.push_str(&format!("let {} = ", node.name()));
+                self.node(node.val());
+            }
+            ParserNode::Line(_) => {}
+            ParserNode::List(list) => {
+                self.text.push('[');
+                for (idx, item) in list.iter().enumerate() {
+                    if idx > 0 {
+                        self.text.push(' ');
+                    }
+                    self.node(item);
+                }
+                self.text.push(']');
+            }
+            ParserNode::Load(node) => self.text.push_str(&format!("load \"{}", node.name())),
+            ParserNode::LoadPicture(name) => self.text.push_str(&format!("loadpict \"{}", name)),
+            ParserNode::Math(node) => self.command(Self::math_name(node.op()), &[node.arg()]),
+            ParserNode::Move(node) => {
+                self.command(Self::direction_name(node.direction()), &[node.distance()])
+            }
+            ParserNode::Number(num) => self.text.push_str(&num.to_string()),
+            ParserNode::Param(node) => {
+                let name = format!("param :{}", node.name());
+                self.command(&name, &[node.min(), node.max()]);
+            }
+            ParserNode::Pen(node) => self.command(Self::pen_name(node), &[]),
+            ParserNode::PickColor => self.command("pickcolor", &[]),
+            ParserNode::Point(node) => {
+                let name = match node.op() {
+                    PointOp::Distance => "distance",
+                    PointOp::Towards => "towards",
+                };
+                self.position(name, node.x(), node.y());
+            }
+            ParserNode::Pops => self.command("pops", &[]),
+            ParserNode::Pots => self.command("pots", &[]),
+            ParserNode::Print(node) => {
+                let name = match node.op() {
+                    PrintOp::Print => "print",
+                    PrintOp::Show => "show",
+                    PrintOp::Type => "type",
+                };
+                self.command(name, &[node.arg()]);
+            }
+            ParserNode::Random(node) => self.command("random", &[node.max()]),
+            ParserNode::Read(ReadOp::List) => self.command("readlist", &[]),
+            ParserNode::Read(ReadOp::Word) => self.command("readword", &[]),
+            ParserNode::Repcount => self.command("repcount", &[]),
+            ParserNode::Repeat(node) => {
+                self.command("repeat", &[node.count()]);
+                self.text.push(' ');
+                self.block(node.list());
+            }
+            ParserNode::Rotate(node) => {
+                self.command(Self::direction_name(node.direction()), &[node.angle()])
+            }
+            ParserNode::Save(name) => self.text.push_str(&format!("save \"{}", name)),
+            ParserNode::SetGeometric(val) => self.text.push_str(&format!("setgeom {}", val)),
+            ParserNode::SetGradient(node) => {
+                self.command("setgradient", &[node.colors(), node.moves()])
+            }
+            ParserNode::SetHeading(node) => self.command("seth", &[node.angle()]),
+            ParserNode::SetPenColor(node) => self.command("setpc", &[node.color()]),
+            ParserNode::SetPosition(node) => match (node.x(), node.y()) {
+                (Some(x), Some(y)) => self.position("setpos", x, y),
+                (Some(x), None) => self.command("setx", &[x]),
+                (None, Some(y)) => self.command("sety", &[y]),
+                (None, None) => {}
+            },
+            ParserNode::SetScreenColor(node) => self.command("setsc", &[node.color()]),
+            ParserNode::SetShape(shape) => {
+                self.text.push_str(&format!("setshape \"{}", shape.name()))
+            }
+            ParserNode::SetSize(node) => self.command("setsize", &[node.size()]),
+            ParserNode::SetSpeed(node) => self.command("setspeed", &[node.speed()]),
+            ParserNode::SetTextColor(node) => self.command("settc", &[node.color()]),
+            ParserNode::ShowTurtle(true) => self.command("st", &[]),
+            ParserNode::ShowTurtle(false) => self.command("ht", &[]),
+            ParserNode::Shown => self.command("shownp", &[]),
+            ParserNode::Symmetry(node) => self.command("symmetry", &[node.ways()]),
+            ParserNode::Transform(node) => match node {
+                TransformNode::Pop => self.command("poptransform", &[]),
+                TransformNode::Push => self.command("pushtransform", &[]),
+                TransformNode::Rotate(angle) => self.command("rotatecs", &[angle]),
+                TransformNode::Scale(factor) => self.command("scale", &[factor]),
+                TransformNode::Translate(x, y) => self.position("translate", x, y),
+            },
+            ParserNode::UnaryExpr(node) => match node.op() {
+                // A minus right before a digit would be read back as part of a
+                // negative number, so numbers keep their parentheses.
+                UnaryOp::Negate => match node.a() {
+                    ParserNode::Number(num) => self.text.push_str(&format!("-({})", num)),
+                    a => {
+                        self.text.push('-');
+                        self.node(a);
+                    }
+                },
+            },
+            ParserNode::Word(word) => self.text.push_str(word),
+        }
+    }
+
+    fn direction_name(direction: &Direction) -> &'static str {
+        match direction {
+            Direction::Backward => "bk",
+            Direction::Forward => "fd",
+            Direction::Left => "lt",
+            Direction::Right => "rt",
+        }
+    }
+
+    fn math_name(op: MathOp) -> &'static str {
+        match op {
+            MathOp::Atan => "arctan",
+            MathOp::Cos => "cos",
+            MathOp::Ln => "ln",
+            MathOp::Log10 => "log10",
+            MathOp::Round => "round",
+            MathOp::Sin => "sin",
+            MathOp::Sqrt => "sqrt",
+        }
+    }
+
+    fn pen_name(pen: &PenNode) -> &'static str {
+        match pen {
+            PenNode::Down => "pd",
+            PenNode::Erase => "pe",
+            PenNode::Paint => "ppt",
+            PenNode::Reverse => "px",
+            PenNode::Up => "pu",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::lexer::Lexer;
+    use super::super::library::Library;
+    use super::super::parser::Parser;
+    use super::super::warning::Warnings;
+    use super::*;
+
+    fn parse(input: &str) -> ParserOutput {
+        let warnings = Warnings::default();
+        let lexer_out = Lexer::new(&warnings).go(input).unwrap();
+        Parser::new(&warnings, &Library::default())
+            .go(&lexer_out)
+            .unwrap()
+    }
+
+    fn round_trip(input: &str) -> String {
+        let first = parse(input);
+        let text = Printer::new(&first.fmap).program(&first.list);
+        let second = parse(&text);
+
+        assert_eq!(second.list, first.list, "{}", text);
+        assert_eq!(second.fmap.len(), first.fmap.len());
+        for (name, func) in first.fmap.iter() {
+            assert_eq!(second.fmap[name].list, func.list, "{}", text);
+        }
+
+        text
+    }
+
+    #[test]
+    fn it_prints_canonical_source() {
+        let text = round_trip("fn square{repeat 4{forward 10 right 90}} square");
+        assert_eq!(
+            text,
+            "fn square {\n    repeat 4 {\n        fd 10\n        rt 90\n    }\n}\nsquare\n"
+        );
+    }
+
+    #[test]
+    fn it_round_trips() {
+        round_trip(
+            "let size = 10
+            let angle = -size
+            let d = -(5)
+            let e = (-size * ((size + 1) / 2))
+            fn star {
+                for i 1 5 1 { fd (size * i) lt (144 % 360) }
+            }
+            setpc [255 128 (size ^ 2)]
+            setpc [0 0 255 128]
+            setsc 3 setsc \"orange setpc \"random
+            let c = pickcolor
+            setpchsv [(repcount * 10) 1 0.5] setschsv [0 0 1] setpc hsv c
+            setgradient [\"red [0 0 255]] 100 setgradient [c c] (size * 2)
+            setxy 10 -20 setx 5 sety size
+            seth towards [0 0]
+            let far = distance [3 4]
+            repeat far { bk 1 rt (repcount * 2) lt random 3 }
+            pu pe ppt px pd ht st setgeom true
+            let shown = shownp let box = drawingbounds
+            home clean cs fill
+            let r = round sqrt 16
+            assert (r = 4)
+            let a = arctan cos sin ln log10 100
+            repeat 2 { star }
+            every 0.5 { rt 6 } forever { fd 1 }
+            pots pops
+            print [1 [2 3]] show size type -size
+            setshape \"circle setsize (size * 2)
+            setspeed 8 symmetry (size / 2)
+            pushtransform scale 0.5 rotatecs (size * 3) translate [10 -size] poptransform
+            let line = readlist let w = rw
+            erase \"star
+            save \"shapes
+            loadpict \"photo.png",
+        );
+    }
+
+    #[test]
+    fn it_prints_snippets_on_one_line() {
+        let out = parse("pu setpos [1 -2] pd repeat 2 { fd 1 }");
+        let text = Printer::inline(&out.fmap).program(&out.list);
+        assert_eq!(text, "pu setpos [1 -2] pd repeat 2 { fd 1 }");
+    }
+}
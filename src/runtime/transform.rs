@@ -0,0 +1,106 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use druid::Point;
+use druid::Vec2;
+
+/// Where the turtle's own coordinates land on the canvas: scaled, turned
+/// clockwise, then moved. Each change applies in the current coordinates,
+/// so `translate [10 0]` after `rotatecs 90` moves down the canvas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    scale: f64,
+    /// Degrees clockwise.
+    angle: f64,
+    offset: Vec2,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            angle: 0.0,
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+impl Transform {
+    pub fn scale(&mut self, factor: f64) {
+        self.scale *= factor;
+    }
+
+    pub fn rotate(&mut self, degrees: f64) {
+        self.angle = (self.angle + degrees).rem_euclid(360.0);
+    }
+
+    pub fn translate(&mut self, x: f64, y: f64) {
+        let by = self.turn(Point::new(x, y));
+        self.offset += Vec2::new(by.x, by.y) * self.scale;
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, p: Point) -> Point {
+        let turned = self.turn(p);
+        Point::new(
+            turned.x * self.scale + self.offset.x,
+            turned.y * self.scale + self.offset.y,
+        )
+    }
+
+    /// A turtle heading as it looks on the canvas. A negative scale turns
+    /// everything half way round.
+    pub fn heading(&self, heading: f64) -> f64 {
+        let flip = if self.scale < 0.0 { 180.0 } else { 0.0 };
+        (heading + self.angle + flip).rem_euclid(360.0)
+    }
+
+    fn turn(&self, p: Point) -> Point {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        Point::new(p.x * cos + p.y * sin, p.y * cos - p.x * sin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(p: Point) -> Point {
+        Point::new((p.x * 1e9).round() / 1e9, (p.y * 1e9).round() / 1e9)
+    }
+
+    #[test]
+    fn it_applies_in_local_coordinates() {
+        let mut transform = Transform::default();
+        assert!(transform.is_identity());
+
+        transform.translate(10.0, 0.0);
+        transform.rotate(90.0);
+        transform.scale(2.0);
+        assert_eq!(
+            round(transform.apply(Point::new(0.0, 5.0))),
+            Point::new(20.0, 0.0)
+        );
+
+        transform.translate(0.0, 5.0);
+        assert_eq!(round(transform.apply(Point::ZERO)), Point::new(20.0, 0.0));
+        assert_eq!(transform.heading(0.0), 90.0);
+
+        transform.scale(-1.0);
+        assert_eq!(transform.heading(0.0), 270.0);
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::error::Span;
+use super::error::Stage;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+const CLIPPED_HINT: &str = "try smaller sizes, or a bigger drawing surface in the preferences";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WarningKind {
+    ColorComponentRounded(f64),
+    /// Where the turtle first left the canvas, and how many lines were cut
+    /// short at its edge.
+    OffCanvas {
+        x: f64,
+        y: f64,
+        clipped: u32,
+    },
+    /// A procedure defined again. Every call uses the last definition.
+    Redefined(String),
+    UnclosedBracket(char),
+    UnusedVariable(String),
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::ColorComponentRounded(comp) => {
+                write!(
+                    f,
+                    "color component {} rounded down to {}",
+                    comp,
+                    comp.floor()
+                )
+            }
+            WarningKind::OffCanvas { x, y, clipped } => {
+                write!(f, "turtle left the canvas at [{} {}]", x, y)?;
+                match clipped {
+                    0 => Ok(()),
+                    1 => write!(f, ", and 1 line went off the edge; {}", CLIPPED_HINT),
+                    n => write!(f, ", and {} lines went off the edge; {}", n, CLIPPED_HINT),
+                }
+            }
+            WarningKind::Redefined(name) => write!(f, "redefined {}", name),
+            WarningKind::UnclosedBracket(c) => write!(f, "missing \'{}\'", c),
+            WarningKind::UnusedVariable(name) => write!(f, "variable \"{}\" is never used", name),
+        }
+    }
+}
+
+/// A problem worth mentioning that doesn't stop the program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning {
+    pub stage: Stage,
+    pub kind: WarningKind,
+    pub span: Option<Span>,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(
+                f,
+                "warning: {}: {}: {}",
+                self.stage,
+                span.start + 1,
+                self.kind
+            ),
+            None => write!(f, "warning: {}: {}", self.stage, self.kind),
+        }
+    }
+}
+
+/// Side channel shared by every stage of a run. Warnings collected before an
+/// error are kept, so they can be shown alongside it.
+#[derive(Clone, Debug, Default)]
+pub struct Warnings {
+    list: Arc<Mutex<Vec<Warning>>>,
+}
+
+impl Warnings {
+    pub fn push(&self, stage: Stage, kind: WarningKind, span: Option<Span>) {
+        let warning = Warning { stage, kind, span };
+        self.list.lock().unwrap().push(warning);
+    }
+
+    pub fn take(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.list.lock().unwrap())
+    }
+}
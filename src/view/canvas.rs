@@ -12,73 +12,310 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::constants::*;
+use super::overlay;
+use super::sprite::Sprite;
+use super::theme;
+use crate::common::commands;
 use crate::common::constants::*;
+use crate::common::i18n;
+use crate::common::profile;
+use crate::common::profile::Phase;
 use crate::graphics;
 use crate::model::app::AppState;
+use crate::model::picture::Picture;
+use crate::model::pixbuf::PixBuf;
 use crate::model::render::*;
-use druid::kurbo::Circle;
+use crate::model::speed::Pace;
+use druid::kurbo::Affine;
 use druid::piet::ImageFormat;
 use druid::piet::InterpolationMode;
+use druid::piet::PietImage;
+use druid::piet::Text;
+use druid::piet::TextLayout;
+use druid::piet::TextLayoutBuilder;
 use druid::widget::prelude::*;
-use druid::Color;
+use druid::Data;
+use druid::FontFamily;
+use druid::KbKey;
 use druid::Point;
 use druid::Rect;
 use druid::TimerToken;
+use druid::Vec2;
 use druid::Widget;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Duration;
+use std::time::Instant;
 
 pub struct Canvas {
-    render_rx: RenderRx,
+    click_pos: Option<Point>,
+    drag_pos: Option<Point>,
+    /// The drawing as the paint context has it, and which frame that was.
+    image: Option<(u64, PietImage)>,
+    /// The background picture as the paint context has it, and which picture
+    /// that was.
+    backdrop: Option<(Arc<Picture>, PietImage)>,
+    last_tick: Option<Instant>,
+    pace: Pace,
+    /// Fit the whole drawing to the canvas, and leave the mouse and keys
+    /// alone.
+    presenting: bool,
+    /// Shared with the canvas for presentation mode, since either one can be
+    /// showing when commands come in.
+    render_rx: Rc<RefCell<RenderRx>>,
+    sprite: Sprite,
     timer_id: TimerToken,
 }
 
 impl Canvas {
-    pub fn new(render_rx: RenderRx) -> Self {
+    pub fn new(render_rx: Rc<RefCell<RenderRx>>) -> Self {
         Self {
+            click_pos: None,
+            drag_pos: None,
+            image: None,
+            backdrop: None,
+            last_tick: None,
+            pace: Pace::default(),
+            presenting: false,
             render_rx,
+            sprite: Sprite::new(),
             timer_id: TimerToken::INVALID,
         }
     }
 
+    /// A canvas that shows the drawing as big as it can, for presentation
+    /// mode.
+    pub fn presenting(mut self) -> Self {
+        self.presenting = true;
+        self
+    }
+
+    fn wake(&mut self, ctx: &mut EventCtx) {
+        if self.timer_id == TimerToken::INVALID {
+            self.last_tick = None;
+            self.timer_id = ctx.request_timer(Duration::from_millis(30));
+        }
+    }
+
     pub fn render_one(&mut self, data: &mut AppState, cmd: RenderCommand) {
-        data.command_count += 1;
+        data.log.push(cmd.clone());
+        if data.command_log_paused {
+            data.recent.skip();
+        } else {
+            data.recent.push(cmd.clone());
+        }
 
-        match cmd {
-            RenderCommand::Fill(color) => {
-                graphics::flood_fill(&mut data.pixels, &data.pos, &color);
-            }
+        data.track(&cmd);
+        if let RenderCommand::Speed(speed) = cmd {
+            data.speed.set(speed);
+        }
 
-            RenderCommand::MoveTo(move_to) => {
-                let p = data.pos;
-                let q = move_to.pos;
-                if is_pen_down(move_to.pen_flags) {
-                    let color = if is_pen_erase(move_to.pen_flags) {
-                        &Color::BLACK
-                    } else {
-                        &move_to.color
-                    };
-                    graphics::line(&mut data.pixels, &p, &q, color);
-                }
-                data.pos = q;
-            }
+        let rendered = profile::time(Phase::Rasterize, || {
+            graphics::render(&mut data.layers.drawing, &mut data.pos, &cmd)
+        });
+        data.stats.add(&cmd, rendered);
 
-            RenderCommand::ShowTurtle(val) => {
-                data.show_turtle = val;
-            }
+        // Slow enough to follow along, a blink for each command helps tie
+        // what the turtle does to the code doing it.
+        if data.prefs.command_flash && data.speed.get() <= FLASH_MAX_SPEED {
+            data.flashes += 1;
+        }
+    }
+
+    /// Reallocate the pixel buffer at the window's scale factor, so that one
+    /// buffer pixel maps onto one device pixel. A drawing that would come out
+    /// bigger than the largest surface stays at one pixel each, to keep the
+    /// buffer to a sensible size.
+    fn match_window_scale(ctx: &mut EventCtx, data: &mut AppState) {
+        let size = data.layers.drawing.size();
+        let scale = ctx
+            .window()
+            .get_scale()
+            .map(|scale| scale.x().round() as u32)
+            .filter(|scale| {
+                let scaled = size * *scale as f64;
+                scaled.width <= MAX_DIMS.width && scaled.height <= MAX_DIMS.height
+            })
+            .unwrap_or(1);
+
+        if scale != data.layers.drawing.scale() {
+            data.layers.drawing = PixBuf::new(size.width as u32, size.height as u32, scale);
+        }
+    }
+
+    /// Where the drawing sits before any zoom or pan, on a canvas of `canvas`
+    /// size. It starts out centered, whichever is bigger.
+    fn surface_offset(data: &AppState, canvas: Size) -> Vec2 {
+        let surface = data.layers.drawing.size();
+        Vec2::new(
+            (canvas.width - surface.width) / 2.0,
+            (canvas.height - surface.height) / 2.0,
+        )
+    }
+
+    /// Map a widget point onto turtle coordinates, where the origin is the
+    /// center of the drawing and y goes up.
+    fn to_turtle(data: &AppState, canvas: Size, p: Point) -> Point {
+        let buffer = data.viewport.to_buffer(p) - Self::surface_offset(data, canvas);
+        let origin = data.layers.drawing.origin();
+        Point::new(buffer.x - origin.x, origin.y - buffer.y)
+    }
+
+    fn nudge(key: &KbKey, large: bool) -> Option<Vec2> {
+        let step = if large { NUDGE_STEP_LARGE } else { NUDGE_STEP };
+
+        match key {
+            KbKey::ArrowUp => Some(Vec2::new(0.0, step)),
+            KbKey::ArrowDown => Some(Vec2::new(0.0, -step)),
+            KbKey::ArrowLeft => Some(Vec2::new(-step, 0.0)),
+            KbKey::ArrowRight => Some(Vec2::new(step, 0.0)),
+            _ => None,
         }
     }
 
+    /// What the canvas shows, in words: "drawing area, turtle at 10,-20
+    /// heading 90".
+    pub fn describe(data: &AppState) -> String {
+        i18n::text_with(
+            data.prefs.ui_language,
+            "canvas-description",
+            &[
+                ("x", &data.pos.x.round()),
+                ("y", &data.pos.y.round()),
+                ("heading", &data.heading.round()),
+            ],
+        )
+    }
+
+    /// Where the drawing goes on a canvas of `canvas` size, and how far it's
+    /// scaled up: as panned and zoomed, or when presenting, as big as fits in
+    /// the middle.
+    fn placement(&self, data: &AppState, canvas: Size) -> (Affine, f64) {
+        if !self.presenting {
+            let offset = Self::surface_offset(data, canvas);
+            return (
+                data.viewport.affine() * Affine::translate(offset),
+                data.viewport.zoom,
+            );
+        }
+
+        let surface = data.layers.drawing.size();
+        let zoom = (canvas.width / surface.width).min(canvas.height / surface.height);
+        let offset = (canvas.to_vec2() - surface.to_vec2() * zoom) / 2.0;
+        (Affine::translate(offset) * Affine::scale(zoom), zoom)
+    }
+
+    /// Fit the picture inside the canvas, centered and keeping its shape, so
+    /// the drawing lands on top of it. Its image is only made when the
+    /// picture changes; the screen color and the canvas size don't go into
+    /// it, so repaints for those reuse it.
+    fn paint_background(&mut self, ctx: &mut PaintCtx, picture: &Arc<Picture>, canvas: Rect) {
+        let size = picture.size();
+        let scale = (canvas.width() / size.width).min(canvas.height() / size.height);
+        let rect = Rect::from_center_size(canvas.center(), size * scale);
+
+        let stale = !matches!(&self.backdrop, Some((shown, _)) if Arc::ptr_eq(shown, picture));
+        if stale {
+            let image = ctx
+                .make_image(
+                    picture.width() as usize,
+                    picture.height() as usize,
+                    picture.rgba(),
+                    ImageFormat::RgbaSeparate,
+                )
+                .unwrap();
+            self.backdrop = Some((picture.clone(), image));
+        }
+        let (_, image) = self.backdrop.as_ref().unwrap();
+        ctx.draw_image(image, rect, InterpolationMode::Bilinear);
+    }
+
+    fn paint_drawing(&mut self, ctx: &mut PaintCtx, data: &AppState) {
+        let (placement, zoom) = self.placement(data, ctx.size());
+        ctx.transform(placement);
+
+        let size = data.layers.drawing.size();
+        let rect = Rect::from_origin_size((0.0, 0.0), size);
+
+        match &data.layers.background.picture {
+            Some(picture) => self.paint_background(ctx, picture, rect),
+            None => self.backdrop = None,
+        }
+
+        // Copying the buffer into an image costs more than drawing it at
+        // full speed, so that only happens when there's a new frame. Repaints
+        // for the turtle, the overlays or zooming reuse the last one. A
+        // profiling build shows how long the copies took, and how many there
+        // were, next to the number of commands drawn.
+        let drawing = &data.layers.drawing;
+        let stale = !matches!(&self.image, Some((version, _)) if *version == drawing.version());
+        if stale {
+            let image = profile::time(Phase::Upload, || {
+                ctx.make_image(
+                    drawing.width() as usize,
+                    drawing.height() as usize,
+                    drawing.bytes(),
+                    ImageFormat::RgbaSeparate,
+                )
+            })
+            .unwrap();
+            self.image = Some((drawing.version(), image));
+        }
+        let (_, image) = self.image.as_ref().unwrap();
+
+        // Magnified pixels stay square for the pixel-art look; smoothing only
+        // helps when shrinking.
+        let interp = if data.prefs.antialias && zoom < 1.0 {
+            InterpolationMode::Bilinear
+        } else {
+            InterpolationMode::NearestNeighbor
+        };
+        ctx.draw_image(image, rect, interp);
+
+        if data.layers.overlay.grid {
+            overlay::paint_grid(ctx, size, data.layers.drawing.origin(), zoom);
+        }
+
+        if data.layers.overlay.trace {
+            let segments = data.log.segments();
+            overlay::paint_trace(ctx, &segments, data.layers.drawing.origin(), zoom);
+        }
+
+        if data.layers.overlay.turtle {
+            let center = data.layers.drawing.origin();
+            let pos = Point::new(data.pos.x + center.x, center.y - data.pos.y);
+            self.sprite.paint(ctx, data, pos);
+        }
+    }
+
+    /// Draw however many commands are due since the last tick.
     pub fn render(&mut self, data: &mut AppState) -> bool {
+        let now = Instant::now();
+        let elapsed = self.last_tick.map_or(RENDER_DELAY, |last| now - last);
+        self.last_tick = Some(now);
+
+        // A path counts a command for each of its lines, the same as the
+        // interpreter does when it paces itself.
         let mut dirty = false;
-        for _ in 0..MAX_SPEED {
-            if let Ok(Some(cmd)) = self.render_rx.try_next() {
+        let mut due = self.pace.due(elapsed, data.speed.per_second());
+        while due > 0 {
+            let next = self.render_rx.borrow_mut().try_next();
+            if let Ok(Some(cmd)) = next {
+                due = due.saturating_sub(cmd.moves().len().max(1));
                 self.render_one(data, cmd);
                 dirty = true;
             } else {
+                self.pace.rest();
                 break;
             }
         }
 
+        if dirty {
+            data.layers.drawing.present();
+        }
+
         dirty
     }
 }
@@ -86,17 +323,114 @@ impl Canvas {
 impl Widget<AppState> for Canvas {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, _env: &Env) {
         match event {
+            // Keep checking while a program runs or commands keep coming,
+            // and stop once it's all quiet, to leave the CPU alone. Only the
+            // canvas on show draws, so the other one doesn't take commands off
+            // the queue as well.
             Event::Timer(timer_id) => {
-                if self.timer_id == *timer_id {
-                    if self.render(data) {
+                if self.timer_id == *timer_id && self.presenting != data.presenting {
+                    self.timer_id = TimerToken::INVALID;
+                } else if self.timer_id == *timer_id {
+                    // Escape only reaches the window through whatever has the
+                    // focus, and nothing else is on show while presenting.
+                    if self.presenting && !ctx.has_focus() {
+                        ctx.request_focus();
+                    }
+
+                    let dirty = self.render(data);
+                    if dirty {
                         ctx.request_paint();
                     }
-                    self.timer_id = ctx.request_timer(Duration::from_millis(30));
+
+                    self.timer_id = if dirty || data.session.is_running() {
+                        ctx.request_timer(Duration::from_millis(30))
+                    } else {
+                        TimerToken::INVALID
+                    };
                 }
             }
 
+            Event::Command(cmd) if cmd.is(commands::WAKE) => self.wake(ctx),
+
+            Event::Command(cmd) if cmd.is(commands::CANVAS_DISCARD) => {
+                let mut render_rx = self.render_rx.borrow_mut();
+                while let Ok(Some(_)) = render_rx.try_next() {}
+            }
+
             Event::WindowConnected => {
-                self.timer_id = ctx.request_timer(Duration::from_millis(30));
+                Self::match_window_scale(ctx, data);
+                self.wake(ctx);
+            }
+
+            // Presenting is only for looking.
+            Event::Wheel(_)
+            | Event::MouseDown(_)
+            | Event::MouseMove(_)
+            | Event::MouseUp(_)
+            | Event::KeyDown(_)
+                if self.presenting => {}
+
+            Event::Wheel(mouse) if mouse.mods.ctrl() || mouse.mods.meta() => {
+                let factor = (-mouse.wheel_delta.y * ZOOM_SENSITIVITY).exp();
+                data.viewport.zoom_at(mouse.pos, factor);
+                ctx.set_handled();
+            }
+
+            Event::MouseDown(mouse) => {
+                self.click_pos = Some(mouse.pos);
+                self.drag_pos = Some(mouse.pos);
+                ctx.set_active(true);
+                ctx.request_focus();
+            }
+
+            // While recording, dragging draws instead of panning.
+            Event::MouseMove(mouse) => {
+                if let (Some(drag_pos), false) = (self.drag_pos, data.recording) {
+                    data.viewport.pan_by(mouse.pos - drag_pos);
+                    self.drag_pos = Some(mouse.pos);
+                }
+
+                if let Some(click_pos) = self.click_pos {
+                    if (mouse.pos - click_pos).hypot() > CLICK_SLOP {
+                        self.click_pos = None;
+                    }
+                }
+            }
+
+            // A click without a drag sends the turtle to that spot.
+            Event::MouseUp(mouse) => {
+                if let Some(click_pos) = self.click_pos.take() {
+                    if !data.recording {
+                        data.viewport.pan_by(click_pos - mouse.pos);
+                    }
+                    let p = Self::to_turtle(data, ctx.size(), click_pos);
+                    ctx.submit_command(commands::TURTLE_MOVE.with((p, false)));
+                } else if let (Some(drag_pos), true) = (self.drag_pos, data.recording) {
+                    let from = Self::to_turtle(data, ctx.size(), drag_pos);
+                    let to = Self::to_turtle(data, ctx.size(), mouse.pos);
+                    ctx.submit_command(commands::TURTLE_MOVE.with((from, false)));
+                    ctx.submit_command(commands::TURTLE_MOVE.with((to, true)));
+                }
+
+                self.drag_pos = None;
+                ctx.set_active(false);
+            }
+
+            Event::KeyDown(key) if key.key == KbKey::Tab => {
+                if key.mods.shift() {
+                    ctx.focus_prev();
+                } else {
+                    ctx.focus_next();
+                }
+                ctx.set_handled();
+            }
+
+            Event::KeyDown(key) => {
+                if let Some(delta) = Self::nudge(&key.key, key.mods.shift()) {
+                    let p = data.pos + delta;
+                    ctx.submit_command(commands::TURTLE_MOVE.with((p, false)));
+                    ctx.set_handled();
+                }
             }
 
             _ => {}
@@ -105,42 +439,91 @@ impl Widget<AppState> for Canvas {
 
     fn lifecycle(
         &mut self,
-        _ctx: &mut LifeCycleCtx,
-        _event: &LifeCycle,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
         _data: &AppState,
         _env: &Env,
     ) {
+        match event {
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(_) => ctx.request_paint(),
+            _ => {}
+        }
     }
 
-    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &AppState, _data: &AppState, _env: &Env) {
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, _env: &Env) {
+        if !old_data.viewport.same(&data.viewport)
+            || !old_data.layers.same(&data.layers)
+            || !old_data.prefs.same(&data.prefs)
+            || !old_data.pos.same(&data.pos)
+            || old_data.heading != data.heading
+            || old_data.turtle_shape != data.turtle_shape
+            || old_data.turtle_size != data.turtle_size
+        {
+            ctx.request_paint();
+        }
+
+        if old_data.presenting != data.presenting && self.timer_id == TimerToken::INVALID {
+            self.last_tick = None;
+            self.timer_id = ctx.request_timer(Duration::from_millis(30));
+        }
     }
 
     fn layout(
         &mut self,
         _layout_ctx: &mut LayoutCtx,
         bc: &BoxConstraints,
-        _data: &AppState,
+        data: &AppState,
         _env: &Env,
     ) -> Size {
-        bc.constrain(DIMS)
+        if self.presenting && bc.is_width_bounded() && bc.is_height_bounded() {
+            return bc.max();
+        }
+
+        // Take whatever width the split gives, showing more of a bigger
+        // drawing, or more of the background around it.
+        let canvas = data.prefs.canvas_size();
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            canvas.width
+        };
+        bc.constrain(Size::new(width, canvas.height))
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, _env: &Env) {
-        let image = ctx
-            .make_image(
-                DIMS.width as usize,
-                DIMS.height as usize,
-                &data.pixels.bytes(),
-                ImageFormat::RgbaSeparate,
-            )
-            .unwrap();
-        let rect = Rect::from_origin_size((0.0, 0.0), DIMS);
-        ctx.draw_image(&image, rect, InterpolationMode::Bilinear);
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
+        let bounds = ctx.size().to_rect();
+        ctx.clip(bounds);
+        ctx.fill(bounds, &data.layers.background.color);
+        ctx.with_save(|ctx| self.paint_drawing(ctx, data));
 
-        if data.show_turtle {
-            let origin = Point::new(data.pos.x + ORIGIN.x, (-data.pos.y) + ORIGIN.y);
-            let c = Circle::new(origin, 1.0);
-            ctx.stroke(c, &Color::WHITE, 2.0);
+        if ctx.has_focus() && !self.presenting {
+            paint_focus(ctx, &Self::describe(data), env);
         }
     }
 }
+
+/// With the keyboard on the canvas, ring it and spell out where the turtle
+/// is, since there's nothing else to say so without looking at the drawing.
+fn paint_focus(ctx: &mut PaintCtx, description: &str, env: &Env) {
+    let bounds = ctx.size().to_rect();
+    ctx.stroke(
+        bounds.inset(-1.0),
+        &env.get(druid::theme::SELECTED_TEXT_BACKGROUND_COLOR),
+        2.0,
+    );
+
+    let label = ctx
+        .text()
+        .new_text_layout(description.to_string())
+        .font(FontFamily::MONOSPACE, FONT_SIZE)
+        .text_color(env.get(theme::PANEL_TEXT))
+        .build();
+    if let Ok(label) = label {
+        let size = label.size();
+        let origin = Point::new(4.0, bounds.height() - size.height - 8.0);
+        let rect = Rect::from_origin_size(origin, size + Size::new(8.0, 4.0));
+        ctx.fill(rect, &env.get(theme::PANEL_BACKGROUND));
+        ctx.draw_text(&label, origin + (4.0, 2.0));
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::constants::*;
+use super::theme;
+use crate::common::i18n;
+use crate::model::app::AppState;
+use crate::model::log::CommandRing;
+use druid::widget::Checkbox;
+use druid::widget::CrossAxisAlignment;
+use druid::widget::Flex;
+use druid::widget::Label;
+use druid::widget::LineBreaking;
+use druid::widget::Scroll;
+use druid::widget::Widget;
+use druid::Env;
+use druid::FontDescriptor;
+use druid::FontFamily;
+use druid::WidgetExt;
+
+/// Lists the render commands as the canvas consumes them. Pausing stops new
+/// commands coming in, so the list holds still while you read it.
+pub fn build() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(build_header())
+        .with_default_spacer()
+        .with_flex_child(build_list(), 1.0)
+        .padding(8.0)
+        .background(theme::PANEL_BACKGROUND)
+}
+
+/// A label in the interface language.
+fn text(key: &'static str) -> impl Fn(&AppState, &Env) -> String {
+    move |data: &AppState, _env: &Env| i18n::text(data.prefs.ui_language, key)
+}
+
+fn build_header() -> impl Widget<AppState> {
+    Flex::row()
+        .with_child(Label::new(text("log-title")).with_text_color(theme::PANEL_TEXT))
+        .with_flex_spacer(1.0)
+        .with_child(Checkbox::new("").lens(AppState::command_log_paused))
+        .with_child(Label::new(text("log-pause")).with_text_color(theme::PANEL_TEXT))
+}
+
+fn build_list() -> impl Widget<AppState> {
+    let label = Label::new(|recent: &CommandRing, _: &_| recent.text())
+        .with_font(FontDescriptor::new(FontFamily::MONOSPACE).with_size(FONT_SIZE))
+        .with_text_color(theme::PANEL_TEXT)
+        .with_line_break_mode(LineBreaking::Clip)
+        .lens(AppState::recent);
+
+    Scroll::new(label).vertical().expand_width()
+}
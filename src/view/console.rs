@@ -12,82 +12,338 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use druid::kurbo::Affine;
+use druid::kurbo::Line;
+use druid::kurbo::Vec2;
+use druid::piet::PietTextLayout;
+use druid::piet::Text;
+use druid::piet::TextAttribute;
+use druid::piet::TextLayout;
+use druid::piet::TextLayoutBuilder;
 use druid::widget::prelude::*;
-use druid::widget::Label;
-use druid::widget::LineBreaking;
+use druid::widget::Controller;
+use druid::Application;
 use druid::Color;
-use druid::TextAlignment;
+use druid::FontFamily;
+use druid::KbKey;
+use druid::Key;
+use druid::Point;
 use druid::TimerToken;
 use druid::Widget;
+use std::ops::Range;
 use std::time::Duration;
 
 use super::constants::*;
+use super::theme;
+use crate::common::commands;
+use crate::common::i18n;
 use crate::model::app::AppState;
+use crate::model::console::Severity;
+use crate::model::session::SessionStatus;
 
-fn build_console_label() -> Label<AppState> {
-    Label::new("")
-        .with_font(druid::FontDescriptor::new(druid::FontFamily::MONOSPACE).with_size(FONT_SIZE))
-        .with_text_color(Color::WHITE)
-        .with_text_alignment(TextAlignment::Start)
-        .with_line_break_mode(LineBreaking::WordWrap)
+fn color(severity: Severity) -> Key<Color> {
+    match severity {
+        Severity::Info => theme::PANEL_TEXT,
+        Severity::Warning => theme::PANEL_WARNING,
+        Severity::Error => theme::PANEL_ERROR,
+        Severity::Run => theme::PANEL_DIVIDER,
+    }
+}
+
+/// A place in the log: an entry number, and a byte offset into its row.
+type Spot = (u64, usize);
+
+struct Row {
+    layout: PietTextLayout,
+    severity: Severity,
+    source: Option<(usize, usize)>,
+    /// Length of the text in bytes.
+    len: usize,
 }
 
+/// Everything written to the console, oldest first, each entry in the color
+/// of its severity. What the program is up to now, like waiting for input,
+/// shows underneath. It keeps scrolled to the bottom unless scrolled back.
+/// Dragging selects part of the log, and copying while it has the focus
+/// takes the selection, or the whole log if nothing is selected. Clicking an
+/// error shows where it is in the input. Each run starts under a header
+/// ruled off from the one before. Programs can color what they print with
+/// settextcolor.
 pub struct Console {
-    label: Label<AppState>,
-    output: String,
+    /// The log version and status last laid out.
+    version: u64,
+    status: String,
+    /// A row for each entry, then one for the status if there is one.
+    rows: Vec<Row>,
+    /// The number of the entry in the first row, and how many rows are
+    /// entries.
+    first: u64,
+    entries: usize,
+    /// Where a drag started, and where it's got to. The selection stays put
+    /// as new entries come in, and goes as its entries drop off.
+    selection: Option<(Spot, Spot)>,
+    content_height: f64,
+    /// How far down the content the view starts.
+    offset: f64,
+    /// Follow new entries, unless scrolled back to read older ones.
+    pinned: bool,
+    /// The row of a clickable error under the mouse, underlined.
+    hovered: Option<usize>,
     timer_id: TimerToken,
 }
 
 impl Console {
     pub fn new() -> Self {
         Self {
-            label: build_console_label(),
-            output: "".to_string(),
+            version: 0,
+            status: String::new(),
+            rows: Vec::new(),
+            first: 0,
+            entries: 0,
+            selection: None,
+            content_height: 0.0,
+            offset: 0.0,
+            pinned: true,
+            hovered: None,
             timer_id: TimerToken::INVALID,
         }
     }
 
+    fn wake(&mut self, ctx: &mut EventCtx) {
+        if self.timer_id == TimerToken::INVALID {
+            self.timer_id = ctx.request_timer(Duration::from_millis(100));
+        }
+    }
+
     fn update_output(&mut self, data: &mut AppState) -> bool {
-        let output = { data.output.lock().unwrap().clone() };
+        // A crashed interpreter leaves no output of its own.
+        if let Some(err) = data.session.poll() {
+            data.console.lock().unwrap().push(Severity::Error, &err);
+        }
+
+        // While the program waits for a line, show what it printed so far.
+        let mut status = data.session.prompt().unwrap_or_default();
+
+        // Sum up the drawing once the program is done, or say that it's held.
+        let state = match data.session.status() {
+            SessionStatus::Idle if data.stats.commands > 0 => i18n::text_with(
+                data.prefs.ui_language,
+                "console-stats",
+                &[
+                    ("commands", &data.stats.commands),
+                    ("pixels", &data.stats.pixels),
+                    ("clipped", &data.stats.clipped),
+                ],
+            ),
+            SessionStatus::Paused => i18n::text(data.prefs.ui_language, "console-paused"),
+            SessionStatus::Reading => i18n::text(data.prefs.ui_language, "console-reading"),
+            _ => String::new(),
+        };
+        if !state.is_empty() {
+            if !status.is_empty() {
+                status.push('\n');
+            }
+            status.push_str(&state);
+        }
 
-        if output == self.output {
+        let version = data.console.lock().unwrap().version();
+        if version == self.version && status == self.status {
             return false;
         }
 
-        self.output = output;
-        self.label.set_text(self.output.clone());
+        self.version = version;
+        self.status = status;
         true
     }
+
+    fn max_offset(&self, height: f64) -> f64 {
+        (self.content_height - height).max(0.0)
+    }
+
+    /// The row at `y` in the view, if it's about somewhere in the input.
+    fn source_at(&self, y: f64) -> Option<usize> {
+        let mut top = -self.offset;
+        for (idx, row) in self.rows.iter().enumerate() {
+            let bottom = top + row.layout.size().height;
+            if (top..bottom).contains(&y) {
+                return row.source.map(|_| idx);
+            }
+            top = bottom;
+        }
+        None
+    }
+
+    /// The place in the log nearest `pos` in the view. Anywhere below the
+    /// last entry is the end of it.
+    fn spot_at(&self, pos: Point) -> Option<Spot> {
+        let mut top = -self.offset;
+        for (idx, row) in self.rows[..self.entries].iter().enumerate() {
+            let bottom = top + row.layout.size().height;
+            if pos.y < bottom {
+                let hit = row.layout.hit_test_point(Point::new(pos.x, pos.y - top));
+                return Some((self.first + idx as u64, hit.idx));
+            }
+            top = bottom;
+        }
+
+        let last = self.entries.checked_sub(1)?;
+        Some((self.first + last as u64, self.rows[last].len))
+    }
+
+    /// The part of the entry row `idx` that's selected, if any.
+    fn selected_in(&self, idx: usize) -> Option<Range<usize>> {
+        let (from, to) = self.selection?;
+        let (start, end) = if from <= to { (from, to) } else { (to, from) };
+
+        let num = self.first + idx as u64;
+        if idx >= self.entries || !(start.0..=end.0).contains(&num) {
+            return None;
+        }
+
+        let len = self.rows[idx].len;
+        let left = if num == start.0 { start.1.min(len) } else { 0 };
+        let right = if num == end.0 { end.1.min(len) } else { len };
+        (left < right).then(|| left..right)
+    }
+
+    fn scroll_to(&mut self, ctx: &mut EventCtx, offset: f64) {
+        let max = self.max_offset(ctx.size().height);
+        self.offset = offset.max(0.0).min(max);
+        self.pinned = self.offset >= max;
+        ctx.request_paint();
+    }
 }
 
 impl Widget<AppState> for Console {
-    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, env: &Env) {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, _env: &Env) {
         match event {
+            // Like the canvas, only keep looking while there's something to
+            // see.
             Event::Timer(timer_id) => {
                 if self.timer_id == *timer_id {
-                    if self.update_output(data) {
-                        ctx.request_update();
+                    let changed = self.update_output(data);
+                    if changed {
+                        ctx.request_layout();
+                    }
+
+                    let reading = data.session.status() == SessionStatus::Reading;
+                    if reading != data.reading {
+                        data.reading = reading;
+                        if reading {
+                            ctx.submit_command(commands::INTERPRETER_READ);
+                        }
+                    }
+
+                    self.timer_id = if changed || data.session.is_running() {
+                        ctx.request_timer(Duration::from_millis(100))
+                    } else {
+                        TimerToken::INVALID
+                    };
+                }
+            }
+
+            Event::Command(cmd) if cmd.is(commands::WAKE) => self.wake(ctx),
+
+            Event::Command(cmd) if cmd.is(druid::commands::COPY) && ctx.has_focus() => {
+                let log = data.console.lock().unwrap();
+                let text = match self.selection {
+                    Some((from, to)) if from != to => log.text_between(from, to),
+                    _ => log.text(),
+                };
+                Application::global().clipboard().put_string(text);
+                ctx.set_handled();
+            }
+
+            Event::WindowConnected => self.wake(ctx),
+
+            Event::MouseDown(mouse) => {
+                ctx.request_focus();
+                ctx.set_active(true);
+                self.selection = self.spot_at(mouse.pos).map(|spot| (spot, spot));
+                ctx.request_paint();
+            }
+
+            Event::MouseMove(mouse) => {
+                if ctx.is_active() {
+                    if let (Some((from, _)), Some(to)) = (self.selection, self.spot_at(mouse.pos)) {
+                        self.selection = Some((from, to));
+                        ctx.request_paint();
+                    }
+                }
+
+                let hovered = self.source_at(mouse.pos.y);
+                if hovered != self.hovered {
+                    self.hovered = hovered;
+                    ctx.request_paint();
+                }
+            }
+
+            // A click that didn't drag out a selection shows where an error
+            // is.
+            Event::MouseUp(mouse) if ctx.is_active() => {
+                ctx.set_active(false);
+                if matches!(self.selection, Some((from, to)) if from == to) {
+                    self.selection = None;
+                    let source = self
+                        .source_at(mouse.pos.y)
+                        .and_then(|idx| self.rows[idx].source);
+                    if let Some(source) = source {
+                        ctx.submit_command(commands::SHOW_SOURCE.with(source));
                     }
-                    self.timer_id = ctx.request_timer(Duration::from_millis(100));
                 }
             }
 
-            Event::WindowConnected => {
-                self.timer_id = ctx.request_timer(Duration::from_millis(100));
+            Event::Wheel(mouse) => {
+                self.scroll_to(ctx, self.offset + mouse.wheel_delta.y);
+                ctx.set_handled();
+            }
+
+            // Tab moves on, so the keyboard can get from the console back to
+            // the input.
+            Event::KeyDown(key) if key.key == KbKey::Tab => {
+                if key.mods.shift() {
+                    ctx.focus_prev();
+                } else {
+                    ctx.focus_next();
+                }
+                ctx.set_handled();
+            }
+
+            Event::KeyDown(key) => {
+                let page = ctx.size().height - FONT_SIZE;
+                let offset = match key.key {
+                    KbKey::PageUp => self.offset - page,
+                    KbKey::PageDown => self.offset + page,
+                    KbKey::Home => 0.0,
+                    KbKey::End => self.content_height,
+                    _ => return,
+                };
+                self.scroll_to(ctx, offset);
+                ctx.set_handled();
             }
 
             _ => {}
         }
-
-        self.label.event(ctx, event, data, env);
     }
 
-    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &AppState, env: &Env) {
-        self.label.lifecycle(ctx, event, data, env);
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        _data: &AppState,
+        _env: &Env,
+    ) {
+        match event {
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(_) => ctx.request_paint(),
+            _ => {}
+        }
     }
 
-    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, env: &Env) {
-        self.label.update(ctx, old_data, data, env);
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, _env: &Env) {
+        if !old_data.prefs.same(&data.prefs) {
+            ctx.request_layout();
+        }
     }
 
     fn layout(
@@ -97,11 +353,130 @@ impl Widget<AppState> for Console {
         data: &AppState,
         env: &Env,
     ) -> Size {
-        self.label.layout(ctx, bc, data, env);
-        bc.max()
+        let size = bc.max();
+
+        let log = data.console.lock().unwrap();
+        self.first = log.first();
+        let mut rows: Vec<_> = log
+            .entries()
+            .map(|entry| {
+                // The time goes in front, so colors move along by as much.
+                let text = entry.to_string();
+                let shift = text.len() - entry.text.len();
+                let colors: Vec<_> = entry
+                    .colors
+                    .iter()
+                    .map(|(range, color)| (range.start + shift..range.end + shift, color.clone()))
+                    .collect();
+                (text, entry.severity, entry.source, colors)
+            })
+            .collect();
+        drop(log);
+        self.entries = rows.len();
+        if !self.status.is_empty() {
+            rows.push((self.status.clone(), Severity::Info, None, Vec::new()));
+        }
+
+        self.rows = rows
+            .into_iter()
+            .filter_map(|(text, severity, source, colors)| {
+                let len = text.len();
+                let mut builder = ctx
+                    .text()
+                    .new_text_layout(text)
+                    .font(FontFamily::MONOSPACE, FONT_SIZE)
+                    .text_color(env.get(color(severity)))
+                    .max_width(size.width);
+                for (range, color) in colors {
+                    builder = builder.range_attribute(range, TextAttribute::TextColor(color));
+                }
+                builder.build().ok().map(|layout| Row {
+                    layout,
+                    severity,
+                    source,
+                    len,
+                })
+            })
+            .collect();
+        self.hovered = None;
+        self.content_height = self.rows.iter().map(|row| row.layout.size().height).sum();
+
+        let max = self.max_offset(size.height);
+        if self.pinned || self.offset > max {
+            self.offset = max;
+        }
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &AppState, env: &Env) {
+        let size = ctx.size();
+
+        ctx.with_save(|ctx| {
+            ctx.clip(size.to_rect());
+            ctx.transform(Affine::translate((0.0, -self.offset)));
+
+            let mut y = 0.0;
+            for (idx, row) in self.rows.iter().enumerate() {
+                let row_size = row.layout.size();
+                if y + row_size.height >= self.offset && y <= self.offset + size.height {
+                    if let Some(range) = self.selected_in(idx) {
+                        let highlight = env.get(druid::theme::SELECTED_TEXT_BACKGROUND_COLOR);
+                        for rect in row.layout.rects_for_range(range) {
+                            ctx.fill(rect + Vec2::new(0.0, y), &highlight);
+                        }
+                    }
+                    ctx.draw_text(&row.layout, (0.0, y));
+                    if self.hovered == Some(idx) {
+                        let bottom = y + row_size.height - 1.0;
+                        let underline = Line::new((0.0, bottom), (row_size.width, bottom));
+                        ctx.stroke(underline, &env.get(color(row.severity)), 1.0);
+                    }
+                    if row.severity == Severity::Run {
+                        let mid = y + row_size.height / 2.0;
+                        let rule = Line::new((row_size.width + 8.0, mid), (size.width, mid));
+                        ctx.stroke(rule, &env.get(theme::PANEL_DIVIDER), 1.0);
+                    }
+                }
+                y += row_size.height;
+            }
+        });
+
+        if ctx.has_focus() {
+            let rect = size.to_rect().inset(-1.0);
+            ctx.stroke(
+                rect,
+                &env.get(druid::theme::SELECTED_TEXT_BACKGROUND_COLOR),
+                2.0,
+            );
+        }
     }
+}
+
+/// Sends the line typed below the console to the program on Enter, and takes
+/// the focus when the program starts waiting for one.
+pub struct ReplyController {}
 
-    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
-        self.label.paint(ctx, data, env);
+impl<W: Widget<AppState>> Controller<AppState, W> for ReplyController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx<'_, '_>,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::KeyDown(key) if key.key == KbKey::Enter => {
+                ctx.submit_command(commands::INTERPRETER_ANSWER);
+                ctx.set_handled();
+            }
+
+            Event::Command(cmd) if cmd.is(commands::INTERPRETER_READ) => {
+                ctx.request_focus();
+            }
+
+            _ => child.event(ctx, event, data, env),
+        }
     }
 }
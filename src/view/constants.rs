@@ -16,6 +16,63 @@ pub const FONT_SIZE: f64 = 14.0;
 
 pub const CONSOLE_HEIGHT: f64 = FONT_SIZE * 6.0 + 8.0;
 
-pub const INPUT_WIDTH: f64 = 300.0;
+/// The bar between the input and the canvas, and how far either side of it
+/// can be grabbed to drag it.
+pub const SPLIT_BAR_SIZE: f64 = 1.0;
+
+pub const SPLIT_BAR_AREA: f64 = 8.0;
+
+/// How long the split has to sit still before its position is saved.
+pub const SPLIT_SETTLE_MILLIS: u64 = 500;
+
+/// How long typing has to stop before live mode runs the program again.
+pub const LIVE_DELAY_MILLIS: u64 = 500;
+
+/// Room for line numbers to the left of the input.
+pub const GUTTER_WIDTH: f64 = 36.0;
+
+pub const COMMAND_LOG_WIDTH: f64 = 320.0;
+
+pub const COMPLETION_ROWS: usize = 6;
+
+pub const COMPLETION_WIDTH: f64 = 160.0;
 
 pub const STATUS_BAR_HEIGHT: f64 = FONT_SIZE + 8.0;
+
+pub const SPEED_SLIDER_WIDTH: f64 = 80.0;
+
+/// Sliders for params go under the input, named on the left.
+pub const PARAM_NAME_WIDTH: f64 = 80.0;
+
+pub const PARAM_PADDING: f64 = 4.0;
+
+/// The fastest speed at which the status bar flashes for each command.
+pub const FLASH_MAX_SPEED: u32 = 2;
+
+pub const FLASH_MILLIS: u64 = 80;
+
+pub const PREFS_LABEL_WIDTH: f64 = 170.0;
+
+pub const PREFS_WINDOW_SIZE: (f64, f64) = (450.0, 580.0);
+
+pub const SWATCH_SIZE: f64 = 20.0;
+
+/// How far the mouse can wander between press and release and still count
+/// as a click rather than a drag.
+pub const CLICK_SLOP: f64 = 3.0;
+
+pub const NUDGE_STEP: f64 = 1.0;
+
+pub const NUDGE_STEP_LARGE: f64 = 10.0;
+
+pub const ZOOM_SENSITIVITY: f64 = 0.002;
+
+pub const GRID_SPACING: f64 = 50.0;
+
+pub const GALLERY_COLUMNS: usize = 3;
+
+pub const GALLERY_WINDOW_SIZE: (f64, f64) = (580.0, 640.0);
+
+pub const HELP_WINDOW_SIZE: (f64, f64) = (480.0, 600.0);
+
+pub const TRACE_SEGMENTS: usize = 40;
@@ -0,0 +1,352 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::constants::*;
+use super::theme;
+use crate::common::commands;
+use crate::model::app::AppState;
+use crate::runtime;
+use crate::runtime::keywords;
+use druid::piet::Text;
+use druid::piet::TextLayout;
+use druid::piet::TextLayoutBuilder;
+use druid::text::Selection;
+use druid::widget::prelude::*;
+use druid::widget::TextBox;
+use druid::FontFamily;
+use druid::KbKey;
+use druid::Point;
+use druid::Rect;
+use druid::TimerToken;
+use druid::Vec2;
+use druid::WidgetPod;
+use std::iter;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The input editor: a multiline text box with a completion popup for
+/// keywords and the names defined by the last successful run. Line numbers
+/// run down the left, and while a program is held a step at a time, the
+/// line it runs next is picked out.
+pub struct Editor {
+    textbox: WidgetPod<Arc<String>, TextBox<Arc<String>>>,
+    completions: Vec<String>,
+    selected: usize,
+    word_start: usize,
+    pending_caret: Option<usize>,
+    /// The line picked out for a held program, counted from one.
+    held_line: Option<usize>,
+    timer_id: TimerToken,
+}
+
+impl Editor {
+    pub fn new(textbox: TextBox<Arc<String>>) -> Self {
+        Self {
+            textbox: WidgetPod::new(textbox),
+            completions: Vec::new(),
+            selected: 0,
+            word_start: 0,
+            pending_caret: None,
+            held_line: None,
+            timer_id: TimerToken::INVALID,
+        }
+    }
+
+    fn wake(&mut self, ctx: &mut EventCtx) {
+        if self.timer_id == TimerToken::INVALID {
+            self.timer_id = ctx.request_timer(Duration::from_millis(100));
+        }
+    }
+
+    /// Where the text is drawn, relative to the editor.
+    fn text_origin(&self) -> Point {
+        let text = self.textbox.widget().text();
+        self.textbox.layout_rect().origin() + text.layout_rect().origin().to_vec2()
+    }
+
+    fn caret(&self) -> usize {
+        self.textbox
+            .widget()
+            .text()
+            .widget()
+            .borrow()
+            .selection()
+            .active
+    }
+
+    fn dismiss(&mut self) {
+        self.completions.clear();
+        self.selected = 0;
+    }
+
+    /// Find the word ending at the caret, and offer whatever extends it.
+    fn refresh(&mut self, data: &AppState) {
+        let caret = self.caret().min(data.input.len());
+        let before = &data.input[..caret];
+        let word_start = before
+            .rfind(|c: char| !runtime::is_identifier(c, false))
+            .map(|idx| idx + before[idx..].chars().next().unwrap().len_utf8())
+            .unwrap_or(0);
+
+        let symbols = data.symbols.lock().unwrap();
+        self.completions = keywords::complete(&before[word_start..], data.prefs.language, &symbols);
+        self.selected = 0;
+        self.word_start = word_start;
+    }
+
+    fn accept(&mut self, data: &mut AppState) {
+        let caret = self.caret().min(data.input.len());
+        let completion = &self.completions[self.selected];
+
+        let mut input = data.input.to_string();
+        input.replace_range(self.word_start..caret, completion);
+        data.input = Arc::new(input);
+
+        self.pending_caret = Some(self.word_start + completion.len());
+        self.dismiss();
+    }
+
+    /// Keys that drive the popup while it's showing. Returns true if the key
+    /// was used, in which case the text box never sees it.
+    fn handle_key(&mut self, key: &KbKey, data: &mut AppState) -> bool {
+        match key {
+            KbKey::Tab | KbKey::Enter => self.accept(data),
+            KbKey::ArrowDown => self.selected = (self.selected + 1) % self.completions.len(),
+            KbKey::ArrowUp => {
+                let len = self.completions.len();
+                self.selected = (self.selected + len - 1) % len;
+            }
+            KbKey::Escape => self.dismiss(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Where the popup goes: just below the caret, kept inside the editor.
+    fn anchor(&self, size: Size, popup: Size) -> Point {
+        let line = self
+            .textbox
+            .widget()
+            .text()
+            .widget()
+            .borrow()
+            .layout
+            .cursor_line_for_text_position(self.caret());
+        let origin = self.text_origin() + line.p1.to_vec2();
+
+        Point::new(
+            origin.x.min(size.width - popup.width).max(0.0),
+            origin.y.min(size.height - popup.height).max(0.0),
+        )
+    }
+
+    /// Number the lines of the input, and lay a highlight over the one a
+    /// held program runs next.
+    fn paint_gutter(&self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
+        let size = ctx.size();
+        let gutter = Rect::from_origin_size(Point::ORIGIN, (GUTTER_WIDTH, size.height));
+        ctx.fill(gutter, &env.get(theme::PANEL_BACKGROUND));
+
+        // Leave the placeholder on its own.
+        if data.input.is_empty() {
+            return;
+        }
+
+        let origin = self.text_origin();
+        let starts = iter::once(0).chain(data.input.match_indices('\n').map(|(idx, _)| idx + 1));
+        let color = env.get(theme::PANEL_MUTED);
+        for (idx, start) in starts.enumerate() {
+            let line = self
+                .textbox
+                .widget()
+                .text()
+                .widget()
+                .borrow()
+                .layout
+                .cursor_line_for_text_position(start);
+            let (top, bottom) = (origin.y + line.p0.y, origin.y + line.p1.y);
+            if bottom < 0.0 {
+                continue;
+            }
+            if top > size.height {
+                break;
+            }
+
+            if self.held_line == Some(idx + 1) {
+                let rect = Rect::new(GUTTER_WIDTH, top, size.width, bottom);
+                ctx.fill(rect, &env.get(theme::PANEL_HIGHLIGHT));
+            }
+
+            let label = ctx
+                .text()
+                .new_text_layout((idx + 1).to_string())
+                .font(FontFamily::MONOSPACE, FONT_SIZE)
+                .text_color(color.clone())
+                .build();
+            if let Ok(label) = label {
+                let x = GUTTER_WIDTH - label.size().width - 6.0;
+                ctx.draw_text(&label, (x, top));
+            }
+        }
+    }
+
+    fn paint_completions(&self, ctx: &mut PaintCtx, env: &Env) {
+        let row_height = FONT_SIZE + 6.0;
+        let rows = self.completions.len().min(COMPLETION_ROWS);
+        let first = (self.selected + 1).saturating_sub(rows);
+
+        let popup = Size::new(COMPLETION_WIDTH, row_height * rows as f64);
+        let anchor = self.anchor(ctx.size(), popup);
+        let rect = Rect::from_origin_size(anchor, popup);
+        ctx.fill(rect, &env.get(theme::PANEL_BACKGROUND));
+        ctx.stroke(rect, &env.get(theme::PANEL_DIVIDER), 1.0);
+
+        let text_color = env.get(theme::PANEL_TEXT);
+        let completions = self.completions.iter().enumerate().skip(first).take(rows);
+        for (row, (idx, name)) in completions.enumerate() {
+            let origin = anchor + (0.0, row_height * row as f64);
+            if idx == self.selected {
+                let row_rect = Rect::from_origin_size(origin, (COMPLETION_WIDTH, row_height));
+                let selection = env.get(druid::theme::SELECTED_TEXT_BACKGROUND_COLOR);
+                ctx.fill(row_rect, &selection);
+            }
+
+            let label = ctx
+                .text()
+                .new_text_layout(name.clone())
+                .font(FontFamily::MONOSPACE, FONT_SIZE)
+                .text_color(text_color.clone())
+                .build();
+            if let Ok(label) = label {
+                ctx.draw_text(&label, origin + (4.0, 3.0));
+            }
+        }
+    }
+}
+
+impl Widget<AppState> for Editor {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, env: &Env) {
+        match event {
+            Event::KeyDown(key) if !self.completions.is_empty() => {
+                if self.handle_key(&key.key, data) {
+                    ctx.request_paint();
+                    ctx.set_handled();
+                    return;
+                }
+            }
+
+            // Tab belongs to the text, so Ctrl-Tab is the way out to the
+            // canvas and console.
+            Event::KeyDown(key) if key.key == KbKey::Tab && key.mods.ctrl() => {
+                if key.mods.shift() {
+                    ctx.focus_prev();
+                } else {
+                    ctx.focus_next();
+                }
+                ctx.set_handled();
+                return;
+            }
+
+            // Clicking an error in the console shows where it is.
+            Event::Command(cmd) if cmd.is(commands::EDITOR_CARET) => {
+                let caret = (*cmd.get_unchecked(commands::EDITOR_CARET)).min(data.input.len());
+                self.dismiss();
+                ctx.set_focus(self.textbox.id());
+                let text = self.textbox.widget_mut().text_mut().widget_mut();
+                if let Some(inval) = text.borrow_mut().set_selection(Selection::caret(caret)) {
+                    ctx.invalidate_text_input(inval);
+                }
+                ctx.request_paint();
+                ctx.set_handled();
+                return;
+            }
+
+            Event::MouseDown(_) if !self.completions.is_empty() => {
+                self.dismiss();
+                ctx.request_paint();
+            }
+
+            // Keep an eye on a held program, to follow it down the input.
+            Event::Timer(timer_id) if *timer_id == self.timer_id => {
+                let held_line = data.session.held_line();
+                if held_line != self.held_line {
+                    self.held_line = held_line;
+                    ctx.request_paint();
+                }
+
+                self.timer_id = if data.session.is_running() {
+                    ctx.request_timer(Duration::from_millis(100))
+                } else {
+                    TimerToken::INVALID
+                };
+                return;
+            }
+
+            Event::Command(cmd) if cmd.is(commands::WAKE) => self.wake(ctx),
+
+            _ => {}
+        }
+
+        let mut input = data.input.clone();
+        self.textbox.event(ctx, event, &mut input, env);
+        if !input.same(&data.input) {
+            data.input = input;
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &AppState, env: &Env) {
+        self.textbox.lifecycle(ctx, event, &data.input, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, env: &Env) {
+        self.textbox.update(ctx, &data.input, env);
+
+        if let Some(caret) = self.pending_caret.take() {
+            // Put the caret after the completion we just inserted.
+            let text = self.textbox.widget_mut().text_mut().widget_mut();
+            if let Some(inval) = text.borrow_mut().set_selection(Selection::caret(caret)) {
+                ctx.invalidate_text_input(inval);
+            }
+        } else if !old_data.input.same(&data.input) {
+            self.refresh(data);
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &AppState,
+        env: &Env,
+    ) -> Size {
+        let gutter = Vec2::new(GUTTER_WIDTH, 0.0);
+        let size = self
+            .textbox
+            .layout(ctx, &bc.shrink(gutter.to_size()), &data.input, env);
+        self.textbox
+            .set_origin(ctx, &data.input, env, gutter.to_point());
+        size + gutter.to_size()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
+        self.textbox.paint(ctx, &data.input, env);
+        self.paint_gutter(ctx, data, env);
+
+        if !self.completions.is_empty() {
+            self.paint_completions(ctx, env);
+        }
+    }
+}
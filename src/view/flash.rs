@@ -0,0 +1,92 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::constants::*;
+use super::theme;
+use crate::model::app::AppState;
+use druid::widget::prelude::*;
+use druid::Point;
+use druid::TimerToken;
+use druid::WidgetPod;
+use std::time::Duration;
+
+/// Paints the panel background behind its child, lighting it up for a moment
+/// each time the canvas counts another flash.
+pub struct Flash<W> {
+    child: WidgetPod<AppState, W>,
+    lit: bool,
+    timer_id: TimerToken,
+}
+
+impl<W: Widget<AppState>> Flash<W> {
+    pub fn new(child: W) -> Self {
+        Self {
+            child: WidgetPod::new(child),
+            lit: false,
+            timer_id: TimerToken::INVALID,
+        }
+    }
+}
+
+impl<W: Widget<AppState>> Widget<AppState> for Flash<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, env: &Env) {
+        if let Event::Timer(timer_id) = event {
+            if self.timer_id == *timer_id {
+                self.lit = false;
+                ctx.request_paint();
+                return;
+            }
+        }
+
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &AppState, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, env: &Env) {
+        if old_data.flashes != data.flashes {
+            self.lit = true;
+            self.timer_id = ctx.request_timer(Duration::from_millis(FLASH_MILLIS));
+            ctx.request_paint();
+        }
+
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &AppState,
+        env: &Env,
+    ) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
+        let color = if self.lit {
+            env.get(theme::PANEL_FLASH)
+        } else {
+            env.get(theme::PANEL_BACKGROUND)
+        };
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect, &color);
+
+        self.child.paint(ctx, data, env);
+    }
+}
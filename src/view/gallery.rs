@@ -0,0 +1,99 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::constants::*;
+use crate::common::commands;
+use crate::common::constants::THUMBNAIL_DIMS;
+use crate::common::examples::Example;
+use crate::common::examples::EXAMPLES;
+use crate::common::i18n;
+use crate::model::app::AppState;
+use druid::piet::ImageFormat;
+use druid::piet::InterpolationMode;
+use druid::theme;
+use druid::widget::prelude::*;
+use druid::widget::Button;
+use druid::widget::CrossAxisAlignment;
+use druid::widget::Flex;
+use druid::widget::Label;
+use druid::widget::Painter;
+use druid::widget::Scroll;
+use druid::WidgetExt;
+use druid::WindowDesc;
+
+pub fn window() -> WindowDesc<AppState> {
+    WindowDesc::new(build_ui())
+        .title(|data: &AppState, _env: &Env| i18n::text(data.prefs.ui_language, "gallery-title"))
+        .window_size(GALLERY_WINDOW_SIZE)
+}
+
+fn build_ui() -> impl Widget<AppState> {
+    let mut grid = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+
+    for examples in EXAMPLES.chunks(GALLERY_COLUMNS) {
+        let mut row = Flex::row();
+        for example in examples {
+            row.add_child(build_card(example));
+            row.add_default_spacer();
+        }
+
+        grid.add_child(row);
+        grid.add_default_spacer();
+    }
+
+    Scroll::new(grid.padding(10.0)).vertical()
+}
+
+fn build_card(example: &'static Example) -> impl Widget<AppState> {
+    Flex::column()
+        .with_child(build_thumbnail(example.id))
+        .with_spacer(4.0)
+        .with_child(Label::new(example.name))
+        .with_spacer(4.0)
+        .with_child(
+            Button::new(|data: &AppState, _env: &Env| {
+                i18n::text(data.prefs.ui_language, "gallery-load")
+            })
+            .on_click(move |ctx, _data, _env| {
+                ctx.submit_command(commands::EXAMPLES.with(example.id));
+                ctx.submit_command(druid::commands::CLOSE_WINDOW.to(ctx.window_id()));
+            }),
+        )
+}
+
+fn build_thumbnail(id: &'static str) -> impl Widget<AppState> {
+    Painter::new(move |ctx, data: &AppState, env| {
+        let rect = ctx.size().to_rect();
+
+        match data.thumbnails.get(id) {
+            Some(rgba) => {
+                let image = ctx.make_image(
+                    THUMBNAIL_DIMS.width as usize,
+                    THUMBNAIL_DIMS.height as usize,
+                    rgba,
+                    ImageFormat::RgbaSeparate,
+                );
+                if let Ok(image) = image {
+                    ctx.draw_image(&image, rect, InterpolationMode::Bilinear);
+                }
+            }
+
+            // Still rendering.
+            None => ctx.fill(rect, &env.get(theme::BACKGROUND_LIGHT)),
+        }
+
+        ctx.stroke(rect, &env.get(theme::BORDER_DARK), 1.0);
+    })
+    .fix_size(THUMBNAIL_DIMS.width, THUMBNAIL_DIMS.height)
+}
@@ -0,0 +1,85 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::constants::*;
+use crate::common::i18n;
+use crate::model::app::AppState;
+use crate::runtime::keywords::KEYWORDS;
+use druid::widget::prelude::*;
+use druid::widget::CrossAxisAlignment;
+use druid::widget::Flex;
+use druid::widget::Label;
+use druid::widget::LineBreaking;
+use druid::widget::Scroll;
+use druid::widget::TextBox;
+use druid::FontDescriptor;
+use druid::FontFamily;
+use druid::WidgetExt;
+use druid::WindowDesc;
+use std::sync::Arc;
+
+pub fn window() -> WindowDesc<AppState> {
+    WindowDesc::new(build_ui())
+        .title(|data: &AppState, _env: &Env| i18n::text(data.prefs.ui_language, "help-title"))
+        .window_size(HELP_WINDOW_SIZE)
+}
+
+fn build_ui() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Fill)
+        .with_child(
+            TextBox::new()
+                .with_placeholder("Search")
+                .lens(AppState::help_query),
+        )
+        .with_default_spacer()
+        .with_flex_child(
+            Scroll::new(build_reference().lens(AppState::help_query)).vertical(),
+            1.0,
+        )
+        .padding(10.0)
+}
+
+fn build_reference() -> impl Widget<Arc<String>> {
+    Label::new(|query: &Arc<String>, _: &_| reference(query))
+        .with_font(FontDescriptor::new(FontFamily::MONOSPACE).with_size(FONT_SIZE))
+        .with_line_break_mode(LineBreaking::WordWrap)
+}
+
+/// Format every keyword whose names or description match `query`.
+fn reference(query: &str) -> String {
+    let query = query.trim().to_lowercase();
+
+    let entries: Vec<_> = KEYWORDS
+        .iter()
+        .filter(|def| {
+            def.names.iter().any(|name| name.contains(&query))
+                || def.description.to_lowercase().contains(&query)
+        })
+        .map(|def| {
+            format!(
+                "{}\n    {}\n    {}\n",
+                def.names.join(", "),
+                def.syntax,
+                def.description
+            )
+        })
+        .collect();
+
+    if entries.is_empty() {
+        format!("nothing matches \"{}\"", query)
+    } else {
+        entries.join("\n")
+    }
+}
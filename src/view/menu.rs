@@ -13,81 +13,355 @@
 // limitations under the License.
 
 use crate::common::commands;
+use crate::common::examples;
+use crate::common::i18n;
 use crate::model::app::AppState;
+use crate::model::prefs::Language;
+use crate::model::prefs::Theme;
+use crate::model::prefs::TurtleShape;
 use druid::menu::Menu;
 use druid::menu::MenuItem;
 use druid::widget::prelude::*;
-use druid::LocalizedString;
+use druid::FileDialogOptions;
+use druid::FileSpec;
 use druid::SysMods;
 use druid::WindowId;
 
 pub fn menu_bar(_: Option<WindowId>, _: &AppState, _: &Env) -> Menu<AppState> {
+    // The application menu, with About and Quit, only exists on macOS.
     #[cfg(target_os = "macos")]
     let base = druid::platform_menus::mac::menu_bar();
 
-    #[cfg(any(target_os = "windows", target_os = "linux"))]
-    let base = base.entry(druid::platform_menus::win::file::default());
+    #[cfg(not(target_os = "macos"))]
+    let base = Menu::empty();
 
-    base.entry(build_edit())
+    base.entry(build_file())
+        .entry(build_edit())
+        .entry(build_view())
         .entry(build_interpreter())
         .entry(build_examples())
+        .entry(build_help())
         .rebuild_on(|_old_data, _data, _env| false)
 }
 
+/// A menu label in the interface language.
+fn text(key: &'static str) -> impl Fn(&AppState, &Env) -> String {
+    move |data: &AppState, _env: &Env| i18n::text(data.prefs.ui_language, key)
+}
+
+/// Where to save the input, for both Save As and a first Save.
+pub fn save_program_options() -> FileDialogOptions {
+    let logo = FileSpec::new("Logo Program", &["logo"]);
+    FileDialogOptions::new()
+        .allowed_types(vec![logo])
+        .default_type(logo)
+        .default_name("turtle.logo")
+        .accept_command(commands::FILE_SAVE_AS)
+}
+
+fn build_file() -> Menu<AppState> {
+    let logo = FileSpec::new("Logo Program", &["logo"]);
+    let open_program = FileDialogOptions::new()
+        .allowed_types(vec![logo, FileSpec::TEXT])
+        .default_type(logo)
+        .accept_command(commands::FILE_OPEN);
+
+    let gif = FileSpec::new("GIF Image", &["gif"]);
+    let export_animation = FileDialogOptions::new()
+        .allowed_types(vec![gif])
+        .default_type(gif)
+        .default_name("turtle.gif")
+        .accept_command(commands::EXPORT_ANIMATION);
+
+    let png = FileSpec::new("PNG Image", &["png"]);
+    let export_image = FileDialogOptions::new()
+        .allowed_types(vec![png])
+        .default_type(png)
+        .default_name("turtle.png")
+        .accept_command(commands::EXPORT_IMAGE);
+    let export_image_cropped = FileDialogOptions::new()
+        .allowed_types(vec![png])
+        .default_type(png)
+        .default_name("turtle.png")
+        .accept_command(commands::EXPORT_IMAGE_CROPPED);
+
+    let json = FileSpec::new("Turtle Commands", &["json"]);
+    let export_commands = FileDialogOptions::new()
+        .allowed_types(vec![json])
+        .default_type(json)
+        .default_name("turtle.json")
+        .accept_command(commands::EXPORT_COMMANDS);
+    let import_commands = FileDialogOptions::new()
+        .allowed_types(vec![json])
+        .default_type(json)
+        .accept_command(commands::IMPORT_COMMANDS);
+
+    let menu = Menu::new(text("menu-file"))
+        .entry(
+            MenuItem::new(text("menu-file-open"))
+                .hotkey(SysMods::Cmd, "o")
+                .command(druid::commands::SHOW_OPEN_PANEL.with(open_program)),
+        )
+        .entry(
+            MenuItem::new(text("menu-file-save"))
+                .hotkey(SysMods::Cmd, "s")
+                .command(commands::FILE_SAVE),
+        )
+        .entry(
+            MenuItem::new(text("menu-file-save-as"))
+                .hotkey(SysMods::CmdShift, "s")
+                .command(druid::commands::SHOW_SAVE_PANEL.with(save_program_options())),
+        )
+        .entry(
+            MenuItem::new(text("menu-file-watch"))
+                .selected_if(|data: &AppState, _env| data.watch.is_some())
+                .enabled_if(|data: &AppState, _env| data.document.is_some())
+                .command(commands::FILE_WATCH),
+        )
+        .entry(
+            MenuItem::new(text("menu-file-restore-backup"))
+                .enabled_if(|data: &AppState, _env| data.recovered.is_some())
+                .command(commands::FILE_RESTORE),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(text("menu-file-export-image"))
+                .hotkey(SysMods::CmdShift, "x")
+                .command(druid::commands::SHOW_SAVE_PANEL.with(export_image)),
+        )
+        .entry(
+            MenuItem::new(text("menu-file-export-image-cropped"))
+                .command(druid::commands::SHOW_SAVE_PANEL.with(export_image_cropped)),
+        )
+        .entry(
+            MenuItem::new(text("menu-file-export-animation"))
+                .enabled_if(|data: &AppState, _env| !data.log.is_empty())
+                .hotkey(SysMods::CmdShift, "e")
+                .command(druid::commands::SHOW_SAVE_PANEL.with(export_animation)),
+        )
+        .entry(
+            MenuItem::new(text("menu-file-export-commands"))
+                .enabled_if(|data: &AppState, _env| !data.log.is_empty())
+                .hotkey(SysMods::CmdShift, "j")
+                .command(druid::commands::SHOW_SAVE_PANEL.with(export_commands)),
+        )
+        .entry(
+            MenuItem::new(text("menu-file-import-commands"))
+                .enabled_if(|data: &AppState, _env| !data.session.is_running())
+                .hotkey(SysMods::CmdShift, "o")
+                .command(druid::commands::SHOW_OPEN_PANEL.with(import_commands)),
+        );
+
+    // macOS quits from the application menu; everywhere else it is File > Exit.
+    if cfg!(target_os = "macos") {
+        menu
+    } else {
+        menu.separator()
+            .entry(druid::platform_menus::win::file::exit())
+    }
+}
+
 fn build_edit() -> Menu<AppState> {
-    Menu::new(LocalizedString::new("common-menu-edit-menu"))
+    Menu::new(text("menu-edit"))
         .entry(druid::platform_menus::common::undo())
         .entry(druid::platform_menus::common::redo())
         .separator()
         .entry(druid::platform_menus::common::cut())
         .entry(druid::platform_menus::common::copy())
+        .entry(
+            MenuItem::new(text("menu-edit-copy-image"))
+                .hotkey(SysMods::CmdShift, "c")
+                .command(commands::COPY_IMAGE),
+        )
         .entry(druid::platform_menus::common::paste())
+        .separator()
+        .entry(
+            MenuItem::new(text("menu-edit-clear-console"))
+                .hotkey(SysMods::CmdShift, "k")
+                .command(commands::CONSOLE_CLEAR),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(text("menu-edit-preferences"))
+                .hotkey(SysMods::Cmd, ",")
+                .command(commands::PREFERENCES),
+        )
+}
+
+fn build_view() -> Menu<AppState> {
+    let image = FileSpec::new("PNG Image", &["png"]);
+    let turtle_image = FileDialogOptions::new()
+        .allowed_types(vec![image])
+        .default_type(image)
+        .accept_command(commands::VIEW_TURTLE_IMAGE);
+    let background_image = FileDialogOptions::new()
+        .allowed_types(vec![image])
+        .default_type(image)
+        .accept_command(commands::VIEW_BACKGROUND_IMAGE);
+
+    Menu::new(text("menu-view"))
+        .entry(
+            MenuItem::new(text("menu-view-dark-theme"))
+                .selected_if(|data: &AppState, _env| data.prefs.theme == Theme::Dark)
+                .command(commands::VIEW_THEME.with(Theme::Dark)),
+        )
+        .entry(
+            MenuItem::new(text("menu-view-light-theme"))
+                .selected_if(|data: &AppState, _env| data.prefs.theme == Theme::Light)
+                .command(commands::VIEW_THEME.with(Theme::Light)),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(text("menu-view-triangle-turtle"))
+                .selected_if(|data: &AppState, _env| {
+                    data.prefs.turtle_shape == TurtleShape::Triangle
+                })
+                .command(commands::VIEW_TURTLE_SHAPE.with(TurtleShape::Triangle)),
+        )
+        .entry(
+            MenuItem::new(text("menu-view-turtle-bitmap"))
+                .selected_if(|data: &AppState, _env| data.prefs.turtle_shape == TurtleShape::Turtle)
+                .command(commands::VIEW_TURTLE_SHAPE.with(TurtleShape::Turtle)),
+        )
+        .entry(
+            MenuItem::new(text("menu-view-turtle-image"))
+                .selected_if(|data: &AppState, _env| data.prefs.turtle_shape == TurtleShape::Image)
+                .command(druid::commands::SHOW_OPEN_PANEL.with(turtle_image)),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(text("menu-view-background-image"))
+                .command(druid::commands::SHOW_OPEN_PANEL.with(background_image)),
+        )
+        .entry(
+            MenuItem::new(text("menu-view-clear-background"))
+                .enabled_if(|data: &AppState, _env| data.layers.background.picture.is_some())
+                .command(commands::VIEW_BACKGROUND_CLEAR),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(text("menu-view-grid"))
+                .selected_if(|data: &AppState, _env| data.layers.overlay.grid)
+                .hotkey(SysMods::CmdShift, "g")
+                .command(commands::VIEW_GRID),
+        )
+        .entry(
+            MenuItem::new(text("menu-view-trace"))
+                .selected_if(|data: &AppState, _env| data.layers.overlay.trace)
+                .hotkey(SysMods::CmdShift, "t")
+                .command(commands::VIEW_TRACE),
+        )
+        .entry(
+            MenuItem::new(text("menu-view-command-log"))
+                .selected_if(|data: &AppState, _env| data.show_command_log)
+                .hotkey(SysMods::CmdShift, "l")
+                .command(commands::VIEW_COMMAND_LOG),
+        )
+        .entry(
+            MenuItem::new(text("menu-view-presentation"))
+                .selected_if(|data: &AppState, _env| data.presenting)
+                .hotkey(SysMods::CmdShift, "p")
+                .command(commands::VIEW_PRESENTATION),
+        )
+        .entry(
+            MenuItem::new(text("menu-view-reset"))
+                .hotkey(SysMods::Cmd, "0")
+                .command(commands::VIEW_RESET),
+        )
 }
 
 fn build_interpreter() -> Menu<AppState> {
-    Menu::new(LocalizedString::new("Interpreter"))
+    Menu::new(text("menu-interpreter"))
         .entry(
-            MenuItem::new(LocalizedString::new("Go"))
+            MenuItem::new(text("menu-interpreter-go"))
                 .enabled_if(|data: &AppState, _env| data.input.len() > 0)
                 .hotkey(SysMods::Cmd, "g")
                 .command(commands::INTERPRETER_GO),
         )
+        .entry(
+            MenuItem::new(text("menu-interpreter-step"))
+                .enabled_if(|data: &AppState, _env| data.input.len() > 0)
+                .hotkey(SysMods::Cmd, "j")
+                .command(commands::INTERPRETER_STEP),
+        )
+        .entry(
+            MenuItem::new(text("menu-interpreter-stop"))
+                .hotkey(SysMods::Cmd, ".")
+                .command(commands::INTERPRETER_STOP),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(text("menu-interpreter-format"))
+                .enabled_if(|data: &AppState, _env| data.input.len() > 0)
+                .hotkey(SysMods::CmdShift, "f")
+                .command(commands::INTERPRETER_FORMAT),
+        )
+        .entry(
+            MenuItem::new(text("menu-interpreter-check"))
+                .enabled_if(|data: &AppState, _env| data.input.len() > 0)
+                .hotkey(SysMods::Cmd, "k")
+                .command(commands::INTERPRETER_CHECK),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(text("menu-interpreter-record"))
+                .selected_if(|data: &AppState, _env| data.recording)
+                .hotkey(SysMods::CmdShift, "r")
+                .command(commands::TURTLE_RECORD),
+        )
+        .entry(
+            MenuItem::new(text("menu-interpreter-live"))
+                .selected_if(|data: &AppState, _env| data.live)
+                .hotkey(SysMods::Cmd, "l")
+                .command(commands::INTERPRETER_LIVE),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(text("menu-interpreter-english"))
+                .selected_if(|data: &AppState, _env| data.prefs.language == Language::English)
+                .command(commands::INTERPRETER_LANGUAGE.with(Language::English)),
+        )
+        .entry(
+            MenuItem::new(text("menu-interpreter-french"))
+                .selected_if(|data: &AppState, _env| data.prefs.language == Language::French)
+                .command(commands::INTERPRETER_LANGUAGE.with(Language::French)),
+        )
+        .entry(
+            MenuItem::new(text("menu-interpreter-spanish"))
+                .selected_if(|data: &AppState, _env| data.prefs.language == Language::Spanish)
+                .command(commands::INTERPRETER_LANGUAGE.with(Language::Spanish)),
+        )
         .separator()
         .entry(
-            MenuItem::new(LocalizedString::new("Faster"))
+            MenuItem::new(text("menu-interpreter-faster"))
                 .hotkey(SysMods::Cmd, "+")
                 .command(commands::INTERPRETER_SPEED.with(true)),
         )
         .entry(
-            MenuItem::new(LocalizedString::new("Slower"))
+            MenuItem::new(text("menu-interpreter-slower"))
                 .hotkey(SysMods::Cmd, "-")
                 .command(commands::INTERPRETER_SPEED.with(false)),
         )
 }
 
-use std::collections::HashMap;
 fn build_examples() -> Menu<AppState> {
-    let examples: HashMap<&'static str, &'static str> = crate::hashmap![
-        "Color Ball" => "color-ball",
-        "Color Star" => "color-star",
-        "Fan Flower" => "fan-flower",
-        "Fill" => "fill",
-        "For Loop" => "for-loop",
-        "Spin Wheel" => "spin-wheel",
-        "Spiral" => "spiral",
-        "Squares" => "squares",
-        "Square Flower" => "square-flower"
-    ];
-
-    let mut menu = Menu::new(LocalizedString::new("Examples"));
-
-    let mut keys: Vec<_> = examples.keys().collect();
-    keys.sort();
-    for k in keys {
-        let v = examples[k];
-        let entry = MenuItem::new(LocalizedString::new(&k)).command(commands::EXAMPLES.with(&v));
-        menu = menu.entry(entry);
-    }
+    let menu = Menu::new(text("menu-examples")).entry(
+        MenuItem::new(text("menu-examples-gallery"))
+            .hotkey(SysMods::Cmd, "e")
+            .command(commands::GALLERY),
+    );
+
+    examples::EXAMPLES
+        .iter()
+        .fold(menu.separator(), |menu, example| {
+            menu.entry(MenuItem::new(example.name).command(commands::EXAMPLES.with(example.id)))
+        })
+}
 
-    menu
+fn build_help() -> Menu<AppState> {
+    Menu::new(text("menu-help")).entry(
+        MenuItem::new(text("menu-help-reference"))
+            .hotkey(SysMods::CmdShift, "/")
+            .command(commands::HELP_REFERENCE),
+    )
 }
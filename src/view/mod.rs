@@ -13,7 +13,18 @@
 // limitations under the License.
 
 mod canvas;
+mod command_log;
 mod console;
 mod constants;
-mod menu;
+mod editor;
+mod flash;
+pub mod gallery;
+pub mod help;
+pub mod menu;
+mod overlay;
+mod params;
+pub mod prefs;
+mod sprite;
+mod theme;
+mod timeline;
 pub mod window;
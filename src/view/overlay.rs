@@ -0,0 +1,97 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::constants::*;
+use druid::kurbo::BezPath;
+use druid::kurbo::Circle;
+use druid::kurbo::Line;
+use druid::piet::Text;
+use druid::piet::TextLayoutBuilder;
+use druid::widget::prelude::*;
+use druid::Color;
+use druid::FontFamily;
+use druid::Point;
+
+/// Draw a coordinate grid, the x/y axes, and an origin marker. Coordinates
+/// are logical canvas pixels; `zoom` keeps strokes a constant screen width.
+pub fn paint_grid(ctx: &mut PaintCtx, size: Size, origin: Point, zoom: f64) {
+    let grid_color = Color::rgba8(128, 128, 128, 80);
+    let axis_color = Color::rgba8(160, 160, 160, 200);
+    let width = 1.0 / zoom;
+
+    let mut x = origin.x % GRID_SPACING;
+    while x <= size.width {
+        let line = Line::new((x, 0.0), (x, size.height));
+        ctx.stroke(line, &grid_color, width);
+        x += GRID_SPACING;
+    }
+
+    let mut y = origin.y % GRID_SPACING;
+    while y <= size.height {
+        let line = Line::new((0.0, y), (size.width, y));
+        ctx.stroke(line, &grid_color, width);
+        y += GRID_SPACING;
+    }
+
+    let x_axis = Line::new((0.0, origin.y), (size.width, origin.y));
+    ctx.stroke(x_axis, &axis_color, width * 2.0);
+
+    let y_axis = Line::new((origin.x, 0.0), (origin.x, size.height));
+    ctx.stroke(y_axis, &axis_color, width * 2.0);
+
+    ctx.fill(Circle::new(origin, 3.0 / zoom), &axis_color);
+}
+
+/// Draw the last few turtle segments as numbered arrows, oldest first, so the
+/// order and direction of travel are visible.
+pub fn paint_trace(ctx: &mut PaintCtx, segments: &[(Point, Point)], origin: Point, zoom: f64) {
+    let to_canvas = |p: Point| Point::new(origin.x + p.x, origin.y - p.y);
+    let first = segments.len().saturating_sub(TRACE_SEGMENTS);
+
+    for (idx, (p, q)) in segments.iter().enumerate().skip(first) {
+        let p = to_canvas(*p);
+        let q = to_canvas(*q);
+        let v = q - p;
+        let len = v.hypot();
+        if len < f64::EPSILON {
+            continue;
+        }
+
+        let hue = 360.0 * (idx - first) as f64 / TRACE_SEGMENTS as f64;
+        let color = Color::hlc(hue, 70.0, 60.0);
+        ctx.stroke(Line::new(p, q), &color, 1.5 / zoom);
+
+        // Arrowhead at the end of the segment.
+        let dir = v / len;
+        let normal = druid::Vec2::new(-dir.y, dir.x);
+        let head = 6.0 / zoom;
+        let mut arrow = BezPath::new();
+        arrow.move_to(q);
+        arrow.line_to(q - dir * head + normal * head * 0.5);
+        arrow.line_to(q - dir * head - normal * head * 0.5);
+        arrow.close_path();
+        ctx.fill(arrow, &color);
+
+        let label = ctx
+            .text()
+            .new_text_layout(format!("{}", idx + 1))
+            .font(FontFamily::MONOSPACE, FONT_SIZE * 0.75 / zoom)
+            .text_color(color)
+            .build();
+        if let Ok(label) = label {
+            let mid = p.midpoint(q) + normal * (4.0 / zoom);
+            ctx.draw_text(&label, mid);
+        }
+    }
+}
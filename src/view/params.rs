@@ -0,0 +1,135 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::constants::*;
+use super::theme;
+use super::window::restart_run;
+use crate::common::commands;
+use crate::model::app::AppState;
+use crate::model::params;
+use crate::model::params::Param;
+use druid::lens;
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::widget::Flex;
+use druid::widget::Label;
+use druid::widget::List;
+use druid::widget::Slider;
+use druid::FontDescriptor;
+use druid::FontFamily;
+use druid::TimerToken;
+use druid::WidgetExt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A slider for each number the program declared with `param`, under the
+/// input. Moving one runs the program again with the new value. There's
+/// nothing to see until a run declares some.
+pub fn build() -> impl Widget<AppState> {
+    List::new(build_row)
+        .lens(AppState::params)
+        .controller(ParamsController {
+            moved: false,
+            timer_id: TimerToken::INVALID,
+        })
+}
+
+fn build_row() -> impl Widget<Param> {
+    let font = FontDescriptor::new(FontFamily::MONOSPACE).with_size(FONT_SIZE);
+
+    let name = Label::new(|param: &Param, _: &_| param.name.clone())
+        .with_font(font.clone())
+        .with_text_color(theme::PANEL_TEXT)
+        .fix_width(PARAM_NAME_WIDTH);
+
+    let slider = Slider::new().lens(lens::Map::new(Param::fraction, Param::set_fraction));
+
+    let value = Label::new(|param: &Param, _: &_| format!("{:7}", param.value))
+        .with_font(font)
+        .with_text_color(theme::PANEL_TEXT);
+
+    Flex::row()
+        .with_child(name)
+        .with_flex_child(slider, 1.0)
+        .with_child(value)
+        .padding((PARAM_PADDING, 0.0))
+}
+
+/// Picks up the params each run declares, and starts the program over once a
+/// slider moves.
+struct ParamsController {
+    /// A slider has moved since the last run started.
+    moved: bool,
+    timer_id: TimerToken,
+}
+
+impl ParamsController {
+    fn wake(&mut self, ctx: &mut EventCtx) {
+        if self.timer_id == TimerToken::INVALID {
+            self.timer_id = ctx.request_timer(Duration::from_millis(100));
+        }
+    }
+}
+
+impl<W: Widget<AppState>> Controller<AppState, W> for ParamsController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx<'_, '_>,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Timer(timer_id) if *timer_id == self.timer_id => {
+                // Asked first, so a run that ends in between has already left
+                // its params for the next tick.
+                let running = data.session.is_running();
+
+                let declared = data.declared.lock().unwrap().take();
+                if let Some(declared) = declared {
+                    let params = params::refresh(&data.params, declared);
+                    if params != *data.params {
+                        data.params = Arc::new(params);
+                    }
+                }
+
+                if self.moved && restart_run(ctx, data) {
+                    self.moved = false;
+                }
+
+                self.timer_id = if running || self.moved {
+                    ctx.request_timer(Duration::from_millis(100))
+                } else {
+                    TimerToken::INVALID
+                };
+                return;
+            }
+
+            Event::Command(cmd) if cmd.is(commands::WAKE) => self.wake(ctx),
+
+            Event::WindowConnected => self.wake(ctx),
+
+            _ => {}
+        }
+
+        let params = data.params.clone();
+        child.event(ctx, event, data, env);
+
+        if !params.same(&data.params) {
+            self.moved = true;
+            self.wake(ctx);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::constants::*;
+use crate::common::commands;
+use crate::common::constants::*;
+use crate::common::i18n;
+use crate::common::palette::*;
+use crate::model::app::AppState;
+use crate::model::prefs::Language;
+use crate::model::prefs::Preferences;
+use druid::widget::prelude::*;
+use druid::widget::Button;
+use druid::widget::Checkbox;
+use druid::widget::CrossAxisAlignment;
+use druid::widget::Flex;
+use druid::widget::Label;
+use druid::widget::Painter;
+use druid::widget::RadioGroup;
+use druid::widget::Stepper;
+use druid::widget::TextBox;
+use druid::Color;
+use druid::Lens;
+use druid::LensExt;
+use druid::WidgetExt;
+use druid::WindowDesc;
+
+pub fn window() -> WindowDesc<AppState> {
+    WindowDesc::new(build_ui().lens(AppState::prefs))
+        .title(|data: &AppState, _env: &Env| i18n::text(data.prefs.ui_language, "prefs-title"))
+        .window_size(PREFS_WINDOW_SIZE)
+        .resizable(false)
+}
+
+/// A label in the interface language.
+fn text(key: &'static str) -> impl Fn(&Preferences, &Env) -> String {
+    move |data: &Preferences, _env: &Env| i18n::text(data.ui_language, key)
+}
+
+fn build_ui() -> impl Widget<Preferences> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new(text("prefs-pen-color")))
+        .with_child(build_swatches().lens(Preferences::pen_color))
+        .with_default_spacer()
+        .with_child(Label::new(text("prefs-background-color")))
+        .with_child(build_swatches().lens(Preferences::screen_color))
+        .with_default_spacer()
+        .with_child(build_row(
+            "prefs-speed",
+            build_stepper(MIN_SPEED as f64, MAX_SPEED as f64, 1.0).lens(
+                Preferences::speed.map(|speed| *speed as f64, |speed, val| *speed = val as u32),
+            ),
+        ))
+        .with_child(build_row(
+            "prefs-width",
+            build_stepper(MIN_DIMS.width, MAX_DIMS.width, 10.0).lens(Preferences::width),
+        ))
+        .with_child(build_row(
+            "prefs-height",
+            build_stepper(MIN_DIMS.height, MAX_DIMS.height, 10.0).lens(Preferences::height),
+        ))
+        .with_child(build_row(
+            "prefs-surface-width",
+            build_stepper(MIN_DIMS.width, MAX_DIMS.width, 10.0).lens(Preferences::surface_width),
+        ))
+        .with_child(build_row(
+            "prefs-surface-height",
+            build_stepper(MIN_DIMS.height, MAX_DIMS.height, 10.0).lens(Preferences::surface_height),
+        ))
+        .with_child(build_row(
+            "prefs-time-limit",
+            build_stepper(0.0, MAX_TIME_LIMIT as f64, 5.0).lens(
+                Preferences::time_limit
+                    .map(|limit| *limit as f64, |limit, val| *limit = val as u32),
+            ),
+        ))
+        .with_child(build_row(
+            "prefs-autosave",
+            build_stepper(0.0, MAX_AUTOSAVE as f64, 10.0).lens(
+                Preferences::autosave.map(|secs| *secs as f64, |secs, val| *secs = val as u32),
+            ),
+        ))
+        .with_child(build_row(
+            "prefs-export-step",
+            build_stepper(1.0, 1000.0, 1.0).lens(
+                Preferences::export_step.map(|step| *step as f64, |step, val| *step = val as u32),
+            ),
+        ))
+        .with_child(build_row(
+            "prefs-export-delay",
+            build_stepper(10.0, 1000.0, 10.0).lens(
+                Preferences::export_delay
+                    .map(|delay| *delay as f64, |delay, val| *delay = val as u32),
+            ),
+        ))
+        .with_child(
+            Flex::row()
+                .with_child(Label::new(text("prefs-library-path")).fix_width(PREFS_LABEL_WIDTH))
+                .with_flex_child(
+                    TextBox::new()
+                        .expand_width()
+                        .lens(Preferences::library_path),
+                    1.0,
+                ),
+        )
+        .with_default_spacer()
+        .with_child(build_checkbox("prefs-antialias", Preferences::antialias))
+        .with_child(build_checkbox("prefs-optimize", Preferences::optimize))
+        .with_child(build_checkbox(
+            "prefs-command-flash",
+            Preferences::command_flash,
+        ))
+        .with_default_spacer()
+        .with_child(
+            Flex::row()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(Label::new(text("prefs-language")).fix_width(PREFS_LABEL_WIDTH))
+                .with_child(build_language_picker().lens(Preferences::ui_language)),
+        )
+        .with_flex_spacer(1.0)
+        .with_child(
+            Button::new(text("prefs-save")).on_click(|ctx, _data, _env| {
+                ctx.submit_command(commands::PREFERENCES_SAVE);
+                ctx.submit_command(druid::commands::CLOSE_WINDOW.to(ctx.window_id()));
+            }),
+        )
+        .padding(10.0)
+}
+
+fn build_row(
+    key: &'static str,
+    field: impl Widget<Preferences> + 'static,
+) -> impl Widget<Preferences> {
+    Flex::row()
+        .with_child(Label::new(text(key)).fix_width(PREFS_LABEL_WIDTH))
+        .with_child(field)
+}
+
+fn build_stepper(min: f64, max: f64, step: f64) -> impl Widget<f64> {
+    Flex::row()
+        .with_child(Label::new(|val: &f64, _: &_| format!("{}", val)).fix_width(60.0))
+        .with_child(Stepper::new().with_range(min, max).with_step(step))
+}
+
+/// A checkbox whose label follows the interface language. The checkbox's own
+/// label can only see the bool it toggles.
+fn build_checkbox(
+    key: &'static str,
+    lens: impl Lens<Preferences, bool> + 'static,
+) -> impl Widget<Preferences> {
+    Flex::row()
+        .with_child(Checkbox::new("").lens(lens))
+        .with_child(Label::new(text(key)))
+}
+
+/// Each language is listed under its own name, so it can be found from any
+/// other.
+fn build_language_picker() -> impl Widget<Language> {
+    RadioGroup::new(vec![
+        ("English", Language::English),
+        ("Français", Language::French),
+        ("Español", Language::Spanish),
+    ])
+}
+
+fn build_swatches() -> impl Widget<Color> {
+    let pal = palette();
+
+    let mut row = Flex::row();
+    for idx in 0..PALETTE_SIZE {
+        row.add_child(build_swatch(pal[&idx].clone()));
+        row.add_spacer(2.0);
+    }
+
+    row
+}
+
+fn build_swatch(color: Color) -> impl Widget<Color> {
+    let paint_color = color.clone();
+
+    Painter::new(move |ctx, data: &Color, _env| {
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect, &paint_color);
+        if *data == paint_color {
+            ctx.stroke(rect.inset(-1.0), &Color::grey8(128), 2.0);
+        }
+    })
+    .fix_size(SWATCH_SIZE, SWATCH_SIZE)
+    .on_click(move |_ctx, data: &mut Color, _env| *data = color.clone())
+}
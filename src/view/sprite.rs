@@ -0,0 +1,152 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::model::app::AppState;
+use crate::model::prefs::TurtleShape;
+use druid::kurbo::Affine;
+use druid::kurbo::BezPath;
+use druid::kurbo::Circle;
+use druid::piet::ImageFormat;
+use druid::piet::InterpolationMode;
+use druid::widget::prelude::*;
+use druid::Color;
+use druid::ImageBuf;
+use druid::Point;
+use druid::Rect;
+
+/// The turtle bitmap, pointing up. Each letter is a pixel: `g` for skin, `s`
+/// for the shell's rim, `o` for its plates, and anything else is clear.
+const TURTLE_BITMAP: &[&str] = &[
+    "......ggg......",
+    ".....ggggg.....",
+    "......ggg......",
+    "..gg.sssss.gg..",
+    "..ggsooooosgg..",
+    "....sosssos....",
+    "...soosssoos...",
+    "...sosooosos...",
+    "...soosssoos...",
+    "....sosssos....",
+    "..ggsooooosgg..",
+    "..gg.sssss.gg..",
+    ".......g.......",
+];
+
+/// Draws the turtle at its position and heading, in whichever shape and size
+/// the program or preferences ask for. A picture from disk is loaded once, and again only
+/// when the path changes; if it can't be read, the turtle falls back to the
+/// triangle.
+pub struct Sprite {
+    bitmap: ImageBuf,
+    image: Option<ImageBuf>,
+    image_path: String,
+}
+
+impl Sprite {
+    pub fn new() -> Self {
+        Self {
+            bitmap: turtle_bitmap(),
+            image: None,
+            image_path: String::new(),
+        }
+    }
+
+    /// `pos` is the turtle's position in canvas coordinates.
+    pub fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, pos: Point) {
+        let size = data.turtle_size;
+        let zoom = data.viewport.zoom;
+        let image = match data.turtle_shape {
+            TurtleShape::Circle | TurtleShape::Triangle => None,
+            TurtleShape::Turtle => Some((&self.bitmap, InterpolationMode::NearestNeighbor)),
+            TurtleShape::Image => self
+                .load(&data.prefs.turtle_image)
+                .map(|image| (image, InterpolationMode::Bilinear)),
+        };
+
+        let rotate = Affine::rotate(data.heading.to_radians());
+        ctx.with_save(|ctx| {
+            ctx.transform(Affine::translate(pos.to_vec2()) * rotate);
+
+            match (image, data.turtle_shape) {
+                (Some((image, interp)), _) => paint_image(ctx, image, interp, size),
+                (None, TurtleShape::Circle) => paint_circle(ctx, size, zoom),
+                (None, _) => paint_triangle(ctx, size, zoom),
+            }
+        });
+    }
+
+    fn load(&mut self, path: &str) -> Option<&ImageBuf> {
+        if path != self.image_path {
+            self.image_path = path.to_string();
+            self.image = ImageBuf::from_file(path).ok();
+        }
+
+        self.image.as_ref()
+    }
+}
+
+/// Scale the image so its longer side is the turtle size, centered on the
+/// turtle.
+fn paint_image(ctx: &mut PaintCtx, image: &ImageBuf, interp: InterpolationMode, size: f64) {
+    let image_size = image.size();
+    let scale = size / image_size.width.max(image_size.height);
+    let rect = Rect::from_center_size(Point::ZERO, image_size * scale);
+
+    let image = image.to_image(ctx.render_ctx);
+    ctx.draw_image(&image, rect, interp);
+}
+
+fn paint_circle(ctx: &mut PaintCtx, size: f64, zoom: f64) {
+    let circle = Circle::new(Point::ZERO, size / 2.0);
+    ctx.fill(circle, &Color::rgba8(255, 255, 255, 64));
+    ctx.stroke(circle, &Color::WHITE, 1.5 / zoom);
+
+    // A tick shows which way the turtle faces.
+    ctx.fill(Circle::new((0.0, -size / 2.0), 2.0 / zoom), &Color::WHITE);
+}
+
+fn paint_triangle(ctx: &mut PaintCtx, size: f64, zoom: f64) {
+    let half = size / 2.0;
+
+    let mut path = BezPath::new();
+    path.move_to((0.0, -half));
+    path.line_to((half * 0.7, half));
+    path.line_to((0.0, half * 0.6));
+    path.line_to((-half * 0.7, half));
+    path.close_path();
+
+    ctx.fill(&path, &Color::rgba8(255, 255, 255, 64));
+    ctx.stroke(&path, &Color::WHITE, 1.5 / zoom);
+}
+
+fn turtle_bitmap() -> ImageBuf {
+    let width = TURTLE_BITMAP[0].len();
+    let height = TURTLE_BITMAP.len();
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for row in TURTLE_BITMAP {
+        for c in row.chars() {
+            let color = match c {
+                'g' => Color::rgb8(102, 187, 106),
+                'o' => Color::rgb8(141, 110, 60),
+                's' => Color::rgb8(78, 52, 26),
+                _ => Color::TRANSPARENT,
+            };
+            let (r, g, b, a) = color.as_rgba8();
+            pixels.extend_from_slice(&[r, g, b, a]);
+        }
+    }
+
+    ImageBuf::from_raw(pixels, ImageFormat::RgbaSeparate, width, height)
+}
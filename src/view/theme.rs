@@ -0,0 +1,89 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::model::prefs::Theme;
+use druid::theme;
+use druid::Color;
+use druid::Env;
+use druid::Key;
+
+pub const PANEL_BACKGROUND: Key<Color> = Key::new("turtle.panel-background");
+pub const PANEL_DIVIDER: Key<Color> = Key::new("turtle.panel-divider");
+pub const PANEL_ERROR: Key<Color> = Key::new("turtle.panel-error");
+pub const PANEL_FLASH: Key<Color> = Key::new("turtle.panel-flash");
+/// Laid over the line a held program runs next.
+pub const PANEL_HIGHLIGHT: Key<Color> = Key::new("turtle.panel-highlight");
+/// Line numbers, and anything else that shouldn't stand out.
+pub const PANEL_MUTED: Key<Color> = Key::new("turtle.panel-muted");
+pub const PANEL_TEXT: Key<Color> = Key::new("turtle.panel-text");
+pub const PANEL_WARNING: Key<Color> = Key::new("turtle.panel-warning");
+
+struct Palette {
+    background: Color,
+    divider: Color,
+    error: Color,
+    flash: Color,
+    highlight: Color,
+    muted: Color,
+    text: Color,
+    warning: Color,
+    selection: Color,
+}
+
+fn palette(theme: Theme) -> Palette {
+    match theme {
+        Theme::Dark => Palette {
+            background: Color::BLACK,
+            divider: Color::WHITE,
+            error: Color::rgb8(255, 110, 100),
+            flash: Color::rgb8(40, 70, 130),
+            highlight: Color::rgba8(255, 200, 60, 60),
+            muted: Color::rgb8(120, 120, 120),
+            text: Color::WHITE,
+            warning: Color::rgb8(255, 200, 60),
+            selection: Color::rgb8(100, 100, 100),
+        },
+        Theme::Light => Palette {
+            background: Color::rgb8(248, 248, 248),
+            divider: Color::rgb8(180, 180, 180),
+            error: Color::rgb8(190, 30, 30),
+            flash: Color::rgb8(200, 220, 255),
+            highlight: Color::rgba8(255, 200, 0, 80),
+            muted: Color::rgb8(150, 150, 150),
+            text: Color::BLACK,
+            warning: Color::rgb8(170, 100, 0),
+            selection: Color::rgb8(180, 200, 230),
+        },
+    }
+}
+
+/// Set the panel colors, and the druid theme keys used by the input editor.
+pub fn apply(env: &mut Env, theme: Theme) {
+    let pal = palette(theme);
+
+    env.set(PANEL_BACKGROUND, pal.background.clone());
+    env.set(PANEL_DIVIDER, pal.divider);
+    env.set(PANEL_ERROR, pal.error);
+    env.set(PANEL_FLASH, pal.flash);
+    env.set(PANEL_HIGHLIGHT, pal.highlight);
+    env.set(PANEL_MUTED, pal.muted);
+    env.set(PANEL_TEXT, pal.text.clone());
+    env.set(PANEL_WARNING, pal.warning);
+
+    env.set(theme::BACKGROUND_LIGHT, pal.background.clone());
+    env.set(theme::PRIMARY_LIGHT, pal.background.clone());
+    env.set(theme::BORDER_DARK, pal.background);
+    env.set(theme::SELECTED_TEXT_BACKGROUND_COLOR, pal.selection);
+    env.set(theme::CURSOR_COLOR, pal.text);
+}
@@ -0,0 +1,88 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::constants::*;
+use super::theme;
+use crate::graphics;
+use crate::model::app::AppState;
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::widget::Flex;
+use druid::widget::Label;
+use druid::widget::Slider;
+use druid::FontDescriptor;
+use druid::FontFamily;
+use druid::WidgetExt;
+
+/// A slider for scrubbing back and forth through a finished drawing. Moving
+/// it re-rasterizes the canvas from the command log, up to the chosen point.
+pub fn build() -> impl Widget<AppState> {
+    let slider = Slider::new()
+        .with_range(0.0, 1.0)
+        .lens(AppState::timeline)
+        .controller(TimelineController {})
+        .disabled_if(|data: &AppState, _env| data.session.is_running() || data.log.is_empty());
+
+    let position = Label::new(|data: &AppState, _: &_| {
+        format!("{:6} / {:<6}", replay_count(data), data.log.len())
+    })
+    .with_font(FontDescriptor::new(FontFamily::MONOSPACE).with_size(FONT_SIZE))
+    .with_text_color(theme::PANEL_TEXT);
+
+    Flex::row()
+        .with_default_spacer()
+        .with_flex_child(slider, 1.0)
+        .with_default_spacer()
+        .with_child(position)
+}
+
+/// How many commands from the log the timeline is showing.
+fn replay_count(data: &AppState) -> usize {
+    (data.timeline * data.log.len() as f64).round() as usize
+}
+
+fn replay(data: &mut AppState) {
+    let count = replay_count(data);
+
+    data.layers.reset(&data.prefs.screen_color);
+    data.reset_turtle();
+
+    for cmd in data.log.commands()[..count].iter() {
+        data.track(cmd);
+
+        graphics::render(&mut data.layers.drawing, &mut data.pos, cmd);
+    }
+
+    data.layers.drawing.present();
+}
+
+struct TimelineController {}
+
+impl<W: Widget<AppState>> Controller<AppState, W> for TimelineController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx<'_, '_>,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        let count = replay_count(data);
+        child.event(ctx, event, data, env);
+
+        if replay_count(data) != count {
+            replay(data);
+        }
+    }
+}
@@ -13,49 +13,126 @@
 // limitations under the License.
 
 use super::canvas::Canvas;
+use super::command_log;
 use super::console::Console;
+use super::console::ReplyController;
 use super::constants::*;
+use super::editor::Editor;
+use super::flash::Flash;
 use super::menu;
-use crate::common::constants::*;
+use super::params;
+use super::theme;
+use super::timeline;
+use crate::common::commands;
+use crate::common::constants::AUTOSAVE;
+use crate::common::constants::MAX_SPEED;
+use crate::common::constants::MIN_DIMS;
+use crate::common::constants::MIN_INPUT_WIDTH;
+use crate::common::constants::MIN_SPEED;
+use crate::common::i18n;
+use crate::common::profile;
 use crate::model::app::AppState;
+use crate::model::prefs::Preferences;
 use crate::model::render::RenderRx;
-use druid::theme;
 use druid::widget::prelude::*;
 use druid::widget::Container;
 use druid::widget::Controller;
 use druid::widget::CrossAxisAlignment;
+use druid::widget::Either;
 use druid::widget::Flex;
 use druid::widget::Label;
-use druid::widget::MainAxisAlignment;
+use druid::widget::SizedBox;
+use druid::widget::Slider;
+use druid::widget::Split;
 use druid::widget::TextBox;
 use druid::widget::Widget;
-use druid::Color;
 use druid::FontDescriptor;
 use druid::FontFamily;
+use druid::KbKey;
+use druid::LensExt;
 use druid::Size;
+use druid::TimerToken;
 use druid::WidgetExt;
 use druid::WindowDesc;
-use std::sync::atomic::Ordering;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
 
-pub fn window(render_rx: RenderRx) -> WindowDesc<AppState> {
-    let ui = build_ui(render_rx);
+pub fn window(render_rx: RenderRx, prefs: &Preferences) -> WindowDesc<AppState> {
+    let dims = prefs.canvas_size();
+    let ui = build_ui(Rc::new(RefCell::new(render_rx)), dims, prefs.input_width);
 
     WindowDesc::new(ui)
-        .title("Turtle")
+        .title(|data: &AppState, _env: &Env| title(data))
         .menu(menu::menu_bar)
-        .window_size(window_size())
+        .window_size(window_size(dims, prefs.input_width))
 }
 
-fn build_ui(render_rx: RenderRx) -> impl Widget<AppState> {
-    Flex::row()
+/// "spiral.logo — Turtle", with a dot in front while there are unsaved
+/// changes.
+fn title(data: &AppState) -> String {
+    let name = match &data.document {
+        Some(path) => path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        None if data.input.is_empty() => return "Turtle".to_string(),
+        None => i18n::text(data.prefs.ui_language, "window-untitled"),
+    };
+
+    let dirty = if data.is_dirty() { "\u{2022} " } else { "" };
+    format!("{}{} \u{2014} Turtle", dirty, name)
+}
+
+fn build_ui(
+    render_rx: Rc<RefCell<RenderRx>>,
+    dims: Size,
+    input_width: f64,
+) -> impl Widget<AppState> {
+    let workspace = Flex::row()
         .cross_axis_alignment(CrossAxisAlignment::Start)
-        .with_child(build_input())
-        .with_child(build_center_pane(render_rx))
-        .background(Color::WHITE)
-        .controller(WindowController {})
+        .with_flex_child(build_split(render_rx.clone(), dims, input_width), 1.0)
+        .with_child(build_command_log());
+
+    Either::new(
+        |data: &AppState, _env| data.presenting,
+        Canvas::new(render_rx).presenting(),
+        workspace,
+    )
+    .background(theme::PANEL_DIVIDER)
+    .env_scope(|env, data: &AppState| theme::apply(env, data.prefs.theme))
+    .controller(WindowController {
+        autosave: TimerToken::INVALID,
+    })
 }
 
-fn build_center_pane(render_rx: RenderRx) -> impl Widget<AppState> {
+/// The input beside everything to do with the drawing. Dragging the bar
+/// between them gives either side more room, and the preferences keep where
+/// it was left.
+fn build_split(
+    render_rx: Rc<RefCell<RenderRx>>,
+    dims: Size,
+    input_width: f64,
+) -> impl Widget<AppState> {
+    let input = build_input()
+        .controller(LiveController {
+            timer_id: TimerToken::INVALID,
+        })
+        .controller(InputWidthController {
+            width: input_width,
+            timer_id: TimerToken::INVALID,
+        });
+
+    Split::columns(input, build_center_pane(render_rx))
+        .split_point(input_width / (input_width + SPLIT_BAR_SIZE + dims.width))
+        .min_size(MIN_INPUT_WIDTH, MIN_DIMS.width)
+        .bar_size(SPLIT_BAR_SIZE)
+        .min_bar_area(SPLIT_BAR_AREA)
+        .draggable(true)
+        .solid_bar(true)
+}
+
+fn build_center_pane(render_rx: Rc<RefCell<RenderRx>>) -> impl Widget<AppState> {
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::End)
         .with_child(build_canvas(render_rx))
@@ -66,8 +143,18 @@ fn build_center_pane(render_rx: RenderRx) -> impl Widget<AppState> {
         .with_default_spacer()
 }
 
-fn build_canvas(render_rx: RenderRx) -> impl Widget<AppState> {
-    Canvas::new(render_rx).background(Color::BLACK)
+fn build_canvas(render_rx: Rc<RefCell<RenderRx>>) -> impl Widget<AppState> {
+    Canvas::new(render_rx)
+}
+
+fn build_command_log() -> impl Widget<AppState> {
+    Either::new(
+        |data: &AppState, _env| data.show_command_log,
+        command_log::build()
+            .fix_width(COMMAND_LOG_WIDTH)
+            .expand_height(),
+        SizedBox::empty(),
+    )
 }
 
 fn build_input() -> impl Widget<AppState> {
@@ -77,69 +164,121 @@ fn build_input() -> impl Widget<AppState> {
         turtle carry them out, press\n\
         Command-G.\n\
         \n\
-        Look under the Examples menu for\n\
+        Open File > Example Gallery for\n\
         ideas!";
 
-    Container::new(
-        TextBox::multiline()
-            .with_placeholder(placeholder)
-            .with_text_color(Color::WHITE)
-            .with_font(FontDescriptor::new(FontFamily::MONOSPACE).with_size(FONT_SIZE))
-            .with_line_wrapping(false)
-            .fix_width(INPUT_WIDTH)
-            .expand_height()
-            .env_scope(|env, _| {
-                env.set(theme::BACKGROUND_LIGHT, Color::BLACK);
-                env.set(theme::PRIMARY_LIGHT, Color::BLACK);
-                env.set(theme::BORDER_DARK, Color::BLACK);
-                env.set(
-                    theme::SELECTED_TEXT_BACKGROUND_COLOR,
-                    Color::rgb8(100, 100, 100),
-                );
-                env.set(theme::CURSOR_COLOR, Color::WHITE);
-            })
-            .lens(AppState::input),
-    )
+    let textbox = TextBox::multiline()
+        .with_placeholder(placeholder)
+        .with_text_color(theme::PANEL_TEXT)
+        .with_font(FontDescriptor::new(FontFamily::MONOSPACE).with_size(FONT_SIZE))
+        .with_line_wrapping(false);
+
+    Flex::column()
+        .with_flex_child(Container::new(Editor::new(textbox).expand()), 1.0)
+        .with_child(params::build())
 }
 
 fn build_console() -> impl Widget<AppState> {
-    Flex::row()
-        .main_axis_alignment(MainAxisAlignment::Start)
-        .with_child(Console::new())
-        .background(Color::BLACK)
-        .fix_width(DIMS.width)
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_flex_child(Console::new().expand_width(), 1.0)
+        .with_child(build_reply())
+        .background(theme::PANEL_BACKGROUND)
+        .expand_width()
         .fix_height(CONSOLE_HEIGHT)
 }
 
+/// Where to type the line a program asks for with readlist or readword. It
+/// only takes input while the program is waiting.
+fn build_reply() -> impl Widget<AppState> {
+    TextBox::new()
+        .with_placeholder("Answers to readlist and readword go here.")
+        .with_text_color(theme::PANEL_TEXT)
+        .with_font(FontDescriptor::new(FontFamily::MONOSPACE).with_size(FONT_SIZE))
+        .lens(AppState::reply)
+        .disabled_if(|data: &AppState, _env| !data.reading)
+        .controller(ReplyController {})
+        .expand_width()
+}
+
+/// The turtle's position and heading, and what the last run drew. The
+/// turtle's numbers keep their width, so the label doesn't jump as it moves.
 fn build_status_label() -> impl Widget<AppState> {
     Label::new(|data: &AppState, _: &_| {
-        format!(
-            "commands: {:6}   speed: {:2}",
-            data.command_count,
-            data.speed.load(Ordering::Relaxed)
-        )
+        // Adding zero turns -0, from rounding a small negative, into 0.
+        let whole = |num: f64| num.round() + 0.0;
+        let status = i18n::text_with(
+            data.prefs.ui_language,
+            "status-bar",
+            &[
+                ("x", &format!("{:5}", whole(data.pos.x))),
+                ("y", &format!("{:<5}", whole(data.pos.y))),
+                ("heading", &format!("{:3}", whole(data.heading))),
+                ("commands", &format!("{:6}", data.stats.commands)),
+                ("clipped", &format!("{:4}", data.stats.clipped)),
+            ],
+        );
+        // The speed slider follows the label, so the timings go first.
+        if profile::is_enabled() {
+            format!("{}   {}", profile::snapshot(), status)
+        } else {
+            status
+        }
     })
     .with_font(druid::FontDescriptor::new(druid::FontFamily::MONOSPACE).with_size(FONT_SIZE))
-    .with_text_color(Color::WHITE)
+    .with_text_color(theme::PANEL_TEXT)
+}
+
+/// Sets the speed of the program that's running as well as the next one.
+fn build_speed_control() -> impl Widget<AppState> {
+    let slider = Slider::new()
+        .with_range(MIN_SPEED as f64, MAX_SPEED as f64)
+        .lens(AppState::speed.map(
+            |speed| speed.get() as f64,
+            |speed, val| speed.set(val.round() as u32),
+        ))
+        .fix_width(SPEED_SLIDER_WIDTH);
+
+    let value = Label::new(|data: &AppState, _: &_| format!("{:2}", data.speed.get()))
+        .with_font(druid::FontDescriptor::new(druid::FontFamily::MONOSPACE).with_size(FONT_SIZE))
+        .with_text_color(theme::PANEL_TEXT);
+
+    Flex::row().with_child(slider).with_child(value)
 }
 
 fn build_status_bar() -> impl Widget<AppState> {
-    Flex::row()
-        .main_axis_alignment(MainAxisAlignment::End)
+    let row = Flex::row()
+        .with_flex_child(timeline::build(), 1.0)
+        .with_default_spacer()
         .with_child(build_status_label())
-        .fix_width(DIMS.width)
-        .fix_height(STATUS_BAR_HEIGHT)
-        .background(Color::BLACK)
+        .with_child(build_speed_control());
+
+    Flash::new(row).expand_width().fix_height(STATUS_BAR_HEIGHT)
 }
 
-fn window_size() -> Size {
+fn window_size(dims: Size, input_width: f64) -> Size {
     Size::new(
-        DIMS.width + INPUT_WIDTH,
-        DIMS.height + CONSOLE_HEIGHT + STATUS_BAR_HEIGHT + 2.0,
+        input_width + SPLIT_BAR_SIZE + dims.width,
+        dims.height + CONSOLE_HEIGHT + STATUS_BAR_HEIGHT + 2.0,
     )
 }
 
-struct WindowController {}
+struct WindowController {
+    /// Ticks for backing up the input.
+    autosave: TimerToken,
+}
+
+impl WindowController {
+    /// Wait as long as the preferences say between backups. While they're
+    /// off, keep looking in case they're turned back on.
+    fn schedule(&mut self, ctx: &mut EventCtx<'_, '_>, data: &AppState) {
+        let secs = match data.prefs.autosave {
+            0 => AUTOSAVE,
+            secs => secs,
+        };
+        self.autosave = ctx.request_timer(Duration::from_secs(secs.into()));
+    }
+}
 
 impl<W: Widget<AppState>> Controller<AppState, W> for WindowController {
     fn event(
@@ -150,6 +289,153 @@ impl<W: Widget<AppState>> Controller<AppState, W> for WindowController {
         data: &mut AppState,
         env: &Env,
     ) {
+        match event {
+            Event::WindowConnected => self.schedule(ctx, data),
+            // Escape is the way out of presenting, as well as the menu.
+            Event::KeyDown(key) if data.presenting && key.key == KbKey::Escape => {
+                ctx.submit_command(commands::VIEW_PRESENTATION);
+                ctx.set_handled();
+                return;
+            }
+            Event::Timer(token) if *token == self.autosave => {
+                ctx.submit_command(commands::FILE_BACKUP);
+                self.schedule(ctx, data);
+            }
+            _ => {}
+        }
+
+        child.event(ctx, event, data, env);
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx<'_, '_>,
+        old_data: &AppState,
+        data: &AppState,
+        env: &Env,
+    ) {
+        // Grow the window to make room for the command log, rather than
+        // squeezing the canvas.
+        if old_data.show_command_log != data.show_command_log {
+            let size = ctx.window().get_size();
+            let width = if data.show_command_log {
+                size.width + COMMAND_LOG_WIDTH
+            } else {
+                size.width - COMMAND_LOG_WIDTH
+            };
+            ctx.window().set_size(Size::new(width, size.height));
+        }
+
+        child.update(ctx, old_data, data, env);
+    }
+}
+
+/// Notices the split moving, and once it settles, asks for the input's new
+/// width to be kept.
+struct InputWidthController {
+    width: f64,
+    timer_id: TimerToken,
+}
+
+impl<W: Widget<AppState>> Controller<AppState, W> for InputWidthController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx<'_, '_>,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        if let Event::Timer(token) = event {
+            if *token == self.timer_id {
+                self.timer_id = TimerToken::INVALID;
+                ctx.submit_command(commands::VIEW_INPUT_WIDTH.with(self.width));
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx<'_, '_>,
+        event: &LifeCycle,
+        data: &AppState,
+        env: &Env,
+    ) {
+        // Dragging resizes the input over and over, so wait for it to stop.
+        if let LifeCycle::Size(size) = event {
+            let width = size.width.round();
+            if width != self.width {
+                self.width = width;
+                self.timer_id = ctx.request_timer(Duration::from_millis(SPLIT_SETTLE_MILLIS));
+            }
+        }
+
+        child.lifecycle(ctx, event, data, env);
+    }
+}
+
+/// Start the program over, a tick at a time. A run that's still going is
+/// stopped first. Once it has, whatever it left waiting to be drawn is
+/// dropped, so the new run starts on a clean canvas. Says whether the new
+/// run has started, or it needs asking again on a later tick.
+pub(super) fn restart_run(ctx: &mut EventCtx, data: &AppState) -> bool {
+    if data.session.is_running() {
+        ctx.submit_command(commands::INTERPRETER_STOP);
+        false
+    } else {
+        ctx.submit_command(commands::CANVAS_DISCARD);
+        ctx.submit_command(commands::INTERPRETER_GO);
+        true
+    }
+}
+
+/// In live mode, runs the program again once typing stops for a moment.
+struct LiveController {
+    timer_id: TimerToken,
+}
+
+impl<W: Widget<AppState>> Controller<AppState, W> for LiveController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx<'_, '_>,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        if let Event::Timer(token) = event {
+            if *token == self.timer_id {
+                self.timer_id = if data.live && !restart_run(ctx, data) {
+                    ctx.request_timer(Duration::from_millis(100))
+                } else {
+                    TimerToken::INVALID
+                };
+                return;
+            }
+        }
+
         child.event(ctx, event, data, env);
     }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx<'_, '_>,
+        old_data: &AppState,
+        data: &AppState,
+        env: &Env,
+    ) {
+        // Each change puts the run off again, so it waits for a pause.
+        let changed = !old_data.input.same(&data.input) || old_data.live != data.live;
+        if data.live && changed {
+            self.timer_id = ctx.request_timer(Duration::from_millis(LIVE_DELAY_MILLIS));
+        }
+
+        child.update(ctx, old_data, data, env);
+    }
 }
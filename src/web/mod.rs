@@ -0,0 +1,40 @@
+// Copyright 2021 Andy King
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Logo in a web page. A browser can't run a program on a thread of its own
+//! while the page stays responsive, so each program runs to the end in one go
+//! and the finished drawing comes back as RGBA pixels, ready to go into an
+//! HTML canvas's `ImageData`. See `web/index.html` for a page that does that.
+
+use crate::common::constants::*;
+use crate::export::headless;
+use crate::model::prefs::Preferences;
+use wasm_bindgen::prelude::*;
+
+/// Run `source` on a canvas `width` by `height` pixels, and give back the
+/// drawing. A program that fails throws its error message.
+#[wasm_bindgen]
+pub fn render(source: &str, width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+    let prefs = Preferences {
+        width: (width as f64).clamp(MIN_DIMS.width, MAX_DIMS.width),
+        height: (height as f64).clamp(MIN_DIMS.height, MAX_DIMS.height),
+        // There's no library on disk to load from or save to.
+        library_path: String::new(),
+        ..Default::default()
+    };
+
+    let pixels =
+        headless::run(source, &prefs).map_err(|err| JsValue::from_str(&err.to_string()))?;
+    Ok(pixels.flatten(&prefs.screen_color))
+}